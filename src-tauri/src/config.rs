@@ -31,7 +31,12 @@ pub struct RawConfig {
     pub tasks: Vec<TaskConfig>,
     pub actions: ActionsConfig,
     pub scratch: ScratchConfig,
+    #[serde(rename = "changedFiles")]
+    pub changed_files: ChangedFilesConfig,
+    pub git: GitConfig,
+    pub watcher: WatcherConfig,
     pub mappings: MappingsConfig,
+    pub updates: UpdatesConfig,
     #[serde(rename = "unfocusedOpacity")]
     pub unfocused_opacity: f64,
     /// Theme configuration. Can be a single theme name (string) or an object with light/dark themes.
@@ -53,7 +58,11 @@ impl Default for RawConfig {
             tasks: Vec::new(),
             actions: ActionsConfig::default(),
             scratch: ScratchConfig::default(),
+            changed_files: ChangedFilesConfig::default(),
+            git: GitConfig::default(),
+            watcher: WatcherConfig::default(),
             mappings: MappingsConfig::default(),
+            updates: UpdatesConfig::default(),
             unfocused_opacity: 1.0,
             theme: None, // Uses default Catppuccin themes when None
             theme_border_style: ThemeBorderStyle::default(),
@@ -73,7 +82,12 @@ pub struct Config {
     pub tasks: Vec<TaskConfig>,
     pub actions: ActionsConfig,
     pub scratch: ScratchConfig,
+    #[serde(rename = "changedFiles")]
+    pub changed_files: ChangedFilesConfig,
+    pub git: GitConfig,
+    pub watcher: WatcherConfig,
     pub mappings: MappingsConfig,
+    pub updates: UpdatesConfig,
     /// Opacity (0.0 to 1.0) applied to unfocused panes (main terminal or drawer)
     #[serde(rename = "unfocusedOpacity")]
     pub unfocused_opacity: f64,
@@ -97,7 +111,11 @@ impl Config {
             tasks: raw.tasks,
             actions: raw.actions,
             scratch: raw.scratch,
+            changed_files: raw.changed_files,
+            git: raw.git,
+            watcher: raw.watcher,
             mappings: raw.mappings,
+            updates: raw.updates,
             unfocused_opacity: raw.unfocused_opacity,
             theme: raw.theme,
             theme_border_style: raw.theme_border_style,
@@ -206,6 +224,10 @@ pub struct TaskConfig {
     /// Example: { "Dev": "http://localhost:{{ branch | hash_port }}" }
     #[serde(default)]
     pub urls: UrlMap,
+    /// Log file the task writes to, relative to the task's working directory.
+    /// Supports the same templates as `command`. Enables tailing the log.
+    #[serde(default, rename = "logFile")]
+    pub log_file: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -352,6 +374,92 @@ impl Default for ScratchConfig {
     }
 }
 
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UpdatesConfig {
+    /// URL of a JSON manifest (`{"version": "...", "changelogUrl": "..."}`)
+    /// describing the latest release. No update check is made when unset.
+    pub endpoint: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChangedFilesConfig {
+    /// Report submodules with new commits or dirty content as changed entries
+    #[serde(rename = "includeSubmodules")]
+    pub include_submodules: bool,
+}
+
+impl Default for ChangedFilesConfig {
+    fn default() -> Self {
+        Self {
+            include_submodules: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GitConfig {
+    /// Subcommands `run_git_command` may run, e.g. "status". Anything else is rejected.
+    /// Ignored in a repo's committed `.shellflow/config.jsonc`.
+    #[serde(rename = "allowedCommands")]
+    pub allowed_commands: Vec<String>,
+    /// Offer each git alias as a task in the task switcher
+    #[serde(rename = "aliasTasks")]
+    pub alias_tasks: bool,
+}
+
+impl Default for GitConfig {
+    fn default() -> Self {
+        Self {
+            allowed_commands: ["status", "log", "show", "diff"]
+                .iter()
+                .map(|c| c.to_string())
+                .collect(),
+            alias_tasks: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WatcherConfig {
+    /// Ignore churn inside `.git` (locks, logs, ORIG_HEAD). Index and HEAD
+    /// changes still refresh the changed files list.
+    #[serde(rename = "ignoreGit")]
+    pub ignore_git: bool,
+    /// Ignore paths matched by the repository's gitignore rules, like build output
+    #[serde(rename = "respectGitignore")]
+    pub respect_gitignore: bool,
+    /// Attempts to re-establish a watch lost mid-run (e.g. a directory briefly
+    /// moved away) before reporting the worktree as removed or the watcher as failed
+    #[serde(rename = "maxRestarts")]
+    pub max_restarts: u32,
+    /// Milliseconds before the first restart attempt, doubled for each next one
+    #[serde(rename = "restartDelayMs")]
+    pub restart_delay_ms: u64,
+    /// Milliseconds between rescans on platforms without native file events
+    #[serde(rename = "pollIntervalMs")]
+    pub poll_interval_ms: u64,
+    /// Milliseconds without changes before a worktree's changes are reported
+    #[serde(rename = "debounceMs")]
+    pub debounce_ms: u64,
+}
+
+impl Default for WatcherConfig {
+    fn default() -> Self {
+        Self {
+            ignore_git: true,
+            respect_gitignore: true,
+            max_restarts: 3,
+            restart_delay_ms: 200,
+            poll_interval_ms: 2000,
+            debounce_ms: 500,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct MainConfig {
@@ -407,6 +515,14 @@ pub struct WorktreeConfig {
 
     /// Configuration for delete operations
     pub delete: DeleteConfig,
+
+    /// Warn before creating a worktree when free disk space drops below this many MB (0 disables)
+    #[serde(rename = "lowDiskSpaceWarningMb")]
+    pub low_disk_space_warning_mb: u64,
+
+    /// Create and focus a worktree when a newly added project has none
+    #[serde(rename = "autoCreateFirstWorktree")]
+    pub auto_create_first_worktree: bool,
 }
 
 impl Default for WorktreeConfig {
@@ -418,6 +534,8 @@ impl Default for WorktreeConfig {
             focus_new_branch_names: false,
             merge: MergeConfig::default(),
             delete: DeleteConfig::default(),
+            low_disk_space_warning_mb: 1024,
+            auto_create_first_worktree: false,
         }
     }
 }
@@ -522,6 +640,9 @@ pub enum AppConfig {
         /// Where to open the app
         #[serde(default)]
         target: AppTarget,
+        /// Editor arguments for opening a file at a line, with `{{ path }}` and `{{ line }}`
+        #[serde(rename = "lineFormat", default, skip_serializing_if = "Option::is_none")]
+        line_format: Option<String>,
     },
 }
 
@@ -542,6 +663,14 @@ impl AppConfig {
             AppConfig::Full { target, .. } => *target,
         }
     }
+
+    /// Get the line format, if specified
+    pub fn line_format(&self) -> Option<&str> {
+        match self {
+            AppConfig::Simple(_) => None,
+            AppConfig::Full { line_format, .. } => line_format.as_deref(),
+        }
+    }
 }
 
 impl Default for AppConfig {
@@ -549,6 +678,7 @@ impl Default for AppConfig {
         AppConfig::Full {
             command: None,
             target: AppTarget::External,
+            line_format: None,
         }
     }
 }
@@ -587,6 +717,11 @@ impl AppsConfig {
         self.editor.as_ref().map(|c| c.target()).unwrap_or(AppTarget::Terminal)
     }
 
+    /// Get the editor line format, if specified
+    pub fn editor_line_format(&self) -> Option<&str> {
+        self.editor.as_ref().and_then(|c| c.line_format())
+    }
+
     /// Get the file manager command, if specified
     pub fn file_manager_command(&self) -> Option<&str> {
         self.file_manager.as_ref().and_then(|c| c.command())
@@ -969,6 +1104,14 @@ fn deep_merge(base: &mut serde_json::Value, overlay: &serde_json::Value) {
     }
 }
 
+/// Drop settings a repo-committed config must not grant itself. Whoever pushes
+/// to the repo would otherwise choose which git commands Shellflow runs.
+fn strip_untrusted(repo_value: &mut serde_json::Value) {
+    if let Some(git) = repo_value.get_mut("git").and_then(|git| git.as_object_mut()) {
+        git.remove("allowedCommands");
+    }
+}
+
 /// Merge two arrays. If items are objects with a "name" field, merge by name.
 /// Items with matching names are overridden; unique items accumulate.
 fn merge_arrays(base: &mut serde_json::Value, overlay: &serde_json::Value) {
@@ -1012,7 +1155,8 @@ fn merge_arrays(base: &mut serde_json::Value, overlay: &serde_json::Value) {
 /// Load config with optional project-specific overrides.
 /// Config files are merged in order: global <- repo <- local
 /// - Global: ~/.config/shellflow/config.jsonc
-/// - Repo: {project_path}/.shellflow/config.jsonc (tracked in git)
+/// - Repo: {project_path}/.shellflow/config.jsonc (tracked in git, cannot set
+///   `git.allowedCommands`)
 /// - Local: {project_path}/.shellflow/config.local.jsonc (gitignored)
 pub fn load_config_for_project(project_path: Option<&str>) -> Config {
     load_config_with_errors(project_path).config
@@ -1065,7 +1209,10 @@ pub fn load_config_with_errors(project_path: Option<&str>) -> ConfigResult {
         if repo_config_path.exists() {
             match std::fs::read_to_string(&repo_config_path) {
                 Ok(content) => match parse_jsonc_value(&content) {
-                    Ok(repo_value) => deep_merge(&mut merged, &repo_value),
+                    Ok(mut repo_value) => {
+                        strip_untrusted(&mut repo_value);
+                        deep_merge(&mut merged, &repo_value);
+                    }
                     Err(e) => errors.push(ConfigError {
                         file: repo_config_path.display().to_string(),
                         message: e,
@@ -1343,6 +1490,7 @@ mod tests {
             let config = AppConfig::Full {
                 command: Some("nvim".to_string()),
                 target: AppTarget::Drawer,
+                line_format: None,
             };
             let json = serde_json::to_string(&config).unwrap();
             assert!(json.contains("nvim"));
@@ -1350,6 +1498,26 @@ mod tests {
         }
     }
 
+    mod repo_config {
+        use super::*;
+
+        #[test]
+        fn cannot_allow_git_commands() {
+            let mut merged = serde_json::json!({ "git": { "allowedCommands": ["status"] } });
+            let mut repo_value = serde_json::json!({
+                "git": { "allowedCommands": ["push"], "aliasTasks": true },
+            });
+
+            strip_untrusted(&mut repo_value);
+            deep_merge(&mut merged, &repo_value);
+
+            assert_eq!(
+                merged,
+                serde_json::json!({ "git": { "allowedCommands": ["status"], "aliasTasks": true } })
+            );
+        }
+    }
+
     mod apps_config {
         use super::*;
 
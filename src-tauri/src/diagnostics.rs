@@ -0,0 +1,361 @@
+//! Health scan ("doctor") across the open projects.
+//!
+//! Each check turns a broken or surprising setup into a `Finding` that says
+//! what is wrong and what to do about it. Scanning never changes anything.
+
+use crate::config;
+use crate::git::{self, RegisteredWorktree};
+use crate::state::{PersistedState, Project};
+use crate::watcher;
+use crate::worktree::{self, canonical};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FindingKind {
+    /// A worktree we track has no directory on disk
+    MissingWorktreeDirectory,
+    /// Git still records a worktree whose directory is gone
+    StaleWorktreeMetadata,
+    /// A branch worktree has a detached HEAD
+    DetachedHead,
+    /// A worktree has uncommitted changes
+    Dirty,
+    /// A directory in the worktree base that neither we nor git know about
+    OrphanedDirectory,
+    /// The worktree's file watcher failed
+    WatcherFailure,
+    /// Another worktree entry already points at the same directory
+    DuplicateWorktreePath,
+    /// The worktree's path is a symlink to its directory
+    SymlinkedWorktreePath,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Finding {
+    pub severity: Severity,
+    pub kind: FindingKind,
+    pub project_id: String,
+    pub worktree_id: Option<String>,
+    pub path: String,
+    pub message: String,
+    /// What the user can do to resolve it
+    pub suggestion: String,
+}
+
+/// Scan every open project, most severe findings first.
+pub fn scan(state: &PersistedState) -> Vec<Finding> {
+    let mut findings: Vec<Finding> = state
+        .projects
+        .iter()
+        .filter(|p| p.is_active)
+        .flat_map(scan_project)
+        .collect();
+    findings.sort_by_key(|f| std::cmp::Reverse(f.severity));
+    findings
+}
+
+fn scan_project(project: &Project) -> Vec<Finding> {
+    let project_path = Path::new(&project.path);
+    let finding =
+        |severity, kind, worktree_id: Option<&str>, path: &Path, message, suggestion| Finding {
+            severity,
+            kind,
+            project_id: project.id.clone(),
+            worktree_id: worktree_id.map(String::from),
+            path: path.to_string_lossy().to_string(),
+            message,
+            suggestion,
+        };
+    let mut findings = Vec::new();
+
+    let registered = git::list_registered_worktrees(project_path).unwrap_or_default();
+    let find_registered = |path: &Path| -> Option<&RegisteredWorktree> {
+        let path = canonical(path);
+        registered.iter().find(|r| canonical(&r.path) == path)
+    };
+
+    for wt in &project.worktrees {
+        let path = Path::new(&wt.path);
+        if !path.is_dir() {
+            findings.push(finding(
+                Severity::Error,
+                FindingKind::MissingWorktreeDirectory,
+                Some(&wt.id),
+                path,
+                format!("Worktree {} has no directory", wt.name),
+                "Delete the worktree from Shellflow to remove it".to_string(),
+            ));
+            continue;
+        }
+
+        if worktree::is_symlinked(wt) {
+            findings.push(finding(
+                Severity::Info,
+                FindingKind::SymlinkedWorktreePath,
+                Some(&wt.id),
+                path,
+                format!(
+                    "Worktree {} is a symlink to {}",
+                    wt.name,
+                    canonical(path).display()
+                ),
+                "Paths are matched through the symlink; use the real path if tools disagree"
+                    .to_string(),
+            ));
+        }
+
+        if !wt.detached && find_registered(path).is_some_and(|r| r.detached) {
+            findings.push(finding(
+                Severity::Warning,
+                FindingKind::DetachedHead,
+                Some(&wt.id),
+                path,
+                format!("Worktree {} is not on branch {}", wt.name, wt.branch),
+                format!("Run `git switch {}` in the worktree", wt.branch),
+            ));
+        }
+
+        if git::has_uncommitted_changes_at_path(path).unwrap_or(false) {
+            findings.push(finding(
+                Severity::Info,
+                FindingKind::Dirty,
+                Some(&wt.id),
+                path,
+                format!("Worktree {} has uncommitted changes", wt.name),
+                "Commit or stash the changes before merging or deleting".to_string(),
+            ));
+        }
+
+        if let Some(error) = watcher::watch_failure(&wt.id) {
+            findings.push(finding(
+                Severity::Error,
+                FindingKind::WatcherFailure,
+                Some(&wt.id),
+                path,
+                format!("File watcher for {} failed: {}", wt.name, error),
+                "Reopen the project to restart its watchers".to_string(),
+            ));
+        }
+    }
+
+    for ids in worktree::find_duplicate_paths(project) {
+        let Some(kept) = project.worktrees.iter().find(|wt| wt.id == ids[0]) else {
+            continue;
+        };
+        for wt in project
+            .worktrees
+            .iter()
+            .filter(|wt| ids[1..].contains(&wt.id))
+        {
+            findings.push(finding(
+                Severity::Warning,
+                FindingKind::DuplicateWorktreePath,
+                Some(&wt.id),
+                Path::new(&wt.path),
+                format!(
+                    "Worktree {} points at the same directory as {}",
+                    wt.name, kept.name
+                ),
+                "Repair the project to drop the duplicate entry".to_string(),
+            ));
+        }
+    }
+
+    for stale in registered.iter().filter(|r| r.prunable) {
+        findings.push(finding(
+            Severity::Warning,
+            FindingKind::StaleWorktreeMetadata,
+            None,
+            &stale.path,
+            format!("Git still records a worktree at {}", stale.path.display()),
+            "Run `git worktree prune` in the project".to_string(),
+        ));
+    }
+
+    let known: HashSet<PathBuf> = project
+        .worktrees
+        .iter()
+        .map(|wt| canonical(Path::new(&wt.path)))
+        .chain(registered.iter().map(|r| canonical(&r.path)))
+        .collect();
+    for base in worktree_bases(project) {
+        let Ok(entries) = std::fs::read_dir(&base) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() && !known.contains(&canonical(&path)) {
+                findings.push(finding(
+                    Severity::Warning,
+                    FindingKind::OrphanedDirectory,
+                    None,
+                    &path,
+                    format!("{} is not a worktree of {}", path.display(), project.name),
+                    "Delete the directory if it is no longer needed".to_string(),
+                ));
+            }
+        }
+    }
+
+    findings
+}
+
+/// Directories that only hold this project's worktrees: the configured base
+/// and the parents of its worktrees. A directory that contains the project
+/// itself (e.g. `~/code` for sibling layouts) holds unrelated repos too, so it
+/// is skipped.
+fn worktree_bases(project: &Project) -> Vec<PathBuf> {
+    let project_path = canonical(Path::new(&project.path));
+    let cfg = config::load_config_for_project(Some(&project.path));
+
+    let configured = worktree::resolve_worktree_directory(
+        cfg.worktree.directory.as_deref(),
+        &project_path,
+        None,
+        None,
+    )
+    .ok();
+    let parents = project
+        .worktrees
+        .iter()
+        .filter_map(|wt| Path::new(&wt.path).parent().map(Path::to_path_buf));
+
+    let mut bases: Vec<PathBuf> = Vec::new();
+    for base in configured.into_iter().chain(parents) {
+        let base = canonical(&base);
+        if !project_path.starts_with(&base) && !bases.contains(&base) {
+            bases.push(base);
+        }
+    }
+    bases
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestRepo;
+
+    fn state_with(project: Project) -> PersistedState {
+        PersistedState {
+            projects: vec![project],
+            ..Default::default()
+        }
+    }
+
+    fn kinds(findings: &[Finding]) -> Vec<FindingKind> {
+        findings.iter().map(|f| f.kind).collect()
+    }
+
+    #[test]
+    fn healthy_project_has_no_findings() {
+        let repo = TestRepo::new();
+        let mut project = worktree::create_project(&repo.path).unwrap();
+        repo.create_worktree(&mut project, "feature");
+
+        assert!(scan(&state_with(project)).is_empty());
+    }
+
+    #[test]
+    fn orphaned_directory_in_worktree_base_is_reported() {
+        let repo = TestRepo::new();
+        let mut project = worktree::create_project(&repo.path).unwrap();
+        repo.create_worktree(&mut project, "feature");
+        let orphan = repo.root().join("worktrees").join("leftover");
+        std::fs::create_dir_all(&orphan).unwrap();
+
+        let findings = scan(&state_with(project));
+
+        assert_eq!(kinds(&findings), vec![FindingKind::OrphanedDirectory]);
+        assert_eq!(findings[0].severity, Severity::Warning);
+        assert_eq!(Path::new(&findings[0].path), canonical(&orphan));
+    }
+
+    #[test]
+    fn removed_worktree_directory_is_missing_and_stale() {
+        let repo = TestRepo::new();
+        let mut project = worktree::create_project(&repo.path).unwrap();
+        let wt = repo.create_worktree(&mut project, "feature");
+        std::fs::remove_dir_all(&wt.path).unwrap();
+
+        let findings = scan(&state_with(project));
+
+        assert_eq!(
+            kinds(&findings),
+            vec![
+                FindingKind::MissingWorktreeDirectory,
+                FindingKind::StaleWorktreeMetadata
+            ]
+        );
+        assert_eq!(findings[0].worktree_id.as_deref(), Some(wt.id.as_str()));
+    }
+
+    #[test]
+    fn dirty_and_detached_worktrees_are_reported() {
+        let repo = TestRepo::new();
+        let mut project = worktree::create_project(&repo.path).unwrap();
+        let wt = repo.create_worktree(&mut project, "feature");
+        let wt_path = Path::new(&wt.path);
+        crate::test_utils::git_in(wt_path, &["checkout", "--detach"]);
+        crate::test_utils::write_in(wt_path, "README.md", "changed\n");
+
+        let findings = scan(&state_with(project));
+
+        assert_eq!(
+            kinds(&findings),
+            vec![FindingKind::DetachedHead, FindingKind::Dirty]
+        );
+    }
+
+    #[test]
+    fn duplicate_worktree_paths_are_reported() {
+        let repo = TestRepo::new();
+        let mut project = worktree::create_project(&repo.path).unwrap();
+        let wt = repo.create_worktree(&mut project, "feature");
+        let mut duplicate = wt.clone();
+        duplicate.id = "duplicate".into();
+        project.worktrees.push(duplicate);
+
+        let findings = scan(&state_with(project));
+
+        assert_eq!(kinds(&findings), vec![FindingKind::DuplicateWorktreePath]);
+        assert_eq!(findings[0].worktree_id.as_deref(), Some("duplicate"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinked_worktree_paths_are_reported() {
+        let repo = TestRepo::new();
+        let mut project = worktree::create_project(&repo.path).unwrap();
+        let wt = repo.create_worktree(&mut project, "feature");
+        let link = repo.root().join("feature-link");
+        std::os::unix::fs::symlink(&wt.path, &link).unwrap();
+        project.worktrees[0].path = link.to_string_lossy().to_string();
+
+        let findings = scan(&state_with(project));
+
+        assert_eq!(kinds(&findings), vec![FindingKind::SymlinkedWorktreePath]);
+        assert_eq!(findings[0].worktree_id.as_deref(), Some(wt.id.as_str()));
+    }
+
+    #[test]
+    fn inactive_projects_are_skipped() {
+        let repo = TestRepo::new();
+        let mut project = worktree::create_project(&repo.path).unwrap();
+        std::fs::create_dir_all(repo.path.join(".worktrees/leftover")).unwrap();
+        project.is_active = false;
+
+        assert!(scan(&state_with(project)).is_empty());
+    }
+}
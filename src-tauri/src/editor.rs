@@ -0,0 +1,203 @@
+//! Building editor commands that open a file at a specific line.
+//!
+//! Editors disagree on how to receive a line number: VS Code-likes want
+//! `-g file:line`, terminal editors want `+line file`. A per-editor
+//! `lineFormat` from settings wins over the built-in defaults below.
+
+use std::path::{Path, PathBuf};
+
+/// Built-in line format for well-known editors, keyed by program name.
+pub fn default_line_format(program: &str) -> Option<&'static str> {
+    match program {
+        "code" | "code-insiders" | "cursor" | "codium" | "windsurf" => {
+            Some("-g {{ path }}:{{ line }}")
+        }
+        "zed" | "subl" | "hx" | "helix" => Some("{{ path }}:{{ line }}"),
+        "vim" | "nvim" | "vi" | "nano" | "emacs" | "emacsclient" | "kak" | "micro" => {
+            Some("+{{ line }} {{ path }}")
+        }
+        _ => None,
+    }
+}
+
+/// Program name of an editor command, without directory, extension or
+/// arguments, lowercased so `Code.exe` matches `code`.
+fn program_name(editor: &str) -> Option<String> {
+    let program = PathBuf::from(crate::settings::program_of(editor)?);
+    Some(program.file_stem()?.to_string_lossy().to_lowercase())
+}
+
+/// Rewrite an editor command so it opens at `line`.
+///
+/// The result still contains `{{ path }}` (or relies on the path being
+/// appended), so it can be passed to the usual path substitution.
+/// Editors without a known or configured format ignore the line. Without a
+/// line, a `{{ line }}` in the command is dropped along with its format.
+pub fn command_at_line(editor: &str, line: Option<usize>, line_format: Option<&str>) -> String {
+    let Some(line) = line else {
+        return without_line(editor);
+    };
+    let line = line.to_string();
+
+    if editor.contains("{{ line }}") {
+        return editor.replace("{{ line }}", &line);
+    }
+
+    let Some(format) = line_format.or_else(|| default_line_format(&program_name(editor)?)) else {
+        return editor.to_string();
+    };
+
+    let command = if editor.contains("{{ path }}") {
+        editor.replace("{{ path }}", format)
+    } else {
+        format!("{} {}", editor, format)
+    };
+    command.replace("{{ line }}", &line)
+}
+
+/// Drop the `{{ line }}` placeholder and the format around it from `editor`:
+/// `-g {{ path }}:{{ line }}` keeps `-g {{ path }}`, `+{{ line }}` goes
+/// entirely, and so does a flag whose whole value is the line (`-l {{ line }}`).
+fn without_line(editor: &str) -> String {
+    const LINE: &str = "\0line\0";
+    const PATH: &str = "\0path\0";
+    if !editor.contains("{{ line }}") {
+        return editor.to_string();
+    }
+
+    // The placeholders contain a space; swap them out so each stays in its word
+    let words = editor
+        .replace("{{ line }}", LINE)
+        .replace("{{ path }}", PATH);
+    let mut kept: Vec<&str> = Vec::new();
+    for word in words.split_whitespace() {
+        match (word.find(LINE), word.find(PATH)) {
+            (None, _) => kept.push(word),
+            // Keep the path and whatever is on the far side of it from the line
+            (Some(line), Some(path)) if line > path => kept.push(&word[..path + PATH.len()]),
+            (Some(_), Some(path)) => kept.push(&word[path..]),
+            (Some(_), None) => {
+                let flag = kept
+                    .last()
+                    .is_some_and(|w| w.starts_with('-') && !w.contains('\0'));
+                if word == LINE && flag {
+                    kept.pop();
+                }
+            }
+        }
+    }
+    kept.join(" ").replace(PATH, "{{ path }}")
+}
+
+/// Resolve `file` (absolute or relative to the worktree) and ensure it lies inside the worktree.
+pub fn resolve_in_worktree(worktree: &Path, file: &str) -> Result<PathBuf, String> {
+    let worktree = worktree
+        .canonicalize()
+        .map_err(|e| format!("Invalid worktree path {}: {}", worktree.display(), e))?;
+    let resolved = worktree
+        .join(file)
+        .canonicalize()
+        .map_err(|e| format!("Invalid file path {}: {}", file, e))?;
+
+    if !resolved.starts_with(&worktree) {
+        return Err(format!("{} is outside the worktree", file));
+    }
+    Ok(resolved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vscode_uses_goto_flag() {
+        assert_eq!(
+            command_at_line("code", Some(42), None),
+            "code -g {{ path }}:42"
+        );
+        assert_eq!(command_at_line("code", None, None), "code");
+    }
+
+    #[test]
+    fn vim_uses_plus_line() {
+        assert_eq!(
+            command_at_line("/usr/bin/nvim", Some(7), None),
+            "/usr/bin/nvim +7 {{ path }}"
+        );
+        assert_eq!(command_at_line("nvim", None, None), "nvim");
+    }
+
+    #[test]
+    fn quoted_program_paths_use_their_editor_format() {
+        assert_eq!(
+            command_at_line(
+                "\"/Applications/Visual Studio Code.app/bin/Code\" --wait",
+                Some(4),
+                None
+            ),
+            "\"/Applications/Visual Studio Code.app/bin/Code\" --wait -g {{ path }}:4"
+        );
+    }
+
+    #[test]
+    fn path_template_is_replaced_by_line_format() {
+        assert_eq!(
+            command_at_line("code --reuse-window {{ path }}", Some(3), None),
+            "code --reuse-window -g {{ path }}:3"
+        );
+    }
+
+    #[test]
+    fn configured_format_overrides_default() {
+        assert_eq!(
+            command_at_line("code", Some(5), Some("{{ path }}#L{{ line }}")),
+            "code {{ path }}#L5"
+        );
+        assert_eq!(
+            command_at_line("myeditor", Some(5), Some("-l {{ line }}")),
+            "myeditor -l 5"
+        );
+    }
+
+    #[test]
+    fn line_placeholder_is_dropped_without_a_line() {
+        assert_eq!(
+            command_at_line("code -g {{ path }}:{{ line }}", None, None),
+            "code -g {{ path }}"
+        );
+        assert_eq!(
+            command_at_line("nvim +{{ line }} {{ path }}", None, None),
+            "nvim {{ path }}"
+        );
+        assert_eq!(
+            command_at_line("ed {{ path }}#L{{ line }} -w", None, None),
+            "ed {{ path }} -w"
+        );
+        assert_eq!(
+            command_at_line("ed -l {{ line }} {{ path }}", None, None),
+            "ed {{ path }}"
+        );
+        assert_eq!(command_at_line("ed --at={{ line }}", None, None), "ed");
+    }
+
+    #[test]
+    fn unknown_editor_ignores_line() {
+        assert_eq!(command_at_line("myeditor", Some(5), None), "myeditor");
+    }
+
+    #[test]
+    fn rejects_files_outside_worktree() {
+        let root = std::env::temp_dir().join(format!("shellflow-editor-{}", uuid::Uuid::new_v4()));
+        let worktree = root.join("wt");
+        std::fs::create_dir_all(worktree.join("src")).unwrap();
+        std::fs::write(worktree.join("src/main.rs"), "").unwrap();
+        std::fs::write(root.join("secret"), "").unwrap();
+
+        let resolved = resolve_in_worktree(&worktree, "src/main.rs").unwrap();
+        assert!(resolved.ends_with("src/main.rs"));
+        assert!(resolve_in_worktree(&worktree, "../secret").is_err());
+        assert!(resolve_in_worktree(&worktree, root.join("secret").to_str().unwrap()).is_err());
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
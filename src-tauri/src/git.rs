@@ -1,8 +1,10 @@
 use crate::config::{BaseBranch, BaseBranchMode, MergeStrategy};
-use crate::state::{FileChange, FileStatus};
-use git2::{BranchType, Repository, Status, StatusOptions};
+use crate::state::{ChangeTree, ChangedFilesViewMode, FileChange, FileStatus, SubmoduleChange};
+use git2::{BranchType, Repository, Status, StatusOptions, SubmoduleIgnore, SubmoduleStatus};
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -17,6 +19,56 @@ pub enum GitError {
     BranchNotFound(String),
     #[error("Repository has uncommitted changes")]
     UncommittedChanges,
+    #[error("No changes to export")]
+    EmptyPatch,
+    #[error("Patch does not apply cleanly: {}", .0.join(", "))]
+    PatchDoesNotApply(Vec<String>),
+    #[error("Start point does not resolve to a commit: {0}")]
+    InvalidStartPoint(String),
+    #[error("Remote not found: {0}")]
+    RemoteNotFound(String),
+    #[error("git {0} timed out")]
+    Timeout(String),
+    #[error("Invalid config key: {0}")]
+    InvalidConfigKey(String),
+    #[error("git command not allowed: {0}")]
+    CommandNotAllowed(String),
+    #[error("No default branch: origin/HEAD is unset and there is no main or master branch")]
+    NoDefaultBranch,
+    #[error("Nothing to snapshot")]
+    NothingToSnapshot,
+    #[error("HEAD is not a WIP snapshot: {0}")]
+    NotWipSnapshot(String),
+    #[error("{0}")]
+    InvalidBranchName(String),
+    #[error("Branch '{0}' already exists")]
+    BranchExists(String),
+}
+
+impl GitError {
+    /// Stable machine-readable identifier for the frontend to localize.
+    /// The `Display` strings are for logs and may change; these must not.
+    pub fn code(&self) -> &'static str {
+        match self {
+            GitError::Git(_) => "git.libgit2",
+            GitError::Io(_) => "git.io",
+            GitError::MergeConflict(_) => "git.merge_conflict",
+            GitError::BranchNotFound(_) => "git.branch_not_found",
+            GitError::UncommittedChanges => "git.uncommitted_changes",
+            GitError::EmptyPatch => "git.empty_patch",
+            GitError::PatchDoesNotApply(_) => "git.patch_does_not_apply",
+            GitError::InvalidStartPoint(_) => "git.invalid_start_point",
+            GitError::RemoteNotFound(_) => "git.remote_not_found",
+            GitError::Timeout(_) => "git.timeout",
+            GitError::InvalidConfigKey(_) => "git.invalid_config_key",
+            GitError::CommandNotAllowed(_) => "git.command_not_allowed",
+            GitError::NoDefaultBranch => "git.no_default_branch",
+            GitError::NothingToSnapshot => "git.nothing_to_snapshot",
+            GitError::NotWipSnapshot(_) => "git.not_wip_snapshot",
+            GitError::InvalidBranchName(_) => "git.invalid_branch_name",
+            GitError::BranchExists(_) => "git.branch_exists",
+        }
+    }
 }
 
 /// Result of checking merge feasibility
@@ -55,6 +107,34 @@ pub struct WorktreeDeleteStatus {
     pub branch_name: String,
 }
 
+/// Outcome of applying (or checking) a patch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApplyResult {
+    /// Whether the patch was written to the worktree (false for a check-only run)
+    pub applied: bool,
+    /// Files touched by the patch
+    pub files: Vec<String>,
+}
+
+/// Size of the staged change set, for seeding a commit message
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StagedSummary {
+    pub files: usize,
+    pub insertions: usize,
+    pub deletions: usize,
+    /// Per file extension (lowercase, without the dot; empty for files without one)
+    pub by_type: BTreeMap<String, TypeSummary>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TypeSummary {
+    pub files: usize,
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
 pub fn is_git_repo(path: &Path) -> bool {
     Repository::open(path).is_ok()
 }
@@ -66,24 +146,33 @@ pub fn get_repo_name(path: &Path) -> String {
         .to_string()
 }
 
-pub fn get_default_branch(repo: &Repository) -> Result<String, GitError> {
-    // Try to find the default branch (main or master)
-    for branch_name in ["main", "master"] {
-        if repo
-            .find_branch(branch_name, git2::BranchType::Local)
-            .is_ok()
-        {
-            return Ok(branch_name.to_string());
-        }
-    }
+/// The repository's main branch, for repos where `origin/HEAD` may not be
+/// set. Tries, in order: the branch `origin/HEAD` points at, `init.defaultBranch`
+/// if that branch exists locally, then a local `main` or `master`.
+pub fn default_branch(repo_path: &Path) -> Result<String, GitError> {
+    default_branch_of(&Repository::open(repo_path)?)
+}
 
-    // Fall back to HEAD
-    let head = repo.head()?;
-    if let Some(name) = head.shorthand() {
-        return Ok(name.to_string());
+/// `default_branch` for an already opened repository
+fn default_branch_of(repo: &Repository) -> Result<String, GitError> {
+    let origin_head = repo
+        .find_reference("refs/remotes/origin/HEAD")
+        .ok()
+        .and_then(|r| r.symbolic_target().map(String::from));
+    if let Some(branch) = origin_head
+        .as_deref()
+        .and_then(|target| target.strip_prefix("refs/remotes/origin/"))
+    {
+        return Ok(branch.to_string());
     }
 
-    Ok("main".to_string())
+    let configured = repo.config()?.get_string("init.defaultBranch").ok();
+    let is_local = |name: &str| repo.find_branch(name, BranchType::Local).is_ok();
+    configured
+        .into_iter()
+        .chain(["main".to_string(), "master".to_string()])
+        .find(|name| is_local(name))
+        .ok_or(GitError::NoDefaultBranch)
 }
 
 pub fn get_current_branch(repo: &Repository) -> Result<String, GitError> {
@@ -101,7 +190,7 @@ pub fn get_current_branch(repo: &Repository) -> Result<String, GitError> {
 /// Resolve a BaseBranch config to an actual branch name
 pub fn resolve_target_branch(repo: &Repository, base_branch: &BaseBranch) -> Result<String, GitError> {
     match base_branch {
-        BaseBranch::Mode(BaseBranchMode::Auto) => get_default_branch(repo),
+        BaseBranch::Mode(BaseBranchMode::Auto) => default_branch_of(repo),
         BaseBranch::Mode(BaseBranchMode::Current) => get_current_branch(repo),
         BaseBranch::Named { name } => {
             // Verify the branch exists
@@ -113,6 +202,13 @@ pub fn resolve_target_branch(repo: &Repository, base_branch: &BaseBranch) -> Res
     }
 }
 
+/// Resolve the branch a new worktree will be created from.
+/// "auto" picks the repo's default branch, "current" uses whatever the main checkout has checked out.
+pub fn resolve_worktree_base(repo_path: &Path, base_branch: &BaseBranch) -> Result<String, GitError> {
+    let repo = Repository::open(repo_path)?;
+    resolve_target_branch(&repo, base_branch)
+}
+
 /// Check if a branch with the given name exists
 pub fn branch_exists(repo_path: &Path, branch_name: &str) -> Result<bool, GitError> {
     let repo = Repository::open(repo_path)?;
@@ -120,14 +216,80 @@ pub fn branch_exists(repo_path: &Path, branch_name: &str) -> Result<bool, GitErr
     Ok(exists)
 }
 
+/// The commit a worktree's HEAD points at, for "last commit 2h ago: fix login"
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitSummary {
+    pub sha: String,
+    pub short_sha: String,
+    pub author: String,
+    /// Commit time in seconds since the epoch
+    pub timestamp: i64,
+    /// Age of the commit, e.g. "2h ago"
+    pub relative_time: String,
+    /// First line of the commit message
+    pub subject: String,
+}
+
+/// Summary of the HEAD commit of the repo or worktree at `repo_path`,
+/// or None if HEAD is unborn (no commits yet)
+pub fn last_commit(repo_path: &Path) -> Result<Option<CommitSummary>, GitError> {
+    let repo = Repository::open(repo_path)?;
+    let head = match repo.head() {
+        Ok(head) => head,
+        Err(e) if e.code() == git2::ErrorCode::UnbornBranch => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let commit = head.peel_to_commit()?;
+
+    let sha = commit.id().to_string();
+    let timestamp = commit.time().seconds();
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(timestamp);
+    let summary = CommitSummary {
+        short_sha: sha[..7].to_string(),
+        sha,
+        author: commit.author().name().unwrap_or_default().to_string(),
+        timestamp,
+        relative_time: relative_time(now - timestamp),
+        subject: commit.summary().unwrap_or_default().to_string(),
+    };
+    Ok(Some(summary))
+}
+
+/// Commit time (seconds since the epoch) of HEAD, or None if HEAD is unborn
+pub fn last_commit_time(repo_path: &Path) -> Result<Option<i64>, GitError> {
+    Ok(last_commit(repo_path)?.map(|commit| commit.timestamp))
+}
+
+/// Compact age such as "5m ago" or "3d ago". Future times (clock skew) count as now.
+fn relative_time(secs_ago: i64) -> String {
+    const MINUTE: i64 = 60;
+    const HOUR: i64 = 60 * MINUTE;
+    const DAY: i64 = 24 * HOUR;
+    const WEEK: i64 = 7 * DAY;
+    const MONTH: i64 = 30 * DAY;
+    const YEAR: i64 = 365 * DAY;
+
+    match secs_ago {
+        s if s < MINUTE => "just now".to_string(),
+        s if s < HOUR => format!("{}m ago", s / MINUTE),
+        s if s < DAY => format!("{}h ago", s / HOUR),
+        s if s < WEEK => format!("{}d ago", s / DAY),
+        s if s < MONTH => format!("{}w ago", s / WEEK),
+        s if s < YEAR => format!("{}mo ago", s / MONTH),
+        s => format!("{}y ago", s / YEAR),
+    }
+}
+
 pub fn create_worktree(
     repo_path: &Path,
     worktree_path: &Path,
     branch_name: &str,
     base_branch: &BaseBranch,
 ) -> Result<(), GitError> {
-    use std::process::Command;
-
     log::info!("[git::create_worktree] Creating worktree at {:?}", worktree_path);
 
     // Check for modified/staged changes before proceeding using libgit2
@@ -143,21 +305,95 @@ pub fn create_worktree(
     }
 
     // Resolve the base branch to branch from based on config
-    let source_branch = {
+    let source_branch = resolve_worktree_base(repo_path, base_branch)?;
+    log::info!("[git::create_worktree] Using source branch: {}", source_branch);
+
+    add_branch_worktree(repo_path, worktree_path, branch_name, &source_branch)?;
+
+    log::info!("[git::create_worktree] Worktree created successfully");
+    Ok(())
+}
+
+/// With the "auto" policy, the commit a new worktree should start from: the
+/// default branch as its remote has it now, so the worktree doesn't start
+/// from a stale local branch. The fetch uses the remote-command guards and
+/// may take a while. None for other policies, or when there is no remote or
+/// the fetch fails (e.g. offline), in which case the local branch is used.
+pub fn fetched_worktree_base(repo_path: &Path, base_branch: &BaseBranch) -> Option<String> {
+    if !matches!(base_branch, BaseBranch::Mode(BaseBranchMode::Auto)) {
+        return None;
+    }
+    let branch = resolve_worktree_base(repo_path, base_branch).ok()?;
+    fetched_start_point_with(&SystemGit, repo_path, &branch)
+}
+
+/// The commit the remote of `branch` has for it after fetching. A sha rather
+/// than the remote-tracking ref, so a branch started there doesn't track it.
+fn fetched_start_point_with<R: GitRunner>(
+    runner: &R,
+    repo_path: &Path,
+    branch: &str,
+) -> Option<String> {
+    let repo = Repository::open(repo_path).ok()?;
+    let upstream = repo
+        .branch_upstream_remote(&format!("refs/heads/{}", branch))
+        .ok()
+        .and_then(|remote| remote.as_str().map(String::from));
+    let origin = || repo.find_remote("origin").ok().map(|_| "origin".to_string());
+    let remote = upstream.or_else(origin)?;
+
+    let command = GitCommand::remote(&["fetch", &remote, branch]);
+    if let Err(e) = runner.run(repo_path, &command) {
+        log::warn!("[git::fetched_worktree_base] Fetch of {} failed, using local: {}", branch, e);
+        return None;
+    }
+    let tracking = format!("{}/{}", remote, branch);
+    let fetched = repo.revparse_single(&format!("refs/remotes/{}^{{commit}}", tracking));
+    match fetched.map(|commit| commit.id().to_string()) {
+        Ok(sha) => Some(sha),
+        Err(e) => {
+            log::warn!("[git::fetched_worktree_base] No {}, using local branch: {}", tracking, e);
+            None
+        }
+    }
+}
+
+/// Create a worktree on a new branch starting at `start_point`, any
+/// committish (branch, tag, sha, `HEAD~2`...). Defaults to HEAD.
+pub fn create_worktree_with_start(
+    repo_path: &Path,
+    worktree_path: &Path,
+    branch_name: &str,
+    start_point: Option<&str>,
+) -> Result<(), GitError> {
+    let start_point = start_point.unwrap_or("HEAD");
+    {
         let repo = Repository::open(repo_path)?;
-        match base_branch {
-            BaseBranch::Mode(BaseBranchMode::Auto) => get_default_branch(&repo)?,
-            BaseBranch::Mode(BaseBranchMode::Current) => get_current_branch(&repo)?,
-            BaseBranch::Named { name } => {
-                // Verify the branch exists
-                if repo.find_branch(name, BranchType::Local).is_err() {
-                    return Err(GitError::BranchNotFound(name.clone()));
-                }
-                name.clone()
-            }
+        if has_modified_or_staged_changes(&repo)? {
+            return Err(GitError::UncommittedChanges);
         }
-    };
-    log::info!("[git::create_worktree] Using source branch: {}", source_branch);
+        repo.revparse_single(&format!("{}^{{commit}}", start_point))
+            .map_err(|_| GitError::InvalidStartPoint(start_point.to_string()))?;
+    }
+
+    add_branch_worktree(repo_path, worktree_path, branch_name, start_point)?;
+
+    log::info!(
+        "[git::create_worktree_with_start] Created worktree at {:?} from {}",
+        worktree_path,
+        start_point
+    );
+    Ok(())
+}
+
+/// `git worktree add -b <branch> <path> <start>`
+fn add_branch_worktree(
+    repo_path: &Path,
+    worktree_path: &Path,
+    branch_name: &str,
+    start_point: &str,
+) -> Result<(), GitError> {
+    use std::process::Command;
 
     // Use git CLI for worktree creation - handles locking properly
     let output = Command::new("git")
@@ -167,7 +403,7 @@ pub fn create_worktree(
             "-b",
             branch_name,
             &worktree_path.to_string_lossy(),
-            &source_branch,
+            start_point,
         ])
         .current_dir(repo_path)
         .output()?;
@@ -179,11 +415,80 @@ pub fn create_worktree(
             format!("git worktree add failed: {}", stderr),
         )));
     }
+    Ok(())
+}
 
-    log::info!("[git::create_worktree] Worktree created successfully");
+/// Create a worktree with a detached HEAD at the repository's current commit.
+/// No branch is created, which makes it suitable for throwaway experiments.
+pub fn create_detached_worktree(repo_path: &Path, worktree_path: &Path) -> Result<(), GitError> {
+    {
+        let repo = Repository::open(repo_path)?;
+        if has_modified_or_staged_changes(&repo)? {
+            return Err(GitError::UncommittedChanges);
+        }
+    }
+
+    run_git(
+        repo_path,
+        &["worktree", "add", "--detach", &worktree_path.to_string_lossy()],
+    )?;
+
+    log::info!("[git::create_detached_worktree] Created worktree at {:?}", worktree_path);
     Ok(())
 }
 
+/// A worktree as git records it, from `git worktree list`
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegisteredWorktree {
+    pub path: PathBuf,
+    /// Short name of the checked out branch, None when detached
+    pub branch: Option<String>,
+    pub detached: bool,
+    /// Git's metadata points at a directory that no longer exists
+    pub prunable: bool,
+}
+
+/// Every worktree git knows about for a repo, including the main checkout
+pub fn list_registered_worktrees(repo_path: &Path) -> Result<Vec<RegisteredWorktree>, GitError> {
+    let output = run_git(repo_path, &["worktree", "list", "--porcelain"])?;
+
+    let mut worktrees = Vec::new();
+    // Entries are blocks of lines separated by a blank line
+    for block in output.split("\n\n") {
+        let mut lines = block.lines();
+        let Some(path) = lines.next().and_then(|l| l.strip_prefix("worktree ")) else {
+            continue;
+        };
+        let mut worktree = RegisteredWorktree {
+            path: PathBuf::from(path),
+            branch: None,
+            detached: false,
+            prunable: false,
+        };
+        for line in lines {
+            if let Some(branch) = line.strip_prefix("branch refs/heads/") {
+                worktree.branch = Some(branch.to_string());
+            } else if line == "detached" {
+                worktree.detached = true;
+            } else if line.starts_with("prunable") {
+                worktree.prunable = true;
+            }
+        }
+        worktrees.push(worktree);
+    }
+    Ok(worktrees)
+}
+
+/// Path of the worktree (or main checkout) that has `branch` checked out.
+/// Git refuses to check a branch out twice, so callers check this first to
+/// report which worktree is in the way.
+pub fn worktree_for_branch(repo_path: &Path, branch: &str) -> Result<Option<PathBuf>, GitError> {
+    Ok(list_registered_worktrees(repo_path)?
+        .into_iter()
+        .find(|w| !w.prunable && w.branch.as_deref() == Some(branch))
+        .map(|w| w.path))
+}
+
 pub fn delete_worktree(repo_path: &Path, worktree_name: &str) -> Result<(), GitError> {
     let repo = Repository::open(repo_path)?;
 
@@ -206,7 +511,94 @@ pub fn delete_worktree(repo_path: &Path, worktree_name: &str) -> Result<(), GitE
     Ok(())
 }
 
-pub fn get_changed_files(worktree_path: &Path) -> Result<Vec<FileChange>, GitError> {
+/// List uncommitted changes in a worktree. When `include_submodules` is set,
+/// submodules with new commits or dirty content are reported as their own
+/// entries with `submodule` details; otherwise they are left out entirely.
+/// When `include_ignored` is set, gitignored paths are listed as `Ignored`, like
+/// `git status --ignored`: a wholly ignored directory is one entry, not every file in it.
+pub fn get_changed_files(
+    worktree_path: &Path,
+    include_submodules: bool,
+    include_ignored: bool,
+) -> Result<Vec<FileChange>, GitError> {
+    changed_files_matching(worktree_path, include_submodules, include_ignored, &[])
+}
+
+/// Update `previous`, the last full list of uncommitted changes, after the
+/// files at `paths` changed. Only those paths are re-examined, which is far
+/// cheaper than a full status on a large repo. Paths outside the working tree
+/// (e.g. the git directory) can change the status of any file, and ignored
+/// entries collapse whole directories, so those cases fall back to a full scan.
+pub fn get_changed_files_scoped(
+    worktree_path: &Path,
+    paths: &[PathBuf],
+    previous: &[FileChange],
+    include_submodules: bool,
+    include_ignored: bool,
+) -> Result<Vec<FileChange>, GitError> {
+    let full_scan = || get_changed_files(worktree_path, include_submodules, include_ignored);
+    if include_ignored || paths.is_empty() {
+        return full_scan();
+    }
+
+    let mut scopes = Vec::new();
+    for path in paths {
+        let relative = match path.strip_prefix(worktree_path) {
+            Ok(relative) if !relative.as_os_str().is_empty() => relative,
+            _ => return full_scan(),
+        };
+        if relative.components().any(|c| c.as_os_str() == ".git") {
+            return full_scan();
+        }
+        let Some(relative) = relative.to_str() else {
+            return full_scan();
+        };
+        scopes.push(relative.replace('\\', "/"));
+    }
+
+    if include_submodules {
+        // A change inside a submodule shows up as the submodule itself
+        let repo = Repository::open(worktree_path)?;
+        for submodule in repo.submodules()? {
+            let Some(sm_path) = submodule.path().to_str() else { continue };
+            for scope in scopes.iter_mut() {
+                if within(scope, sm_path) {
+                    *scope = sm_path.to_string();
+                }
+            }
+        }
+    }
+    scopes.sort();
+    scopes.dedup();
+
+    let mut changes: Vec<FileChange> = previous
+        .iter()
+        .filter(|change| !scopes.iter().any(|scope| within(&change.path, scope)))
+        .cloned()
+        .collect();
+    changes.extend(changed_files_matching(
+        worktree_path,
+        include_submodules,
+        include_ignored,
+        &scopes,
+    )?);
+    sort_changes(&mut changes);
+    Ok(changes)
+}
+
+/// Whether repo-relative `path` is `scope` or lies inside it
+fn within(path: &str, scope: &str) -> bool {
+    path.strip_prefix(scope).is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+/// Uncommitted changes limited to the literal repo-relative paths in
+/// `pathspecs`, or all of them when it's empty
+fn changed_files_matching(
+    worktree_path: &Path,
+    include_submodules: bool,
+    include_ignored: bool,
+    pathspecs: &[String],
+) -> Result<Vec<FileChange>, GitError> {
     use std::collections::HashMap;
     use std::process::Command;
 
@@ -214,13 +606,18 @@ pub fn get_changed_files(worktree_path: &Path) -> Result<Vec<FileChange>, GitErr
 
     // Get diff stats using git diff --numstat (for both staged and unstaged)
     let mut diff_stats: HashMap<String, (usize, usize)> = HashMap::new();
+    let numstat = |cached: bool| {
+        let mut cmd = Command::new("git");
+        cmd.arg("diff");
+        if cached {
+            cmd.arg("--cached");
+        }
+        cmd.arg("--numstat").arg("--").args(pathspecs);
+        cmd.env("GIT_LITERAL_PATHSPECS", "1").current_dir(worktree_path).output()
+    };
 
     // Unstaged changes
-    if let Ok(output) = Command::new("git")
-        .args(["diff", "--numstat"])
-        .current_dir(worktree_path)
-        .output()
-    {
+    if let Ok(output) = numstat(false) {
         if output.status.success() {
             for line in String::from_utf8_lossy(&output.stdout).lines() {
                 let parts: Vec<&str> = line.split('\t').collect();
@@ -235,11 +632,7 @@ pub fn get_changed_files(worktree_path: &Path) -> Result<Vec<FileChange>, GitErr
     }
 
     // Staged changes
-    if let Ok(output) = Command::new("git")
-        .args(["diff", "--cached", "--numstat"])
-        .current_dir(worktree_path)
-        .output()
-    {
+    if let Ok(output) = numstat(true) {
         if output.status.success() {
             for line in String::from_utf8_lossy(&output.stdout).lines() {
                 let parts: Vec<&str> = line.split('\t').collect();
@@ -258,14 +651,41 @@ pub fn get_changed_files(worktree_path: &Path) -> Result<Vec<FileChange>, GitErr
     let mut opts = StatusOptions::new();
     opts.include_untracked(true)
         .recurse_untracked_dirs(true)
-        .include_ignored(false);
+        .include_ignored(include_ignored)
+        .exclude_submodules(!include_submodules);
+    if !pathspecs.is_empty() {
+        opts.disable_pathspec_match(true);
+        for pathspec in pathspecs {
+            opts.pathspec(pathspec);
+        }
+    }
 
     let statuses = repo.statuses(Some(&mut opts))?;
 
+    // Submodule status is looked up by name, so map checkout paths to names
+    let submodule_names: HashMap<String, String> = if include_submodules {
+        repo.submodules()?
+            .iter()
+            .filter_map(|sm| {
+                let path = sm.path().to_str()?.to_string();
+                Some((path, sm.name()?.to_string()))
+            })
+            .collect()
+    } else {
+        HashMap::new()
+    };
+
     let mut changes = Vec::new();
 
     for entry in statuses.iter() {
         if let Some(path) = entry.path() {
+            if let Some(name) = submodule_names.get(path) {
+                if let Some(change) = submodule_change(&repo, path, name)? {
+                    changes.push(change);
+                }
+                continue;
+            }
+
             let status = entry.status();
             let file_status = if status.contains(Status::INDEX_NEW) {
                 // Staged new file
@@ -285,6 +705,8 @@ pub fn get_changed_files(worktree_path: &Path) -> Result<Vec<FileChange>, GitErr
                 || status.contains(Status::INDEX_RENAMED)
             {
                 FileStatus::Renamed
+            } else if status.contains(Status::IGNORED) {
+                FileStatus::Ignored
             } else {
                 continue;
             };
@@ -292,20 +714,60 @@ pub fn get_changed_files(worktree_path: &Path) -> Result<Vec<FileChange>, GitErr
             let (insertions, deletions) = diff_stats.get(path).copied().unwrap_or((0, 0));
 
             changes.push(FileChange {
-                path: path.to_string(),
+                // Ignored directories come back with a trailing slash
+                path: path.trim_end_matches('/').to_string(),
                 status: file_status,
                 insertions: if insertions > 0 || deletions > 0 { Some(insertions) } else { None },
                 deletions: if insertions > 0 || deletions > 0 { Some(deletions) } else { None },
+                submodule: None,
             });
         }
     }
 
-    // Sort by path for consistent ordering
-    changes.sort_by(|a, b| a.path.cmp(&b.path));
+    sort_changes(&mut changes);
 
     Ok(changes)
 }
 
+/// Build a change entry for a submodule, or `None` if it is clean.
+fn submodule_change(
+    repo: &Repository,
+    path: &str,
+    name: &str,
+) -> Result<Option<FileChange>, GitError> {
+    let status = repo.submodule_status(name, SubmoduleIgnore::None)?;
+
+    let new_commits =
+        status.intersects(SubmoduleStatus::INDEX_MODIFIED | SubmoduleStatus::WD_MODIFIED);
+    let modified_content =
+        status.intersects(SubmoduleStatus::WD_INDEX_MODIFIED | SubmoduleStatus::WD_WD_MODIFIED);
+    let untracked_content = status.contains(SubmoduleStatus::WD_UNTRACKED);
+
+    let file_status = if status.intersects(SubmoduleStatus::INDEX_ADDED | SubmoduleStatus::WD_ADDED)
+    {
+        FileStatus::Added
+    } else if status.intersects(SubmoduleStatus::INDEX_DELETED | SubmoduleStatus::WD_DELETED)
+    {
+        FileStatus::Deleted
+    } else if new_commits || modified_content || untracked_content {
+        FileStatus::Modified
+    } else {
+        return Ok(None);
+    };
+
+    Ok(Some(FileChange {
+        path: path.to_string(),
+        status: file_status,
+        insertions: None,
+        deletions: None,
+        submodule: Some(SubmoduleChange {
+            new_commits,
+            modified_content,
+            untracked_content,
+        }),
+    }))
+}
+
 /// Get information about the current branch relative to a base branch
 pub fn get_branch_info(worktree_path: &Path, base_branch: &BaseBranch) -> Result<crate::state::BranchInfo, GitError> {
     let repo = Repository::open(worktree_path)?;
@@ -320,22 +782,170 @@ pub fn get_branch_info(worktree_path: &Path, base_branch: &BaseBranch) -> Result
     })
 }
 
+/// Changed files for the panel's current view mode
+pub fn get_changed_files_for_mode(
+    worktree_path: &Path,
+    mode: ChangedFilesViewMode,
+    base_branch: &BaseBranch,
+    include_submodules: bool,
+    include_ignored: bool,
+) -> Result<Vec<FileChange>, GitError> {
+    match mode {
+        ChangedFilesViewMode::Uncommitted => {
+            get_changed_files(worktree_path, include_submodules, include_ignored)
+        }
+        ChangedFilesViewMode::Branch => get_branch_changed_files(worktree_path, base_branch),
+    }
+}
+
+/// Order changes by status, then path, so repeated refreshes produce identical lists.
+pub fn sort_changes(changes: &mut [FileChange]) {
+    changes.sort_by(|a, b| a.status.cmp(&b.status).then_with(|| a.path.cmp(&b.path)));
+}
+
+/// Changed files grouped into a directory tree with per-directory status counts.
+pub fn changed_files_tree(
+    worktree_path: &Path,
+    include_submodules: bool,
+    include_ignored: bool,
+) -> Result<ChangeTree, GitError> {
+    let files = get_changed_files(worktree_path, include_submodules, include_ignored)?;
+    Ok(build_change_tree(files))
+}
+
+/// Nest a flat list of changes by directory. Every directory on a file's path
+/// counts that file's status, so the root holds the totals.
+pub fn build_change_tree(files: Vec<FileChange>) -> ChangeTree {
+    let mut root = ChangeTree::default();
+
+    for file in files {
+        let directories: Vec<String> = match file.path.rsplit_once('/') {
+            Some((dir, _)) => dir.split('/').map(String::from).collect(),
+            None => Vec::new(),
+        };
+
+        let mut node = &mut root;
+        node.counts.record(&file.status);
+        for name in directories {
+            let index = match node.directories.iter().position(|d| d.name == name) {
+                Some(index) => index,
+                None => {
+                    let path = if node.path.is_empty() {
+                        name.clone()
+                    } else {
+                        format!("{}/{}", node.path, name)
+                    };
+                    node.directories.push(ChangeTree {
+                        name,
+                        path,
+                        ..Default::default()
+                    });
+                    node.directories.len() - 1
+                }
+            };
+            node = &mut node.directories[index];
+            node.counts.record(&file.status);
+        }
+        node.files.push(file);
+    }
+
+    sort_change_tree(&mut root);
+    root
+}
+
+fn sort_change_tree(node: &mut ChangeTree) {
+    node.directories.sort_by(|a, b| a.name.cmp(&b.name));
+    node.files.sort_by(|a, b| a.path.cmp(&b.path));
+    for child in &mut node.directories {
+        sort_change_tree(child);
+    }
+}
+
 /// Get files changed between the working tree and the base branch
 /// This includes committed changes (not in base), uncommitted changes, AND untracked files
 pub fn get_branch_changed_files(
     worktree_path: &Path,
     base_branch: &BaseBranch,
 ) -> Result<Vec<FileChange>, GitError> {
-    use std::collections::HashMap;
+    let repo = Repository::open(worktree_path)?;
+    let target_branch = resolve_target_branch(&repo, base_branch)?;
+    changes_against(worktree_path, &target_branch)
+}
+
+/// Everything the worktree's branch has touched since it forked from the
+/// default branch: commits since the merge base plus uncommitted and
+/// untracked files. Empty when the branch hasn't diverged and is clean.
+pub fn changes_since_fork(worktree_path: &Path) -> Result<Vec<FileChange>, GitError> {
+    let repo = Repository::open(worktree_path)?;
+    let default = default_branch(worktree_path)?;
+    // origin/HEAD may name a branch that only exists on the remote
+    let default_commit = repo
+        .revparse_single(&format!("refs/heads/{}", default))
+        .or_else(|_| repo.revparse_single(&format!("refs/remotes/origin/{}", default)))
+        .map_err(|_| GitError::BranchNotFound(default.clone()))?
+        .peel_to_commit()?;
+    let head = repo.head()?.peel_to_commit()?;
+
+    let fork_point = repo.merge_base(head.id(), default_commit.id())?;
+    changes_against(worktree_path, &fork_point.to_string())
+}
+
+/// Number of commits on the worktree's HEAD that aren't on `base`, e.g. for
+/// a "5 commits on this branch" stat. 0 when the branch hasn't diverged.
+pub fn commit_count_since_fork(worktree_path: &Path, base: &str) -> Result<usize, GitError> {
     use std::process::Command;
 
     let repo = Repository::open(worktree_path)?;
-    let target_branch = resolve_target_branch(&repo, base_branch)?;
+    if repo.revparse_single(base).is_err() {
+        return Err(GitError::BranchNotFound(base.to_string()));
+    }
+
+    let output = Command::new("git")
+        .args(["rev-list", "--count", &format!("{}..HEAD", base)])
+        .current_dir(worktree_path)
+        .output()?;
+    if !output.status.success() {
+        return Err(GitError::Io(std::io::Error::other(format!(
+            "git rev-list --count failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))));
+    }
+    String::from_utf8_lossy(&output.stdout).trim().parse().map_err(|_| {
+        GitError::Io(std::io::Error::other("git rev-list --count returned no number"))
+    })
+}
+
+/// Commits HEAD is ahead of and behind `base`, as `(ahead, behind)`
+pub fn ahead_behind(worktree_path: &Path, base: &str) -> Result<(usize, usize), GitError> {
+    let repo = Repository::open(worktree_path)?;
+    if repo.revparse_single(base).is_err() {
+        return Err(GitError::BranchNotFound(base.to_string()));
+    }
+
+    let output = run_git(
+        worktree_path,
+        &["rev-list", "--left-right", "--count", &format!("HEAD...{}", base)],
+    )?;
+    let counts: Vec<usize> = output.split_whitespace().filter_map(|n| n.parse().ok()).collect();
+    match counts[..] {
+        [ahead, behind] => Ok((ahead, behind)),
+        _ => Err(GitError::Io(std::io::Error::other(format!(
+            "unexpected git rev-list --left-right --count output: {}",
+            output.trim()
+        )))),
+    }
+}
+
+/// Files that differ between `target_branch` and the working tree, including untracked
+/// files. Any revision works, e.g. the fork point `changes_since_fork` passes.
+fn changes_against(worktree_path: &Path, target_branch: &str) -> Result<Vec<FileChange>, GitError> {
+    use std::collections::HashMap;
+    use std::process::Command;
 
     // Get file status changes using git diff --name-status
     // Compare base branch directly to working tree (includes uncommitted changes to tracked files)
     let output = Command::new("git")
-        .args(["diff", "--name-status", &target_branch])
+        .args(["diff", "--name-status", target_branch])
         .current_dir(worktree_path)
         .output()?;
 
@@ -347,22 +957,7 @@ pub fn get_branch_changed_files(
         )));
     }
 
-    // Parse name-status output
-    let mut file_statuses: HashMap<String, FileStatus> = HashMap::new();
-    for line in String::from_utf8_lossy(&output.stdout).lines() {
-        let parts: Vec<&str> = line.split('\t').collect();
-        if parts.len() >= 2 {
-            let status_char = parts[0].chars().next().unwrap_or('M');
-            let path = parts.last().unwrap().to_string();
-            let status = match status_char {
-                'A' => FileStatus::Added,
-                'D' => FileStatus::Deleted,
-                'R' => FileStatus::Renamed,
-                'M' | _ => FileStatus::Modified,
-            };
-            file_statuses.insert(path, status);
-        }
-    }
+    let mut file_statuses = parse_name_status(&output.stdout);
 
     // Also get untracked files using git status
     let output = Command::new("git")
@@ -388,24 +983,92 @@ pub fn get_branch_changed_files(
     // Get diff stats using git diff --numstat
     // Compare base branch directly to working tree
     let output = Command::new("git")
-        .args(["diff", "--numstat", &target_branch])
+        .args(["diff", "--numstat", target_branch])
         .current_dir(worktree_path)
         .output()?;
 
-    let mut diff_stats: HashMap<String, (usize, usize)> = HashMap::new();
-    if output.status.success() {
-        for line in String::from_utf8_lossy(&output.stdout).lines() {
-            let parts: Vec<&str> = line.split('\t').collect();
-            if parts.len() >= 3 {
-                let insertions = parts[0].parse().unwrap_or(0);
-                let deletions = parts[1].parse().unwrap_or(0);
-                let path = parts[2].to_string();
-                diff_stats.insert(path, (insertions, deletions));
-            }
+    let diff_stats = if output.status.success() {
+        parse_numstat(&output.stdout)
+    } else {
+        HashMap::new()
+    };
+
+    Ok(combine_changes(file_statuses, &diff_stats))
+}
+
+/// Files that differ between two branches, relative to their merge base
+/// (`git diff a...b`), so only the changes made on `branch_b` are listed.
+/// Identical branches produce an empty list.
+pub fn diff_branches(
+    project_path: &Path,
+    branch_a: &str,
+    branch_b: &str,
+) -> Result<Vec<FileChange>, GitError> {
+    use std::process::Command;
+
+    let range = format!("{}...{}", branch_a, branch_b);
+    let run = |format: &str| -> Result<Vec<u8>, GitError> {
+        let output = Command::new("git")
+            .args(["diff", format, &range, "--"])
+            .current_dir(project_path)
+            .output()?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(GitError::Io(std::io::Error::other(format!(
+                "git diff {} {} failed: {}",
+                format,
+                range,
+                stderr.trim()
+            ))));
+        }
+        Ok(output.stdout)
+    };
+
+    let file_statuses = parse_name_status(&run("--name-status")?);
+    let diff_stats = parse_numstat(&run("--numstat")?);
+    Ok(combine_changes(file_statuses, &diff_stats))
+}
+
+/// Parse `git diff --name-status` output into path -> status
+fn parse_name_status(stdout: &[u8]) -> std::collections::HashMap<String, FileStatus> {
+    let mut file_statuses = std::collections::HashMap::new();
+    for line in String::from_utf8_lossy(stdout).lines() {
+        let parts: Vec<&str> = line.split('\t').collect();
+        if parts.len() >= 2 {
+            let status_char = parts[0].chars().next().unwrap_or('M');
+            let path = parts.last().unwrap().to_string();
+            let status = match status_char {
+                'A' => FileStatus::Added,
+                'D' => FileStatus::Deleted,
+                'R' => FileStatus::Renamed,
+                _ => FileStatus::Modified,
+            };
+            file_statuses.insert(path, status);
+        }
+    }
+    file_statuses
+}
+
+/// Parse `git diff --numstat` output into path -> (insertions, deletions)
+fn parse_numstat(stdout: &[u8]) -> std::collections::HashMap<String, (usize, usize)> {
+    let mut diff_stats = std::collections::HashMap::new();
+    for line in String::from_utf8_lossy(stdout).lines() {
+        let parts: Vec<&str> = line.split('\t').collect();
+        if parts.len() >= 3 {
+            let insertions = parts[0].parse().unwrap_or(0);
+            let deletions = parts[1].parse().unwrap_or(0);
+            let path = parts[2].to_string();
+            diff_stats.insert(path, (insertions, deletions));
         }
     }
+    diff_stats
+}
 
-    // Combine into FileChange structs
+/// Combine statuses and line counts into sorted FileChange structs
+fn combine_changes(
+    file_statuses: std::collections::HashMap<String, FileStatus>,
+    diff_stats: &std::collections::HashMap<String, (usize, usize)>,
+) -> Vec<FileChange> {
     let mut changes: Vec<FileChange> = file_statuses
         .into_iter()
         .map(|(path, status)| {
@@ -415,14 +1078,13 @@ pub fn get_branch_changed_files(
                 status,
                 insertions: if insertions > 0 || deletions > 0 { Some(insertions) } else { None },
                 deletions: if insertions > 0 || deletions > 0 { Some(deletions) } else { None },
+                submodule: None,
             }
         })
         .collect();
 
-    // Sort by path for consistent ordering
-    changes.sort_by(|a, b| a.path.cmp(&b.path));
-
-    Ok(changes)
+    sort_changes(&mut changes);
+    changes
 }
 
 /// Get file content at a specific git ref (branch, commit, HEAD)
@@ -612,6 +1274,22 @@ pub fn check_merge_feasibility(worktree_path: &Path, base_branch: &BaseBranch) -
     })
 }
 
+/// Whether merging the worktree's HEAD into `target` (a branch or other
+/// revision) would be a fast-forward, i.e. `target` is an ancestor of HEAD.
+/// False when the two have diverged and a merge commit would be needed.
+/// Nothing is modified.
+pub fn can_fast_forward(worktree_path: &Path, target: &str) -> Result<bool, GitError> {
+    let repo = Repository::open(worktree_path)?;
+    let head = repo.head()?.peel_to_commit()?.id();
+    let target = repo
+        .revparse_single(target)
+        .and_then(|obj| obj.peel_to_commit())
+        .map_err(|_| GitError::BranchNotFound(target.to_string()))?
+        .id();
+
+    Ok(repo.merge_base(head, target)? == target)
+}
+
 /// Check if repository has uncommitted changes
 pub fn has_uncommitted_changes_at_path(repo_path: &Path) -> Result<bool, GitError> {
     let repo = Repository::open(repo_path)?;
@@ -628,6 +1306,17 @@ fn has_uncommitted_changes(repo: &Repository) -> Result<bool, GitError> {
     Ok(!statuses.is_empty())
 }
 
+/// Number of files with uncommitted changes, untracked and conflicted files included
+pub fn uncommitted_file_count(repo_path: &Path) -> Result<usize, GitError> {
+    let repo = Repository::open(repo_path)?;
+    let mut opts = StatusOptions::new();
+    opts.include_untracked(true)
+        .include_ignored(false)
+        .recurse_untracked_dirs(true);
+    let count = repo.statuses(Some(&mut opts))?.len();
+    Ok(count)
+}
+
 /// Check if repository has modified or staged changes (excludes untracked files)
 /// This is used for worktree creation where untracked files don't matter
 fn has_modified_or_staged_changes(repo: &Repository) -> Result<bool, GitError> {
@@ -699,10 +1388,71 @@ fn count_commits_ahead_of_base(repo: &Repository, branch_name: &str, target_bran
     Ok(ahead)
 }
 
-/// Stash uncommitted changes in a repository using git CLI.
-/// Returns a unique stash ID that can be used with `stash_pop` to restore the correct stash.
-pub fn stash_changes(repo_path: &Path) -> Result<String, GitError> {
-    use std::process::Command;
+/// Commits the worktree's branch is ahead of and behind its upstream, or
+/// None when HEAD is detached or the branch has no upstream
+pub fn ahead_behind_upstream(worktree_path: &Path) -> Result<Option<(usize, usize)>, GitError> {
+    let repo = Repository::open(worktree_path)?;
+    let head = match repo.head() {
+        Ok(head) if head.is_branch() => head,
+        _ => return Ok(None),
+    };
+    let branch = git2::Branch::wrap(head);
+    let Ok(upstream) = branch.upstream() else {
+        return Ok(None);
+    };
+
+    let local = branch.get().peel_to_commit()?.id();
+    let remote = upstream.get().peel_to_commit()?.id();
+    Ok(Some(repo.graph_ahead_behind(local, remote)?))
+}
+
+/// A git operation left in progress in a worktree, waiting to be continued or aborted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Operation {
+    Merge,
+    Rebase,
+    CherryPick,
+    Revert,
+    Bisect,
+}
+
+impl Operation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Operation::Merge => "merge",
+            Operation::Rebase => "rebase",
+            Operation::CherryPick => "cherry-pick",
+            Operation::Revert => "revert",
+            Operation::Bisect => "bisect",
+        }
+    }
+}
+
+/// The operation in progress in a worktree, or None when it's idle
+pub fn operation_in_progress(worktree_path: &Path) -> Result<Option<Operation>, GitError> {
+    use git2::RepositoryState;
+
+    let repo = Repository::open(worktree_path)?;
+    Ok(match repo.state() {
+        RepositoryState::Clean | RepositoryState::ApplyMailbox => None,
+        RepositoryState::Merge => Some(Operation::Merge),
+        RepositoryState::Rebase
+        | RepositoryState::RebaseInteractive
+        | RepositoryState::RebaseMerge
+        | RepositoryState::ApplyMailboxOrRebase => Some(Operation::Rebase),
+        RepositoryState::CherryPick | RepositoryState::CherryPickSequence => {
+            Some(Operation::CherryPick)
+        }
+        RepositoryState::Revert | RepositoryState::RevertSequence => Some(Operation::Revert),
+        RepositoryState::Bisect => Some(Operation::Bisect),
+    })
+}
+
+/// Stash uncommitted changes in a repository using git CLI.
+/// Returns a unique stash ID that can be used with `stash_pop` to restore the correct stash.
+pub fn stash_changes(repo_path: &Path) -> Result<String, GitError> {
+    use std::process::Command;
     use std::time::{SystemTime, UNIX_EPOCH};
 
     // Generate unique stash ID using timestamp + random suffix
@@ -803,7 +1553,7 @@ pub fn merge_branch_to_target(
             .shorthand()
             .ok_or_else(|| GitError::BranchNotFound("HEAD".to_string()))?
             .to_string();
-        let target = get_default_branch(&repo)?;
+        let target = default_branch_of(&repo)?;
         (current, target)
     };
 
@@ -842,6 +1592,97 @@ pub fn merge_branch_to_target(
     Ok(())
 }
 
+/// How `merge_worktree` ended
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum MergeOutcome {
+    /// The target already contained every commit of the branch
+    UpToDate,
+    /// The target was moved forward to the branch without a merge commit
+    FastForward,
+    /// A merge commit joins the two histories
+    Merged,
+    /// The merge stopped on these files and is left in progress for resolution
+    Conflict { files: Vec<String> },
+}
+
+/// Merge a worktree's branch into `into`, usually the repo's default branch.
+/// The merge happens where `into` is checked out, or in the main checkout
+/// after switching it to `into`. The main checkout is switched back afterwards
+/// unless the merge stopped on conflicts, which have to be resolved on `into`.
+/// The worktree itself is left alone.
+pub fn merge_worktree(
+    project_path: &Path,
+    workspace_branch: &str,
+    into: &str,
+) -> Result<MergeOutcome, GitError> {
+    for branch in [workspace_branch, into] {
+        if !branch_exists(project_path, branch)? {
+            return Err(GitError::BranchNotFound(branch.to_string()));
+        }
+    }
+
+    let checked_out = worktree_for_branch(project_path, into)?;
+    let target_path = checked_out.clone().unwrap_or_else(|| project_path.to_path_buf());
+    if has_modified_or_staged_changes(&Repository::open(&target_path)?)? {
+        return Err(GitError::UncommittedChanges);
+    }
+    if checked_out.is_some() {
+        return merge_into_checkout(&target_path, workspace_branch, into);
+    }
+
+    // A branch name to switch back to, or the commit a detached HEAD was at
+    let previous = {
+        let repo = Repository::open(project_path)?;
+        let head = repo.head()?;
+        match head.shorthand().filter(|_| head.is_branch()) {
+            Some(branch) => vec![branch.to_string()],
+            None => vec!["--detach".to_string(), head.peel_to_commit()?.id().to_string()],
+        }
+    };
+    run_git(project_path, &["checkout", into])?;
+    let outcome = merge_into_checkout(project_path, workspace_branch, into);
+    if !matches!(outcome, Ok(MergeOutcome::Conflict { .. })) {
+        let mut args = vec!["checkout"];
+        args.extend(previous.iter().map(String::as_str));
+        run_git(project_path, &args)?;
+    }
+    outcome
+}
+
+/// Merge `workspace_branch` into `into`, which is checked out at `target_path`
+fn merge_into_checkout(
+    target_path: &Path,
+    workspace_branch: &str,
+    into: &str,
+) -> Result<MergeOutcome, GitError> {
+    let is_ancestor = |ancestor: &str, of: &str| -> Result<bool, GitError> {
+        let status = std::process::Command::new("git")
+            .args(["merge-base", "--is-ancestor", ancestor, of])
+            .current_dir(target_path)
+            .status()?;
+        Ok(status.success())
+    };
+    if is_ancestor(workspace_branch, into)? {
+        return Ok(MergeOutcome::UpToDate);
+    }
+    if is_ancestor(into, workspace_branch)? {
+        run_git(target_path, &["merge", "--ff-only", workspace_branch])?;
+        return Ok(MergeOutcome::FastForward);
+    }
+
+    match run_git(target_path, &["merge", "--no-ff", "--no-edit", workspace_branch]) {
+        Ok(_) => Ok(MergeOutcome::Merged),
+        Err(error) => {
+            let files = get_conflicted_files(target_path)?;
+            if files.is_empty() {
+                return Err(error);
+            }
+            Ok(MergeOutcome::Conflict { files })
+        }
+    }
+}
+
 /// Abort an in-progress merge operation
 pub fn abort_merge(repo_path: &Path) -> Result<(), GitError> {
     use std::process::Command;
@@ -883,7 +1724,7 @@ pub fn rebase_branch_onto_target(
     use std::process::Command;
 
     let repo = Repository::open(worktree_path)?;
-    let target_branch = get_default_branch(&repo)?;
+    let target_branch = default_branch_of(&repo)?;
     drop(repo);
 
     // Rebase onto target branch
@@ -914,27 +1755,23 @@ pub fn delete_local_branch(repo_path: &Path, branch_name: &str) -> Result<(), Gi
     Ok(())
 }
 
-/// Delete a remote branch by pushing a delete refspec
-pub fn delete_remote_branch(repo_path: &Path, branch_name: &str) -> Result<(), GitError> {
-    use std::process::Command;
-
-    let output = Command::new("git")
-        .args(["push", "origin", "--delete", branch_name])
-        .current_dir(repo_path)
-        .output()?;
+/// Delete a remote branch by pushing a delete refspec, reporting push progress
+pub fn delete_remote_branch(
+    repo_path: &Path,
+    branch_name: &str,
+    on_progress: &mut dyn FnMut(GitProgress),
+) -> Result<(), GitError> {
+    let command = GitCommand::remote(&["push", "--progress", "origin", "--delete", branch_name]);
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
+    match SystemGit.run_with_progress(repo_path, &command, on_progress) {
         // Don't fail if branch doesn't exist on remote
-        if !stderr.contains("remote ref does not exist") {
-            return Err(GitError::Io(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                format!("Failed to delete remote branch: {}", stderr),
-            )));
-        }
+        Err(GitError::Io(e)) if e.to_string().contains("remote ref does not exist") => Ok(()),
+        Err(GitError::Io(e)) => Err(GitError::Io(std::io::Error::other(format!(
+            "Failed to delete remote branch: {}",
+            e
+        )))),
+        result => result.map(|_| ()),
     }
-
-    Ok(())
 }
 
 /// Validate a git branch name according to git's rules
@@ -1020,6 +1857,673 @@ pub fn rename_branch(repo_path: &Path, old_name: &str, new_name: &str) -> Result
     Ok(())
 }
 
+/// Run a git command in `repo_path`, returning stdout or an error containing stderr.
+fn run_git(repo_path: &Path, args: &[&str]) -> Result<String, GitError> {
+    let stdout = run_git_bytes(repo_path, args)?;
+    Ok(String::from_utf8_lossy(&stdout).into_owned())
+}
+
+/// `run_git` for output that must be kept byte for byte, like a patch
+fn run_git_bytes(repo_path: &Path, args: &[&str]) -> Result<Vec<u8>, GitError> {
+    git_output(repo_path, args)?.map_err(|stderr| {
+        GitError::Io(std::io::Error::other(format!(
+            "git {} failed: {}",
+            args.first().copied().unwrap_or_default(),
+            stderr.trim()
+        )))
+    })
+}
+
+/// Run a git command in `repo_path`, giving its stdout if it succeeded or its
+/// stderr if it failed, for callers that make sense of git's complaints.
+/// The outer error means git couldn't be run at all.
+fn git_output(repo_path: &Path, args: &[&str]) -> Result<Result<Vec<u8>, String>, GitError> {
+    use std::process::Command;
+
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(repo_path)
+        .output()?;
+
+    if !output.status.success() {
+        return Ok(Err(String::from_utf8_lossy(&output.stderr).into_owned()));
+    }
+    Ok(Ok(output.stdout))
+}
+
+/// Time limit for git commands that talk to a remote
+const NETWORK_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// A git invocation, kept as data so tests can check what would run
+#[derive(Debug, Clone, PartialEq)]
+pub struct GitCommand {
+    pub args: Vec<String>,
+    pub env: Vec<(String, String)>,
+    /// Kill git if it hasn't finished after this long
+    pub timeout: Option<Duration>,
+}
+
+impl GitCommand {
+    /// A command that may contact a remote. With no terminal attached a
+    /// credential prompt would hang forever, so prompting is disabled (the
+    /// command fails instead) and the command is bounded by `NETWORK_TIMEOUT`.
+    fn remote(args: &[&str]) -> Self {
+        Self {
+            args: args.iter().map(|a| a.to_string()).collect(),
+            env: vec![
+                ("GIT_TERMINAL_PROMPT".to_string(), "0".to_string()),
+                ("GCM_INTERACTIVE".to_string(), "never".to_string()),
+            ],
+            timeout: Some(NETWORK_TIMEOUT),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct GitOutput {
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// One step of git's `--progress` output, e.g. "Receiving objects: 45%".
+/// Remote branch deletion and allowlisted transfer commands report these;
+/// `git worktree add` has no `--progress`, so creating worktrees doesn't.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitProgress {
+    /// The git subcommand reporting progress, e.g. "push"
+    pub command: String,
+    pub phase: String,
+    pub percent: u8,
+}
+
+/// Parse a `--progress` line like `remote: Counting objects:  45% (9/20)`
+/// into its phase and percentage. Lines without a percentage give None.
+fn parse_progress(line: &str) -> Option<(String, u8)> {
+    let line = line.trim();
+    let line = line.strip_prefix("remote:").unwrap_or(line).trim_start();
+    let (phase, rest) = line.split_once(':')?;
+    let (percent, _) = rest.trim_start().split_once('%')?;
+    let percent: u8 = percent.parse().ok()?;
+    if phase.is_empty() || percent > 100 {
+        return None;
+    }
+    Some((phase.to_string(), percent))
+}
+
+/// Runs git commands; the seam that lets tests avoid real remotes
+pub trait GitRunner {
+    fn run(&self, repo_path: &Path, command: &GitCommand) -> Result<GitOutput, GitError> {
+        self.run_with_progress(repo_path, command, &mut |_| {})
+    }
+
+    /// Run `command`, reporting each `--progress` step from stderr as it arrives.
+    /// The command's args must ask for progress for there to be any.
+    fn run_with_progress(
+        &self,
+        repo_path: &Path,
+        command: &GitCommand,
+        on_progress: &mut dyn FnMut(GitProgress),
+    ) -> Result<GitOutput, GitError>;
+}
+
+/// Runs the `git` binary on PATH
+pub struct SystemGit;
+
+impl GitRunner for SystemGit {
+    fn run_with_progress(
+        &self,
+        repo_path: &Path,
+        command: &GitCommand,
+        on_progress: &mut dyn FnMut(GitProgress),
+    ) -> Result<GitOutput, GitError> {
+        use std::process::{Command, Stdio};
+        use std::sync::mpsc;
+
+        let mut child = Command::new("git")
+            .args(&command.args)
+            .envs(command.env.iter().map(|(k, v)| (k, v)))
+            .current_dir(repo_path)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        // Drain both pipes while waiting so a chatty git can't block on a full pipe
+        let (progress_tx, progress_rx) = mpsc::channel();
+        let stdout = read_in_background(child.stdout.take());
+        let stderr = read_stderr_in_background(child.stderr.take(), progress_tx);
+
+        let subcommand = command.args.first().cloned().unwrap_or_default();
+        let mut report = |(phase, percent)| {
+            on_progress(GitProgress {
+                command: subcommand.clone(),
+                phase,
+                percent,
+            })
+        };
+
+        let started = std::time::Instant::now();
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+            if command.timeout.is_some_and(|limit| started.elapsed() >= limit) {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(GitError::Timeout(command.args.join(" ")));
+            }
+            // Doubles as the poll interval
+            if let Ok(step) = progress_rx.recv_timeout(Duration::from_millis(20)) {
+                report(step);
+            }
+        };
+
+        let output = GitOutput {
+            stdout: stdout.join().unwrap_or_default(),
+            stderr: stderr.join().unwrap_or_default(),
+        };
+        progress_rx.try_iter().for_each(&mut report);
+        if !status.success() {
+            return Err(GitError::Io(std::io::Error::other(format!(
+                "git {} failed: {}",
+                subcommand,
+                output.stderr.trim()
+            ))));
+        }
+        Ok(output)
+    }
+}
+
+fn read_in_background<R: std::io::Read + Send + 'static>(
+    pipe: Option<R>,
+) -> std::thread::JoinHandle<String> {
+    std::thread::spawn(move || {
+        let mut out = String::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_string(&mut out);
+        }
+        out
+    })
+}
+
+/// Read all of stderr, sending progress steps as they arrive. Git redraws
+/// progress in place with `\r`, so both `\r` and `\n` end a line.
+fn read_stderr_in_background<R: std::io::Read + Send + 'static>(
+    pipe: Option<R>,
+    progress: std::sync::mpsc::Sender<(String, u8)>,
+) -> std::thread::JoinHandle<String> {
+    std::thread::spawn(move || {
+        let Some(mut pipe) = pipe else {
+            return String::new();
+        };
+        let mut all = Vec::new();
+        let mut line = Vec::new();
+        let mut last = None;
+        let mut buf = [0u8; 4096];
+        while let Ok(n) = pipe.read(&mut buf) {
+            if n == 0 {
+                break;
+            }
+            for &byte in &buf[..n] {
+                all.push(byte);
+                if byte != b'\r' && byte != b'\n' {
+                    line.push(byte);
+                    continue;
+                }
+                if let Some(step) = parse_progress(&String::from_utf8_lossy(&line)) {
+                    // Throughput updates repeat the same percentage
+                    if last.as_ref() != Some(&step) {
+                        let _ = progress.send(step.clone());
+                        last = Some(step);
+                    }
+                }
+                line.clear();
+            }
+        }
+        String::from_utf8_lossy(&all).into_owned()
+    })
+}
+
+/// Delete remote-tracking refs whose branches no longer exist on `remote`.
+/// Returns the pruned refs, e.g. `origin/feature`.
+pub fn prune_remote(project_path: &Path, remote: &str) -> Result<Vec<String>, GitError> {
+    prune_remote_with(&SystemGit, project_path, remote)
+}
+
+fn prune_remote_with<R: GitRunner>(
+    runner: &R,
+    project_path: &Path,
+    remote: &str,
+) -> Result<Vec<String>, GitError> {
+    // Only configured remotes, which also keeps option-like names off the command line
+    let repo = Repository::open(project_path)?;
+    if repo.find_remote(remote).is_err() {
+        return Err(GitError::RemoteNotFound(remote.to_string()));
+    }
+
+    let output = runner.run(project_path, &GitCommand::remote(&["remote", "prune", remote]))?;
+    Ok(parse_pruned_refs(&output.stdout))
+}
+
+/// Whether the worktree's branch exists on `remote`, asking the remote with
+/// `git ls-remote`. If the remote can't be reached, falls back to the local
+/// remote-tracking ref, which reflects the last fetch.
+pub fn remote_branch_exists(worktree_path: &Path, remote: &str) -> Result<bool, GitError> {
+    remote_branch_exists_with(&SystemGit, worktree_path, remote)
+}
+
+fn remote_branch_exists_with<R: GitRunner>(
+    runner: &R,
+    worktree_path: &Path,
+    remote: &str,
+) -> Result<bool, GitError> {
+    let repo = Repository::open(worktree_path)?;
+    if repo.find_remote(remote).is_err() {
+        return Err(GitError::RemoteNotFound(remote.to_string()));
+    }
+    let branch = get_current_branch(&repo)?;
+    let head_ref = format!("refs/heads/{}", branch);
+
+    let command = GitCommand::remote(&["ls-remote", "--heads", remote, &head_ref]);
+    match runner.run(worktree_path, &command) {
+        Ok(output) => Ok(lists_ref(&output.stdout, &head_ref)),
+        Err(e) => {
+            log::warn!("[remote_branch_exists] ls-remote {} failed, offline: {}", remote, e);
+            Ok(repo.find_branch(&format!("{}/{}", remote, branch), BranchType::Remote).is_ok())
+        }
+    }
+}
+
+/// Whether `git ls-remote` output (`<sha>\t<ref>` lines) lists exactly `full_ref`.
+/// ls-remote patterns match on trailing path components, so `feature` also
+/// returns `refs/heads/team/feature`.
+fn lists_ref(output: &str, full_ref: &str) -> bool {
+    output
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .any(|(_, name)| name.trim() == full_ref)
+}
+
+/// Refs from `git remote prune` output lines like ` * [pruned] origin/feature`
+fn parse_pruned_refs(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("* [pruned] "))
+        .map(|r| r.trim().to_string())
+        .collect()
+}
+
+/// Subcommands that transfer objects and report it with `--progress`
+const PROGRESS_COMMANDS: [&str; 4] = ["clone", "fetch", "pull", "push"];
+
+/// Run `git <args>` in a worktree and return its stdout, provided the
+/// subcommand (the first argument) is in `allowed`. Options that write to a
+/// file (`--output`) are rejected as well. Runs with the same prompt and
+/// timeout guards as remote commands, since `log` or `show` may still fetch
+/// in a partial clone. Clones, fetches, pulls and pushes report their
+/// progress to `on_progress`.
+pub fn run_allowlisted(
+    worktree_path: &Path,
+    args: &[String],
+    allowed: &[String],
+    on_progress: &mut dyn FnMut(GitProgress),
+) -> Result<String, GitError> {
+    run_allowlisted_with(&SystemGit, worktree_path, args, allowed, on_progress)
+}
+
+fn run_allowlisted_with<R: GitRunner>(
+    runner: &R,
+    worktree_path: &Path,
+    args: &[String],
+    allowed: &[String],
+    on_progress: &mut dyn FnMut(GitProgress),
+) -> Result<String, GitError> {
+    let subcommand = args.first().map(String::as_str).unwrap_or_default();
+    if !allowed.iter().any(|a| a == subcommand) {
+        return Err(GitError::CommandNotAllowed(subcommand.to_string()));
+    }
+    if let Some(arg) = args.iter().find(|a| a.starts_with("--output")) {
+        return Err(GitError::CommandNotAllowed(format!("{} {}", subcommand, arg)));
+    }
+
+    let mut args: Vec<&str> = args.iter().map(String::as_str).collect();
+    if PROGRESS_COMMANDS.contains(&subcommand) && !args.contains(&"--progress") {
+        args.insert(1, "--progress");
+    }
+    let command = GitCommand::remote(&args);
+    Ok(runner.run_with_progress(worktree_path, &command, on_progress)?.stdout)
+}
+
+/// Subject prefix that marks a commit made by `wip_snapshot`
+pub const WIP_PREFIX: &str = "WIP: ";
+
+/// Commit everything in the worktree, untracked files included, as a WIP
+/// snapshot so risky operations can be undone. Hooks are skipped since the
+/// snapshot isn't meant to be kept. Returns the snapshot's sha.
+pub fn wip_snapshot(worktree_path: &Path, message: &str) -> Result<String, GitError> {
+    if !has_uncommitted_changes_at_path(worktree_path)? {
+        return Err(GitError::NothingToSnapshot);
+    }
+    let message = format!("{}{}", WIP_PREFIX, message);
+    run_git(worktree_path, &["add", "--all"])?;
+    run_git(worktree_path, &["commit", "--quiet", "--no-verify", "-m", &message])?;
+    Ok(run_git(worktree_path, &["rev-parse", "HEAD"])?.trim().to_string())
+}
+
+/// Undo a `wip_snapshot`: drop the commit and put its changes back in the
+/// working tree, unstaged. Refuses if HEAD isn't a WIP snapshot, so a real
+/// commit can't be undone by accident.
+pub fn undo_wip_snapshot(worktree_path: &Path) -> Result<(), GitError> {
+    let head = last_commit(worktree_path)?
+        .ok_or_else(|| GitError::NotWipSnapshot("HEAD".to_string()))?;
+    if !head.subject.starts_with(WIP_PREFIX) {
+        return Err(GitError::NotWipSnapshot(head.short_sha));
+    }
+    // A mixed reset leaves the files untouched and unstages them, so files
+    // that were untracked before the snapshot are untracked again
+    run_git(worktree_path, &["reset", "--quiet", "HEAD~1"])?;
+    Ok(())
+}
+
+/// Stage every change in the worktree, including untracked files and deletions
+pub fn stage_all(worktree_path: &Path) -> Result<(), GitError> {
+    run_git(worktree_path, &["add", "--all"])?;
+    Ok(())
+}
+
+/// Unstage everything, leaving the working tree untouched
+pub fn unstage_all(worktree_path: &Path) -> Result<(), GitError> {
+    run_git(worktree_path, &["reset", "--quiet"])?;
+    Ok(())
+}
+
+/// Discard all staged and unstaged changes to tracked files.
+/// If `include_untracked` is set, untracked files and directories are deleted as well.
+pub fn discard_all(worktree_path: &Path, include_untracked: bool) -> Result<(), GitError> {
+    log::info!("[discard_all] Discarding changes in {:?} (untracked: {})", worktree_path, include_untracked);
+
+    run_git(worktree_path, &["reset", "--hard", "--quiet", "HEAD"])?;
+
+    if include_untracked {
+        run_git(worktree_path, &["clean", "-fd", "--quiet"])?;
+    }
+
+    Ok(())
+}
+
+/// Write a worktree's changes to `output` as a patch that `git apply` accepts.
+/// With a `base`, exports the commits made since the branch diverged from it;
+/// otherwise exports uncommitted changes to tracked files.
+pub fn export_patch(worktree_path: &Path, base: Option<&str>, output: &Path) -> Result<(), GitError> {
+    let range = match base {
+        Some(base) => format!("{}...HEAD", base),
+        None => "HEAD".to_string(),
+    };
+
+    let patch = run_git_bytes(worktree_path, &["diff", "--binary", &range])?;
+    if patch.is_empty() {
+        return Err(GitError::EmptyPatch);
+    }
+
+    std::fs::write(output, &patch)?;
+    log::info!("[git::export_patch] Wrote {} bytes to {:?}", patch.len(), output);
+    Ok(())
+}
+
+/// Summarize what is staged in a worktree. Renames count as a delete plus an
+/// add so each side lands under its own extension; binary files count as
+/// changed with no line counts.
+pub fn staged_summary(worktree_path: &Path) -> Result<StagedSummary, GitError> {
+    let output = run_git(worktree_path, &["diff", "--cached", "--numstat", "--no-renames"])?;
+    let mut summary = StagedSummary::default();
+
+    for line in output.lines() {
+        let mut parts = line.splitn(3, '\t');
+        let (Some(insertions), Some(deletions), Some(path)) =
+            (parts.next(), parts.next(), parts.next())
+        else {
+            continue;
+        };
+        // Binary files report "-" for both counts
+        let insertions: usize = insertions.parse().unwrap_or(0);
+        let deletions: usize = deletions.parse().unwrap_or(0);
+        let extension = Path::new(path)
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        summary.files += 1;
+        summary.insertions += insertions;
+        summary.deletions += deletions;
+        let by_type = summary.by_type.entry(extension).or_default();
+        by_type.files += 1;
+        by_type.insertions += insertions;
+        by_type.deletions += deletions;
+    }
+
+    Ok(summary)
+}
+
+/// Config sections that feed cached repo data (default branch, remotes, user)
+const FINGERPRINT_CONFIG_PREFIXES: &[&str] = &["branch.", "core.", "init.", "remote.", "user."];
+
+/// What a checkout's HEAD points at
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HeadInfo {
+    /// Checked-out branch; None when HEAD is detached
+    pub branch: Option<String>,
+    /// Commit HEAD resolves to; None when the branch has no commits yet
+    pub sha: Option<String>,
+    pub detached: bool,
+}
+
+/// The repository's shared git directory, the same for the main checkout and
+/// each of its linked worktrees
+pub fn common_dir(path: &Path) -> Result<PathBuf, GitError> {
+    let repo = Repository::open(path)?;
+    let dir = match repo.path().ancestors().nth(2) {
+        Some(common_dir) if repo.is_worktree() => common_dir,
+        _ => repo.path(),
+    };
+    Ok(dir.canonicalize()?)
+}
+
+/// HEAD of the project's main checkout. Given a linked worktree's path, this
+/// still describes the main checkout rather than the worktree.
+pub fn main_head(project_path: &Path) -> Result<HeadInfo, GitError> {
+    let repo = Repository::open(project_path)?;
+    // A linked worktree's git dir is `<common dir>/worktrees/<name>`
+    let repo = match repo.path().ancestors().nth(2) {
+        Some(common_dir) if repo.is_worktree() => Repository::open(common_dir)?,
+        _ => repo,
+    };
+
+    let head = repo.head();
+    match head {
+        Ok(head) => {
+            let detached = !head.is_branch();
+            Ok(HeadInfo {
+                branch: if detached { None } else { head.shorthand().map(String::from) },
+                sha: head.target().map(|oid| oid.to_string()),
+                detached,
+            })
+        }
+        Err(e) if e.code() == git2::ErrorCode::UnbornBranch => {
+            let head = repo.find_reference("HEAD")?;
+            let branch = head
+                .symbolic_target()
+                .map(|target| target.trim_start_matches("refs/heads/").to_string());
+            Ok(HeadInfo {
+                branch,
+                sha: None,
+                detached: false,
+            })
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// A hash of the repo state that cached data is derived from: the relevant
+/// `git config` values, HEAD, and the remote default branch. Compare
+/// fingerprints to invalidate caches when something actually changed.
+pub fn config_fingerprint(project_path: &Path) -> Result<String, GitError> {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    let mut hasher = DefaultHasher::new();
+
+    let config = run_git(project_path, &["config", "--list"])?;
+    // Keep git's order: later values of a repeated key win
+    config
+        .lines()
+        .filter(|line| FINGERPRINT_CONFIG_PREFIXES.iter().any(|p| line.starts_with(p)))
+        .for_each(|line| line.hash(&mut hasher));
+
+    let repo = Repository::open(project_path)?;
+    match repo.head() {
+        Ok(head) => {
+            head.name().hash(&mut hasher);
+            head.target().map(|oid| oid.to_string()).hash(&mut hasher);
+        }
+        // Unborn HEAD: still distinguish which branch it points at
+        Err(_) => repo
+            .find_reference("HEAD")
+            .ok()
+            .and_then(|r| r.symbolic_target().map(String::from))
+            .hash(&mut hasher),
+    }
+    repo.find_reference("refs/remotes/origin/HEAD")
+        .ok()
+        .and_then(|r| r.symbolic_target().map(String::from))
+        .hash(&mut hasher);
+
+    Ok(format!("{:016x}", hasher.finish()))
+}
+
+/// Check that `key` is a git config key: `section.name` or
+/// `section.subsection.name`. Section and name are alphanumeric or `-`, and
+/// the name starts with a letter.
+pub fn validate_config_key(key: &str) -> Result<(), GitError> {
+    let invalid = || GitError::InvalidConfigKey(key.to_string());
+    let (section, rest) = key.split_once('.').ok_or_else(invalid)?;
+    let (subsection, name) = match rest.rsplit_once('.') {
+        Some((subsection, name)) => (Some(subsection), name),
+        None => (None, rest),
+    };
+
+    let is_word =
+        |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+    let valid = is_word(section)
+        && is_word(name)
+        && name.starts_with(|c: char| c.is_ascii_alphabetic())
+        && !subsection.is_some_and(|s| s.contains(['\n', '\0']));
+    if valid {
+        Ok(())
+    } else {
+        Err(invalid())
+    }
+}
+
+/// The effective value of a config key in a worktree's repository, taking
+/// local, global and system config into account. None if it isn't set.
+pub fn get_config(worktree_path: &Path, key: &str) -> Result<Option<String>, GitError> {
+    validate_config_key(key)?;
+    let config = Repository::open(worktree_path)?.config()?;
+    match config.get_string(key) {
+        Ok(value) => Ok(Some(value)),
+        Err(e) if e.code() == git2::ErrorCode::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Set a config key for a worktree's repository (`local`), shared by all of
+/// the project's worktrees, or in the user's global config.
+pub fn set_config(
+    worktree_path: &Path,
+    key: &str,
+    value: &str,
+    local: bool,
+) -> Result<(), GitError> {
+    validate_config_key(key)?;
+    let scope = if local { "--local" } else { "--global" };
+    run_git(worktree_path, &["config", scope, key, value])?;
+    Ok(())
+}
+
+/// The repository's git aliases as (name, expansion) pairs, in config order.
+/// Includes aliases from global and system config.
+pub fn list_aliases(project_path: &Path) -> Result<Vec<(String, String)>, GitError> {
+    use std::process::Command;
+
+    let output = Command::new("git")
+        .args(["config", "--get-regexp", r"^alias\."])
+        .current_dir(project_path)
+        .output()?;
+    // Exit code 1 just means no alias is defined
+    match output.status.code() {
+        Some(0) => Ok(parse_aliases(&String::from_utf8_lossy(&output.stdout))),
+        Some(1) => Ok(Vec::new()),
+        _ => Err(GitError::Io(std::io::Error::other(format!(
+            "git config --get-regexp failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )))),
+    }
+}
+
+/// Parse `git config --get-regexp '^alias\.'` lines like `alias.co checkout`
+fn parse_aliases(output: &str) -> Vec<(String, String)> {
+    output
+        .lines()
+        .filter_map(|line| line.strip_prefix("alias."))
+        .filter_map(|line| {
+            let (name, command) = line.split_once(' ').unwrap_or((line, ""));
+            (!name.is_empty()).then(|| (name.to_string(), command.to_string()))
+        })
+        .collect()
+}
+
+/// Apply a patch file to a worktree. The patch is always checked with
+/// `git apply --check` first; if any hunk fails, nothing is written and the
+/// failing files are returned in `GitError::PatchDoesNotApply`.
+pub fn apply_patch(worktree_path: &Path, patch: &Path, check_only: bool) -> Result<ApplyResult, GitError> {
+    let patch_arg = patch.to_string_lossy();
+
+    if let Err(stderr) = git_output(worktree_path, &["apply", "--check", &patch_arg])? {
+        let mut conflicts: Vec<String> = Vec::new();
+        for line in stderr.lines() {
+            // e.g. "error: patch failed: src/app.ts:12"
+            if let Some(rest) = line.strip_prefix("error: patch failed: ") {
+                let file = rest.rsplit_once(':').map_or(rest, |(file, _)| file).to_string();
+                if !conflicts.contains(&file) {
+                    conflicts.push(file);
+                }
+            }
+        }
+        if conflicts.is_empty() {
+            conflicts.push(stderr.trim().to_string());
+        }
+        return Err(GitError::PatchDoesNotApply(conflicts));
+    }
+
+    let files = run_git(worktree_path, &["apply", "--numstat", &patch_arg])?
+        .lines()
+        .filter_map(|line| line.splitn(3, '\t').nth(2))
+        .map(|path| path.to_string())
+        .collect();
+
+    if !check_only {
+        run_git(worktree_path, &["apply", &patch_arg])?;
+        log::info!("[git::apply_patch] Applied {:?} to {:?}", patch, worktree_path);
+    }
+
+    Ok(ApplyResult {
+        applied: !check_only,
+        files,
+    })
+}
+
 /// Execute the full merge workflow
 pub fn execute_merge_workflow(
     worktree_path: &Path,
@@ -1052,6 +2556,7 @@ pub fn execute_merge_workflow(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_utils::{self, TestRepo};
 
     #[test]
     fn file_status_serializes_to_lowercase() {
@@ -1076,6 +2581,7 @@ mod tests {
             status: FileStatus::Modified,
             insertions: Some(10),
             deletions: Some(5),
+            submodule: None,
         };
 
         let json = serde_json::to_value(&change).unwrap();
@@ -1092,6 +2598,7 @@ mod tests {
             status: FileStatus::Untracked,
             insertions: None,
             deletions: None,
+            submodule: None,
         };
 
         let json = serde_json::to_value(&change).unwrap();
@@ -1102,25 +2609,589 @@ mod tests {
     }
 
     #[test]
-    fn validate_branch_name_empty() {
-        let result = validate_branch_name("");
-        assert_eq!(result, Some("Branch name cannot be empty".to_string()));
+    fn last_commit_reports_head_commit() {
+        let repo = TestRepo::new();
+        let sha = repo.commit_file("login.rs", "fn login() {}\n", "Fix login\n\nLonger body");
+
+        let commit = last_commit(&repo.path).unwrap().unwrap();
+
+        assert_eq!(commit.sha, sha);
+        assert_eq!(commit.short_sha, &sha[..7]);
+        assert_eq!(commit.author, "Shellflow Test");
+        assert_eq!(commit.subject, "Fix login");
+        assert_eq!(commit.relative_time, "just now");
+        let committed_at: i64 = repo.git(&["log", "-1", "--format=%ct"]).trim().parse().unwrap();
+        assert_eq!(commit.timestamp, committed_at);
     }
 
     #[test]
-    fn validate_branch_name_too_long() {
-        let long_name = "a".repeat(251);
-        let result = validate_branch_name(&long_name);
-        assert_eq!(
-            result,
-            Some("Branch name is too long (max 250 characters)".to_string())
-        );
+    fn last_commit_is_none_for_unborn_head() {
+        let repo = TestRepo::new();
+        repo.git(&["checkout", "--orphan", "empty"]);
+
+        assert_eq!(last_commit(&repo.path).unwrap(), None);
+        assert_eq!(last_commit_time(&repo.path).unwrap(), None);
     }
 
     #[test]
-    fn validate_branch_name_starts_with_dot() {
-        let result = validate_branch_name(".hidden");
-        assert_eq!(result, Some("Branch name cannot start with '.'".to_string()));
+    fn diff_branches_lists_changes_since_merge_base() {
+        let repo = TestRepo::new();
+        repo.commit_file("shared.rs", "one\n", "Add shared");
+        repo.git(&["checkout", "-b", "feature-a"]);
+        repo.commit_file("a.rs", "a\n", "Add a");
+        repo.git(&["checkout", "-b", "feature-b", "main"]);
+        repo.commit_file("b.rs", "b\n", "Add b");
+        repo.commit_file("shared.rs", "one\ntwo\n", "Extend shared");
+        repo.git(&["rm", "-q", "README.md"]);
+        repo.git(&["commit", "-m", "Drop readme"]);
+
+        let changes = diff_branches(&repo.path, "feature-a", "feature-b").unwrap();
+
+        let summary: Vec<(&str, FileStatus, Option<usize>)> = changes
+            .iter()
+            .map(|c| (c.path.as_str(), c.status, c.insertions))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("b.rs", FileStatus::Added, Some(1)),
+                ("shared.rs", FileStatus::Modified, Some(1)),
+                ("README.md", FileStatus::Deleted, Some(0)),
+            ]
+        );
+        // a.rs only exists on feature-a, so it isn't part of feature-b's changes
+        let reverse = diff_branches(&repo.path, "feature-b", "feature-a").unwrap();
+        let paths: Vec<&str> = reverse.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(paths, vec!["a.rs"]);
+    }
+
+    #[test]
+    fn changes_since_fork_covers_commits_and_working_tree() {
+        let repo = TestRepo::new();
+        repo.git(&["checkout", "-b", "feature"]);
+        repo.commit_file("feature.rs", "f\n", "Add feature");
+        repo.commit_file("README.md", "# feature\n", "Edit readme");
+        repo.write("wip.txt", "wip\n");
+        // Later work on main isn't part of the branch's changes
+        repo.git(&["checkout", "-q", "main"]);
+        repo.commit_file("main-only.rs", "m\n", "Main moves on");
+        repo.git(&["checkout", "-q", "feature"]);
+
+        let changes = changes_since_fork(&repo.path).unwrap();
+
+        let summary: Vec<(&str, FileStatus)> =
+            changes.iter().map(|c| (c.path.as_str(), c.status)).collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("feature.rs", FileStatus::Added),
+                ("README.md", FileStatus::Modified),
+                ("wip.txt", FileStatus::Untracked),
+            ]
+        );
+    }
+
+    #[test]
+    fn changes_since_fork_is_empty_before_diverging() {
+        let repo = TestRepo::new();
+        repo.git(&["checkout", "-b", "feature"]);
+
+        assert!(changes_since_fork(&repo.path).unwrap().is_empty());
+    }
+
+    #[test]
+    fn commit_count_since_fork_counts_branch_commits() {
+        let repo = TestRepo::new();
+        repo.git(&["checkout", "-b", "feature"]);
+        assert_eq!(commit_count_since_fork(&repo.path, "main").unwrap(), 0);
+
+        for n in 1..=3 {
+            repo.commit_file(&format!("file{}.rs", n), "x\n", &format!("Commit {}", n));
+        }
+        // Commits on main after the fork don't count
+        repo.git(&["checkout", "-q", "main"]);
+        repo.commit_file("main-only.rs", "m\n", "Main moves on");
+        repo.git(&["checkout", "-q", "feature"]);
+
+        assert_eq!(commit_count_since_fork(&repo.path, "main").unwrap(), 3);
+        assert!(matches!(
+            commit_count_since_fork(&repo.path, "missing"),
+            Err(GitError::BranchNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn diff_branches_is_empty_for_identical_branches() {
+        let repo = TestRepo::new();
+        repo.git(&["branch", "copy"]);
+
+        assert!(diff_branches(&repo.path, "main", "copy").unwrap().is_empty());
+        assert!(diff_branches(&repo.path, "main", "main").unwrap().is_empty());
+        assert!(diff_branches(&repo.path, "main", "missing").is_err());
+    }
+
+    #[test]
+    fn local_config_is_read_back_from_every_worktree() {
+        let repo = TestRepo::new();
+        let linked = repo.root().join("linked");
+        repo.git(&["worktree", "add", "-b", "linked", linked.to_str().unwrap()]);
+
+        set_config(&linked, "user.email", "team@example.com", true).unwrap();
+
+        assert_eq!(
+            get_config(&linked, "user.email").unwrap().as_deref(),
+            Some("team@example.com")
+        );
+        assert_eq!(
+            get_config(&repo.path, "user.email").unwrap().as_deref(),
+            Some("team@example.com")
+        );
+        assert_eq!(get_config(&repo.path, "shellflow.unset-key").unwrap(), None);
+    }
+
+    #[test]
+    fn config_keys_are_validated() {
+        for key in ["user.email", "remote.origin.url", "branch.feat/x.y.merge", "core.hooks-path"] {
+            assert!(validate_config_key(key).is_ok(), "{} should be valid", key);
+        }
+        for key in ["email", "user.", ".email", "user.1email", "us er.email", "user.e_mail"] {
+            let err = validate_config_key(key).unwrap_err();
+            assert_eq!(err.code(), "git.invalid_config_key", "{} should be invalid", key);
+        }
+
+        let repo = TestRepo::new();
+        assert!(matches!(
+            set_config(&repo.path, "email", "x", true),
+            Err(GitError::InvalidConfigKey(_))
+        ));
+        assert!(get_config(&repo.path, "email").is_err());
+    }
+
+    #[test]
+    fn default_branch_prefers_origin_head() {
+        let repo = TestRepo::new();
+        repo.git(&["update-ref", "refs/remotes/origin/trunk", "HEAD"]);
+        repo.git(&["symbolic-ref", "refs/remotes/origin/HEAD", "refs/remotes/origin/trunk"]);
+
+        assert_eq!(default_branch(&repo.path).unwrap(), "trunk");
+    }
+
+    #[test]
+    fn default_branch_falls_back_to_init_default_branch() {
+        let repo = TestRepo::new();
+        repo.git(&["branch", "-m", "main", "develop"]);
+        repo.git(&["branch", "master"]);
+        repo.git(&["config", "init.defaultBranch", "develop"]);
+
+        assert_eq!(default_branch(&repo.path).unwrap(), "develop");
+
+        // A configured name without a matching branch is skipped
+        repo.git(&["config", "init.defaultBranch", "missing"]);
+        assert_eq!(default_branch(&repo.path).unwrap(), "master");
+    }
+
+    #[test]
+    fn default_branch_finds_main_or_master() {
+        let repo = TestRepo::new();
+        // Keep the developer's global init.defaultBranch out of the picture
+        repo.git(&["config", "init.defaultBranch", "missing"]);
+        assert_eq!(default_branch(&repo.path).unwrap(), "main");
+
+        repo.git(&["branch", "-m", "main", "master"]);
+        assert_eq!(default_branch(&repo.path).unwrap(), "master");
+
+        repo.git(&["branch", "-m", "master", "trunk"]);
+        let err = default_branch(&repo.path).unwrap_err();
+        assert!(matches!(err, GitError::NoDefaultBranch));
+    }
+
+    #[test]
+    fn wip_snapshot_commits_everything_and_undo_restores_it() {
+        let repo = TestRepo::new();
+        let before = repo.head();
+        repo.write("README.md", "# changed\n");
+        repo.write("notes/new.txt", "draft\n");
+        let status = repo.git(&["status", "--porcelain", "-uall"]);
+
+        let sha = wip_snapshot(&repo.path, "before rebase").unwrap();
+
+        assert_eq!(sha, repo.head());
+        assert_eq!(repo.git(&["log", "-1", "--format=%s"]).trim(), "WIP: before rebase");
+        assert!(repo.git(&["status", "--porcelain"]).is_empty());
+
+        undo_wip_snapshot(&repo.path).unwrap();
+
+        assert_eq!(repo.head(), before);
+        assert_eq!(repo.git(&["status", "--porcelain", "-uall"]), status);
+        let readme = std::fs::read_to_string(repo.path.join("README.md")).unwrap();
+        assert_eq!(readme, "# changed\n");
+    }
+
+    #[test]
+    fn wip_snapshot_needs_changes_and_undo_needs_a_snapshot() {
+        let repo = TestRepo::new();
+        assert!(matches!(
+            wip_snapshot(&repo.path, "nothing"),
+            Err(GitError::NothingToSnapshot)
+        ));
+
+        let head = repo.head();
+        assert!(matches!(
+            undo_wip_snapshot(&repo.path),
+            Err(GitError::NotWipSnapshot(sha)) if head.starts_with(&sha)
+        ));
+        assert_eq!(repo.head(), head);
+    }
+
+    #[test]
+    fn aliases_are_parsed_into_name_and_command() {
+        let output = "alias.co checkout\n\
+                      alias.lg log --graph --oneline --decorate\n\
+                      alias.wip !git add -A && git commit -m \"wip\"\n\
+                      alias.empty\n";
+
+        assert_eq!(
+            parse_aliases(output),
+            vec![
+                ("co".to_string(), "checkout".to_string()),
+                ("lg".to_string(), "log --graph --oneline --decorate".to_string()),
+                ("wip".to_string(), "!git add -A && git commit -m \"wip\"".to_string()),
+                ("empty".to_string(), String::new()),
+            ]
+        );
+        assert!(parse_aliases("").is_empty());
+    }
+
+    #[test]
+    fn list_aliases_reads_repo_config() {
+        let repo = TestRepo::new();
+        repo.git(&["config", "alias.st", "status --short"]);
+
+        let aliases = list_aliases(&repo.path).unwrap();
+        assert!(aliases.contains(&("st".to_string(), "status --short".to_string())));
+    }
+
+    #[test]
+    fn relative_time_picks_largest_unit() {
+        assert_eq!(relative_time(-30), "just now");
+        assert_eq!(relative_time(59), "just now");
+        assert_eq!(relative_time(5 * 60), "5m ago");
+        assert_eq!(relative_time(2 * 3600 + 59 * 60), "2h ago");
+        assert_eq!(relative_time(3 * 86400), "3d ago");
+        assert_eq!(relative_time(15 * 86400), "2w ago");
+        assert_eq!(relative_time(90 * 86400), "3mo ago");
+        assert_eq!(relative_time(800 * 86400), "2y ago");
+    }
+
+    #[test]
+    fn main_head_follows_main_checkout_not_worktrees() {
+        let repo = TestRepo::new();
+        let head = main_head(&repo.path).unwrap();
+        assert_eq!(head.branch.as_deref(), Some("main"));
+        assert_eq!(head.sha, Some(repo.head()));
+        assert!(!head.detached);
+
+        // A worktree on another branch doesn't change what the main checkout reports
+        let wt_path = repo.root().join("wt");
+        repo.git(&["worktree", "add", "-b", "feature", &wt_path.to_string_lossy()]);
+        test_utils::git_in(&wt_path, &["commit", "--allow-empty", "-m", "Work"]);
+        assert_eq!(main_head(&wt_path).unwrap(), head);
+
+        repo.git(&["switch", "-c", "release"]);
+        let sha = repo.commit_file("CHANGELOG.md", "v1\n", "Release");
+        let switched = main_head(&repo.path).unwrap();
+        assert_eq!(switched.branch.as_deref(), Some("release"));
+        assert_eq!(switched.sha, Some(sha));
+
+        repo.git(&["checkout", "--detach"]);
+        let detached = main_head(&repo.path).unwrap();
+        assert_eq!(detached.branch, None);
+        assert!(detached.detached);
+    }
+
+    #[test]
+    fn can_fast_forward_only_when_target_has_not_diverged() {
+        let repo = TestRepo::new();
+        repo.git(&["switch", "-c", "feature"]);
+        repo.commit_file("feature.txt", "feature\n", "Add feature");
+
+        assert!(can_fast_forward(&repo.path, "main").unwrap());
+        assert!(can_fast_forward(&repo.path, "feature").unwrap());
+
+        repo.git(&["switch", "main"]);
+        let main_sha = repo.commit_file("main.txt", "main\n", "Move main on");
+        repo.git(&["switch", "feature"]);
+        let feature_sha = repo.head();
+
+        assert!(!can_fast_forward(&repo.path, "main").unwrap());
+        // Checking leaves both branches where they were
+        assert_eq!(repo.rev_parse("main"), main_sha);
+        assert_eq!(repo.rev_parse("feature"), feature_sha);
+
+        assert!(matches!(
+            can_fast_forward(&repo.path, "missing"),
+            Err(GitError::BranchNotFound(_))
+        ));
+    }
+
+    /// Records commands instead of running them and replies with canned stdout
+    struct MockGit {
+        stdout: &'static str,
+        commands: std::cell::RefCell<Vec<GitCommand>>,
+    }
+
+    impl GitRunner for MockGit {
+        fn run_with_progress(
+            &self,
+            _repo_path: &Path,
+            command: &GitCommand,
+            _on_progress: &mut dyn FnMut(GitProgress),
+        ) -> Result<GitOutput, GitError> {
+            self.commands.borrow_mut().push(command.clone());
+            Ok(GitOutput {
+                stdout: self.stdout.to_string(),
+                stderr: String::new(),
+            })
+        }
+    }
+
+    #[test]
+    fn allowlisted_command_runs_with_guards() {
+        let runner = MockGit {
+            stdout: "## main\n",
+            commands: Default::default(),
+        };
+        let allowed = vec!["status".to_string(), "log".to_string()];
+        let args = vec!["status".to_string(), "--short".to_string(), "--branch".to_string()];
+
+        let stdout =
+            run_allowlisted_with(&runner, Path::new("/tmp"), &args, &allowed, &mut |_| {})
+                .unwrap();
+
+        assert_eq!(stdout, "## main\n");
+        let commands = runner.commands.borrow();
+        assert_eq!(commands[0].args, args);
+        assert_eq!(commands[0].timeout, Some(NETWORK_TIMEOUT));
+        assert!(commands[0].env.contains(&("GIT_TERMINAL_PROMPT".to_string(), "0".to_string())));
+    }
+
+    #[test]
+    fn allowlisted_transfers_ask_for_progress() {
+        let runner = MockGit {
+            stdout: "",
+            commands: Default::default(),
+        };
+        let allowed = vec!["fetch".to_string()];
+        for args in [vec!["fetch", "origin"], vec!["fetch", "--progress", "origin"]] {
+            let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+            run_allowlisted_with(&runner, Path::new("/tmp"), &args, &allowed, &mut |_| {})
+                .unwrap();
+        }
+
+        let commands = runner.commands.borrow();
+        assert_eq!(commands[0].args, vec!["fetch", "--progress", "origin"]);
+        assert_eq!(commands[1].args, vec!["fetch", "--progress", "origin"]);
+    }
+
+    #[test]
+    fn commands_off_the_allowlist_are_rejected_without_running() {
+        let runner = MockGit {
+            stdout: "",
+            commands: Default::default(),
+        };
+        let allowed = vec!["status".to_string(), "diff".to_string()];
+        let rejected: [&[&str]; 5] = [
+            &["push", "--force"],
+            &["-c", "core.pager=rm", "status"],
+            &["Status"],
+            &[],
+            &["diff", "--output=/tmp/x"],
+        ];
+
+        for args in rejected {
+            let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+            let err = run_allowlisted_with(&runner, Path::new("/tmp"), &args, &allowed, &mut |_| {})
+                .unwrap_err();
+            assert_eq!(err.code(), "git.command_not_allowed", "{:?} should be rejected", args);
+        }
+        assert!(runner.commands.borrow().is_empty());
+
+        // The real runner, against a real repository
+        let repo = TestRepo::new();
+        let args = vec!["log".to_string(), "--format=%s".to_string()];
+        let log = run_allowlisted(&repo.path, &args, &["log".to_string()], &mut |_| {}).unwrap();
+        assert_eq!(log, "Initial commit\n");
+    }
+
+    /// Fails every command, like a remote that can't be reached
+    struct OfflineGit;
+
+    impl GitRunner for OfflineGit {
+        fn run_with_progress(
+            &self,
+            _repo_path: &Path,
+            command: &GitCommand,
+            _on_progress: &mut dyn FnMut(GitProgress),
+        ) -> Result<GitOutput, GitError> {
+            Err(GitError::Timeout(command.args.join(" ")))
+        }
+    }
+
+    #[test]
+    fn remote_branch_exists_parses_ls_remote_output() {
+        let repo = TestRepo::new();
+        repo.git(&["remote", "add", "origin", "https://example.com/repo.git"]);
+        repo.git(&["checkout", "-b", "feature"]);
+        let ls_remote = |stdout: &'static str| MockGit {
+            stdout,
+            commands: Default::default(),
+        };
+
+        let present = ls_remote("1111111111111111111111111111111111111111\trefs/heads/feature\n");
+        assert!(remote_branch_exists_with(&present, &repo.path, "origin").unwrap());
+        let commands = present.commands.borrow();
+        assert_eq!(commands[0].args, vec!["ls-remote", "--heads", "origin", "refs/heads/feature"]);
+        assert_eq!(commands[0].timeout, Some(NETWORK_TIMEOUT));
+
+        let absent = ls_remote("");
+        assert!(!remote_branch_exists_with(&absent, &repo.path, "origin").unwrap());
+        // A branch that only shares the trailing name doesn't count
+        let other = ls_remote("2222222222222222222222222222222222222222\trefs/heads/a/feature\n");
+        assert!(!remote_branch_exists_with(&other, &repo.path, "origin").unwrap());
+
+        assert!(matches!(
+            remote_branch_exists_with(&absent, &repo.path, "upstream"),
+            Err(GitError::RemoteNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn remote_branch_exists_uses_tracking_ref_offline() {
+        let repo = TestRepo::new();
+        repo.git(&["remote", "add", "origin", "https://example.com/repo.git"]);
+        repo.git(&["checkout", "-b", "feature"]);
+
+        assert!(!remote_branch_exists_with(&OfflineGit, &repo.path, "origin").unwrap());
+
+        repo.git(&["update-ref", "refs/remotes/origin/feature", "HEAD"]);
+        assert!(remote_branch_exists_with(&OfflineGit, &repo.path, "origin").unwrap());
+    }
+
+    #[test]
+    fn prune_remote_runs_guarded_prune_and_reports_refs() {
+        let repo = TestRepo::new();
+        repo.git(&["remote", "add", "origin", "https://example.com/repo.git"]);
+        let runner = MockGit {
+            stdout: "Pruning origin\nURL: https://example.com/repo.git\n \
+                     * [pruned] origin/feature\n * [pruned] origin/fix/login\n",
+            commands: Default::default(),
+        };
+
+        let pruned = prune_remote_with(&runner, &repo.path, "origin").unwrap();
+
+        assert_eq!(pruned, vec!["origin/feature", "origin/fix/login"]);
+        let commands = runner.commands.borrow();
+        assert_eq!(commands.len(), 1);
+        assert_eq!(commands[0].args, vec!["remote", "prune", "origin"]);
+        assert!(commands[0]
+            .env
+            .contains(&("GIT_TERMINAL_PROMPT".to_string(), "0".to_string())));
+        assert_eq!(commands[0].timeout, Some(NETWORK_TIMEOUT));
+    }
+
+    #[test]
+    fn prune_remote_rejects_unknown_remote() {
+        let repo = TestRepo::new();
+        let runner = MockGit {
+            stdout: "",
+            commands: Default::default(),
+        };
+
+        let result = prune_remote_with(&runner, &repo.path, "--all");
+
+        assert!(matches!(result, Err(GitError::RemoteNotFound(_))));
+        assert!(runner.commands.borrow().is_empty());
+    }
+
+    #[test]
+    fn parse_pruned_refs_ignores_other_lines() {
+        assert!(parse_pruned_refs("Pruning origin\nURL: git@host:repo.git\n").is_empty());
+        assert_eq!(
+            parse_pruned_refs(" * [pruned] upstream/old\n * [would prune] upstream/other\n"),
+            vec!["upstream/old"]
+        );
+    }
+
+    #[test]
+    fn parses_progress_lines() {
+        let cases = [
+            (
+                "Receiving objects:  45% (450/1000), 1.20 MiB | 512.00 KiB/s",
+                Some(("Receiving objects", 45)),
+            ),
+            ("remote: Counting objects: 100% (10/10), done.", Some(("Counting objects", 100))),
+            ("Resolving deltas:   0% (0/12)", Some(("Resolving deltas", 0))),
+            ("Updating files:  12% (120/1000)", Some(("Updating files", 12))),
+            (
+                "Writing objects: 100% (3/3), 300 bytes | 300.00 KiB/s, done.",
+                Some(("Writing objects", 100)),
+            ),
+            ("To github.com:shkm/shellflow.git", None),
+            (" - [deleted]         feature", None),
+            ("remote: Enumerating objects: 5, done.", None),
+        ];
+
+        for (line, expected) in cases {
+            let expected = expected.map(|(phase, percent)| (phase.to_string(), percent));
+            assert_eq!(parse_progress(line), expected, "{}", line);
+        }
+    }
+
+    #[test]
+    fn stderr_progress_is_split_on_carriage_returns() {
+        let stderr = "Receiving objects:  10% (1/10)\r\
+                      Receiving objects:  10% (1/10), 1.00 KiB | 1.00 MiB/s\r\
+                      Receiving objects: 100% (10/10), done.\n\
+                      Resolving deltas: 100% (2/2), done.\n\
+                      error: something else\n";
+        let (tx, rx) = std::sync::mpsc::channel();
+
+        let all = read_stderr_in_background(Some(std::io::Cursor::new(stderr)), tx)
+            .join()
+            .unwrap();
+
+        let steps: Vec<(String, u8)> = rx.try_iter().collect();
+        assert_eq!(
+            steps,
+            vec![
+                ("Receiving objects".to_string(), 10),
+                ("Receiving objects".to_string(), 100),
+                ("Resolving deltas".to_string(), 100),
+            ]
+        );
+        assert_eq!(all, stderr);
+    }
+
+    #[test]
+    fn validate_branch_name_empty() {
+        let result = validate_branch_name("");
+        assert_eq!(result, Some("Branch name cannot be empty".to_string()));
+    }
+
+    #[test]
+    fn validate_branch_name_too_long() {
+        let long_name = "a".repeat(251);
+        let result = validate_branch_name(&long_name);
+        assert_eq!(
+            result,
+            Some("Branch name is too long (max 250 characters)".to_string())
+        );
+    }
+
+    #[test]
+    fn validate_branch_name_starts_with_dot() {
+        let result = validate_branch_name(".hidden");
+        assert_eq!(result, Some("Branch name cannot start with '.'".to_string()));
     }
 
     #[test]
@@ -1237,4 +3308,699 @@ mod tests {
         let result = validate_branch_name(&name);
         assert_eq!(result, None);
     }
+
+    #[test]
+    fn create_worktree_from_default_branch() {
+        let repo = TestRepo::new();
+        let main_sha = repo.head();
+        repo.git(&["checkout", "-b", "feature"]);
+        repo.commit_file("feature.txt", "feature\n", "Feature commit");
+
+        let wt_path = repo.root().join("wt-auto");
+        create_worktree(&repo.path, &wt_path, "from-auto", &BaseBranch::Mode(BaseBranchMode::Auto)).unwrap();
+
+        assert_eq!(repo.rev_parse("from-auto"), main_sha);
+    }
+
+    #[test]
+    fn create_worktree_from_current_branch() {
+        let repo = TestRepo::new();
+        repo.git(&["checkout", "-b", "feature"]);
+        let feature_sha = repo.commit_file("feature.txt", "feature\n", "Feature commit");
+
+        let wt_path = repo.root().join("wt-current");
+        create_worktree(&repo.path, &wt_path, "from-current", &BaseBranch::Mode(BaseBranchMode::Current)).unwrap();
+
+        assert_eq!(repo.rev_parse("from-current"), feature_sha);
+    }
+
+    #[test]
+    fn create_worktree_with_explicit_start_point() {
+        let repo = TestRepo::new();
+        let first = repo.head();
+        repo.commit_file("second.txt", "second\n", "Second commit");
+
+        let wt_path = repo.root().join("wt-start");
+        create_worktree_with_start(&repo.path, &wt_path, "from-first", Some(&first)).unwrap();
+
+        assert_eq!(repo.rev_parse("from-first"), first);
+        assert_eq!(test_utils::git_in(&wt_path, &["rev-parse", "HEAD"]).trim(), first);
+    }
+
+    #[test]
+    fn create_worktree_with_start_defaults_to_head() {
+        let repo = TestRepo::new();
+        let head = repo.commit_file("second.txt", "second\n", "Second commit");
+
+        let wt_path = repo.root().join("wt-head");
+        create_worktree_with_start(&repo.path, &wt_path, "from-head", None).unwrap();
+
+        assert_eq!(repo.rev_parse("from-head"), head);
+    }
+
+    #[test]
+    fn create_worktree_rejects_unresolvable_start_point() {
+        let repo = TestRepo::new();
+        let wt_path = repo.root().join("wt-bad");
+
+        let err = create_worktree_with_start(&repo.path, &wt_path, "bad", Some("no-such-ref"))
+            .unwrap_err();
+
+        assert!(matches!(err, GitError::InvalidStartPoint(ref s) if s == "no-such-ref"));
+        assert!(!wt_path.exists());
+        assert!(!branch_exists(&repo.path, "bad").unwrap());
+    }
+
+    #[test]
+    fn resolve_worktree_base_follows_policy() {
+        let repo = TestRepo::new();
+        repo.git(&["checkout", "-b", "feature"]);
+
+        let auto = resolve_worktree_base(&repo.path, &BaseBranch::Mode(BaseBranchMode::Auto)).unwrap();
+        let current = resolve_worktree_base(&repo.path, &BaseBranch::Mode(BaseBranchMode::Current)).unwrap();
+
+        assert_eq!(auto, "main");
+        assert_eq!(current, "feature");
+    }
+
+    #[test]
+    fn auto_base_starts_from_the_fetched_default_branch() {
+        let repo = TestRepo::new();
+        let local_head = repo.head();
+        let origin = repo.root().join("origin.git").to_string_lossy().to_string();
+        repo.git(&["clone", "--bare", ".", &origin]);
+        repo.git(&["remote", "add", "origin", &origin]);
+        // Someone else moves origin ahead; the local main doesn't know yet
+        let other = repo.root().join("other");
+        test_utils::git_in(repo.root(), &["clone", &origin, &other.to_string_lossy()]);
+        let identity = ["-c", "user.name=Other", "-c", "user.email=other@shellflow.dev"];
+        let commit = [&identity[..], &["commit", "--allow-empty", "-m", "Upstream work"]].concat();
+        test_utils::git_in(&other, &commit);
+        test_utils::git_in(&other, &["push", "origin", "main"]);
+        let upstream_head = test_utils::git_in(&other, &["rev-parse", "HEAD"]).trim().to_string();
+
+        let auto = BaseBranch::Mode(BaseBranchMode::Auto);
+        let current = BaseBranch::Mode(BaseBranchMode::Current);
+        assert_eq!(fetched_worktree_base(&repo.path, &current), None);
+        let fetched = fetched_worktree_base(&repo.path, &auto);
+        assert_eq!(fetched.as_deref(), Some(upstream_head.as_str()));
+
+        let fresh = repo.root().join("fresh");
+        create_worktree_with_start(&repo.path, &fresh, "fresh", fetched.as_deref()).unwrap();
+        let current_wt = repo.root().join("current");
+        create_worktree(&repo.path, &current_wt, "current", &current).unwrap();
+
+        assert_eq!(test_utils::git_in(&fresh, &["rev-parse", "HEAD"]).trim(), upstream_head);
+        assert_eq!(test_utils::git_in(&current_wt, &["rev-parse", "HEAD"]).trim(), local_head);
+        assert_eq!(repo.rev_parse("main"), local_head, "the local branch is left alone");
+        let upstream = repo.git(&["for-each-ref", "--format=%(upstream)", "refs/heads/fresh"]);
+        assert!(upstream.trim().is_empty(), "the new branch must not track main");
+
+        // Offline, the local branch is used
+        assert_eq!(fetched_start_point_with(&OfflineGit, &repo.path, "main"), None);
+    }
+
+    #[test]
+    fn stage_all_stages_everything() {
+        let repo = TestRepo::new();
+        repo.write("README.md", "changed\n");
+        repo.write("new.txt", "new\n");
+
+        stage_all(&repo.path).unwrap();
+
+        let staged = repo.git(&["diff", "--cached", "--name-only"]);
+        assert_eq!(staged.lines().collect::<Vec<_>>(), vec!["README.md", "new.txt"]);
+        assert!(repo.git(&["diff", "--name-only"]).is_empty());
+    }
+
+    #[test]
+    fn unstage_all_keeps_working_tree() {
+        let repo = TestRepo::new();
+        repo.write("README.md", "changed\n");
+        repo.git(&["add", "README.md"]);
+
+        unstage_all(&repo.path).unwrap();
+
+        assert!(repo.git(&["diff", "--cached", "--name-only"]).is_empty());
+        assert_eq!(repo.git(&["diff", "--name-only"]).trim(), "README.md");
+    }
+
+    #[test]
+    fn discard_all_returns_clean_tree() {
+        let repo = TestRepo::new();
+        repo.write("README.md", "changed\n");
+        repo.write("staged.txt", "staged\n");
+        repo.git(&["add", "staged.txt"]);
+        repo.write("untracked.txt", "untracked\n");
+
+        discard_all(&repo.path, true).unwrap();
+
+        assert!(repo.git(&["status", "--porcelain"]).is_empty());
+        assert!(!repo.path.join("untracked.txt").exists());
+    }
+
+    #[test]
+    fn discard_all_can_keep_untracked_files() {
+        let repo = TestRepo::new();
+        repo.write("README.md", "changed\n");
+        repo.write("untracked.txt", "untracked\n");
+
+        discard_all(&repo.path, false).unwrap();
+
+        assert_eq!(repo.git(&["status", "--porcelain"]).trim(), "?? untracked.txt");
+    }
+
+    /// A superproject with `libs/sub` checked out as a submodule of a second repo.
+    fn repo_with_submodule() -> (TestRepo, TestRepo) {
+        let upstream = TestRepo::new();
+        let repo = TestRepo::new();
+        let url = upstream.path.to_str().unwrap();
+        repo.git(&["-c", "protocol.file.allow=always", "submodule", "add", url, "libs/sub"]);
+        repo.git(&["commit", "-m", "Add submodule"]);
+        let sub = repo.path.join("libs/sub");
+        test_utils::git_in(&sub, &["config", "user.name", "Shellflow Test"]);
+        test_utils::git_in(&sub, &["config", "user.email", "test@shellflow.dev"]);
+        test_utils::git_in(&sub, &["config", "commit.gpgsign", "false"]);
+        (repo, upstream)
+    }
+
+    #[test]
+    fn changed_files_reports_submodule_with_new_commits() {
+        let (repo, _upstream) = repo_with_submodule();
+        let sub = repo.path.join("libs/sub");
+        test_utils::write_in(&sub, "lib.txt", "lib\n");
+        test_utils::git_in(&sub, &["add", "lib.txt"]);
+        test_utils::git_in(&sub, &["commit", "-m", "Move pointer"]);
+
+        let changes = get_changed_files(&repo.path, true, false).unwrap();
+
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].path, "libs/sub");
+        assert!(matches!(changes[0].status, FileStatus::Modified));
+        let submodule = changes[0].submodule.as_ref().expect("submodule details");
+        assert!(submodule.new_commits);
+        assert!(!submodule.modified_content);
+        assert!(!submodule.untracked_content);
+    }
+
+    #[test]
+    fn changed_files_reports_dirty_submodule() {
+        let (repo, _upstream) = repo_with_submodule();
+        let sub = repo.path.join("libs/sub");
+        test_utils::write_in(&sub, "README.md", "dirty\n");
+        test_utils::write_in(&sub, "scratch.txt", "scratch\n");
+
+        let changes = get_changed_files(&repo.path, true, false).unwrap();
+
+        assert_eq!(changes.len(), 1);
+        let submodule = changes[0].submodule.as_ref().expect("submodule details");
+        assert!(!submodule.new_commits);
+        assert!(submodule.modified_content);
+        assert!(submodule.untracked_content);
+    }
+
+    #[test]
+    fn changed_files_can_exclude_submodules() {
+        let (repo, _upstream) = repo_with_submodule();
+        let sub = repo.path.join("libs/sub");
+        test_utils::write_in(&sub, "lib.txt", "lib\n");
+        test_utils::git_in(&sub, &["add", "lib.txt"]);
+        test_utils::git_in(&sub, &["commit", "-m", "Move pointer"]);
+        repo.write("README.md", "changed\n");
+
+        let changes = get_changed_files(&repo.path, false, false).unwrap();
+
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].path, "README.md");
+        assert!(changes[0].submodule.is_none());
+    }
+
+    #[test]
+    fn changed_files_lists_ignored_only_when_asked() {
+        let repo = TestRepo::new();
+        repo.commit_file(".gitignore", ".env\ntarget/\n", "Ignore env and build output");
+        repo.write(".env", "SECRET=1\n");
+        repo.write("target/debug/app", "binary\n");
+        repo.write("README.md", "changed\n");
+
+        let paths = |include_ignored| -> Vec<(String, FileStatus)> {
+            get_changed_files(&repo.path, false, include_ignored)
+                .unwrap()
+                .into_iter()
+                .map(|f| (f.path, f.status))
+                .collect()
+        };
+
+        assert_eq!(paths(false), vec![("README.md".to_string(), FileStatus::Modified)]);
+        assert_eq!(
+            paths(true),
+            vec![
+                ("README.md".to_string(), FileStatus::Modified),
+                (".env".to_string(), FileStatus::Ignored),
+                ("target".to_string(), FileStatus::Ignored),
+            ]
+        );
+    }
+
+    #[test]
+    fn scoped_changed_files_agree_with_a_full_scan() {
+        let repo = TestRepo::new();
+        for dir in 0..10 {
+            for file in 0..30 {
+                repo.write(&format!("src/m{dir}/f{file}.rs"), "fn a() {}\n");
+            }
+        }
+        repo.git(&["add", "."]);
+        repo.git(&["commit", "-m", "Add sources"]);
+
+        // Each round edits a few files, then refreshes only those paths
+        let rounds: Vec<Vec<(&str, Option<&str>)>> = vec![
+            vec![("src/m1/f1.rs", Some("fn b() {}\n")), ("README.md", Some("changed\n"))],
+            vec![("src/new/a.rs", Some("new\n")), ("src/new/b.rs", Some("new\n"))],
+            // Reverting an edit must drop it from the list
+            vec![("src/m1/f1.rs", Some("fn a() {}\n")), ("src/m2/f3.rs", Some("\n"))],
+            vec![("src/new", None)],
+        ];
+
+        let mut files = get_changed_files(&repo.path, true, false).unwrap();
+        for round in rounds {
+            let mut paths = Vec::new();
+            for (file, content) in round {
+                match content {
+                    Some(content) => repo.write(file, content),
+                    // Staging changes the status without touching the file
+                    None => {
+                        repo.git(&["add", file]);
+                    }
+                }
+                paths.push(repo.path.join(file));
+            }
+
+            files = get_changed_files_scoped(&repo.path, &paths, &files, true, false).unwrap();
+            assert_eq!(files, get_changed_files(&repo.path, true, false).unwrap());
+        }
+        let statuses: Vec<_> = files.iter().map(|f| (f.path.as_str(), &f.status)).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ("src/new/a.rs", &FileStatus::Added),
+                ("src/new/b.rs", &FileStatus::Added),
+                ("README.md", &FileStatus::Modified),
+                ("src/m2/f3.rs", &FileStatus::Modified),
+            ]
+        );
+    }
+
+    #[test]
+    fn scoped_changed_files_rescan_for_git_dir_changes() {
+        let repo = TestRepo::new();
+        repo.write("README.md", "changed\n");
+        let stale = Vec::new();
+        let index = repo.path.join(".git/index");
+
+        let files = get_changed_files_scoped(&repo.path, &[index], &stale, false, false).unwrap();
+
+        assert_eq!(files, get_changed_files(&repo.path, false, false).unwrap());
+        assert_eq!(files.len(), 1);
+    }
+
+    /// A linked worktree on `feature` with one commit changing `file`
+    fn feature_worktree(repo: &TestRepo, file: &str, content: &str) -> PathBuf {
+        let worktree = repo.root().join("feature");
+        repo.git(&["worktree", "add", "-b", "feature", &worktree.to_string_lossy()]);
+        test_utils::write_in(&worktree, file, content);
+        test_utils::git_in(&worktree, &["commit", "-am", "Feature work"]);
+        worktree
+    }
+
+    #[test]
+    fn merge_worktree_fast_forwards_when_the_target_has_not_moved() {
+        let repo = TestRepo::new();
+        let worktree = feature_worktree(&repo, "README.md", "feature\n");
+
+        let outcome = merge_worktree(&repo.path, "feature", "main").unwrap();
+
+        assert_eq!(outcome, MergeOutcome::FastForward);
+        assert_eq!(repo.rev_parse("main"), repo.rev_parse("feature"));
+        assert!(worktree.exists(), "the worktree is kept");
+        assert_eq!(merge_worktree(&repo.path, "feature", "main").unwrap(), MergeOutcome::UpToDate);
+    }
+
+    #[test]
+    fn merge_worktree_creates_a_merge_commit_for_diverged_branches() {
+        let repo = TestRepo::new();
+        feature_worktree(&repo, "README.md", "feature\n");
+        repo.commit_file("main.txt", "main\n", "Main work");
+
+        let outcome = merge_worktree(&repo.path, "feature", "main").unwrap();
+
+        assert_eq!(outcome, MergeOutcome::Merged);
+        let parents = repo.git(&["rev-list", "--parents", "-n", "1", "main"]);
+        assert_eq!(parents.split_whitespace().count(), 3);
+        assert_eq!(std::fs::read_to_string(repo.path.join("README.md")).unwrap(), "feature\n");
+    }
+
+    #[test]
+    fn merge_worktree_switches_the_main_checkout_back() {
+        let repo = TestRepo::new();
+        repo.git(&["branch", "release"]);
+        feature_worktree(&repo, "README.md", "feature\n");
+
+        let outcome = merge_worktree(&repo.path, "feature", "release").unwrap();
+
+        assert_eq!(outcome, MergeOutcome::FastForward);
+        assert_eq!(repo.rev_parse("release"), repo.rev_parse("feature"));
+        assert_eq!(repo.git(&["branch", "--show-current"]).trim(), "main");
+
+        let head = repo.head();
+        repo.git(&["checkout", "--detach", &head]);
+        repo.commit_file("main.txt", "main\n", "Detached work");
+        let detached = repo.head();
+        merge_worktree(&repo.path, "feature", "release").unwrap();
+        assert_eq!(repo.head(), detached);
+        assert!(repo.git(&["branch", "--show-current"]).trim().is_empty());
+    }
+
+    #[test]
+    fn merge_worktree_reports_conflicted_files() {
+        let repo = TestRepo::new();
+        feature_worktree(&repo, "README.md", "feature\n");
+        repo.commit_file("README.md", "main\n", "Main work");
+
+        let outcome = merge_worktree(&repo.path, "feature", "main").unwrap();
+
+        assert_eq!(outcome, MergeOutcome::Conflict { files: vec!["README.md".to_string()] });
+        assert_eq!(operation_in_progress(&repo.path).unwrap(), Some(Operation::Merge));
+    }
+
+    #[test]
+    fn ahead_behind_counts_commits_on_each_side() {
+        let repo = TestRepo::new();
+        repo.git(&["checkout", "-b", "feature"]);
+        assert_eq!(ahead_behind(&repo.path, "main").unwrap(), (0, 0));
+
+        repo.commit_file("a.txt", "a\n", "Feature 1");
+        repo.commit_file("b.txt", "b\n", "Feature 2");
+        repo.git(&["checkout", "main"]);
+        repo.commit_file("c.txt", "c\n", "Main 1");
+        repo.git(&["checkout", "feature"]);
+
+        assert_eq!(ahead_behind(&repo.path, "main").unwrap(), (2, 1));
+        assert_eq!(ahead_behind(&repo.path, "feature").unwrap(), (0, 0));
+    }
+
+    #[test]
+    fn ahead_behind_reports_a_missing_base() {
+        let repo = TestRepo::new();
+
+        let error = ahead_behind(&repo.path, "develop").unwrap_err();
+
+        assert!(matches!(error, GitError::BranchNotFound(ref name) if name == "develop"));
+    }
+
+    #[test]
+    fn staged_summary_groups_by_extension() {
+        let repo = TestRepo::new();
+        repo.commit_file("src/app.ts", "a\nb\nc\n", "Add app");
+        repo.write("src/app.ts", "a\nB\nc\nd\n");
+        repo.write("src/util.TS", "x\n");
+        repo.write("README.md", "# changed\nmore\n");
+        repo.write("Makefile", "all:\n");
+        repo.write("notes.txt", "unstaged\n");
+        repo.git(&["add", "src", "README.md", "Makefile"]);
+
+        let summary = staged_summary(&repo.path).unwrap();
+
+        assert_eq!(summary.files, 4);
+        assert_eq!(summary.insertions, 2 + 1 + 2 + 1);
+        assert_eq!(summary.deletions, 1 + 1);
+        let keys: Vec<&str> = summary.by_type.keys().map(String::as_str).collect();
+        assert_eq!(keys, vec!["", "md", "ts"]);
+        assert_eq!(
+            summary.by_type["ts"],
+            TypeSummary {
+                files: 2,
+                insertions: 3,
+                deletions: 1
+            }
+        );
+        assert_eq!(summary.by_type[""].files, 1);
+    }
+
+    #[test]
+    fn staged_summary_is_empty_without_staged_changes() {
+        let repo = TestRepo::new();
+        repo.write("README.md", "unstaged\n");
+
+        assert_eq!(staged_summary(&repo.path).unwrap(), StagedSummary::default());
+    }
+
+    #[test]
+    fn config_fingerprint_tracks_config_and_head() {
+        let repo = TestRepo::new();
+        let initial = config_fingerprint(&repo.path).unwrap();
+        assert_eq!(config_fingerprint(&repo.path).unwrap(), initial);
+
+        // Unrelated config doesn't matter
+        repo.git(&["config", "color.ui", "never"]);
+        assert_eq!(config_fingerprint(&repo.path).unwrap(), initial);
+
+        repo.git(&["config", "user.name", "Someone Else"]);
+        let renamed = config_fingerprint(&repo.path).unwrap();
+        assert_ne!(renamed, initial);
+
+        repo.git(&["remote", "add", "origin", "https://example.com/repo.git"]);
+        let with_remote = config_fingerprint(&repo.path).unwrap();
+        assert_ne!(with_remote, renamed);
+
+        repo.commit_file("new.txt", "new\n", "Move HEAD");
+        assert_ne!(config_fingerprint(&repo.path).unwrap(), with_remote);
+    }
+
+    #[test]
+    fn export_patch_from_committed_change_applies_cleanly() {
+        let repo = TestRepo::new();
+        repo.git(&["checkout", "-b", "feature"]);
+        repo.commit_file("src/lib.rs", "pub fn answer() -> u32 { 42 }\n", "Add lib");
+        repo.commit_file("README.md", "# feature\n", "Update readme");
+        let patch = repo.root().join("feature.patch");
+
+        export_patch(&repo.path, Some("main"), &patch).unwrap();
+
+        repo.git(&["checkout", "main"]);
+        let patch_path = patch.to_str().unwrap();
+        repo.git(&["apply", "--check", patch_path]);
+        repo.git(&["apply", patch_path]);
+        assert_eq!(
+            std::fs::read_to_string(repo.path.join("src/lib.rs")).unwrap(),
+            "pub fn answer() -> u32 { 42 }\n"
+        );
+        assert_eq!(std::fs::read_to_string(repo.path.join("README.md")).unwrap(), "# feature\n");
+    }
+
+    #[test]
+    fn export_patch_without_changes_is_an_error() {
+        let repo = TestRepo::new();
+        let patch = repo.root().join("empty.patch");
+
+        let result = export_patch(&repo.path, None, &patch);
+
+        assert!(matches!(result, Err(GitError::EmptyPatch)));
+        assert!(!patch.exists());
+    }
+
+    #[test]
+    fn apply_patch_applies_generated_patch() {
+        let repo = TestRepo::new();
+        repo.git(&["checkout", "-b", "feature"]);
+        repo.commit_file("notes.txt", "hello\n", "Add notes");
+        let patch = repo.root().join("feature.patch");
+        export_patch(&repo.path, Some("main"), &patch).unwrap();
+        repo.git(&["checkout", "main"]);
+
+        let checked = apply_patch(&repo.path, &patch, true).unwrap();
+        assert!(!checked.applied);
+        assert_eq!(checked.files, vec!["notes.txt"]);
+        assert!(!repo.path.join("notes.txt").exists());
+
+        let applied = apply_patch(&repo.path, &patch, false).unwrap();
+        assert!(applied.applied);
+        assert_eq!(std::fs::read_to_string(repo.path.join("notes.txt")).unwrap(), "hello\n");
+    }
+
+    #[test]
+    fn apply_patch_reports_conflicting_files() {
+        let repo = TestRepo::new();
+        repo.git(&["checkout", "-b", "feature"]);
+        repo.commit_file("README.md", "# feature\n", "Change readme");
+        let patch = repo.root().join("feature.patch");
+        export_patch(&repo.path, Some("main"), &patch).unwrap();
+        repo.git(&["checkout", "main"]);
+        repo.commit_file("README.md", "# diverged\n", "Diverge readme");
+
+        let result = apply_patch(&repo.path, &patch, false);
+
+        match result {
+            Err(GitError::PatchDoesNotApply(files)) => assert_eq!(files, vec!["README.md"]),
+            other => panic!("expected PatchDoesNotApply, got {:?}", other),
+        }
+        assert_eq!(std::fs::read_to_string(repo.path.join("README.md")).unwrap(), "# diverged\n");
+    }
+
+    fn change(path: &str, status: FileStatus) -> FileChange {
+        FileChange {
+            path: path.to_string(),
+            status,
+            insertions: None,
+            deletions: None,
+            submodule: None,
+        }
+    }
+
+    #[test]
+    fn change_tree_nests_files_and_aggregates_counts() {
+        let tree = build_change_tree(vec![
+            change("src/ui/button.tsx", FileStatus::Modified),
+            change("README.md", FileStatus::Modified),
+            change("src/main.rs", FileStatus::Added),
+            change("src/ui/old.tsx", FileStatus::Deleted),
+            change("docs/guide.md", FileStatus::Untracked),
+        ]);
+
+        assert_eq!(tree.counts.total(), 5);
+        assert_eq!(tree.counts.modified, 2);
+        assert_eq!(tree.files.len(), 1);
+        assert_eq!(tree.files[0].path, "README.md");
+
+        let names: Vec<&str> = tree.directories.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(names, vec!["docs", "src"]);
+
+        let src = &tree.directories[1];
+        assert_eq!(src.path, "src");
+        assert_eq!(
+            src.counts,
+            crate::state::StatusCounts {
+                added: 1,
+                modified: 1,
+                deleted: 1,
+                ..Default::default()
+            }
+        );
+        assert_eq!(src.files.len(), 1);
+        assert_eq!(src.files[0].path, "src/main.rs");
+
+        let ui = &src.directories[0];
+        assert_eq!(ui.path, "src/ui");
+        assert_eq!(ui.counts.total(), 2);
+        assert_eq!(ui.counts.deleted, 1);
+        let ui_files: Vec<&str> = ui.files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(ui_files, vec!["src/ui/button.tsx", "src/ui/old.tsx"]);
+        assert!(ui.directories.is_empty());
+    }
+
+    #[test]
+    fn sort_changes_orders_by_status_then_path() {
+        let mut changes = vec![
+            change("z.txt", FileStatus::Untracked),
+            change("b.rs", FileStatus::Modified),
+            change("c.rs", FileStatus::Added),
+            change("a.rs", FileStatus::Modified),
+            change("gone.rs", FileStatus::Deleted),
+            change("a.txt", FileStatus::Untracked),
+        ];
+
+        sort_changes(&mut changes);
+
+        let order: Vec<&str> = changes.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(order, vec!["c.rs", "a.rs", "b.rs", "gone.rs", "a.txt", "z.txt"]);
+
+        // Sorting an already sorted list is a no-op
+        let before = order.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+        sort_changes(&mut changes);
+        let after: Vec<String> = changes.iter().map(|c| c.path.clone()).collect();
+        assert_eq!(before, after);
+    }
+
+    #[test]
+    fn change_tree_from_worktree() {
+        let repo = TestRepo::new();
+        repo.write("a/b/new.txt", "new\n");
+        repo.write("README.md", "changed\n");
+
+        let tree = changed_files_tree(&repo.path, false, false).unwrap();
+
+        assert_eq!(tree.counts.total(), 2);
+        assert_eq!(tree.directories[0].path, "a");
+        assert_eq!(tree.directories[0].directories[0].path, "a/b");
+        assert_eq!(tree.directories[0].directories[0].counts.untracked, 1);
+    }
+
+    #[test]
+    fn changed_files_for_mode_selects_source() {
+        let repo = TestRepo::new();
+        repo.git(&["checkout", "-b", "feature"]);
+        repo.commit_file("committed.txt", "committed\n", "Add committed file");
+        repo.write("README.md", "uncommitted\n");
+        let base = BaseBranch::Mode(BaseBranchMode::Auto);
+
+        let paths = |mode| -> Vec<String> {
+            get_changed_files_for_mode(&repo.path, mode, &base, true, false)
+                .unwrap()
+                .into_iter()
+                .map(|f| f.path)
+                .collect()
+        };
+
+        assert_eq!(paths(ChangedFilesViewMode::Uncommitted), vec!["README.md"]);
+        assert_eq!(paths(ChangedFilesViewMode::Branch), vec!["committed.txt", "README.md"]);
+    }
+
+    #[test]
+    fn error_codes_are_stable_and_unique() {
+        let errors = [
+            GitError::Git(git2::Error::from_str("boom")),
+            GitError::Io(std::io::Error::other("boom")),
+            GitError::MergeConflict("a.txt".into()),
+            GitError::BranchNotFound("main".into()),
+            GitError::UncommittedChanges,
+            GitError::EmptyPatch,
+            GitError::PatchDoesNotApply(vec!["a.txt".into()]),
+            GitError::InvalidStartPoint("nope".into()),
+            GitError::RemoteNotFound("origin".into()),
+            GitError::Timeout("fetch".into()),
+            GitError::InvalidConfigKey("email".into()),
+            GitError::CommandNotAllowed("push".into()),
+            GitError::NoDefaultBranch,
+            GitError::NothingToSnapshot,
+            GitError::NotWipSnapshot("abc1234".into()),
+            GitError::InvalidBranchName("Branch name cannot be empty".into()),
+            GitError::BranchExists("main".into()),
+        ];
+        let codes: Vec<&str> = errors.iter().map(|e| e.code()).collect();
+
+        assert_eq!(
+            codes,
+            vec![
+                "git.libgit2",
+                "git.io",
+                "git.merge_conflict",
+                "git.branch_not_found",
+                "git.uncommitted_changes",
+                "git.empty_patch",
+                "git.patch_does_not_apply",
+                "git.invalid_start_point",
+                "git.remote_not_found",
+                "git.timeout",
+                "git.invalid_config_key",
+                "git.command_not_allowed",
+                "git.no_default_branch",
+                "git.nothing_to_snapshot",
+                "git.not_wip_snapshot",
+                "git.invalid_branch_name",
+                "git.branch_exists",
+            ]
+        );
+        let unique: std::collections::HashSet<_> = codes.iter().collect();
+        assert_eq!(unique.len(), codes.len());
+    }
 }
@@ -1,16 +1,26 @@
 mod cleanup;
 mod config;
+mod diagnostics;
+mod editor;
 mod git;
 mod mappings;
 mod menu;
+mod navigation;
 mod pty;
+mod settings;
 mod state;
+mod tasks;
 mod template;
+#[cfg(test)]
+mod test_utils;
 mod theme;
+mod time;
+mod updates;
 mod watcher;
+mod windows;
 mod worktree;
 
-use config::MergeStrategy;
+use config::{BaseBranch, MergeStrategy};
 use git::{MergeFeasibility, WorktreeDeleteStatus};
 use log::info;
 use serde::{Deserialize, Serialize};
@@ -27,11 +37,64 @@ fn map_err<E: std::fmt::Display>(e: E) -> String {
     e.to_string()
 }
 
+/// Error for commands whose failures the frontend localizes: `code` is the
+/// error's stable identifier, `message` its log string
+#[derive(Debug, Clone, Serialize)]
+struct CodedError {
+    code: &'static str,
+    message: String,
+}
+
+impl CodedError {
+    /// A command called with arguments it can't act on
+    fn invalid_argument(message: impl Into<String>) -> Self {
+        CodedError {
+            code: "invalid_argument",
+            message: message.into(),
+        }
+    }
+}
+
+type CodedResult<T> = std::result::Result<T, CodedError>;
+
+macro_rules! coded_errors {
+    ($($error:ty),* $(,)?) => {$(
+        impl From<$error> for CodedError {
+            fn from(e: $error) -> Self {
+                CodedError {
+                    code: e.code(),
+                    message: e.to_string(),
+                }
+            }
+        }
+    )*};
+}
+
+coded_errors!(
+    git::GitError,
+    worktree::WorktreeError,
+    navigation::NavigationError,
+    settings::SettingsError,
+);
+
+/// Failures outside git and worktree handling, e.g. saving the app state
+impl From<std::io::Error> for CodedError {
+    fn from(e: std::io::Error) -> Self {
+        CodedError {
+            code: "io",
+            message: e.to_string(),
+        }
+    }
+}
+
 // Project commands
 #[tauri::command]
-fn add_project(state: State<'_, Arc<AppState>>, path: &str) -> Result<Project> {
-    let path = Path::new(path);
-    let canonical_path = path.canonicalize().map_err(map_err)?;
+fn add_project(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    path: &str,
+) -> CodedResult<Project> {
+    let canonical_path = worktree::normalize_project_path(Path::new(path))?;
 
     {
         let mut persisted = state.persisted.write();
@@ -47,19 +110,33 @@ fn add_project(state: State<'_, Arc<AppState>>, path: &str) -> Result<Project> {
             existing.last_accessed_at = Some(worktree::chrono_lite_now());
             let project = existing.clone();
             drop(persisted);
-            state.save().map_err(map_err)?;
+            state.save()?;
             return Ok(project);
         }
     }
 
-    let project = worktree::create_project(path).map_err(map_err)?;
+    let mut project = worktree::create_project(&canonical_path)?;
+
+    // Optionally start with a worktree ready; failing to make one doesn't fail the add
+    let cfg = config::load_config_for_project(Some(&project.path));
+    let first = worktree::auto_create_first_worktree(&mut project, &cfg.worktree)
+        .unwrap_or_else(|e| {
+            log::warn!("[add_project] Could not create first worktree: {}", e);
+            None
+        });
 
     {
         let mut persisted = state.persisted.write();
         persisted.projects.push(project.clone());
     }
 
-    state.save().map_err(map_err)?;
+    state.save()?;
+
+    if let Some(wt) = first {
+        spawn_copy_gitignored_files(&app, &cfg, Path::new(&project.path), &wt);
+        // The returned project includes it, for the frontend to select
+        watcher::watch_worktree(app.clone(), wt.id.clone(), wt.path.clone());
+    }
     Ok(project)
 }
 
@@ -68,19 +145,35 @@ fn list_projects(state: State<'_, Arc<AppState>>) -> Result<Vec<Project>> {
     Ok(state.persisted.read().projects.clone())
 }
 
+/// Open project paths for compact display as (project id, path) pairs, with
+/// any meaningful shared parent directory stripped
+#[tauri::command]
+fn get_project_display_paths(state: State<'_, Arc<AppState>>) -> Vec<(String, String)> {
+    state.persisted.read().display_paths()
+}
+
 #[tauri::command]
 fn hide_project(state: State<'_, Arc<AppState>>, project_id: &str) -> Result<()> {
-    {
+    let worktree_ids: Vec<String> = {
         let mut persisted = state.persisted.write();
-        if let Some(project) = persisted.projects.iter_mut().find(|p| p.id == project_id) {
-            // Stop watching individual worktrees
-            for wt in &project.worktrees {
-                watcher::stop_watching(&wt.id);
+        match persisted.projects.iter_mut().find(|p| p.id == project_id) {
+            Some(project) => {
+                // Mark as hidden (keeps in project list for reopening)
+                project.is_active = false;
+                project.worktrees.iter().map(|wt| wt.id.clone()).collect()
             }
-            // Mark as hidden (keeps in project list for reopening)
-            project.is_active = false;
+            None => return Ok(()),
         }
+    };
+
+    // Stop watchers only after releasing the state lock: their signal handlers
+    // take it on the dispatch thread, which `stop_watching` waits on
+    for id in &worktree_ids {
+        watcher::stop_watching(id);
     }
+    watcher::stop_main_head_watcher(project_id);
+    // A hidden project no longer blocks menu items, even if its directory is gone
+    menu::set_project_path_missing(project_id, false);
     state.save().map_err(map_err)?;
     Ok(())
 }
@@ -98,6 +191,8 @@ fn touch_project(
             let was_inactive = !project.is_active;
             project.last_accessed_at = Some(worktree::chrono_lite_now());
             project.is_active = true;
+            // No-op when already watching
+            watcher::watch_main_head(app.clone(), project.id.clone(), project.path.clone());
 
             // If reactivating, collect worktrees to watch
             worktrees_to_watch = if was_inactive {
@@ -111,22 +206,222 @@ fn touch_project(
     }
 
     // Start watching worktrees outside the lock
+    let reactivated = !worktrees_to_watch.is_empty();
     for (id, path) in worktrees_to_watch {
         watcher::watch_worktree(app.clone(), id, path);
     }
+    if reactivated {
+        let project = state
+            .persisted
+            .read()
+            .projects
+            .iter()
+            .find(|p| p.id == project_id)
+            .cloned();
+        if let Some(project) = project {
+            watcher::check_project_paths(&app, &project);
+        }
+    }
 
     state.save().map_err(map_err)?;
     Ok(())
 }
 
+#[tauri::command]
+fn touch_worktree(state: State<'_, Arc<AppState>>, worktree_id: &str) -> Result<()> {
+    {
+        let mut persisted = state.persisted.write();
+        let worktree = persisted
+            .projects
+            .iter_mut()
+            .flat_map(|p| p.worktrees.iter_mut())
+            .find(|wt| wt.id == worktree_id)
+            .ok_or_else(|| format!("Worktree not found: {}", worktree_id))?;
+        worktree.last_accessed_at = Some(worktree::chrono_lite_now());
+    }
+    state.save().map_err(map_err)?;
+    Ok(())
+}
+
+/// A worktree's location relative to its project (e.g. `../fuzzy-fox`), or
+/// "external" when the two are unrelated
+#[tauri::command]
+fn get_worktree_relative_path(
+    state: State<'_, Arc<AppState>>,
+    worktree_id: &str,
+) -> Result<String> {
+    let persisted = state.persisted.read();
+    persisted
+        .projects
+        .iter()
+        .find_map(|p| {
+            let worktree = p.worktrees.iter().find(|w| w.id == worktree_id)?;
+            Some(worktree::relative_to_project(p, worktree))
+        })
+        .ok_or_else(|| format!("Worktree not found: {}", worktree_id))
+}
+
+/// Check open projects for directories deleted from disk, emitting
+/// `project-path-missing` for each affected one. Returns the affected project ids.
+#[tauri::command]
+fn check_project_paths(app: AppHandle, state: State<'_, Arc<AppState>>) -> Vec<String> {
+    let projects: Vec<Project> = state
+        .persisted
+        .read()
+        .projects
+        .iter()
+        .filter(|p| p.is_active)
+        .cloned()
+        .collect();
+
+    projects
+        .iter()
+        .filter(|p| watcher::check_project_paths(&app, p))
+        .map(|p| p.id.clone())
+        .collect()
+}
+
+/// Save the set of open projects and the focused project/worktree under `name`.
+#[tauri::command]
+fn save_layout(
+    state: State<'_, Arc<AppState>>,
+    name: &str,
+    active_project_id: Option<String>,
+    active_worktree_id: Option<String>,
+) -> Result<state::Layout> {
+    let layout = state
+        .persisted
+        .write()
+        .save_layout(name, active_project_id, active_worktree_id);
+    state.save().map_err(map_err)?;
+    Ok(layout)
+}
+
+#[tauri::command]
+fn list_layouts(state: State<'_, Arc<AppState>>) -> Result<Vec<String>> {
+    Ok(state.persisted.read().layouts.keys().cloned().collect())
+}
+
+/// Reopen the projects saved in layout `name` and close the others,
+/// starting and stopping worktree watchers to match.
+#[tauri::command]
+fn restore_layout(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    name: &str,
+) -> Result<state::RestoredLayout> {
+    let (restored, to_watch, to_unwatch) = {
+        let mut persisted = state.persisted.write();
+        let restored = persisted
+            .restore_layout(name)
+            .ok_or_else(|| format!("Layout not found: {}", name))?;
+
+        let worktrees_of = |ids: &[String]| -> Vec<(String, String)> {
+            persisted
+                .projects
+                .iter()
+                .filter(|p| ids.contains(&p.id))
+                .flat_map(|p| p.worktrees.iter().map(|wt| (wt.id.clone(), wt.path.clone())))
+                .collect()
+        };
+        let to_watch = worktrees_of(&restored.opened_project_ids);
+        let to_unwatch = worktrees_of(&restored.closed_project_ids);
+        (restored, to_watch, to_unwatch)
+    };
+
+    for (id, _) in to_unwatch {
+        watcher::stop_watching(&id);
+    }
+    for (id, path) in to_watch {
+        watcher::watch_worktree(app.clone(), id, path);
+    }
+
+    state.save().map_err(map_err)?;
+    Ok(restored)
+}
+
+/// Set or clear (`None`) the path template for a project's new worktrees
+#[tauri::command]
+fn set_worktree_layout(
+    state: State<'_, Arc<AppState>>,
+    project_path: &str,
+    layout: Option<String>,
+) -> CodedResult<()> {
+    let layout = layout.filter(|l| !l.trim().is_empty());
+    if let Some(layout) = &layout {
+        worktree::validate_layout(layout, Path::new(project_path))?;
+    }
+
+    {
+        let mut persisted = state.persisted.write();
+        let project = persisted
+            .projects
+            .iter_mut()
+            .find(|p| p.path == project_path)
+            .ok_or_else(|| worktree::WorktreeError::ProjectNotFound(project_path.to_string()))?;
+        project.worktree_layout = layout;
+    }
+    Ok(state.save()?)
+}
+
+/// Check no open project creates its worktrees inside another open project,
+/// e.g. after the worktree directory setting or a layout changed
+#[tauri::command]
+fn validate_worktree_base_dir(state: State<'_, Arc<AppState>>) -> CodedResult<()> {
+    let projects = state.persisted.read().projects.clone();
+    Ok(worktree::validate_base_dir(&projects)?)
+}
+
+/// Emit `worktree-base-dir-invalid` with the error's code and message when an
+/// open project's worktrees would be created inside another open project.
+/// Run at startup and whenever the config changes.
+fn warn_if_base_dir_nested(app: &AppHandle) {
+    let Some(state) = app.try_state::<Arc<AppState>>() else {
+        return;
+    };
+    let projects = state.persisted.read().projects.clone();
+    if let Err(e) = worktree::validate_base_dir(&projects) {
+        log::warn!("{}", e);
+        let _ = app.emit("worktree-base-dir-invalid", CodedError::from(e));
+    }
+}
+
+/// Check a directory (or the closest existing directory above it) is writable
+/// before creating, deleting or moving worktrees there
+#[tauri::command]
+fn check_worktree_writable(path: &str) -> CodedResult<()> {
+    Ok(worktree::check_writable(Path::new(path))?)
+}
+
 // Worktree commands
+
+/// Where a new worktree would be created, and whether that path is taken
+#[tauri::command]
+fn preview_worktree_path(
+    state: State<'_, Arc<AppState>>,
+    project_path: &str,
+    name: Option<String>,
+) -> CodedResult<worktree::WorktreePathPreview> {
+    let cfg = config::load_config_for_project(Some(project_path));
+    let persisted = state.persisted.read();
+    let project = persisted
+        .projects
+        .iter()
+        .find(|p| p.path == project_path)
+        .ok_or_else(|| worktree::WorktreeError::ProjectNotFound(project_path.to_string()))?;
+
+    Ok(worktree::preview_worktree_path(project, name, cfg.worktree.directory.as_deref())?)
+}
+
 #[tauri::command]
 fn create_worktree(
     app: AppHandle,
     state: State<'_, Arc<AppState>>,
     project_path: &str,
     name: Option<String>,
-) -> Result<Worktree> {
+    base_branch: Option<BaseBranch>,
+    start_point: Option<String>,
+) -> CodedResult<Worktree> {
     let total_start = Instant::now();
     info!("[create_worktree] Starting...");
 
@@ -134,6 +429,15 @@ fn create_worktree(
     let cfg = config::load_config_for_project(Some(project_path));
     info!("[create_worktree] load_config took {:?}", start.elapsed());
 
+    // An explicit start point wins over an explicit base, which wins over the configured policy
+    let base_branch = base_branch.unwrap_or_else(|| cfg.worktree.base_branch.clone());
+
+    // Fetched before taking the state lock, as it goes over the network
+    let start = Instant::now();
+    let start_point = start_point
+        .or_else(|| git::fetched_worktree_base(Path::new(project_path), &base_branch));
+    info!("[create_worktree] fetch base took {:?}", start.elapsed());
+
     let start = Instant::now();
     let mut persisted = state.persisted.write();
     info!("[create_worktree] acquire write lock took {:?}", start.elapsed());
@@ -142,27 +446,127 @@ fn create_worktree(
         .projects
         .iter_mut()
         .find(|p| p.path == project_path)
-        .ok_or_else(|| format!("Project not found: {}", project_path))?;
+        .ok_or_else(|| worktree::WorktreeError::ProjectNotFound(project_path.to_string()))?;
 
     let project_path_buf = Path::new(&project.path).to_path_buf();
 
+    let start_from = match start_point.as_deref() {
+        Some(start_point) => worktree::WorktreeStart::Commit(start_point),
+        None => worktree::WorktreeStart::Base(&base_branch),
+    };
+
+    // Low space is only a warning: the create proceeds regardless
+    warn_if_low_disk_space(&app, &cfg, &project_path_buf);
+
     let start = Instant::now();
-    let wt = worktree::create_worktree(
+    let wt = worktree::create_worktree_from(
         project,
         name,
         cfg.worktree.directory.as_deref(),
-        &cfg.worktree.base_branch,
-    )
-    .map_err(map_err)?;
+        start_from,
+    )?;
     info!("[create_worktree] worktree::create_worktree took {:?}", start.elapsed());
+    worktree::invalidate_project_stats(project_path);
 
     // Copy gitignored files if enabled in config (in background thread)
+    spawn_copy_gitignored_files(&app, &cfg, &project_path_buf, &wt);
+
+    // Start file watcher for this worktree
+    let start = Instant::now();
+    watcher::watch_worktree(app.clone(), wt.id.clone(), wt.path.clone());
+    info!("[create_worktree] watch_worktree took {:?}", start.elapsed());
+
+    drop(persisted);
+
+    let start = Instant::now();
+    state.save()?;
+    info!("[create_worktree] state.save took {:?}", start.elapsed());
+
+    info!("[create_worktree] TOTAL took {:?}", total_start.elapsed());
+    Ok(wt)
+}
+
+/// Create a throwaway worktree at the project's HEAD without creating a branch.
+#[tauri::command]
+fn create_detached_worktree(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    project_path: &str,
+) -> CodedResult<Worktree> {
+    let cfg = config::load_config_for_project(Some(project_path));
+    let mut persisted = state.persisted.write();
+
+    let project = persisted
+        .projects
+        .iter_mut()
+        .find(|p| p.path == project_path)
+        .ok_or_else(|| worktree::WorktreeError::ProjectNotFound(project_path.to_string()))?;
+
+    let project_path_buf = Path::new(&project.path).to_path_buf();
+
+    let wt = worktree::create_detached_worktree(project, cfg.worktree.directory.as_deref())?;
+    worktree::invalidate_project_stats(project_path);
+
+    spawn_copy_gitignored_files(&app, &cfg, &project_path_buf, &wt);
+    watcher::watch_worktree(app.clone(), wt.id.clone(), wt.path.clone());
+
+    drop(persisted);
+    state.save()?;
+
+    Ok(wt)
+}
+
+/// Keep the work done in a scratch terminal by moving its directory's
+/// uncommitted changes into a new worktree on branch `name`
+#[tauri::command]
+fn promote_scratch_to_worktree(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    project_path: &str,
+    scratch_dir: &str,
+    name: &str,
+) -> CodedResult<Worktree> {
+    if let Some(error) = git::validate_branch_name(name) {
+        return Err(git::GitError::InvalidBranchName(error).into());
+    }
+    let cfg = config::load_config_for_project(Some(project_path));
+    let mut persisted = state.persisted.write();
+
+    let project = persisted
+        .projects
+        .iter_mut()
+        .find(|p| p.path == project_path)
+        .ok_or_else(|| worktree::WorktreeError::ProjectNotFound(project_path.to_string()))?;
+
+    let wt = worktree::promote_scratch_to_worktree(
+        project,
+        Path::new(scratch_dir),
+        name.to_string(),
+        cfg.worktree.directory.as_deref(),
+    )?;
+    worktree::invalidate_project_stats(project_path);
+    watcher::watch_worktree(app.clone(), wt.id.clone(), wt.path.clone());
+
+    drop(persisted);
+    state.save()?;
+
+    Ok(wt)
+}
+
+/// Copy gitignored files into a new worktree on a background thread if the
+/// project config asks for it, emitting copy started/completed events.
+fn spawn_copy_gitignored_files(
+    app: &AppHandle,
+    cfg: &config::Config,
+    project_path: &Path,
+    wt: &Worktree,
+) {
     if cfg.worktree.copy.gitignored {
         let worktree_path = wt.path.clone();
         let worktree_id = wt.id.clone();
         let except = cfg.worktree.copy.except.clone();
         let app_handle = app.clone();
-        let project_path_buf_clone = project_path_buf.clone();
+        let project_path_buf = project_path.to_path_buf();
 
         // Emit copy started event
         let _ = app_handle.emit("worktree-copy-started", &worktree_id);
@@ -170,14 +574,14 @@ fn create_worktree(
         std::thread::spawn(move || {
             let start = Instant::now();
             let result = worktree::copy_gitignored_files(
-                &project_path_buf_clone,
+                &project_path_buf,
                 Path::new(&worktree_path),
                 &except,
             );
 
             match &result {
-                Ok(()) => info!("[create_worktree] background copy_gitignored_files took {:?}", start.elapsed()),
-                Err(e) => info!("[create_worktree] background copy_gitignored_files failed: {}", e),
+                Ok(()) => info!("[spawn_copy_gitignored_files] background copy_gitignored_files took {:?}", start.elapsed()),
+                Err(e) => info!("[spawn_copy_gitignored_files] background copy_gitignored_files failed: {}", e),
             }
 
             // Emit copy completed event
@@ -187,59 +591,243 @@ fn create_worktree(
                 "durationMs": start.elapsed().as_millis() as u64,
             }));
         });
-        info!("[create_worktree] spawned background thread for copy_gitignored_files");
+        info!("[spawn_copy_gitignored_files] spawned background thread for copy_gitignored_files");
     }
+}
 
-    // Start file watcher for this worktree
-    let start = Instant::now();
-    watcher::watch_worktree(app.clone(), wt.id.clone(), wt.path.clone());
-    info!("[create_worktree] watch_worktree took {:?}", start.elapsed());
+/// Resolve the branch a new worktree would be created from.
+/// Uses `base_branch` if provided, otherwise the project's configured policy.
+#[tauri::command]
+fn get_worktree_base_branch(
+    project_path: &str,
+    base_branch: Option<BaseBranch>,
+) -> CodedResult<String> {
+    let cfg = config::load_config_for_project(Some(project_path));
+    let base_branch = base_branch.unwrap_or(cfg.worktree.base_branch);
+    Ok(git::resolve_worktree_base(Path::new(project_path), &base_branch)?)
+}
 
-    drop(persisted);
+#[tauri::command]
+fn list_worktrees(state: State<'_, Arc<AppState>>, project_path: &str) -> Result<Vec<Worktree>> {
+    let persisted = state.persisted.read();
 
-    let start = Instant::now();
-    state.save().map_err(map_err)?;
-    info!("[create_worktree] state.save took {:?}", start.elapsed());
+    let project = persisted
+        .projects
+        .iter()
+        .find(|p| p.path == project_path)
+        .ok_or_else(|| format!("Project not found: {}", project_path))?;
 
-    info!("[create_worktree] TOTAL took {:?}", total_start.elapsed());
-    Ok(wt)
+    Ok(project.worktrees.clone())
 }
 
+/// The worktree containing a file, matching through symlinks; None if the
+/// file isn't inside any worktree
 #[tauri::command]
-fn list_worktrees(state: State<'_, Arc<AppState>>, project_path: &str) -> Result<Vec<Worktree>> {
+fn find_worktree_by_path(state: State<'_, Arc<AppState>>, path: &str) -> Option<Worktree> {
+    let persisted = state.persisted.read();
+    worktree::find_by_path(&persisted.projects, Path::new(path)).cloned()
+}
+
+/// Why the app started with empty state, if the state file was unreadable.
+/// Also emitted as `state-recovery` at startup, possibly before anything listens.
+#[tauri::command]
+fn get_state_recovery(state: State<'_, Arc<AppState>>) -> Option<state::StateRecovery> {
+    state.recovery.read().clone()
+}
+
+/// README, CONTRIBUTING and docs directory at a project's root, for picking one to open
+#[tauri::command]
+fn find_project_docs(state: State<'_, Arc<AppState>>, project_path: &str) -> Result<Vec<String>> {
     let persisted = state.persisted.read();
+    let project = persisted
+        .projects
+        .iter()
+        .find(|p| p.path == project_path)
+        .ok_or_else(|| format!("Project not found: {}", project_path))?;
+    Ok(worktree::find_docs(project)
+        .into_iter()
+        .map(|path| path.to_string_lossy().to_string())
+        .collect())
+}
 
+/// Compact status badge for a worktree, e.g. `●3↑2↓1` (ASCII when `plain`)
+#[tauri::command(async)]
+fn get_status_badge(
+    state: State<'_, Arc<AppState>>,
+    worktree_id: &str,
+    plain: Option<bool>,
+) -> CodedResult<String> {
+    let projects = state.persisted.read().projects.clone();
+    Ok(worktree::status_badge(&projects, worktree_id, plain.unwrap_or(false))?)
+}
+
+/// Free disk space in bytes where new worktrees for a project would be created.
+#[tauri::command]
+fn get_free_space(project_path: &str) -> CodedResult<u64> {
+    let cfg = config::load_config_for_project(Some(project_path));
+    let base = worktree::resolve_worktree_directory(
+        cfg.worktree.directory.as_deref(),
+        Path::new(project_path),
+        None,
+        None,
+    )?;
+    Ok(worktree::free_space_for(&base)?)
+}
+
+/// Emit `low-disk-space` when the worktree base is below the configured threshold.
+fn warn_if_low_disk_space(app: &AppHandle, cfg: &config::Config, project_path: &Path) {
+    let threshold_bytes = cfg.worktree.low_disk_space_warning_mb.saturating_mul(1024 * 1024);
+    if threshold_bytes == 0 {
+        return;
+    }
+
+    let available_bytes = worktree::resolve_worktree_directory(
+        cfg.worktree.directory.as_deref(),
+        project_path,
+        None,
+        None,
+    )
+    .and_then(|base| worktree::free_space_for(&base));
+
+    match available_bytes {
+        Ok(available_bytes) if available_bytes < threshold_bytes => {
+            log::warn!(
+                "[create_worktree] Low disk space: {} bytes free, threshold {}",
+                available_bytes, threshold_bytes
+            );
+            let _ = app.emit(
+                "low-disk-space",
+                LowDiskSpace {
+                    project_path: project_path.to_string_lossy().to_string(),
+                    available_bytes,
+                    threshold_bytes,
+                },
+            );
+        }
+        Ok(_) => {}
+        Err(e) => info!("[create_worktree] Could not determine free space: {}", e),
+    }
+}
+
+/// Disk usage per worktree of a project, largest first, for the cleanup view.
+/// Runs off the main thread since uncached worktrees need a full directory walk.
+#[tauri::command(async)]
+fn get_disk_usage_breakdown(
+    state: State<'_, Arc<AppState>>,
+    project_path: String,
+) -> CodedResult<Vec<worktree::WorktreeDiskUsage>> {
+    let project = state
+        .persisted
+        .read()
+        .projects
+        .iter()
+        .find(|p| p.path == project_path)
+        .cloned()
+        .ok_or_else(|| worktree::WorktreeError::ProjectNotFound(project_path.clone()))?;
+
+    Ok(worktree::disk_usage_breakdown(&project)?)
+}
+
+/// A project's worktrees that are ahead of or behind their upstream
+#[tauri::command(async)]
+fn get_diverged_worktrees(
+    state: State<'_, Arc<AppState>>,
+    project_path: String,
+) -> CodedResult<Vec<worktree::DivergedWorktree>> {
+    let project = state
+        .persisted
+        .read()
+        .projects
+        .iter()
+        .find(|p| p.path == project_path)
+        .cloned()
+        .ok_or_else(|| worktree::WorktreeError::ProjectNotFound(project_path.clone()))?;
+
+    Ok(worktree::diverged_worktrees(&project)?)
+}
+
+/// Worktrees of a project with no activity for more than `days` days, to prompt cleanup.
+#[tauri::command]
+fn get_stale_worktrees(
+    state: State<'_, Arc<AppState>>,
+    project_path: &str,
+    days: u64,
+) -> Result<Vec<Worktree>> {
+    let persisted = state.persisted.read();
     let project = persisted
         .projects
         .iter()
         .find(|p| p.path == project_path)
         .ok_or_else(|| format!("Project not found: {}", project_path))?;
 
-    Ok(project.worktrees.clone())
+    Ok(worktree::stale_worktrees(project, days))
+}
+
+/// Worktree count, disk usage and dirty count for a project.
+/// Returns cached stats when available; otherwise computes them on a background
+/// thread and emits `project-stats-updated`, returning `None` in the meantime.
+#[tauri::command]
+fn get_project_stats(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    project_path: &str,
+) -> Result<Option<worktree::ProjectStats>> {
+    if let Some(stats) = worktree::cached_project_stats(project_path) {
+        return Ok(Some(stats));
+    }
+
+    let project = state
+        .persisted
+        .read()
+        .projects
+        .iter()
+        .find(|p| p.path == project_path)
+        .cloned()
+        .ok_or_else(|| format!("Project not found: {}", project_path))?;
+
+    std::thread::spawn(move || match worktree::project_stats(&project) {
+        Ok(stats) => {
+            worktree::cache_project_stats(&project, stats.clone());
+            let _ = app.emit(
+                "project-stats-updated",
+                ProjectStatsUpdated {
+                    project_path: project.path.clone(),
+                    stats,
+                },
+            );
+        }
+        Err(e) => info!("[get_project_stats] Failed for {}: {}", project.path, e),
+    });
+
+    Ok(None)
 }
 
 #[tauri::command]
-fn delete_worktree(state: State<'_, Arc<AppState>>, worktree_id: &str) -> Result<()> {
+fn delete_worktree(state: State<'_, Arc<AppState>>, worktree_id: &str) -> CodedResult<()> {
     let mut persisted = state.persisted.write();
 
     // Find the project containing this worktree
     for project in &mut persisted.projects {
         if project.worktrees.iter().any(|w| w.id == worktree_id) {
-            worktree::delete_worktree(project, worktree_id).map_err(map_err)?;
+            worktree::delete_worktree(project, worktree_id)?;
+            worktree::invalidate_project_stats(&project.path);
             drop(persisted);
-            state.save().map_err(map_err)?;
+            state.save()?;
             return Ok(());
         }
     }
 
-    Err(format!("Worktree not found: {}", worktree_id))
+    Err(worktree::WorktreeError::WorktreeNotFound(worktree_id.to_string()).into())
 }
 
 #[tauri::command]
-fn check_worktree_delete_status(worktree_path: &str, project_path: Option<String>) -> Result<WorktreeDeleteStatus> {
+fn check_worktree_delete_status(
+    worktree_path: &str,
+    project_path: Option<String>,
+) -> CodedResult<WorktreeDeleteStatus> {
     let path = Path::new(worktree_path);
     let cfg = config::load_config_for_project(project_path.as_deref());
-    git::check_worktree_delete_status(path, &cfg.worktree.base_branch).map_err(map_err)
+    Ok(git::check_worktree_delete_status(path, &cfg.worktree.base_branch)?)
 }
 
 #[tauri::command]
@@ -261,6 +849,7 @@ fn execute_delete_worktree_workflow(
                     worktree.path.clone(),
                     worktree.branch.clone(),
                     project.path.clone(),
+                    worktree.detached,
                 ));
                 break;
             }
@@ -284,7 +873,7 @@ fn execute_delete_worktree_workflow(
 
     let worktree_id = worktree_id.to_string();
     let app_state = Arc::clone(&*state);
-    let (worktree_name, worktree_path, branch_name, project_path) = worktree_info;
+    let (worktree_name, worktree_path, branch_name, project_path, detached) = worktree_info;
 
     // Spawn background thread to avoid blocking UI
     std::thread::spawn(move || {
@@ -334,8 +923,8 @@ fn execute_delete_worktree_workflow(
             }
         }
 
-        // Step 3: Delete local branch if requested
-        if options.delete_branch {
+        // Step 3: Delete local branch if requested (detached worktrees have none)
+        if options.delete_branch && !detached {
             let _ = app.emit(
                 "delete-worktree-progress",
                 DeleteWorktreeProgress {
@@ -363,6 +952,7 @@ fn execute_delete_worktree_workflow(
             for project in &mut persisted.projects {
                 if let Some(idx) = project.worktrees.iter().position(|w| w.id == worktree_id) {
                     project.worktrees.remove(idx);
+                    worktree::invalidate_project_stats(&project.path);
                     break;
                 }
             }
@@ -401,18 +991,18 @@ fn remove_stale_worktree(state: State<'_, Arc<AppState>>, worktree_path: &str) -
 
     for project in &mut persisted.projects {
         if let Some(idx) = project.worktrees.iter().position(|w| w.path == worktree_path) {
-            let worktree = &project.worktrees[idx];
             info!(
                 "[remove_stale_worktree] Removing '{}' from project '{}'",
-                worktree.name, project.name
+                project.worktrees[idx].name, project.name
             );
 
-            // Stop watching this worktree
-            watcher::stop_watching(&worktree.id);
-
             // Remove from state (don't try to delete files - they're already gone)
-            project.worktrees.remove(idx);
+            let worktree = project.worktrees.remove(idx);
+            worktree::invalidate_project_stats(&project.path);
             drop(persisted);
+
+            // Stop watching this worktree once the state lock is released
+            watcher::stop_watching(&worktree.id);
             state.save().map_err(map_err)?;
             return Ok(());
         }
@@ -592,6 +1182,43 @@ fn stop_rebase_watcher(worktree_id: &str) {
     watcher::stop_rebase_watcher(worktree_id);
 }
 
+/// Temporarily switch a worktree's watcher to content comparison (or back).
+#[tauri::command]
+fn set_watcher_compare_contents(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    worktree_id: &str,
+    enabled: bool,
+) -> Result<()> {
+    let worktree_path = {
+        let persisted = state.persisted.read();
+        persisted
+            .projects
+            .iter()
+            .flat_map(|p| &p.worktrees)
+            .find(|w| w.id == worktree_id)
+            .map(|w| w.path.clone())
+            .ok_or_else(|| format!("Worktree not found: {}", worktree_id))?
+    };
+
+    watcher::set_compare_contents(app, worktree_id.to_string(), worktree_path, enabled);
+    Ok(())
+}
+
+/// Whether another git process holds the worktree's index lock. Changes are
+/// reported as `git-busy` / `git-idle` events while the worktree is watched.
+#[tauri::command]
+fn is_git_busy(worktree_path: &str) -> bool {
+    watcher::is_index_locked(Path::new(worktree_path))
+}
+
+/// Diagnostic counters for a worktree's file watcher, `None` if it isn't watched
+#[tauri::command]
+fn get_watcher_stats(worktree_id: &str) -> Option<watcher::WatcherStats> {
+    watcher::watcher_stats(worktree_id)
+}
+
+/// Run a configured task in the worktree or project `entity_id`. Returns the task's PTY id.
 #[tauri::command]
 fn spawn_task(
     app: AppHandle,
@@ -601,66 +1228,139 @@ fn spawn_task(
     cols: Option<u16>,
     rows: Option<u16>,
 ) -> Result<String> {
-    // Find entity info and project path (entity can be a worktree or a project)
-    // Returns: (entity_path, project_path, branch, optional_worktree_name)
-    let (entity_path, project_path, branch, worktree_name) = {
-        let persisted = state.persisted.read();
-        let mut found = None;
+    // The entity can be any worktree or project, not just the active one;
+    // the task runs in its directory with templates rendered for its branch
+    let target = tasks::resolve_target(&state.persisted.read(), entity_id).map_err(map_err)?;
 
-        // First, try to find a worktree with this ID
-        for project in &persisted.projects {
-            if let Some(worktree) = project.worktrees.iter().find(|w| w.id == entity_id) {
-                found = Some((
-                    worktree.path.clone(),
-                    project.path.clone(),
-                    worktree.branch.clone(),
-                    Some(worktree.name.clone()),
-                ));
-                break;
-            }
-        }
+    // Load config and find the task
+    let cfg = config::load_config_for_project(Some(&target.project_path));
+    let task = tasks::find_task(&cfg, Path::new(&target.project_path), task_name)
+        .ok_or_else(|| format!("Task not found: {}", task_name))?;
 
-        // If not found as worktree, try to find a project with this ID
-        if found.is_none() {
-            if let Some(project) = persisted.projects.iter().find(|p| p.id == entity_id) {
-                // For main project, get current branch from git
-                let repo = git2::Repository::open(&project.path).map_err(map_err)?;
-                let branch = git::get_current_branch(&repo).map_err(map_err)?;
-                found = Some((project.path.clone(), project.path.clone(), branch, None));
-            }
-        }
+    let prepared = tasks::prepare(&task, &target).map_err(map_err)?;
 
-        found.ok_or_else(|| format!("Entity not found: {}", entity_id))?
-    };
+    tasks::record_run(&mut state.persisted.write(), entity_id, task_name);
+    state.save().map_err(map_err)?;
+    let env_vars = if prepared.env.is_empty() { None } else { Some(&prepared.env) };
+
+    let task_id = pty::spawn_pty(&app, &state, entity_id, &prepared.cwd, &prepared.command, cols, rows, prepared.shell.as_deref(), env_vars)
+        .map_err(map_err)?;
+
+    if let Some(pid) = state.pty_sessions.read().get(&task_id).map(|s| s.child_pid) {
+        tasks::register_task(tasks::RunningTask {
+            id: task_id.clone(),
+            worktree_id: entity_id.to_string(),
+            label: task_name.to_string(),
+            started_at: worktree::chrono_lite_now(),
+            pid,
+        });
+    }
+    if let Some(log_file) = prepared.log_file {
+        tasks::register_log(&task_id, log_file);
+    }
 
-    // Load config and find the task
-    let cfg = config::load_config_for_project(Some(&project_path));
-    let task = cfg
-        .tasks
-        .iter()
-        .find(|t| t.name == task_name)
-        .ok_or_else(|| format!("Task not found: {}", task_name))?;
+    Ok(task_id)
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SuggestedTasks {
+    package_manager: tasks::PackageManager,
+    tasks: Vec<config::TaskConfig>,
+}
 
-    // Expand template variables in command
-    let mut ctx = template::TemplateContext::new(&project_path).with_branch(&branch);
-    if let Some(name) = worktree_name {
-        ctx = ctx.with_worktree_name(name);
+/// Default tasks for the project's detected package manager, `None` if none was detected
+#[tauri::command]
+fn suggest_tasks(project_path: &str) -> Option<SuggestedTasks> {
+    tasks::detect_package_manager(Path::new(project_path)).map(|package_manager| SuggestedTasks {
+        package_manager,
+        tasks: package_manager.default_tasks(),
+    })
+}
+
+/// The project's git aliases as (name, expansion) pairs
+#[tauri::command]
+fn list_git_aliases(project_path: &str) -> CodedResult<Vec<(String, String)>> {
+    Ok(git::list_aliases(Path::new(project_path))?)
+}
+
+/// `git <alias>` tasks for the task switcher, empty unless `git.aliasTasks` is enabled
+#[tauri::command]
+fn get_alias_tasks(project_path: &str) -> CodedResult<Vec<config::TaskConfig>> {
+    let cfg = config::load_config_for_project(Some(project_path));
+    if !cfg.git.alias_tasks {
+        return Ok(Vec::new());
     }
-    let command = template::expand_template(&task.command, &ctx).map_err(map_err)?;
+    let aliases = git::list_aliases(Path::new(project_path))?;
+    Ok(tasks::alias_tasks(&aliases))
+}
 
-    // Expand template variables in env vars
-    let expanded_env: std::collections::HashMap<String, String> = task
-        .env
-        .iter()
-        .map(|(key, value)| {
-            let expanded = template::expand_template(value, &ctx).unwrap_or_else(|_| value.clone());
-            (key.clone(), expanded)
-        })
-        .collect();
-    let env_vars = if expanded_env.is_empty() { None } else { Some(&expanded_env) };
+/// Run the task last run in `entity_id` again
+#[tauri::command]
+fn run_last_task(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    entity_id: &str,
+    cols: Option<u16>,
+    rows: Option<u16>,
+) -> Result<String> {
+    let task_name = tasks::last_run(&state.persisted.read(), entity_id).map_err(map_err)?;
+    spawn_task(app, state, entity_id, &task_name, cols, rows)
+}
+
+/// Run `task_name` whenever files change in a worktree, or stop doing so when `task_name` is `None`
+#[tauri::command]
+fn set_task_auto_run(worktree_id: &str, task_name: Option<String>, cooldown_ms: Option<u64>) {
+    let rule = task_name.map(|task_name| tasks::AutoRunRule {
+        task_name,
+        cooldown_ms: cooldown_ms.unwrap_or(2000),
+    });
+    tasks::set_auto_run(worktree_id, rule);
+}
+
+/// Pause the worktree's file watcher while its tasks run, refreshing once on exit
+#[tauri::command]
+fn set_pause_watcher_during_tasks(worktree_id: &str, enabled: bool) {
+    tasks::set_pause_watcher(worktree_id, enabled);
+}
 
-    pty::spawn_pty(&app, &state, entity_id, &entity_path, &command, cols, rows, task.shell.as_deref(), env_vars)
-        .map_err(map_err)
+#[tauri::command]
+fn get_pause_watcher_during_tasks(worktree_id: &str) -> bool {
+    tasks::pauses_watcher(worktree_id)
+}
+
+#[tauri::command]
+fn get_task_auto_run(worktree_id: &str) -> Option<tasks::AutoRunRule> {
+    tasks::auto_run_rule(worktree_id)
+}
+
+/// Tasks currently running across all worktrees and projects
+#[tauri::command]
+fn list_running_tasks() -> Vec<tasks::RunningTask> {
+    tasks::running_tasks()
+}
+
+/// Stop a running task along with any processes it spawned
+#[tauri::command]
+fn cancel_task(app: AppHandle, state: State<'_, Arc<AppState>>, task_id: &str) -> Result<()> {
+    tasks::cancel_task(&app, &state, task_id).map_err(map_err)
+}
+
+/// Last `lines` lines of a running task's log file
+#[tauri::command]
+fn tail_task_log(task_id: &str, lines: usize) -> Result<Vec<String>> {
+    tasks::tail_log(task_id, lines).map_err(map_err)
+}
+
+/// Stream new lines of a running task's log as `task-log-line` events
+#[tauri::command]
+fn follow_task_log(app: AppHandle, task_id: &str) -> Result<()> {
+    tasks::tail_follow(app, task_id).map_err(map_err)
+}
+
+#[tauri::command]
+fn stop_following_task_log(task_id: &str) {
+    tasks::stop_follow(task_id);
 }
 
 /// A named URL returned from get_task_urls
@@ -679,30 +1379,8 @@ fn get_task_urls(
     use template::{expand_template, TemplateContext};
 
     // Find entity info (worktree or project) to get branch and paths
-    let (branch, project_path) = {
-        let persisted = state.persisted.read();
-        let mut found = None;
-
-        // First, try to find a worktree with this ID
-        for project in &persisted.projects {
-            if let Some(worktree) = project.worktrees.iter().find(|w| w.id == entity_id) {
-                found = Some((worktree.branch.clone(), project.path.clone()));
-                break;
-            }
-        }
-
-        // If not found as worktree, try to find a project with this ID
-        if found.is_none() {
-            if let Some(project) = persisted.projects.iter().find(|p| p.id == entity_id) {
-                // For main project, get current branch from git
-                let repo = git2::Repository::open(&project.path).map_err(map_err)?;
-                let branch = git::get_current_branch(&repo).map_err(map_err)?;
-                found = Some((branch, project.path.clone()));
-            }
-        }
-
-        found.ok_or_else(|| format!("Entity not found: {}", entity_id))?
-    };
+    let tasks::TaskTarget { branch, project_path, .. } =
+        tasks::resolve_target(&state.persisted.read(), entity_id).map_err(map_err)?;
 
     // Load config and find the task
     let cfg = config::load_config_for_project(Some(&project_path));
@@ -853,26 +1531,104 @@ fn pty_force_kill(state: State<'_, Arc<AppState>>, pty_id: &str) -> Result<()> {
     pty::force_kill_pty(&state, pty_id).map_err(map_err)
 }
 
-// Config commands
+// Config commands
+#[tauri::command]
+fn get_config(project_path: Option<String>) -> config::ConfigResult {
+    let mut result = config::load_config_with_errors(project_path.as_deref());
+    // Settings are saved by editing the file and reloaded through here, so a
+    // bad editor command shows up now rather than the next time one is opened
+    if let Some(editor) = result.config.apps.editor_command() {
+        if let Err(e) = settings::validate_editor_command(editor) {
+            result.errors.push(config::ConfigError {
+                file: config::get_config_path().display().to_string(),
+                message: e.to_string(),
+            });
+        }
+    }
+    result
+}
+
+#[tauri::command]
+fn watch_config(app: AppHandle, project_path: Option<String>) {
+    watcher::watch_config(app, project_path);
+}
+
+#[tauri::command]
+fn stop_config_watcher() {
+    watcher::stop_config_watcher();
+}
+
+/// Health scan across open projects, most severe findings first
+#[tauri::command]
+fn run_diagnostics(state: State<'_, Arc<AppState>>) -> Vec<diagnostics::Finding> {
+    let persisted = state.persisted.read().clone();
+    diagnostics::scan(&persisted)
+}
+
+/// Drop worktree entries that duplicate another entry's path, as reported by
+/// the health scan. Returns the removed worktree ids.
+#[tauri::command]
+fn repair_duplicate_worktrees(
+    state: State<'_, Arc<AppState>>,
+    project_path: &str,
+) -> Result<Vec<String>> {
+    let dropped = {
+        let mut persisted = state.persisted.write();
+        let project = persisted
+            .projects
+            .iter_mut()
+            .find(|p| p.path == project_path)
+            .ok_or_else(|| format!("Project not found: {}", project_path))?;
+        worktree::drop_duplicate_paths(project)
+    };
+    if dropped.is_empty() {
+        return Ok(dropped);
+    }
+
+    for id in &dropped {
+        watcher::stop_watching(id);
+    }
+    state.save().map_err(map_err)?;
+    Ok(dropped)
+}
+
+/// Running version and, if an update endpoint is configured, the latest release.
+/// Runs off the main thread since the update check goes over the network.
+#[tauri::command(async)]
+fn app_info(app: AppHandle) -> updates::AppInfo {
+    let cfg = config::load_config();
+    let version = app.package_info().version.to_string();
+    updates::app_info(&version, cfg.updates.endpoint.as_deref())
+}
+
+// Mappings commands
+#[tauri::command]
+fn get_mappings() -> mappings::MappingsResult {
+    mappings::load_mappings()
+}
+
+/// Action ids already bound to a chord, for warning about clashes
 #[tauri::command]
-fn get_config(project_path: Option<String>) -> config::ConfigResult {
-    config::load_config_with_errors(project_path.as_deref())
+fn get_actions_for_chord(chord: &str) -> Vec<String> {
+    mappings::actions_for_chord(chord)
 }
 
+/// Another action already bound to a chord, checked before binding it to `action_id`
 #[tauri::command]
-fn watch_config(app: AppHandle, project_path: Option<String>) {
-    watcher::watch_config(app, project_path);
+fn would_mapping_conflict(action_id: &str, chord: &str) -> Option<String> {
+    mappings::would_conflict(action_id, chord)
 }
 
+/// Bindings on chords the OS reserves, as (action id, chord), which never fire
 #[tauri::command]
-fn stop_config_watcher() {
-    watcher::stop_config_watcher();
+fn get_os_reserved_conflicts() -> Vec<(String, String)> {
+    mappings::os_reserved_conflicts()
 }
 
-// Mappings commands
+/// Menu item id to accelerator for every mapped menu item, for external tools
 #[tauri::command]
-fn get_mappings() -> mappings::MappingsResult {
-    mappings::load_mappings()
+fn export_key_map() -> HashMap<String, String> {
+    mappings::export_map()
 }
 
 #[tauri::command]
@@ -944,29 +1700,243 @@ fn expand_action_prompt(
 
 // Git commands
 #[tauri::command]
-fn get_changed_files(worktree_path: &str) -> Result<Vec<FileChange>> {
+fn get_changed_files(
+    state: State<'_, Arc<AppState>>,
+    worktree_path: &str,
+    project_path: Option<String>,
+) -> CodedResult<Vec<FileChange>> {
+    let path = Path::new(worktree_path);
+    let cfg = config::load_config_for_project(project_path.as_deref());
+    let show_ignored = shows_ignored_files(&state, path);
+    Ok(git::get_changed_files(path, cfg.changed_files.include_submodules, show_ignored)?)
+}
+
+/// Changed files grouped by directory, with status counts per directory.
+#[tauri::command]
+fn get_changed_files_tree(
+    state: State<'_, Arc<AppState>>,
+    worktree_path: &str,
+    project_path: Option<String>,
+) -> CodedResult<state::ChangeTree> {
     let path = Path::new(worktree_path);
-    git::get_changed_files(path).map_err(map_err)
+    let cfg = config::load_config_for_project(project_path.as_deref());
+    let show_ignored = shows_ignored_files(&state, path);
+    Ok(git::changed_files_tree(path, cfg.changed_files.include_submodules, show_ignored)?)
+}
+
+/// Whether the worktree at `worktree_path` has ignored files toggled on
+fn shows_ignored_files(state: &AppState, worktree_path: &Path) -> bool {
+    let persisted = state.persisted.read();
+    worktree::find_by_path(&persisted.projects, worktree_path)
+        .is_some_and(|w| w.show_ignored_files)
+}
+
+/// Switch a worktree's changed-files panel between working tree and branch diff,
+/// re-emitting `files-changed` with the file set for the new mode.
+#[tauri::command]
+fn toggle_changed_files_view_mode(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    worktree_id: &str,
+    project_path: Option<String>,
+) -> CodedResult<state::ChangedFilesViewMode> {
+    let (mode, show_ignored, worktree_path) = {
+        let mut persisted = state.persisted.write();
+        let worktree = persisted
+            .projects
+            .iter_mut()
+            .flat_map(|p| p.worktrees.iter_mut())
+            .find(|w| w.id == worktree_id)
+            .ok_or_else(|| worktree::WorktreeError::WorktreeNotFound(worktree_id.to_string()))?;
+        worktree.changed_files_view_mode = worktree.changed_files_view_mode.toggled();
+        (worktree.changed_files_view_mode, worktree.show_ignored_files, worktree.path.clone())
+    };
+    state.save()?;
+
+    let cfg = config::load_config_for_project(project_path.as_deref());
+    let files = git::get_changed_files_for_mode(
+        Path::new(&worktree_path),
+        mode,
+        &cfg.worktree.base_branch,
+        cfg.changed_files.include_submodules,
+        show_ignored,
+    )?;
+    let _ = app.emit("files-changed", watcher::FilesChanged { worktree_path, files });
+
+    Ok(mode)
+}
+
+/// Toggle whether a worktree's uncommitted view lists gitignored files,
+/// re-emitting `files-changed` with the new file set. Returns the new setting.
+#[tauri::command]
+fn toggle_ignored_files(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    worktree_id: &str,
+    project_path: Option<String>,
+) -> CodedResult<bool> {
+    let (mode, show_ignored, worktree_path) = {
+        let mut persisted = state.persisted.write();
+        let worktree = persisted
+            .projects
+            .iter_mut()
+            .flat_map(|p| p.worktrees.iter_mut())
+            .find(|w| w.id == worktree_id)
+            .ok_or_else(|| worktree::WorktreeError::WorktreeNotFound(worktree_id.to_string()))?;
+        worktree.show_ignored_files = !worktree.show_ignored_files;
+        (worktree.changed_files_view_mode, worktree.show_ignored_files, worktree.path.clone())
+    };
+    state.save()?;
+
+    let cfg = config::load_config_for_project(project_path.as_deref());
+    let files = git::get_changed_files_for_mode(
+        Path::new(&worktree_path),
+        mode,
+        &cfg.worktree.base_branch,
+        cfg.changed_files.include_submodules,
+        show_ignored,
+    )?;
+    let _ = app.emit("files-changed", watcher::FilesChanged { worktree_path, files });
+
+    Ok(show_ignored)
 }
 
 #[tauri::command]
 fn get_branch_info(
     worktree_path: &str,
     project_path: Option<String>,
-) -> Result<state::BranchInfo> {
+) -> CodedResult<state::BranchInfo> {
     let path = Path::new(worktree_path);
     let cfg = config::load_config_for_project(project_path.as_deref());
-    git::get_branch_info(path, &cfg.worktree.base_branch).map_err(map_err)
+    Ok(git::get_branch_info(path, &cfg.worktree.base_branch)?)
 }
 
 #[tauri::command]
 fn get_branch_changed_files(
     worktree_path: &str,
     project_path: Option<String>,
-) -> Result<Vec<FileChange>> {
+) -> CodedResult<Vec<FileChange>> {
     let path = Path::new(worktree_path);
     let cfg = config::load_config_for_project(project_path.as_deref());
-    git::get_branch_changed_files(path, &cfg.worktree.base_branch).map_err(map_err)
+    Ok(git::get_branch_changed_files(path, &cfg.worktree.base_branch)?)
+}
+
+/// Commit all of the worktree's changes as a WIP snapshot. Returns its sha.
+#[tauri::command]
+fn wip_snapshot(worktree_path: &str, message: &str) -> CodedResult<String> {
+    Ok(git::wip_snapshot(Path::new(worktree_path), message)?)
+}
+
+/// Undo the WIP snapshot at HEAD, putting its changes back in the working tree
+#[tauri::command]
+fn undo_wip_snapshot(worktree_path: &str) -> CodedResult<()> {
+    Ok(git::undo_wip_snapshot(Path::new(worktree_path))?)
+}
+
+/// Whether the worktree's branch exists on `remote` (default "origin")
+#[tauri::command(async)]
+fn remote_branch_exists(worktree_path: &str, remote: Option<String>) -> CodedResult<bool> {
+    let remote = remote.as_deref().unwrap_or("origin");
+    Ok(git::remote_branch_exists(Path::new(worktree_path), remote)?)
+}
+
+/// Commits on a worktree's branch since it forked from `base`, defaulting to
+/// the configured base branch, for the sidebar
+#[tauri::command]
+fn get_commit_count_since_fork(worktree_path: &str, base: Option<String>) -> CodedResult<usize> {
+    let base = match base {
+        Some(base) => base,
+        None => {
+            let cfg = config::load_config_for_project(Some(worktree_path));
+            git::resolve_worktree_base(Path::new(worktree_path), &cfg.worktree.base_branch)?
+        }
+    };
+    Ok(git::commit_count_since_fork(Path::new(worktree_path), &base)?)
+}
+
+/// The project's main branch: origin/HEAD, init.defaultBranch, then main or master
+#[tauri::command]
+fn get_default_branch(project_path: &str) -> CodedResult<String> {
+    Ok(git::default_branch(Path::new(project_path))?)
+}
+
+/// A git config value as seen from the worktree, None if unset
+#[tauri::command]
+fn get_git_config(worktree_path: &str, key: &str) -> CodedResult<Option<String>> {
+    Ok(git::get_config(Path::new(worktree_path), key)?)
+}
+
+/// Set a git config value for the worktree's repository (`local`) or globally
+#[tauri::command]
+fn set_git_config(worktree_path: &str, key: &str, value: &str, local: bool) -> CodedResult<()> {
+    Ok(git::set_config(Path::new(worktree_path), key, value, local)?)
+}
+
+/// Run a git subcommand from the configured allowlist in the worktree and return its output.
+/// Clones, fetches, pulls and pushes report progress as `git-progress` events.
+#[tauri::command(async)]
+fn run_git_command(
+    app: AppHandle,
+    worktree_path: &str,
+    args: Vec<String>,
+    project_path: Option<String>,
+) -> CodedResult<String> {
+    let cfg = config::load_config_for_project(project_path.as_deref());
+    let mut on_progress = |progress| {
+        let _ = app.emit("git-progress", progress);
+    };
+    let allowed = &cfg.git.allowed_commands;
+    Ok(git::run_allowlisted(Path::new(worktree_path), &args, allowed, &mut on_progress)?)
+}
+
+/// The worktree's changed files as JSON or CSV, for pasting into a report
+#[tauri::command]
+fn export_change_list(
+    state: State<'_, Arc<AppState>>,
+    worktree_id: &str,
+    format: worktree::ExportFormat,
+    project_path: Option<String>,
+) -> CodedResult<String> {
+    let cfg = config::load_config_for_project(project_path.as_deref());
+    let projects = state.persisted.read().projects.clone();
+    Ok(worktree::export_change_list(
+        &projects,
+        worktree_id,
+        &cfg.worktree.base_branch,
+        cfg.changed_files.include_submodules,
+        format,
+    )?)
+}
+
+/// Everything the worktree's branch touched since it forked from the default branch
+#[tauri::command]
+fn get_changes_since_fork(worktree_path: &str) -> CodedResult<Vec<FileChange>> {
+    Ok(git::changes_since_fork(Path::new(worktree_path))?)
+}
+
+/// Files changed on `worktree_b`'s branch since it diverged from `worktree_a`'s.
+/// Both worktrees must belong to the same project.
+#[tauri::command]
+fn diff_worktree_branches(
+    state: State<'_, Arc<AppState>>,
+    worktree_a: &str,
+    worktree_b: &str,
+) -> CodedResult<Vec<FileChange>> {
+    let persisted = state.persisted.read();
+    let find = |id: &str| {
+        persisted
+            .projects
+            .iter()
+            .find_map(|p| p.worktrees.iter().find(|w| w.id == id).map(|w| (p, w)))
+            .ok_or_else(|| worktree::WorktreeError::WorktreeNotFound(id.to_string()))
+    };
+    let (project, a) = find(worktree_a)?;
+    let (other, b) = find(worktree_b)?;
+    if other.id != project.id {
+        return Err(worktree::WorktreeError::ForeignRepository(b.path.clone()).into());
+    }
+
+    Ok(git::diff_branches(Path::new(&project.path), &a.branch, &b.branch)?)
 }
 
 #[tauri::command]
@@ -975,7 +1945,7 @@ fn get_file_diff_content(
     file_path: &str,
     mode: &str,
     project_path: Option<String>,
-) -> Result<state::DiffContent> {
+) -> CodedResult<state::DiffContent> {
     let path = Path::new(worktree_path);
     let cfg = config::load_config_for_project(project_path.as_deref());
     let base_branch = &cfg.worktree.base_branch;
@@ -993,9 +1963,9 @@ fn get_file_diff_content(
             // Original = base branch, Modified = working tree
             // This shows all changes vs base (committed + uncommitted)
             let target_branch = git::resolve_target_branch(
-                &git2::Repository::open(path).map_err(map_err)?,
+                &git2::Repository::open(path).map_err(git::GitError::from)?,
                 base_branch,
-            ).map_err(map_err)?;
+            )?;
             let original = git::get_file_at_ref(path, file_path, &target_branch)
                 .unwrap_or_default();
             let modified = git::get_working_file(path, file_path)
@@ -1003,7 +1973,7 @@ fn get_file_diff_content(
             (original, modified, target_branch, "Working Tree".to_string())
         }
         _ => {
-            return Err("Invalid mode".into());
+            return Err(CodedError::invalid_argument(format!("Invalid mode: {}", mode)));
         }
     };
 
@@ -1019,33 +1989,117 @@ fn get_file_diff_content(
 }
 
 #[tauri::command]
-fn has_uncommitted_changes(project_path: &str) -> Result<bool> {
+fn stage_all(worktree_path: &str) -> CodedResult<()> {
+    Ok(git::stage_all(Path::new(worktree_path))?)
+}
+
+#[tauri::command]
+fn unstage_all(worktree_path: &str) -> CodedResult<()> {
+    Ok(git::unstage_all(Path::new(worktree_path))?)
+}
+
+/// Discard every change in a worktree. This is destructive, so the caller must
+/// pass `confirmed: true` after asking the user.
+#[tauri::command]
+fn discard_all(worktree_path: &str, include_untracked: bool, confirmed: bool) -> CodedResult<()> {
+    if !confirmed {
+        return Err(CodedError::invalid_argument("Discarding all changes must be confirmed"));
+    }
+    Ok(git::discard_all(Path::new(worktree_path), include_untracked)?)
+}
+
+/// Export a worktree's changes as a patch file, returning the path written.
+/// Without `base`, only uncommitted changes are exported.
+#[tauri::command]
+fn export_patch(
+    worktree_path: &str,
+    base: Option<String>,
+    output_path: &str,
+) -> CodedResult<String> {
+    let output = Path::new(output_path);
+    git::export_patch(Path::new(worktree_path), base.as_deref(), output)?;
+    Ok(output.to_string_lossy().to_string())
+}
+
+/// Branch and commit of the project's main checkout. Changes are pushed
+/// through the `main-head-changed` event.
+#[tauri::command]
+fn get_main_head(project_path: &str) -> CodedResult<git::HeadInfo> {
+    Ok(git::main_head(Path::new(project_path))?)
+}
+
+/// Hash of the git config and HEAD that cached repo data depends on
+#[tauri::command]
+fn get_config_fingerprint(project_path: &str) -> CodedResult<String> {
+    Ok(git::config_fingerprint(Path::new(project_path))?)
+}
+
+/// Render a UTC epoch timestamp in the system's local time
+#[tauri::command]
+fn format_local_time(epoch: u64) -> String {
+    time::format_local(epoch)
+}
+
+/// HEAD commit of each worktree, keyed by path. Worktrees without commits
+/// (or that can't be read) map to null.
+#[tauri::command(async)]
+fn get_last_commits(worktree_paths: Vec<String>) -> HashMap<String, Option<git::CommitSummary>> {
+    worktree_paths
+        .into_iter()
+        .map(|path| {
+            let commit = git::last_commit(Path::new(&path)).unwrap_or_else(|e| {
+                log::warn!("Failed to read last commit of {}: {}", path, e);
+                None
+            });
+            (path, commit)
+        })
+        .collect()
+}
+
+/// File, line and per-extension counts of the staged changes in a worktree
+#[tauri::command]
+fn get_staged_summary(worktree_path: &str) -> CodedResult<git::StagedSummary> {
+    Ok(git::staged_summary(Path::new(worktree_path))?)
+}
+
+/// Apply a patch file to a worktree, or only check that it applies cleanly.
+#[tauri::command]
+fn apply_patch(
+    worktree_path: &str,
+    patch_path: &str,
+    check_only: bool,
+) -> CodedResult<git::ApplyResult> {
+    Ok(git::apply_patch(Path::new(worktree_path), Path::new(patch_path), check_only)?)
+}
+
+#[tauri::command]
+fn has_uncommitted_changes(project_path: &str) -> CodedResult<bool> {
     let path = Path::new(project_path);
-    git::has_uncommitted_changes_at_path(path).map_err(map_err)
+    Ok(git::has_uncommitted_changes_at_path(path)?)
 }
 
 #[tauri::command]
-fn stash_changes(project_path: &str) -> Result<String> {
+fn stash_changes(project_path: &str) -> CodedResult<String> {
     let path = Path::new(project_path);
-    git::stash_changes(path).map_err(map_err)
+    Ok(git::stash_changes(path)?)
 }
 
 #[tauri::command]
-fn stash_pop(project_path: &str, stash_id: &str) -> Result<()> {
+fn stash_pop(project_path: &str, stash_id: &str) -> CodedResult<()> {
     let path = Path::new(project_path);
-    git::stash_pop(path, stash_id).map_err(map_err)
+    Ok(git::stash_pop(path, stash_id)?)
 }
 
 #[tauri::command]
-fn abort_merge(project_path: &str) -> Result<()> {
+fn abort_merge(project_path: &str) -> CodedResult<()> {
     let path = Path::new(project_path);
-    git::abort_merge(path).map_err(map_err)
+    Ok(git::abort_merge(path)?)
 }
 
 #[tauri::command]
-fn abort_rebase(project_path: &str) -> Result<()> {
+fn abort_rebase(project_path: &str) -> CodedResult<()> {
     let path = Path::new(project_path);
-    git::abort_rebase(path).map_err(map_err)
+    Ok(git::abort_rebase(path)?)
 }
 
 #[tauri::command]
@@ -1084,10 +2138,25 @@ pub struct MergeProgress {
 }
 
 #[tauri::command]
-fn check_merge_feasibility(worktree_path: &str, project_path: Option<String>) -> Result<MergeFeasibility> {
+fn check_merge_feasibility(
+    worktree_path: &str,
+    project_path: Option<String>,
+) -> CodedResult<MergeFeasibility> {
     let path = Path::new(worktree_path);
     let cfg = config::load_config_for_project(project_path.as_deref());
-    git::check_merge_feasibility(path, &cfg.worktree.base_branch).map_err(map_err)
+    Ok(git::check_merge_feasibility(path, &cfg.worktree.base_branch)?)
+}
+
+/// Drop remote-tracking branches deleted on `remote`, returning the pruned refs
+#[tauri::command(async)]
+fn prune_remote(project_path: String, remote: String) -> CodedResult<Vec<String>> {
+    Ok(git::prune_remote(Path::new(&project_path), &remote)?)
+}
+
+/// Whether merging the worktree into `target` would be a clean fast-forward
+#[tauri::command]
+fn can_fast_forward(worktree_path: &str, target: &str) -> CodedResult<bool> {
+    Ok(git::can_fast_forward(Path::new(worktree_path), target)?)
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1122,6 +2191,68 @@ pub struct DeleteWorktreeCompleted {
     pub error: Option<String>,
 }
 
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectStatsUpdated {
+    pub project_path: String,
+    pub stats: worktree::ProjectStats,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LowDiskSpace {
+    pub project_path: String,
+    pub available_bytes: u64,
+    pub threshold_bytes: u64,
+}
+
+/// Commits a worktree's branch is ahead of and behind its base branch. Kept
+/// current afterwards by `worktree-status` events.
+#[tauri::command(async)]
+fn get_worktree_status(
+    state: State<'_, Arc<AppState>>,
+    worktree_id: String,
+) -> CodedResult<watcher::WorktreeStatus> {
+    let worktree_path = state
+        .persisted
+        .read()
+        .projects
+        .iter()
+        .flat_map(|p| &p.worktrees)
+        .find(|w| w.id == worktree_id)
+        .map(|w| w.path.clone())
+        .ok_or_else(|| worktree::WorktreeError::WorktreeNotFound(worktree_id.clone()))?;
+    let cfg = config::load_config_for_project(Some(&worktree_path));
+    let base_branch = &cfg.worktree.base_branch;
+    Ok(watcher::worktree_status(&worktree_id, Path::new(&worktree_path), base_branch)?)
+}
+
+/// Merge a worktree's branch into `into` (the repo's default branch if not
+/// given), keeping the worktree. Conflicts are left in progress for resolution.
+#[tauri::command(async)]
+fn merge_worktree(
+    state: State<'_, Arc<AppState>>,
+    worktree_id: String,
+    into: Option<String>,
+) -> CodedResult<git::MergeOutcome> {
+    let (project_path, branch) = state
+        .persisted
+        .read()
+        .projects
+        .iter()
+        .find_map(|p| {
+            let worktree = p.worktrees.iter().find(|w| w.id == worktree_id)?;
+            Some((p.path.clone(), worktree.branch.clone()))
+        })
+        .ok_or_else(|| worktree::WorktreeError::WorktreeNotFound(worktree_id.clone()))?;
+    let project_path = Path::new(&project_path);
+    let into = match into {
+        Some(into) => into,
+        None => git::default_branch(project_path)?,
+    };
+    Ok(git::merge_worktree(project_path, &branch, &into)?)
+}
+
 #[tauri::command]
 fn execute_merge_workflow(
     app: AppHandle,
@@ -1262,7 +2393,11 @@ fn execute_merge_workflow(
                 },
             );
 
-            if let Err(e) = git::delete_remote_branch(project_path, &branch_name) {
+            let mut on_progress = |progress| {
+                let _ = app.emit("git-progress", progress);
+            };
+            if let Err(e) = git::delete_remote_branch(project_path, &branch_name, &mut on_progress)
+            {
                 info!("Failed to delete remote branch: {}", e);
             }
         }
@@ -1399,7 +2534,11 @@ fn cleanup_worktree(
                 },
             );
 
-            if let Err(e) = git::delete_remote_branch(project_path, &branch_name) {
+            let mut on_progress = |progress| {
+                let _ = app.emit("git-progress", progress);
+            };
+            if let Err(e) = git::delete_remote_branch(project_path, &branch_name, &mut on_progress)
+            {
                 info!("Failed to delete remote branch: {}", e);
             }
         }
@@ -1545,12 +2684,18 @@ fn open_in_terminal(path: &str, app: Option<&str>) -> Result<()> {
 /// - `app`: Editor command. If None, uses $VISUAL or $EDITOR.
 /// - `target`: "external" (GUI app) or "terminal" (TUI in new terminal window).
 /// - `terminal_app`: Terminal to use when target is "terminal".
+/// - `line`: Line to open the file at, using `line_format`, the configured
+///   editor's `lineFormat`, or the editor's default.
+/// - `worktree_path`: When set, `path` may be relative and must resolve inside this worktree.
 #[tauri::command]
 fn open_in_editor(
     path: &str,
     app: Option<&str>,
     target: Option<&str>,
     terminal_app: Option<&str>,
+    line: Option<usize>,
+    line_format: Option<&str>,
+    worktree_path: Option<&str>,
 ) -> Result<()> {
     let editor = app
         .map(|s| s.to_string())
@@ -1558,13 +2703,87 @@ fn open_in_editor(
         .or_else(|| std::env::var("EDITOR").ok())
         .ok_or_else(|| "No editor configured. Set $VISUAL or $EDITOR, or configure apps.editor.".to_string())?;
 
+    // When opening a file inside a worktree, refuse paths that escape it
+    let path = match worktree_path {
+        Some(worktree) => editor::resolve_in_worktree(Path::new(worktree), path)?
+            .to_string_lossy()
+            .to_string(),
+        None => path.to_string(),
+    };
+    let cfg = line.is_some().then(|| config::load_config_for_project(worktree_path));
+    let line_format = line_format.or_else(|| cfg.as_ref()?.apps.editor_line_format());
+    let editor = editor::command_at_line(&editor, line, line_format);
+
     // Target "terminal": run editor inside a new terminal window
     if target == Some("terminal") {
-        return open_terminal_with_command(&editor, path, terminal_app);
+        return open_terminal_with_command(&editor, &path, terminal_app);
     }
 
     // Target "external": run editor directly (for GUI editors)
-    open_with_app(path, &editor)
+    open_with_app(&path, &editor)
+}
+
+/// Check a custom editor command before it is saved to settings
+#[tauri::command]
+fn validate_editor_command(cmd: &str) -> CodedResult<()> {
+    Ok(settings::validate_editor_command(cmd)?)
+}
+
+/// Open a worktree's most recently modified changed file in an editor, taking
+/// the same editor options as `open_in_editor`. Returns the opened file, or
+/// None if the worktree has no changes to open.
+#[tauri::command]
+fn open_last_changed_file(
+    state: State<'_, Arc<AppState>>,
+    worktree_id: &str,
+    app: Option<&str>,
+    target: Option<&str>,
+    terminal_app: Option<&str>,
+) -> CodedResult<Option<String>> {
+    let worktree_path = state
+        .persisted
+        .read()
+        .projects
+        .iter()
+        .flat_map(|p| p.worktrees.iter())
+        .find(|w| w.id == worktree_id)
+        .map(|w| w.path.clone())
+        .ok_or_else(|| worktree::WorktreeError::WorktreeNotFound(worktree_id.to_string()))?;
+
+    let Some(file) = worktree::last_changed_file(Path::new(&worktree_path))?
+    else {
+        return Ok(None);
+    };
+    let file = file.to_string_lossy().to_string();
+    open_in_editor(&file, app, target, terminal_app, None, None, Some(&worktree_path))
+        .map_err(|message| CodedError { code: "editor.open_failed", message })?;
+    Ok(Some(file))
+}
+
+/// Open one of a project's docs in an editor, taking the same editor options
+/// as `open_in_editor`. `doc` must be one `find_project_docs` returned; when
+/// omitted the first is opened. Returns the opened path, or None if the
+/// project has no docs.
+#[tauri::command]
+fn open_project_doc(
+    state: State<'_, Arc<AppState>>,
+    project_path: &str,
+    doc: Option<String>,
+    app: Option<&str>,
+    target: Option<&str>,
+    terminal_app: Option<&str>,
+) -> Result<Option<String>> {
+    let docs = find_project_docs(state, project_path)?;
+    let doc = match doc {
+        Some(doc) if docs.contains(&doc) => doc,
+        Some(doc) => return Err(format!("Not a project doc: {}", doc)),
+        None => match docs.into_iter().next() {
+            Some(doc) => doc,
+            None => return Ok(None),
+        },
+    };
+    open_in_editor(&doc, app, target, terminal_app, None, None, Some(project_path))?;
+    Ok(Some(doc))
 }
 
 /// Ensure a shellflow config file exists and return its path.
@@ -1704,10 +2923,10 @@ fn rename_worktree(
     state: State<'_, Arc<AppState>>,
     worktree_id: &str,
     new_name: &str,
-) -> Result<()> {
+) -> CodedResult<()> {
     // Validate the new name
     if let Some(error) = git::validate_branch_name(new_name) {
-        return Err(error);
+        return Err(git::GitError::InvalidBranchName(error).into());
     }
 
     let mut persisted = state.persisted.write();
@@ -1716,12 +2935,18 @@ fn rename_worktree(
     let mut found = None;
     for project in &mut persisted.projects {
         if let Some(worktree) = project.worktrees.iter_mut().find(|w| w.id == worktree_id) {
+            if worktree.detached {
+                return Err(CodedError::invalid_argument(
+                    "Detached worktrees have no branch to rename",
+                ));
+            }
             found = Some((project.path.clone(), worktree.branch.clone()));
             break;
         }
     }
 
-    let (project_path, old_name) = found.ok_or_else(|| format!("Worktree not found: {}", worktree_id))?;
+    let (project_path, old_name) = found
+        .ok_or_else(|| worktree::WorktreeError::WorktreeNotFound(worktree_id.to_string()))?;
 
     // Check if new name is same as old name
     if old_name == new_name {
@@ -1730,12 +2955,12 @@ fn rename_worktree(
 
     // Check if the new branch name already exists
     let project_path = std::path::Path::new(&project_path);
-    if git::branch_exists(project_path, new_name).map_err(map_err)? {
-        return Err(format!("Branch '{}' already exists", new_name));
+    if git::branch_exists(project_path, new_name)? {
+        return Err(git::GitError::BranchExists(new_name.to_string()).into());
     }
 
     // Rename the git branch
-    git::rename_branch(project_path, &old_name, new_name).map_err(map_err)?;
+    git::rename_branch(project_path, &old_name, new_name)?;
 
     // Update the worktree state
     for project in &mut persisted.projects {
@@ -1747,7 +2972,7 @@ fn rename_worktree(
     }
 
     drop(persisted);
-    state.save().map_err(map_err)?;
+    state.save()?;
 
     info!("[rename_worktree] Renamed worktree {} from '{}' to '{}'", worktree_id, old_name, new_name);
     Ok(())
@@ -1782,49 +3007,98 @@ fn reorder_projects(state: State<'_, Arc<AppState>>, project_ids: Vec<String>) -
     Ok(())
 }
 
-/// Reorder worktrees within a project by providing the new order of worktree IDs
+/// Sent after a project's worktrees are reordered, so other windows and the
+/// Session N shortcuts pick up the new order
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct WorktreesReordered {
+    project_id: String,
+    worktree_ids: Vec<String>,
+}
+
+/// Reorder worktrees within a project by providing the new order of worktree IDs.
+/// The ids must be exactly the project's worktrees.
 #[tauri::command]
 fn reorder_worktrees(
+    app: AppHandle,
     state: State<'_, Arc<AppState>>,
     project_id: &str,
     worktree_ids: Vec<String>,
-) -> Result<()> {
-    let mut persisted = state.persisted.write();
+) -> CodedResult<()> {
+    {
+        let mut persisted = state.persisted.write();
+        let project = persisted
+            .projects
+            .iter_mut()
+            .find(|p| p.id == project_id)
+            .ok_or_else(|| worktree::WorktreeError::ProjectNotFound(project_id.to_string()))?;
+        worktree::reorder_worktrees(project, &worktree_ids)?;
+    }
+    state.save()?;
 
-    let project = persisted
-        .projects
-        .iter_mut()
-        .find(|p| p.id == project_id)
-        .ok_or_else(|| format!("Project not found: {}", project_id))?;
+    info!("[reorder_worktrees] Reordered {} worktrees in project {}", worktree_ids.len(), project_id);
+    let _ = app.emit(
+        "worktrees-reordered",
+        WorktreesReordered { project_id: project_id.to_string(), worktree_ids },
+    );
+    Ok(())
+}
 
-    // Create order map from the provided order
-    let order_map: std::collections::HashMap<String, i32> = worktree_ids
-        .iter()
-        .enumerate()
-        .map(|(i, id)| (id.clone(), i as i32))
-        .collect();
+// Update menu item enabled states based on action availability from frontend
+/// Menu items backed by native integrations, with the reason any are unavailable
+#[tauri::command]
+fn menu_capabilities() -> Vec<menu::MenuCapability> {
+    menu::menu_capabilities()
+}
 
-    // Update worktree orders
-    for worktree in &mut project.worktrees {
-        if let Some(&order) = order_map.get(&worktree.id) {
-            worktree.order = order;
-        }
-    }
+/// Give the main window the geometry last used with a project (kept on a
+/// visible screen) and remember future moves and resizes for it
+#[tauri::command]
+fn restore_project_window(app: AppHandle, project_id: &str) -> Option<state::WindowBounds> {
+    windows::restore_project_bounds(&app, project_id)
+}
 
-    // Sort worktrees by order
-    project.worktrees.sort_by_key(|w| w.order);
+/// Refresh the changed files of every open worktree, as happens when the main
+/// window regains focus. Returns how many were refreshed (0 when throttled).
+#[tauri::command(async)]
+fn on_focus_refresh(app: AppHandle) -> usize {
+    watcher::on_focus_refresh(&app)
+}
 
-    drop(persisted);
-    state.save().map_err(map_err)?;
+/// Why a menu item is grayed out, for tooltips. None if it's enabled.
+#[tauri::command]
+fn menu_disabled_reason(id: &str) -> Option<String> {
+    menu::disabled_reason(id)
+}
 
-    info!("[reorder_worktrees] Reordered {} worktrees in project {}", worktree_ids.len(), project_id);
-    Ok(())
+/// Select the entry at 1-based index `n` of the frontend's sidebar `entries`
+/// (the Session N shortcuts)
+#[tauri::command]
+fn activate_index(
+    app: AppHandle,
+    entries: Vec<navigation::NavEntry>,
+    n: usize,
+) -> CodedResult<navigation::NavEntry> {
+    Ok(navigation::activate_index(&app, &entries, n)?)
 }
 
-// Update menu item enabled states based on action availability from frontend
 #[tauri::command]
-fn update_action_availability(availability: HashMap<String, bool>) {
-    menu::update_action_availability(availability);
+fn update_action_availability(
+    availability: HashMap<String, bool>,
+    active_project_id: Option<String>,
+) -> HashMap<String, bool> {
+    menu::update_action_availability(availability, active_project_id)
+}
+
+#[tauri::command]
+fn apply_mapping_changes(changes: HashMap<String, String>) -> menu::MappingChangeReport {
+    menu::apply_mapping_changes(changes)
+}
+
+/// Remap a single menu item at runtime, e.g. `session3` for the Session 3 item
+#[tauri::command]
+fn set_menu_accelerator(id: String, shortcut: String) -> menu::MappingChangeReport {
+    menu::apply_mapping_changes(HashMap::from([(id, shortcut)]))
 }
 
 // Shutdown command - gracefully terminates all PTY processes
@@ -1891,11 +3165,16 @@ pub fn run() {
             let config = config::load_config();
 
             // Set up application menu
-            menu::setup_menu(app, &config.mappings)?;
+            menu::setup_menu(app, &config.mappings, &config.apps)?;
+
+            let app_state = app.state::<Arc<AppState>>();
+            if let Some(recovery) = app_state.recovery.read().clone() {
+                let _ = app.handle().emit("state-recovery", recovery);
+            }
+            warn_if_base_dir_nested(app.handle());
 
             // Start file watchers for worktrees in active projects only
             // This enables detection of externally deleted worktree folders
-            let app_state = app.state::<Arc<AppState>>();
             let persisted = app_state.persisted.read();
             for project in persisted.projects.iter().filter(|p| p.is_active) {
                 for wt in &project.worktrees {
@@ -1905,6 +3184,13 @@ pub fn run() {
                         wt.path.clone(),
                     );
                 }
+                watcher::watch_main_head(
+                    app.handle().clone(),
+                    project.id.clone(),
+                    project.path.clone(),
+                );
+                // Don't leave watchers erroring on a project deleted while we were closed
+                watcher::check_project_paths(app.handle(), project);
             }
 
             Ok(())
@@ -1912,10 +3198,34 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             add_project,
             list_projects,
+            get_project_display_paths,
             hide_project,
             touch_project,
+            touch_worktree,
+            get_worktree_relative_path,
+            check_project_paths,
+            save_layout,
+            list_layouts,
+            restore_layout,
             create_worktree,
+            preview_worktree_path,
+            set_worktree_layout,
+            validate_worktree_base_dir,
+            check_worktree_writable,
+            create_detached_worktree,
+            promote_scratch_to_worktree,
+            get_worktree_base_branch,
             list_worktrees,
+            find_worktree_by_path,
+            find_project_docs,
+            get_state_recovery,
+            open_project_doc,
+            get_status_badge,
+            get_project_stats,
+            get_disk_usage_breakdown,
+            get_diverged_worktrees,
+            get_stale_worktrees,
+            get_free_space,
             delete_worktree,
             check_worktree_delete_status,
             execute_delete_worktree_workflow,
@@ -1928,6 +3238,8 @@ pub fn run() {
             open_with_app,
             open_in_terminal,
             open_in_editor,
+            validate_editor_command,
+            open_last_changed_file,
             get_config_file_path,
             open_in_file_manager,
             open_default,
@@ -1941,18 +3253,58 @@ pub fn run() {
             stop_merge_watcher,
             watch_rebase_state,
             stop_rebase_watcher,
+            get_watcher_stats,
+            set_watcher_compare_contents,
+            is_git_busy,
             spawn_project_shell,
             spawn_task,
             get_task_urls,
+            suggest_tasks,
+            list_git_aliases,
+            get_alias_tasks,
+            cancel_task,
+            list_running_tasks,
+            run_last_task,
+            set_task_auto_run,
+            get_task_auto_run,
+            set_pause_watcher_during_tasks,
+            get_pause_watcher_during_tasks,
+            tail_task_log,
+            follow_task_log,
+            stop_following_task_log,
             pty_write,
             pty_resize,
             pty_interrupt,
             pty_kill,
             pty_force_kill,
             get_changed_files,
+            get_changed_files_tree,
+            toggle_changed_files_view_mode,
+            toggle_ignored_files,
             get_branch_info,
             get_branch_changed_files,
+            get_changes_since_fork,
+            diff_worktree_branches,
+            export_change_list,
+            get_default_branch,
+            get_commit_count_since_fork,
+            remote_branch_exists,
+            wip_snapshot,
+            undo_wip_snapshot,
+            get_git_config,
+            set_git_config,
+            run_git_command,
             get_file_diff_content,
+            stage_all,
+            unstage_all,
+            discard_all,
+            export_patch,
+            apply_patch,
+            get_staged_summary,
+            get_config_fingerprint,
+            get_main_head,
+            get_last_commits,
+            format_local_time,
             has_uncommitted_changes,
             stash_changes,
             stash_pop,
@@ -1963,15 +3315,33 @@ pub fn run() {
             get_config,
             watch_config,
             stop_config_watcher,
+            app_info,
+            run_diagnostics,
+            repair_duplicate_worktrees,
             get_mappings,
+            get_actions_for_chord,
+            would_mapping_conflict,
+            get_os_reserved_conflicts,
+            export_key_map,
             watch_mappings,
             stop_mappings_watcher,
             expand_action_prompt,
             check_merge_feasibility,
+            can_fast_forward,
+            prune_remote,
             execute_merge_workflow,
+            merge_worktree,
+            get_worktree_status,
             cleanup_worktree,
             shutdown,
+            on_focus_refresh,
+            restore_project_window,
+            menu_disabled_reason,
+            activate_index,
             update_action_availability,
+            apply_mapping_changes,
+            set_menu_accelerator,
+            menu_capabilities,
             theme::list_themes,
             theme::read_theme,
         ])
@@ -1987,6 +3357,16 @@ pub fn run() {
                     // Final cleanup (in case frontend didn't trigger shutdown)
                     watcher::stop_all_watchers();
                 }
+                tauri::WindowEvent::Moved(_) | tauri::WindowEvent::Resized(_)
+                    if window.label() == "main" =>
+                {
+                    windows::main_window_bounds_changed(window.app_handle());
+                }
+                tauri::WindowEvent::Focused(true) if window.label() == "main" => {
+                    // Files may have changed while we weren't looking; git runs off the UI thread
+                    let app = window.app_handle().clone();
+                    std::thread::spawn(move || watcher::on_focus_refresh(&app));
+                }
                 _ => {}
             }
         })
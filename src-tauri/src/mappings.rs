@@ -154,6 +154,199 @@ pub fn load_mappings() -> MappingsResult {
     MappingsResult { mappings, errors }
 }
 
+/// Modifiers in the order `keyEventToString` emits them on the frontend
+const MODIFIERS: [&str; 4] = ["cmd", "ctrl", "alt", "shift"];
+
+/// Normalize one keystroke: lowercase, `+` as separator, modifiers in canonical order.
+fn normalize_stroke(stroke: &str) -> String {
+    let mut rest = stroke.replace('+', "-");
+    let mut modifiers = [false; 4];
+
+    // Peel off leading modifiers; whatever remains is the key (which may itself be "-")
+    'peel: loop {
+        for (i, modifier) in MODIFIERS.iter().enumerate() {
+            if let Some(stripped) = rest.strip_prefix(&format!("{}-", modifier)) {
+                if !stripped.is_empty() {
+                    modifiers[i] = true;
+                    rest = stripped.to_string();
+                    continue 'peel;
+                }
+            }
+        }
+        break;
+    }
+
+    let mut parts: Vec<&str> = MODIFIERS
+        .iter()
+        .zip(modifiers)
+        .filter(|(_, on)| *on)
+        .map(|(m, _)| *m)
+        .collect();
+    parts.push(&rest);
+    parts.join("-")
+}
+
+/// Normalize a chord (one or more space-separated keystrokes) the way the
+/// frontend's `normalizeKey` does, so `Shift+Cmd+P` and `cmd-shift-p` compare equal.
+pub fn normalize_chord(chord: &str) -> String {
+    chord
+        .to_lowercase()
+        .split_whitespace()
+        .map(normalize_stroke)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Action id of a binding, without any arguments
+fn action_id(action: &Action) -> Option<&str> {
+    match action {
+        Action::Simple(id) => Some(id),
+        Action::WithArgs(args) => args.first().and_then(|v| v.as_str()),
+    }
+}
+
+/// All action ids bound to `chord` in any context, in binding order.
+fn actions_bound_to(mappings: &RawMappings, chord: &str) -> Vec<String> {
+    let chord = normalize_chord(chord);
+    let mut actions: Vec<String> = Vec::new();
+
+    for group in &mappings.bindings {
+        // Bindings are a map, so sort keys for a stable order within a group
+        let mut matching: Vec<(&String, &Action)> = group
+            .bindings
+            .iter()
+            .filter(|(key, _)| normalize_chord(key) == chord)
+            .collect();
+        matching.sort_by_key(|(key, _)| *key);
+
+        for (_, action) in matching {
+            if let Some(id) = action_id(action) {
+                if !actions.iter().any(|a| a == id) {
+                    actions.push(id.to_string());
+                }
+            }
+        }
+    }
+
+    actions
+}
+
+/// All action ids the merged mappings (defaults + user) bind to `chord`,
+/// so the settings UI can warn about clashes before a mapping is saved.
+pub fn actions_for_chord(chord: &str) -> Vec<String> {
+    actions_bound_to(&load_mappings().mappings, chord)
+}
+
+/// First other action bound to `chord`; the action itself never conflicts.
+fn conflicting_action(mappings: &RawMappings, action_id: &str, chord: &str) -> Option<String> {
+    actions_bound_to(mappings, chord)
+        .into_iter()
+        .find(|id| id != action_id)
+}
+
+/// Pre-flight check before binding `chord` to `action_id`: the id of another
+/// action already bound to that chord, if any.
+pub fn would_conflict(action_id: &str, chord: &str) -> Option<String> {
+    conflicting_action(&load_mappings().mappings, action_id, chord)
+}
+
+/// Chords the OS handles before the app sees them, so bindings to them never
+/// fire. In `normalize_chord` form. Off macOS, `cmd` means Ctrl, so those lists
+/// only use `ctrl` (the Windows/Super key can't be bound).
+const MACOS_RESERVED: &[&str] = &[
+    "cmd-tab",
+    "cmd-shift-tab",
+    "cmd-space",
+    "ctrl-space",
+    "cmd-alt-escape",
+    "cmd-ctrl-q",
+    "cmd-shift-3",
+    "cmd-shift-4",
+    "cmd-shift-5",
+    "cmd-alt-d",
+    "ctrl-up",
+    "ctrl-down",
+    "ctrl-left",
+    "ctrl-right",
+];
+
+const WINDOWS_RESERVED: &[&str] = &[
+    "alt-tab",
+    "alt-shift-tab",
+    "alt-escape",
+    "alt-space",
+    "alt-f4",
+    "ctrl-escape",
+    "ctrl-alt-delete",
+    "ctrl-shift-escape",
+];
+
+const LINUX_RESERVED: &[&str] = &[
+    "alt-tab",
+    "alt-shift-tab",
+    "alt-space",
+    "alt-f2",
+    "alt-f4",
+    "ctrl-alt-delete",
+    "ctrl-alt-t",
+    "ctrl-alt-up",
+    "ctrl-alt-down",
+    "ctrl-alt-left",
+    "ctrl-alt-right",
+];
+
+fn reserved_chords(os: &str) -> &'static [&'static str] {
+    match os {
+        "macos" => MACOS_RESERVED,
+        "windows" => WINDOWS_RESERVED,
+        _ => LINUX_RESERVED,
+    }
+}
+
+/// (action id, chord) for each binding whose first keystroke `os` reserves,
+/// sorted by action id then chord
+fn reserved_conflicts(mappings: &RawMappings, os: &str) -> Vec<(String, String)> {
+    let reserved = reserved_chords(os);
+    let mut conflicts: Vec<(String, String)> = Vec::new();
+
+    for group in &mappings.bindings {
+        for (chord, action) in &group.bindings {
+            let normalized = normalize_chord(chord);
+            let first = normalized.split(' ').next().unwrap_or_default();
+            // Off macOS `cmd` is Ctrl; renormalize so `cmd-ctrl-x` collapses to `ctrl-x`
+            let first = if os == "macos" {
+                first.to_string()
+            } else {
+                normalize_stroke(&first.replace("cmd-", "ctrl-"))
+            };
+            if !reserved.contains(&first.as_str()) {
+                continue;
+            }
+            if let Some(id) = action_id(action) {
+                let conflict = (id.to_string(), chord.clone());
+                if !conflicts.contains(&conflict) {
+                    conflicts.push(conflict);
+                }
+            }
+        }
+    }
+
+    conflicts.sort();
+    conflicts
+}
+
+/// Bindings in the merged mappings that collide with a chord this platform
+/// reserves, as (action id, chord), for flagging in settings
+pub fn os_reserved_conflicts() -> Vec<(String, String)> {
+    reserved_conflicts(&load_mappings().mappings, std::env::consts::OS)
+}
+
+/// Menu item id to accelerator string for every mapped menu item, exactly as
+/// the menu shows them, for tools that want the keymap as data
+pub fn export_map() -> HashMap<String, String> {
+    crate::menu::menu_accelerators(&crate::config::load_config().mappings)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -201,6 +394,95 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_normalize_chord() {
+        assert_eq!(normalize_chord("Shift+Cmd+P"), "cmd-shift-p");
+        assert_eq!(normalize_chord("ctrl-cmd-1"), "cmd-ctrl-1");
+        assert_eq!(normalize_chord("cmd--"), "cmd--");
+        assert_eq!(normalize_chord("CMD-K  cmd-S"), "cmd-k cmd-s");
+        assert_eq!(normalize_chord("shift-Escape"), "shift-escape");
+    }
+
+    #[test]
+    fn test_actions_for_chord_returns_every_binding() {
+        let json = r#"{
+            "bindings": [
+                { "bindings": { "cmd-shift-p": "palette::toggle", "cmd-w": "drawer::closeTab" } },
+                {
+                    "context": "drawerFocused",
+                    "bindings": { "shift-cmd-p": ["navigate::toEntity", 0] }
+                }
+            ]
+        }"#;
+        let mappings: RawMappings = serde_json::from_str(json).unwrap();
+
+        assert_eq!(
+            actions_bound_to(&mappings, "Cmd+Shift+P"),
+            vec!["palette::toggle", "navigate::toEntity"]
+        );
+        assert_eq!(actions_bound_to(&mappings, "cmd-w"), vec!["drawer::closeTab"]);
+        assert!(actions_bound_to(&mappings, "cmd-q").is_empty());
+    }
+
+    #[test]
+    fn test_would_conflict() {
+        let json = r#"{
+            "bindings": [
+                { "bindings": { "cmd-shift-p": "palette::toggle", "cmd-w": "drawer::closeTab" } },
+                { "context": "drawerFocused", "bindings": { "cmd-w": "session::closeTab" } }
+            ]
+        }"#;
+        let mappings: RawMappings = serde_json::from_str(json).unwrap();
+
+        assert_eq!(conflicting_action(&mappings, "palette::toggle", "cmd-k"), None);
+        assert_eq!(
+            conflicting_action(&mappings, "drawer::closeTab", "Shift+Cmd+P"),
+            Some("palette::toggle".to_string())
+        );
+        assert_eq!(conflicting_action(&mappings, "palette::toggle", "shift-cmd-p"), None);
+        assert_eq!(
+            conflicting_action(&mappings, "drawer::closeTab", "cmd-w"),
+            Some("session::closeTab".to_string())
+        );
+    }
+
+    #[test]
+    fn test_reserved_conflicts_flag_os_chords() {
+        let json = r#"{
+            "bindings": [
+                { "bindings": { "cmd-space": "palette::toggle", "cmd-shift-p": "palette::toggle" } },
+                {
+                    "context": "drawerFocused",
+                    "bindings": { "alt-F4 x": "drawer::closeTab", "cmd-alt-t": "drawer::newTab" }
+                }
+            ]
+        }"#;
+        let mappings: RawMappings = serde_json::from_str(json).unwrap();
+        let pair = |id: &str, chord: &str| (id.to_string(), chord.to_string());
+
+        assert_eq!(
+            reserved_conflicts(&mappings, "macos"),
+            vec![pair("palette::toggle", "cmd-space")]
+        );
+        // Off macOS cmd is Ctrl, and a reserved first keystroke blocks the whole sequence
+        assert_eq!(
+            reserved_conflicts(&mappings, "linux"),
+            vec![pair("drawer::closeTab", "alt-F4 x"), pair("drawer::newTab", "cmd-alt-t")]
+        );
+        assert_eq!(
+            reserved_conflicts(&mappings, "windows"),
+            vec![pair("drawer::closeTab", "alt-F4 x")]
+        );
+    }
+
+    #[test]
+    fn test_default_mappings_avoid_reserved_chords() {
+        let defaults = load_default_mappings().unwrap();
+        for os in ["macos", "windows", "linux"] {
+            assert_eq!(reserved_conflicts(&defaults, os), vec![], "{}", os);
+        }
+    }
+
     #[test]
     fn test_merge_mappings() {
         let defaults = RawMappings {
@@ -230,4 +512,19 @@ mod tests {
         // User group should be last (higher priority)
         assert_eq!(merged.bindings[1].context, Some("drawerFocused".to_string()));
     }
+
+    #[test]
+    fn test_export_map_follows_the_configured_shortcuts() {
+        let mut mappings = crate::config::MappingsConfig::default();
+        let exported = crate::menu::menu_accelerators(&mappings);
+
+        assert_eq!(exported.len(), 32);
+        assert_eq!(exported["app::quit"], mappings.quit.to_accelerator());
+        assert_eq!(exported["navigate::toEntity9"], mappings.session9.to_accelerator());
+        assert!(exported.values().all(|accelerator| !accelerator.is_empty()));
+
+        mappings.quit = crate::config::Shortcut::Universal("cmd+shift+q".to_string());
+        let exported = crate::menu::menu_accelerators(&mappings);
+        assert_eq!(exported["app::quit"], "Cmd+Shift+Q");
+    }
 }
@@ -1,19 +1,33 @@
-use parking_lot::RwLock;
-use std::collections::HashMap;
+use parking_lot::{Mutex, RwLock};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
 use tauri::menu::{MenuBuilder, MenuItem, MenuItemBuilder, PredefinedMenuItem, SubmenuBuilder};
-use tauri::{Emitter, Manager};
+use tauri::{AppHandle, Emitter, Manager};
 
-use crate::config::MappingsConfig;
+use crate::config::{self, AppsConfig, MappingsConfig, Shortcut};
+use crate::windows::{self, AuxWindow};
 
 /// Holds references to menu items that can be dynamically enabled/disabled.
 pub struct DynamicMenuItems {
+    /// For reporting availability changes to the frontend
+    app: AppHandle,
     items: HashMap<String, MenuItem<tauri::Wry>>,
+    /// Items with a key mapping, with the accelerator they currently show
+    accelerators: HashMap<String, (MenuItem<tauri::Wry>, String)>,
+    /// Why each currently disabled item is disabled, for tooltips
+    disabled_reasons: HashMap<String, String>,
+    /// Enabled state last applied to each item
+    enabled: HashMap<String, bool>,
 }
 
 impl DynamicMenuItems {
-    pub fn new() -> Self {
+    pub fn new(app: AppHandle) -> Self {
         Self {
+            app,
             items: HashMap::new(),
+            accelerators: HashMap::new(),
+            disabled_reasons: HashMap::new(),
+            enabled: HashMap::new(),
         }
     }
 
@@ -21,27 +35,556 @@ impl DynamicMenuItems {
         self.items.insert(id.to_string(), item);
     }
 
-    /// Update menu item enabled states based on the provided availability map
-    pub fn update_availability(&self, availability: &HashMap<String, bool>) {
-        for (id, enabled) in availability {
-            if let Some(item) = self.items.get(id) {
-                let _ = item.set_enabled(*enabled);
+    pub fn track_accelerator(
+        &mut self,
+        id: &str,
+        item: MenuItem<tauri::Wry>,
+        accelerator: String,
+    ) {
+        self.accelerators.insert(id.to_string(), (item, accelerator));
+    }
+
+    /// Apply the changed accelerators from a mappings diff, leaving other items untouched
+    pub fn apply_mapping_changes(
+        &mut self,
+        changes: &HashMap<String, String>,
+    ) -> MappingChangeReport {
+        let current = self
+            .accelerators
+            .iter()
+            .map(|(id, (_, accelerator))| (id.clone(), accelerator.clone()))
+            .collect();
+        let (updates, mut report) = plan_accelerator_changes(&current, changes);
+
+        for (id, accelerator) in updates {
+            let Some((item, shown)) = self.accelerators.get_mut(&id) else {
+                continue;
+            };
+            match item.set_accelerator(Some(&accelerator)) {
+                Ok(()) => {
+                    *shown = accelerator;
+                    report.updated.push(id);
+                }
+                Err(e) => report.skipped.push(SkippedMapping {
+                    action_id: id,
+                    accelerator,
+                    reason: e.to_string(),
+                }),
             }
         }
+        report
     }
+
+    /// Update menu item enabled states based on the provided availability map,
+    /// remembering the reason for each item that ends up disabled. Returns the
+    /// items whose enabled state changed, with their new state.
+    pub fn update_availability(
+        &mut self,
+        availability: &HashMap<String, bool>,
+        reasons: &HashMap<String, String>,
+    ) -> HashMap<String, bool> {
+        let known: HashMap<String, bool> = availability
+            .iter()
+            .filter(|(id, _)| self.items.contains_key(*id))
+            .map(|(id, enabled)| (id.clone(), *enabled))
+            .collect();
+        let changes = availability_changes(&mut self.enabled, &known);
+        for (id, enabled) in &changes {
+            let _ = self.items[id].set_enabled(*enabled);
+        }
+
+        for (id, enabled) in &known {
+            match reasons.get(id) {
+                Some(reason) if !*enabled => {
+                    self.disabled_reasons.insert(id.clone(), reason.clone());
+                }
+                _ => {
+                    self.disabled_reasons.remove(id);
+                }
+            }
+        }
+        changes
+    }
+
+    /// Why the item is disabled, or None if it's enabled or unknown
+    pub fn disabled_reason(&self, id: &str) -> Option<String> {
+        self.disabled_reasons.get(id).cloned()
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MappingChangeReport {
+    /// Actions whose menu accelerator was changed
+    pub updated: Vec<String>,
+    pub skipped: Vec<SkippedMapping>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedMapping {
+    pub action_id: String,
+    pub accelerator: String,
+    pub reason: String,
+}
+
+const ACCELERATOR_MODIFIERS: &[&str] = &[
+    "cmd", "command", "cmdorctrl", "commandorcontrol", "ctrl", "control", "alt", "option",
+    "shift", "super",
+];
+
+const ACCELERATOR_KEYS: &[&str] = &[
+    "escape", "esc", "space", "enter", "return", "backspace", "tab", "delete", "home", "end",
+    "pageup", "pagedown", "up", "down", "left", "right", "arrowup", "arrowdown", "arrowleft",
+    "arrowright", "plus",
+];
+
+/// Check that `accelerator` is modifiers followed by exactly one key the menu can display.
+fn validate_accelerator(accelerator: &str) -> Result<(), String> {
+    let parts: Vec<&str> = accelerator.split('+').collect();
+    let (key, modifiers) = parts.split_last().ok_or("Empty accelerator")?;
+
+    for modifier in modifiers {
+        if !ACCELERATOR_MODIFIERS.contains(&modifier.to_lowercase().as_str()) {
+            return Err(format!("Unknown modifier '{}'", modifier));
+        }
+    }
+
+    let lower = key.to_lowercase();
+    let is_function_key = lower
+        .strip_prefix('f')
+        .and_then(|n| n.parse::<u8>().ok())
+        .is_some_and(|n| (1..=24).contains(&n));
+    let is_char = key.chars().count() == 1 && key.chars().all(|c| c.is_ascii_graphic());
+    if is_char || is_function_key || ACCELERATOR_KEYS.contains(&lower.as_str()) {
+        Ok(())
+    } else if key.is_empty() || ACCELERATOR_MODIFIERS.contains(&lower.as_str()) {
+        Err("Missing key".to_string())
+    } else {
+        Err(format!("Unknown key '{}'", key))
+    }
+}
+
+/// Menu item id for a mappings diff key. Besides item ids, the `sessionN`
+/// mapping names are accepted for the Session N items.
+fn mapped_item_id(key: &str) -> String {
+    match key.strip_prefix("session").and_then(|n| n.parse::<usize>().ok()) {
+        Some(n) if (1..=9).contains(&n) => format!("navigate::toEntity{}", n),
+        _ => key.to_string(),
+    }
+}
+
+/// Split a mappings diff (action id to shortcut, in config format) into the
+/// accelerators that actually change and a report of the invalid ones.
+/// Actions without a menu item are not the menu's concern and are ignored.
+fn plan_accelerator_changes(
+    current: &HashMap<String, String>,
+    changes: &HashMap<String, String>,
+) -> (Vec<(String, String)>, MappingChangeReport) {
+    let mut updates = Vec::new();
+    let mut report = MappingChangeReport::default();
+
+    for (key, shortcut) in changes {
+        let id = mapped_item_id(key);
+        let Some(shown) = current.get(&id) else {
+            continue;
+        };
+        let accelerator = config::shortcut_to_accelerator(shortcut);
+        if &accelerator == shown {
+            continue;
+        }
+        match validate_accelerator(&accelerator) {
+            Ok(()) => updates.push((id, accelerator)),
+            Err(reason) => report.skipped.push(SkippedMapping {
+                action_id: id,
+                accelerator,
+                reason,
+            }),
+        }
+    }
+    updates.sort();
+    updates.dedup_by(|a, b| a.0 == b.0);
+    report.skipped.sort_by(|a, b| a.action_id.cmp(&b.action_id));
+    (updates, report)
+}
+
+/// Record `availability` as applied, returning the entries that differ from
+/// what was applied before. Items seen for the first time count as changed.
+fn availability_changes(
+    applied: &mut HashMap<String, bool>,
+    availability: &HashMap<String, bool>,
+) -> HashMap<String, bool> {
+    availability
+        .iter()
+        .filter(|(id, enabled)| applied.insert((*id).clone(), **enabled) != Some(**enabled))
+        .map(|(id, enabled)| (id.clone(), *enabled))
+        .collect()
+}
+
+/// Availability reported by each source. Kept separately so that an update
+/// from one source is always re-merged with the latest from the other,
+/// instead of the two overwriting each other and flickering the menu.
+#[derive(Default)]
+struct AvailabilitySources {
+    /// Derived in Rust (e.g. missing native integrations). Wins for ids it knows.
+    rust: HashMap<String, bool>,
+    /// Hints from the frontend about the current UI context
+    frontend: HashMap<String, bool>,
+    /// Projects whose directory has disappeared from disk
+    missing_projects: HashSet<String>,
+    /// Project selected in the frontend; project actions act on this one
+    active_project: Option<String>,
+    /// Entries in the sidebar, once known. Session N items beyond it are disabled.
+    navigable_count: Option<usize>,
+}
+
+impl AvailabilitySources {
+    /// Whether the selected project's directory is missing
+    fn active_project_missing(&self) -> bool {
+        self.active_project
+            .as_ref()
+            .is_some_and(|id| self.missing_projects.contains(id))
+    }
+}
+
+/// Actions that touch a project's files and fail while its directory is missing
+const PROJECT_PATH_ACTIONS: [&str; 7] = [
+    "worktree::new",
+    "worktree::merge",
+    "worktree::delete",
+    "task::run",
+    "app::openInFinder",
+    "app::openInTerminal",
+    "app::openInEditor",
+];
+
+lazy_static::lazy_static! {
+    static ref AVAILABILITY: Mutex<AvailabilitySources> = Mutex::new(AvailabilitySources::default());
+}
+
+/// Combine availability maps. Frontend values are the baseline and Rust-derived
+/// values take precedence for any id they contain.
+pub fn merge_availability(
+    rust: &HashMap<String, bool>,
+    frontend: &HashMap<String, bool>,
+) -> HashMap<String, bool> {
+    let mut merged = frontend.clone();
+    merged.extend(rust.iter().map(|(id, enabled)| (id.clone(), *enabled)));
+    merged
+}
+
+/// Merge both sources and apply the result to the menu in one pass.
+/// The sources lock is held throughout so concurrent updates can't interleave.
+/// Items whose enabled state changed go to the frontend as a
+/// `menu-availability-changed` event, so it can re-render just those.
+fn apply_availability(sources: &AvailabilitySources) -> HashMap<String, bool> {
+    let merged = effective_availability(sources);
+    let reasons = disabled_reasons(sources, &CAPABILITIES.read(), &merged);
+    let (app, changes) = match MENU_ITEMS.write().as_mut() {
+        Some(items) => (items.app.clone(), items.update_availability(&merged, &reasons)),
+        None => return HashMap::new(),
+    };
+    if !changes.is_empty() {
+        let _ = app.emit("menu-availability-changed", &changes);
+    }
+    changes
+}
+
+/// Merged availability, with project actions disabled while the selected
+/// project's directory is missing.
+fn effective_availability(sources: &AvailabilitySources) -> HashMap<String, bool> {
+    let mut merged = merge_availability(&sources.rust, &sources.frontend);
+    if sources.active_project_missing() {
+        merged.extend(PROJECT_PATH_ACTIONS.iter().map(|id| (id.to_string(), false)));
+    }
+    if let Some(count) = sources.navigable_count {
+        merged.extend(crate::navigation::index_availability(count));
+    }
+    merged
+}
+
+/// Reasons for every item `availability` disables
+fn disabled_reasons(
+    sources: &AvailabilitySources,
+    capabilities: &[MenuCapability],
+    availability: &HashMap<String, bool>,
+) -> HashMap<String, String> {
+    availability
+        .iter()
+        .filter(|(_, enabled)| !**enabled)
+        .map(|(id, _)| (id.clone(), reason_disabled(sources, capabilities, id)))
+        .collect()
+}
+
+/// Why a disabled item is disabled, checking the most specific causes first.
+/// The frontend only reports a flag, so its reasons are inferred from what
+/// each action needs.
+fn reason_disabled(
+    sources: &AvailabilitySources,
+    capabilities: &[MenuCapability],
+    id: &str,
+) -> String {
+    if sources.active_project_missing() && PROJECT_PATH_ACTIONS.contains(&id) {
+        return "Project directory is missing".to_string();
+    }
+    let capability_reason = capabilities
+        .iter()
+        .find(|c| c.id == id && !c.available)
+        .and_then(|c| c.reason.clone());
+    if let Some(reason) = capability_reason {
+        return reason;
+    }
+    if let Some(n) = id.strip_prefix("navigate::toEntity") {
+        return format!("No entry at position {}", n);
+    }
+
+    match id {
+        "worktree::renameBranch" | "worktree::merge" | "worktree::delete" | "diff::toggleMode" => {
+            "No active worktree"
+        }
+        "worktree::new" | "project::close" => "No active project",
+        "scratch::renameSession" => "No active scratch terminal",
+        "navigate::back" | "navigate::forward" => "No navigation history",
+        "diff::open" => "No changed files",
+        "diff::nextFile" | "diff::prevFile" => "Not viewing a diff",
+        "task::run" => "No task selected",
+        "task::switcher" => "No tasks configured",
+        _ => "Nothing is selected",
+    }
+    .to_string()
+}
+
+/// Record how many entries the sidebar has and re-apply availability
+pub fn set_navigable_count(count: usize) {
+    let mut sources = AVAILABILITY.lock();
+    if sources.navigable_count != Some(count) {
+        sources.navigable_count = Some(count);
+        apply_availability(&sources);
+    }
+}
+
+/// Record whether a project's directory is missing and re-apply availability
+pub fn set_project_path_missing(project_id: &str, missing: bool) {
+    let mut sources = AVAILABILITY.lock();
+    let changed = if missing {
+        sources.missing_projects.insert(project_id.to_string())
+    } else {
+        sources.missing_projects.remove(project_id)
+    };
+    if changed {
+        apply_availability(&sources);
+    }
+}
+
+/// Replace the Rust-derived availability and re-apply the merged result
+pub fn set_rust_availability(availability: HashMap<String, bool>) {
+    let mut sources = AVAILABILITY.lock();
+    sources.rust = availability;
+    apply_availability(&sources);
 }
 
 /// Global storage for dynamic menu items
 pub static MENU_ITEMS: RwLock<Option<DynamicMenuItems>> = RwLock::new(None);
 
+/// Capabilities detected when the menu was built
+static CAPABILITIES: RwLock<Vec<MenuCapability>> = RwLock::new(Vec::new());
+
+/// Whether a menu item's native integration is available, and why not if it isn't
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MenuCapability {
+    pub id: String,
+    pub available: bool,
+    pub reason: Option<String>,
+}
+
+impl MenuCapability {
+    fn available(id: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            available: true,
+            reason: None,
+        }
+    }
+
+    fn missing(id: &str, reason: String) -> Self {
+        Self {
+            id: id.to_string(),
+            available: false,
+            reason: Some(reason),
+        }
+    }
+}
+
+/// The parts of the host that capability detection looks at, so it can be tested
+pub trait HostEnvironment {
+    fn os(&self) -> &str;
+    fn env_var(&self, name: &str) -> Option<String>;
+    fn has_command(&self, command: &str) -> bool;
+}
+
+pub struct SystemEnvironment;
+
+impl HostEnvironment for SystemEnvironment {
+    fn os(&self) -> &str {
+        std::env::consts::OS
+    }
+
+    fn env_var(&self, name: &str) -> Option<String> {
+        std::env::var(name).ok().filter(|v| !v.is_empty())
+    }
+
+    fn has_command(&self, command: &str) -> bool {
+        let path = crate::pty::get_cached_user_path();
+        crate::settings::resolve_executable(command, &path).is_some()
+    }
+}
+
+/// Check a configured or default command, using only the program name
+fn command_capability(
+    env: &dyn HostEnvironment,
+    id: &str,
+    command: &str,
+    hint: &str,
+) -> MenuCapability {
+    let program = crate::settings::program_of(command).unwrap_or_else(|| command.to_string());
+    if env.has_command(&program) {
+        MenuCapability::available(id)
+    } else {
+        MenuCapability::missing(id, format!("'{}' not found on PATH. {}", program, hint))
+    }
+}
+
+/// Detect which native integrations behind menu items are usable.
+/// Mirrors the fallbacks in the open_in_* commands.
+pub fn detect_capabilities(env: &dyn HostEnvironment, apps: &AppsConfig) -> Vec<MenuCapability> {
+    let file_manager = match (apps.file_manager_command(), env.os()) {
+        (Some(cmd), _) => {
+            command_capability(env, "app::openInFinder", cmd, "Check apps.fileManager.")
+        }
+        (None, "linux") => command_capability(
+            env,
+            "app::openInFinder",
+            "xdg-open",
+            "Install it or configure apps.fileManager.",
+        ),
+        (None, _) => MenuCapability::available("app::openInFinder"),
+    };
+
+    let terminal = match (apps.terminal_command(), env.os()) {
+        (Some(cmd), _) => {
+            command_capability(env, "app::openInTerminal", cmd, "Check apps.terminal.")
+        }
+        (None, "linux") => command_capability(
+            env,
+            "app::openInTerminal",
+            "xdg-terminal-exec",
+            "Install it or configure apps.terminal.",
+        ),
+        (None, "windows") => command_capability(
+            env,
+            "app::openInTerminal",
+            "wt",
+            "Install Windows Terminal or configure apps.terminal.",
+        ),
+        (None, _) => MenuCapability::available("app::openInTerminal"),
+    };
+
+    let editor = match apps
+        .editor_command()
+        .map(|s| s.to_string())
+        .or_else(|| env.env_var("VISUAL"))
+        .or_else(|| env.env_var("EDITOR"))
+    {
+        Some(cmd) => command_capability(
+            env,
+            "app::openInEditor",
+            &cmd,
+            "Check apps.editor, $VISUAL or $EDITOR.",
+        ),
+        None => MenuCapability::missing(
+            "app::openInEditor",
+            "No editor configured. Set $VISUAL or $EDITOR, or configure apps.editor.".to_string(),
+        ),
+    };
+
+    vec![file_manager, terminal, editor]
+}
+
+/// Capabilities detected at menu setup, for explaining grayed-out items
+pub fn menu_capabilities() -> Vec<MenuCapability> {
+    CAPABILITIES.read().clone()
+}
+
+/// Menu item id and configured shortcut for every item with an accelerator.
+/// `setup_menu` and the exported keymap both read this list, so they can't drift.
+fn menu_shortcuts(mappings: &MappingsConfig) -> Vec<(&'static str, &Shortcut)> {
+    vec![
+        ("app::quit", &mappings.quit),
+        ("app::addProject", &mappings.add_project),
+        ("palette::projectSwitcher", &mappings.project_switcher),
+        ("worktree::new", &mappings.new_workspace),
+        ("scratch::new", &mappings.new_scratch_terminal),
+        ("session::newTab", &mappings.new_tab),
+        ("session::closeTab", &mappings.close_tab),
+        ("task::run", &mappings.run_task),
+        ("task::switcher", &mappings.task_switcher),
+        ("drawer::toggle", &mappings.toggle_drawer),
+        ("rightPanel::toggle", &mappings.toggle_right_panel),
+        ("drawer::expand", &mappings.expand_drawer),
+        ("palette::toggle", &mappings.command_palette),
+        ("view::zoomIn", &mappings.zoom_in),
+        ("view::zoomOut", &mappings.zoom_out),
+        ("view::zoomReset", &mappings.zoom_reset),
+        ("navigate::prev", &mappings.navigate_prev),
+        ("navigate::next", &mappings.navigate_next),
+        ("navigate::back", &mappings.navigate_back),
+        ("navigate::forward", &mappings.navigate_forward),
+        ("focus::switch", &mappings.switch_focus),
+        ("navigate::toEntity1", &mappings.session1),
+        ("navigate::toEntity2", &mappings.session2),
+        ("navigate::toEntity3", &mappings.session3),
+        ("navigate::toEntity4", &mappings.session4),
+        ("navigate::toEntity5", &mappings.session5),
+        ("navigate::toEntity6", &mappings.session6),
+        ("navigate::toEntity7", &mappings.session7),
+        ("navigate::toEntity8", &mappings.session8),
+        ("navigate::toEntity9", &mappings.session9),
+        ("diff::nextFile", &mappings.next_changed_file),
+        ("diff::prevFile", &mappings.prev_changed_file),
+    ]
+}
+
+/// The accelerator each mapped menu item shows, keyed by menu item id
+pub fn menu_accelerators(mappings: &MappingsConfig) -> HashMap<String, String> {
+    menu_shortcuts(mappings)
+        .into_iter()
+        .map(|(id, shortcut)| (id.to_string(), shortcut.to_accelerator()))
+        .collect()
+}
+
 /// Initialize and build the application menu
-pub fn setup_menu(app: &tauri::App, mappings: &MappingsConfig) -> Result<(), Box<dyn std::error::Error>> {
-    let mut dynamic_items = DynamicMenuItems::new();
+pub fn setup_menu(
+    app: &tauri::App,
+    mappings: &MappingsConfig,
+    apps: &AppsConfig,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut dynamic_items = DynamicMenuItems::new(app.handle().clone());
+    let accelerators = menu_accelerators(mappings);
+    let capabilities = detect_capabilities(&SystemEnvironment, apps);
+    // A present integration doesn't make an item usable on its own (the frontend
+    // still decides based on context), but a missing one always disables it.
+    let unavailable = capabilities
+        .iter()
+        .filter(|c| !c.available)
+        .map(|c| (c.id.clone(), false))
+        .collect();
+    *CAPABILITIES.write() = capabilities;
 
     // === App Menu (Shellflow) ===
     let about_item = PredefinedMenuItem::about(app, Some("About Shellflow"), None)?;
     let quit_item = MenuItemBuilder::with_id("app::quit", "Quit Shellflow")
-        .accelerator(mappings.quit.to_accelerator())
+        .accelerator(&accelerators["app::quit"])
         .build(app)?;
 
     let app_submenu = SubmenuBuilder::new(app, "Shellflow")
@@ -56,36 +599,36 @@ pub fn setup_menu(app: &tauri::App, mappings: &MappingsConfig) -> Result<(), Box
 
     // === File Menu ===
     let add_project = MenuItemBuilder::with_id("app::addProject", "Open Project…")
-        .accelerator(mappings.add_project.to_accelerator())
+        .accelerator(&accelerators["app::addProject"])
         .build(app)?;
     // add_project is always enabled, no need to track it
 
     let switch_project = MenuItemBuilder::with_id("palette::projectSwitcher", "Switch Project…")
-        .accelerator(mappings.project_switcher.to_accelerator())
+        .accelerator(&accelerators["palette::projectSwitcher"])
         .build(app)?;
     // switch_project is always enabled, no need to track it
 
     let new_worktree = MenuItemBuilder::with_id("worktree::new", "New Worktree")
-        .accelerator(mappings.new_workspace.to_accelerator())
+        .accelerator(&accelerators["worktree::new"])
         .enabled(false)
         .build(app)?;
     dynamic_items.insert("worktree::new", new_worktree.clone());
 
     let new_scratch_terminal =
         MenuItemBuilder::with_id("scratch::new", "New Scratch Terminal")
-            .accelerator(mappings.new_scratch_terminal.to_accelerator())
+            .accelerator(&accelerators["scratch::new"])
             .enabled(false)
             .build(app)?;
     dynamic_items.insert("scratch::new", new_scratch_terminal.clone());
 
     let new_tab = MenuItemBuilder::with_id("session::newTab", "New Tab")
-        .accelerator(mappings.new_tab.to_accelerator())
+        .accelerator(&accelerators["session::newTab"])
         .enabled(false)
         .build(app)?;
     dynamic_items.insert("session::newTab", new_tab.clone());
 
     let close_tab = MenuItemBuilder::with_id("session::closeTab", "Close")
-        .accelerator(mappings.close_tab.to_accelerator())
+        .accelerator(&accelerators["session::closeTab"])
         .enabled(false)
         .build(app)?;
     dynamic_items.insert("session::closeTab", close_tab.clone());
@@ -122,13 +665,13 @@ pub fn setup_menu(app: &tauri::App, mappings: &MappingsConfig) -> Result<(), Box
 
     // Task items (in File menu)
     let run_task = MenuItemBuilder::with_id("task::run", "Run Task")
-        .accelerator(mappings.run_task.to_accelerator())
+        .accelerator(&accelerators["task::run"])
         .enabled(false)
         .build(app)?;
     dynamic_items.insert("task::run", run_task.clone());
 
     let task_switcher = MenuItemBuilder::with_id("task::switcher", "Task Switcher")
-        .accelerator(mappings.task_switcher.to_accelerator())
+        .accelerator(&accelerators["task::switcher"])
         .enabled(false)
         .build(app)?;
     dynamic_items.insert("task::switcher", task_switcher.clone());
@@ -169,37 +712,37 @@ pub fn setup_menu(app: &tauri::App, mappings: &MappingsConfig) -> Result<(), Box
 
     // === View Menu ===
     let toggle_drawer = MenuItemBuilder::with_id("drawer::toggle", "Toggle Drawer")
-        .accelerator(mappings.toggle_drawer.to_accelerator())
+        .accelerator(&accelerators["drawer::toggle"])
         .enabled(false)
         .build(app)?;
     dynamic_items.insert("drawer::toggle", toggle_drawer.clone());
 
     let toggle_right_panel =
         MenuItemBuilder::with_id("rightPanel::toggle", "Toggle Changed Files")
-            .accelerator(mappings.toggle_right_panel.to_accelerator())
+            .accelerator(&accelerators["rightPanel::toggle"])
             .enabled(false)
             .build(app)?;
     dynamic_items.insert("rightPanel::toggle", toggle_right_panel.clone());
 
     let expand_drawer = MenuItemBuilder::with_id("drawer::expand", "Expand Drawer")
-        .accelerator(mappings.expand_drawer.to_accelerator())
+        .accelerator(&accelerators["drawer::expand"])
         .enabled(false)
         .build(app)?;
     dynamic_items.insert("drawer::expand", expand_drawer.clone());
 
     let command_palette = MenuItemBuilder::with_id("palette::toggle", "Command Palette…")
-        .accelerator(mappings.command_palette.to_accelerator())
+        .accelerator(&accelerators["palette::toggle"])
         .build(app)?;
     // command_palette is always enabled, no need to track it
 
     let zoom_in = MenuItemBuilder::with_id("view::zoomIn", "Zoom In")
-        .accelerator(mappings.zoom_in.to_accelerator())
+        .accelerator(&accelerators["view::zoomIn"])
         .build(app)?;
     let zoom_out = MenuItemBuilder::with_id("view::zoomOut", "Zoom Out")
-        .accelerator(mappings.zoom_out.to_accelerator())
+        .accelerator(&accelerators["view::zoomOut"])
         .build(app)?;
     let zoom_reset = MenuItemBuilder::with_id("view::zoomReset", "Reset Zoom")
-        .accelerator(mappings.zoom_reset.to_accelerator())
+        .accelerator(&accelerators["view::zoomReset"])
         .build(app)?;
 
     let view_submenu = SubmenuBuilder::new(app, "View")
@@ -216,86 +759,86 @@ pub fn setup_menu(app: &tauri::App, mappings: &MappingsConfig) -> Result<(), Box
 
     // === Navigate Menu ===
     let prev_session = MenuItemBuilder::with_id("navigate::prev", "Previous Session")
-        .accelerator(mappings.navigate_prev.to_accelerator())
+        .accelerator(&accelerators["navigate::prev"])
         .enabled(false)
         .build(app)?;
     dynamic_items.insert("navigate::prev", prev_session.clone());
 
     let next_session = MenuItemBuilder::with_id("navigate::next", "Next Session")
-        .accelerator(mappings.navigate_next.to_accelerator())
+        .accelerator(&accelerators["navigate::next"])
         .enabled(false)
         .build(app)?;
     dynamic_items.insert("navigate::next", next_session.clone());
 
     let go_back = MenuItemBuilder::with_id("navigate::back", "Go Back")
-        .accelerator(mappings.navigate_back.to_accelerator())
+        .accelerator(&accelerators["navigate::back"])
         .enabled(false)
         .build(app)?;
     dynamic_items.insert("navigate::back", go_back.clone());
 
     let go_forward = MenuItemBuilder::with_id("navigate::forward", "Go Forward")
-        .accelerator(mappings.navigate_forward.to_accelerator())
+        .accelerator(&accelerators["navigate::forward"])
         .enabled(false)
         .build(app)?;
     dynamic_items.insert("navigate::forward", go_forward.clone());
 
     let switch_focus = MenuItemBuilder::with_id("focus::switch", "Switch Focus")
-        .accelerator(mappings.switch_focus.to_accelerator())
+        .accelerator(&accelerators["focus::switch"])
         .enabled(false)
         .build(app)?;
     dynamic_items.insert("focus::switch", switch_focus.clone());
 
     // Session 1-9 (sidebar navigation)
     let entity1 = MenuItemBuilder::with_id("navigate::toEntity1", "Session 1")
-        .accelerator(mappings.session1.to_accelerator())
+        .accelerator(&accelerators["navigate::toEntity1"])
         .enabled(false)
         .build(app)?;
     dynamic_items.insert("navigate::toEntity1", entity1.clone());
 
     let entity2 = MenuItemBuilder::with_id("navigate::toEntity2", "Session 2")
-        .accelerator(mappings.session2.to_accelerator())
+        .accelerator(&accelerators["navigate::toEntity2"])
         .enabled(false)
         .build(app)?;
     dynamic_items.insert("navigate::toEntity2", entity2.clone());
 
     let entity3 = MenuItemBuilder::with_id("navigate::toEntity3", "Session 3")
-        .accelerator(mappings.session3.to_accelerator())
+        .accelerator(&accelerators["navigate::toEntity3"])
         .enabled(false)
         .build(app)?;
     dynamic_items.insert("navigate::toEntity3", entity3.clone());
 
     let entity4 = MenuItemBuilder::with_id("navigate::toEntity4", "Session 4")
-        .accelerator(mappings.session4.to_accelerator())
+        .accelerator(&accelerators["navigate::toEntity4"])
         .enabled(false)
         .build(app)?;
     dynamic_items.insert("navigate::toEntity4", entity4.clone());
 
     let entity5 = MenuItemBuilder::with_id("navigate::toEntity5", "Session 5")
-        .accelerator(mappings.session5.to_accelerator())
+        .accelerator(&accelerators["navigate::toEntity5"])
         .enabled(false)
         .build(app)?;
     dynamic_items.insert("navigate::toEntity5", entity5.clone());
 
     let entity6 = MenuItemBuilder::with_id("navigate::toEntity6", "Session 6")
-        .accelerator(mappings.session6.to_accelerator())
+        .accelerator(&accelerators["navigate::toEntity6"])
         .enabled(false)
         .build(app)?;
     dynamic_items.insert("navigate::toEntity6", entity6.clone());
 
     let entity7 = MenuItemBuilder::with_id("navigate::toEntity7", "Session 7")
-        .accelerator(mappings.session7.to_accelerator())
+        .accelerator(&accelerators["navigate::toEntity7"])
         .enabled(false)
         .build(app)?;
     dynamic_items.insert("navigate::toEntity7", entity7.clone());
 
     let entity8 = MenuItemBuilder::with_id("navigate::toEntity8", "Session 8")
-        .accelerator(mappings.session8.to_accelerator())
+        .accelerator(&accelerators["navigate::toEntity8"])
         .enabled(false)
         .build(app)?;
     dynamic_items.insert("navigate::toEntity8", entity8.clone());
 
     let entity9 = MenuItemBuilder::with_id("navigate::toEntity9", "Session 9")
-        .accelerator(mappings.session9.to_accelerator())
+        .accelerator(&accelerators["navigate::toEntity9"])
         .enabled(false)
         .build(app)?;
     dynamic_items.insert("navigate::toEntity9", entity9.clone());
@@ -312,13 +855,13 @@ pub fn setup_menu(app: &tauri::App, mappings: &MappingsConfig) -> Result<(), Box
 
     // Diff navigation items (in Navigate menu)
     let next_changed_file = MenuItemBuilder::with_id("diff::nextFile", "Next Changed File")
-        .accelerator(mappings.next_changed_file.to_accelerator())
+        .accelerator(&accelerators["diff::nextFile"])
         .enabled(false)
         .build(app)?;
     dynamic_items.insert("diff::nextFile", next_changed_file.clone());
 
     let prev_changed_file = MenuItemBuilder::with_id("diff::prevFile", "Previous Changed File")
-        .accelerator(mappings.prev_changed_file.to_accelerator())
+        .accelerator(&accelerators["diff::prevFile"])
         .enabled(false)
         .build(app)?;
     dynamic_items.insert("diff::prevFile", prev_changed_file.clone());
@@ -373,6 +916,44 @@ pub fn setup_menu(app: &tauri::App, mappings: &MappingsConfig) -> Result<(), Box
         .item(&help_release_notes)
         .build()?;
 
+    // Track every mapped item so changed mappings can update accelerators in place
+    for (id, item) in [
+        ("app::quit", &quit_item),
+        ("app::addProject", &add_project),
+        ("palette::projectSwitcher", &switch_project),
+        ("worktree::new", &new_worktree),
+        ("scratch::new", &new_scratch_terminal),
+        ("session::newTab", &new_tab),
+        ("session::closeTab", &close_tab),
+        ("task::run", &run_task),
+        ("task::switcher", &task_switcher),
+        ("drawer::toggle", &toggle_drawer),
+        ("rightPanel::toggle", &toggle_right_panel),
+        ("drawer::expand", &expand_drawer),
+        ("palette::toggle", &command_palette),
+        ("view::zoomIn", &zoom_in),
+        ("view::zoomOut", &zoom_out),
+        ("view::zoomReset", &zoom_reset),
+        ("navigate::prev", &prev_session),
+        ("navigate::next", &next_session),
+        ("navigate::back", &go_back),
+        ("navigate::forward", &go_forward),
+        ("focus::switch", &switch_focus),
+        ("navigate::toEntity1", &entity1),
+        ("navigate::toEntity2", &entity2),
+        ("navigate::toEntity3", &entity3),
+        ("navigate::toEntity4", &entity4),
+        ("navigate::toEntity5", &entity5),
+        ("navigate::toEntity6", &entity6),
+        ("navigate::toEntity7", &entity7),
+        ("navigate::toEntity8", &entity8),
+        ("navigate::toEntity9", &entity9),
+        ("diff::nextFile", &next_changed_file),
+        ("diff::prevFile", &prev_changed_file),
+    ] {
+        dynamic_items.track_accelerator(id, item.clone(), accelerators[id].clone());
+    }
+
     // Build the complete menu
     let menu = MenuBuilder::new(app)
         .item(&app_submenu)
@@ -388,10 +969,20 @@ pub fn setup_menu(app: &tauri::App, mappings: &MappingsConfig) -> Result<(), Box
 
     // Store dynamic items for later updates
     *MENU_ITEMS.write() = Some(dynamic_items);
+    set_rust_availability(unavailable);
 
     // Set up menu event handler
     app.on_menu_event(move |app_handle, event| {
         let menu_id = event.id().as_ref();
+
+        // Settings and mappings get their own native windows
+        if let Some(aux) = AuxWindow::from_action(menu_id) {
+            if let Err(e) = windows::open_or_focus(app_handle, aux) {
+                log::error!("Failed to open {} window: {}", aux.label(), e);
+            }
+            return;
+        }
+
         if let Some(window) = app_handle.get_webview_window("main") {
             match menu_id {
                 "app::quit" => {
@@ -409,9 +1000,364 @@ pub fn setup_menu(app: &tauri::App, mappings: &MappingsConfig) -> Result<(), Box
     Ok(())
 }
 
-/// Update menu item enabled states based on action availability from frontend
-pub fn update_action_availability(availability: HashMap<String, bool>) {
-    if let Some(ref items) = *MENU_ITEMS.read() {
-        items.update_availability(&availability);
+/// Update menu item enabled states based on action availability from frontend,
+/// along with the project it has selected. Updates may be partial; ids not
+/// mentioned keep their last frontend value.
+/// Returns the items whose enabled state changed.
+pub fn update_action_availability(
+    availability: HashMap<String, bool>,
+    active_project: Option<String>,
+) -> HashMap<String, bool> {
+    let mut sources = AVAILABILITY.lock();
+    sources.frontend.extend(availability);
+    sources.active_project = active_project;
+    apply_availability(&sources)
+}
+
+/// Why a menu item is disabled, or None if it's enabled
+pub fn disabled_reason(id: &str) -> Option<String> {
+    MENU_ITEMS.read().as_ref()?.disabled_reason(id)
+}
+
+/// Update menu accelerators from a mappings diff (action id, or `sessionN`
+/// for the Session N items, to shortcut). Only accelerators that differ from
+/// what the menu shows are touched; invalid ones are skipped and reported.
+pub fn apply_mapping_changes(changes: HashMap<String, String>) -> MappingChangeReport {
+    match MENU_ITEMS.write().as_mut() {
+        Some(items) => items.apply_mapping_changes(&changes),
+        None => MappingChangeReport::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+
+    struct MockEnvironment {
+        os: &'static str,
+        vars: HashMap<&'static str, &'static str>,
+        commands: Vec<&'static str>,
+    }
+
+    impl HostEnvironment for MockEnvironment {
+        fn os(&self) -> &str {
+            self.os
+        }
+
+        fn env_var(&self, name: &str) -> Option<String> {
+            self.vars.get(name).map(|v| v.to_string())
+        }
+
+        fn has_command(&self, command: &str) -> bool {
+            self.commands.contains(&command)
+        }
+    }
+
+    fn capability<'a>(caps: &'a [MenuCapability], id: &str) -> &'a MenuCapability {
+        caps.iter().find(|c| c.id == id).unwrap()
+    }
+
+    fn map(entries: &[(&str, bool)]) -> HashMap<String, bool> {
+        entries.iter().map(|(id, v)| (id.to_string(), *v)).collect()
+    }
+
+    #[test]
+    fn merge_availability_prefers_rust_for_known_ids() {
+        let rust = map(&[("app::openInEditor", false), ("worktree::merge", true)]);
+        let frontend = map(&[
+            ("app::openInEditor", true),
+            ("worktree::merge", false),
+            ("session::newTab", true),
+        ]);
+
+        let merged = merge_availability(&rust, &frontend);
+
+        assert_eq!(
+            merged,
+            map(&[
+                ("app::openInEditor", false),
+                ("worktree::merge", true),
+                ("session::newTab", true),
+            ])
+        );
+    }
+
+    #[test]
+    fn merge_availability_keeps_ids_from_either_source() {
+        let rust = map(&[("app::openInTerminal", false)]);
+
+        assert_eq!(merge_availability(&rust, &HashMap::new()), rust);
+        let frontend = map(&[("diff::nextFile", true)]);
+        assert_eq!(merge_availability(&HashMap::new(), &frontend), frontend);
+    }
+
+    #[test]
+    fn availability_changes_are_empty_when_nothing_changed() {
+        let mut applied = HashMap::new();
+        let availability = map(&[("worktree::merge", true), ("diff::open", false)]);
+        assert_eq!(availability_changes(&mut applied, &availability), availability);
+
+        assert!(availability_changes(&mut applied, &availability).is_empty());
+        assert!(availability_changes(&mut applied, &map(&[("diff::open", false)])).is_empty());
+    }
+
+    #[test]
+    fn availability_changes_contain_only_transitioned_ids() {
+        let mut applied = HashMap::new();
+        availability_changes(
+            &mut applied,
+            &map(&[("worktree::merge", true), ("diff::open", false), ("task::run", true)]),
+        );
+
+        let changes = availability_changes(
+            &mut applied,
+            &map(&[("worktree::merge", false), ("diff::open", true), ("task::run", true)]),
+        );
+
+        assert_eq!(changes, map(&[("worktree::merge", false), ("diff::open", true)]));
+        assert_eq!(
+            availability_changes(&mut applied, &map(&[("worktree::merge", true)])),
+            map(&[("worktree::merge", true)])
+        );
+    }
+
+    #[test]
+    fn missing_project_disables_project_actions() {
+        let mut sources = AvailabilitySources {
+            frontend: map(&[("worktree::new", true), ("session::newTab", true)]),
+            ..Default::default()
+        };
+        assert!(effective_availability(&sources)["worktree::new"]);
+
+        // Another project's missing directory doesn't block the selected one
+        sources.missing_projects.insert("project".to_string());
+        sources.active_project = Some("other".to_string());
+        assert!(effective_availability(&sources)["worktree::new"]);
+
+        sources.active_project = Some("project".to_string());
+        let merged = effective_availability(&sources);
+        assert!(!merged["worktree::new"]);
+        assert!(!merged["app::openInEditor"]);
+        assert!(merged["session::newTab"]);
+    }
+
+    #[test]
+    fn navigable_count_disables_indices_beyond_it() {
+        let mut sources = AvailabilitySources {
+            frontend: map(&[("navigate::toEntity3", true)]),
+            ..Default::default()
+        };
+        assert_eq!(effective_availability(&sources).get("navigate::toEntity3"), Some(&true));
+
+        sources.navigable_count = Some(2);
+        let merged = effective_availability(&sources);
+        assert_eq!(merged.get("navigate::toEntity2"), Some(&true));
+        assert_eq!(merged.get("navigate::toEntity3"), Some(&false));
+        assert_eq!(merged.get("navigate::toEntity9"), Some(&false));
+    }
+
+    #[test]
+    fn disabled_items_have_a_reason_and_enabled_items_none() {
+        let mut sources = AvailabilitySources {
+            frontend: map(&[
+                ("worktree::merge", false),
+                ("worktree::new", true),
+                ("app::openInTerminal", true),
+            ]),
+            rust: map(&[("app::openInTerminal", false)]),
+            ..Default::default()
+        };
+        let capabilities = vec![MenuCapability::missing(
+            "app::openInTerminal",
+            "No terminal emulator found".to_string(),
+        )];
+
+        let reasons =
+            disabled_reasons(&sources, &capabilities, &effective_availability(&sources));
+        assert_eq!(reasons["worktree::merge"], "No active worktree");
+        assert_eq!(reasons["app::openInTerminal"], "No terminal emulator found");
+        assert_eq!(reasons.get("worktree::new"), None);
+
+        // A missing project directory explains project actions first
+        sources.missing_projects.insert("p".to_string());
+        sources.active_project = Some("p".to_string());
+        sources.navigable_count = Some(1);
+        let reasons =
+            disabled_reasons(&sources, &capabilities, &effective_availability(&sources));
+        assert_eq!(reasons["worktree::merge"], "Project directory is missing");
+        assert_eq!(reasons["worktree::new"], "Project directory is missing");
+        assert_eq!(reasons["navigate::toEntity2"], "No entry at position 2");
+        assert_eq!(reasons.get("navigate::toEntity1"), None);
+    }
+
+    #[test]
+    fn mapping_changes_only_update_changed_accelerators() {
+        let current: HashMap<String, String> = [
+            ("session::newTab", "Cmd+T"),
+            ("session::closeTab", "Cmd+W"),
+            ("drawer::toggle", "Ctrl+`"),
+        ]
+        .into_iter()
+        .map(|(id, accel)| (id.to_string(), accel.to_string()))
+        .collect();
+        let changes: HashMap<String, String> = [
+            ("session::newTab", "cmd+t"),
+            ("session::closeTab", "cmd+shift+w"),
+            ("drawer::toggle", "ctrl+`"),
+            ("terminal::copy", "cmd+c"),
+        ]
+        .into_iter()
+        .map(|(id, shortcut)| (id.to_string(), shortcut.to_string()))
+        .collect();
+
+        let (updates, report) = plan_accelerator_changes(&current, &changes);
+
+        assert_eq!(
+            updates,
+            vec![("session::closeTab".to_string(), "Cmd+Shift+W".to_string())]
+        );
+        assert!(report.skipped.is_empty());
+    }
+
+    #[test]
+    fn invalid_accelerators_are_skipped_with_reason() {
+        let current: HashMap<String, String> = ["navigate::prev", "navigate::next", "focus::switch"]
+            .into_iter()
+            .map(|id| (id.to_string(), "Cmd+K".to_string()))
+            .collect();
+        let changes: HashMap<String, String> = [
+            ("navigate::prev", "cmd+hyper+k"),
+            ("navigate::next", "cmd+shift"),
+            ("focus::switch", "alt+f12"),
+        ]
+        .into_iter()
+        .map(|(id, shortcut)| (id.to_string(), shortcut.to_string()))
+        .collect();
+
+        let (updates, report) = plan_accelerator_changes(&current, &changes);
+
+        assert_eq!(
+            updates,
+            vec![("focus::switch".to_string(), "Alt+F12".to_string())]
+        );
+        let skipped: Vec<(&str, &str)> = report
+            .skipped
+            .iter()
+            .map(|s| (s.action_id.as_str(), s.reason.as_str()))
+            .collect();
+        assert_eq!(
+            skipped,
+            vec![
+                ("navigate::next", "Missing key"),
+                ("navigate::prev", "Unknown modifier 'hyper'"),
+            ]
+        );
+    }
+
+    #[test]
+    fn remapping_a_session_updates_its_menu_item() {
+        let current = menu_accelerators(&config::MappingsConfig::default());
+        let changes: HashMap<String, String> =
+            [("session3".to_string(), "cmd+shift+3".to_string())].into_iter().collect();
+
+        let (updates, report) = plan_accelerator_changes(&current, &changes);
+
+        assert_eq!(
+            updates,
+            vec![("navigate::toEntity3".to_string(), "Cmd+Shift+3".to_string())]
+        );
+        assert!(report.skipped.is_empty());
+        assert_eq!(mapped_item_id("session10"), "session10");
+        assert_eq!(mapped_item_id("navigate::toEntity3"), "navigate::toEntity3");
+    }
+
+    #[test]
+    fn validates_accelerators() {
+        for valid in ["Cmd+T", "Ctrl+Cmd+J", "F2", "Cmd+Shift+[", "Ctrl+`", "Alt+Enter"] {
+            assert_eq!(validate_accelerator(valid), Ok(()), "{}", valid);
+        }
+        for invalid in ["", "Cmd+", "Cmd+F25", "Meta+K", "Cmd+Return2"] {
+            assert!(validate_accelerator(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn bare_linux_reports_missing_integrations_with_reasons() {
+        let env = MockEnvironment {
+            os: "linux",
+            vars: HashMap::new(),
+            commands: vec!["xdg-open"],
+        };
+
+        let caps = detect_capabilities(&env, &AppsConfig::default());
+
+        assert!(capability(&caps, "app::openInFinder").available);
+        let terminal = capability(&caps, "app::openInTerminal");
+        assert!(!terminal.available);
+        assert!(terminal
+            .reason
+            .as_deref()
+            .unwrap()
+            .contains("xdg-terminal-exec"));
+        let editor = capability(&caps, "app::openInEditor");
+        assert!(!editor.available);
+        assert!(editor.reason.as_deref().unwrap().contains("$EDITOR"));
+    }
+
+    #[test]
+    fn macos_with_configured_apps_checks_configured_commands() {
+        let env = MockEnvironment {
+            os: "macos",
+            vars: HashMap::from([("EDITOR", "nvim")]),
+            commands: vec!["code"],
+        };
+        let apps = AppsConfig {
+            terminal: Some(AppConfig::Simple("ghostty".to_string())),
+            editor: Some(AppConfig::Simple("code -g {{ path }}".to_string())),
+            file_manager: None,
+        };
+
+        let caps = detect_capabilities(&env, &apps);
+
+        assert!(capability(&caps, "app::openInFinder").available);
+        let terminal = capability(&caps, "app::openInTerminal");
+        assert!(!terminal.available);
+        assert!(terminal.reason.as_deref().unwrap().contains("'ghostty'"));
+        // The configured editor wins over $EDITOR and only the program name is looked up
+        assert_eq!(capability(&caps, "app::openInEditor").reason, None);
+    }
+
+    #[test]
+    fn quoted_programs_are_looked_up_whole() {
+        let env = MockEnvironment {
+            os: "macos",
+            vars: HashMap::new(),
+            commands: vec!["/Applications/Visual Studio Code.app/code"],
+        };
+        let apps = AppsConfig {
+            terminal: None,
+            editor: Some(AppConfig::Simple(
+                "\"/Applications/Visual Studio Code.app/code\" -g {{ path }}".to_string(),
+            )),
+            file_manager: None,
+        };
+
+        let caps = detect_capabilities(&env, &apps);
+
+        assert_eq!(capability(&caps, "app::openInEditor").reason, None);
+    }
+
+    #[test]
+    fn editor_falls_back_to_environment() {
+        let env = MockEnvironment {
+            os: "windows",
+            vars: HashMap::from([("VISUAL", "hx")]),
+            commands: vec!["hx", "wt"],
+        };
+
+        let caps = detect_capabilities(&env, &AppsConfig::default());
+
+        assert!(caps.iter().all(|c| c.available));
     }
 }
@@ -0,0 +1,145 @@
+//! Index navigation for the Session N menu items and shortcuts.
+//!
+//! The frontend owns the entry list: scratch terminals, then each open
+//! project and its open worktrees, in sidebar order. It passes that list in,
+//! so index N always means the same row in both places.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tauri::{AppHandle, Emitter};
+use thiserror::Error;
+
+use crate::menu;
+
+/// Number of Session N menu items (`navigate::toEntity1` to `navigate::toEntity9`)
+pub const INDEXED_ENTRIES: usize = 9;
+
+#[derive(Error, Debug)]
+pub enum NavigationError {
+    #[error("No sidebar entry at index {index} ({count} available)")]
+    IndexOutOfRange { index: usize, count: usize },
+}
+
+impl NavigationError {
+    /// Stable machine-readable identifier for the frontend to localize
+    pub fn code(&self) -> &'static str {
+        match self {
+            NavigationError::IndexOutOfRange { .. } => "navigation.index_out_of_range",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryKind {
+    Scratch,
+    Project,
+    Worktree,
+}
+
+/// A scratch terminal, project or worktree row in the sidebar
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NavEntry {
+    #[serde(rename = "type")]
+    pub kind: EntryKind,
+    pub id: String,
+}
+
+/// The entry for the 1-based index `n`, as shown in the menu
+pub fn entry_at(entries: &[NavEntry], n: usize) -> Result<&NavEntry, NavigationError> {
+    n.checked_sub(1)
+        .and_then(|i| entries.get(i))
+        .ok_or(NavigationError::IndexOutOfRange {
+            index: n,
+            count: entries.len(),
+        })
+}
+
+/// Enabled state of each Session N item when the sidebar has `count` entries
+pub fn index_availability(count: usize) -> HashMap<String, bool> {
+    (1..=INDEXED_ENTRIES)
+        .map(|n| (format!("navigate::toEntity{}", n), n <= count))
+        .collect()
+}
+
+/// Activate the entry at 1-based index `n` of `entries` by asking the
+/// frontend to select it, and disable the Session N items beyond the entry
+/// count. An out-of-range index changes nothing besides the menu.
+pub fn activate_index(
+    app: &AppHandle,
+    entries: &[NavEntry],
+    n: usize,
+) -> Result<NavEntry, NavigationError> {
+    menu::set_navigable_count(entries.len());
+
+    let entry = entry_at(entries, n)?.clone();
+    let _ = app.emit("activate-entry", &entry);
+    Ok(entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(kind: EntryKind, id: &str) -> NavEntry {
+        NavEntry {
+            kind,
+            id: id.to_string(),
+        }
+    }
+
+    fn sidebar() -> Vec<NavEntry> {
+        vec![
+            entry(EntryKind::Scratch, "s1"),
+            entry(EntryKind::Project, "a"),
+            entry(EntryKind::Worktree, "a1"),
+        ]
+    }
+
+    #[test]
+    fn entries_use_the_frontend_shape() {
+        let json = serde_json::json!([
+            { "type": "scratch", "id": "s1" },
+            { "type": "project", "id": "a" },
+            { "type": "worktree", "id": "a1" },
+        ]);
+        let entries: Vec<NavEntry> = serde_json::from_value(json.clone()).unwrap();
+
+        assert_eq!(entries, sidebar());
+        assert_eq!(serde_json::to_value(&entries).unwrap(), json);
+    }
+
+    #[test]
+    fn index_one_is_the_first_entry() {
+        let entries = sidebar();
+
+        assert_eq!(entry_at(&entries, 1).unwrap(), &entry(EntryKind::Scratch, "s1"));
+        assert_eq!(entry_at(&entries, 3).unwrap(), &entry(EntryKind::Worktree, "a1"));
+    }
+
+    #[test]
+    fn out_of_range_index_is_an_error() {
+        let entries = sidebar();
+
+        for n in [0, 4, 9] {
+            let err = entry_at(&entries, n).unwrap_err();
+            assert!(matches!(
+                err,
+                NavigationError::IndexOutOfRange { index, count: 3 } if index == n
+            ));
+            assert_eq!(err.code(), "navigation.index_out_of_range");
+        }
+        assert!(entry_at(&[], 1).is_err());
+    }
+
+    #[test]
+    fn indices_beyond_the_count_are_disabled() {
+        let availability = index_availability(3);
+
+        assert_eq!(availability.len(), INDEXED_ENTRIES);
+        assert!(availability["navigate::toEntity1"]);
+        assert!(availability["navigate::toEntity3"]);
+        assert!(!availability["navigate::toEntity4"]);
+        assert!(index_availability(20).values().all(|enabled| *enabled));
+    }
+}
@@ -394,6 +394,8 @@ pub fn spawn_pty(
             crate::cleanup::remove_pid(child_pid_for_cleanup);
         }
 
+        crate::tasks::task_exited(&pty_id_clone);
+
         eprintln!("[PTY:{}] Reader thread exiting, emitting pty-exit event", pty_id_clone);
         let _ = app_handle.emit("pty-exit", serde_json::json!({
             "ptyId": pty_id_clone,
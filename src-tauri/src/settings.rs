@@ -0,0 +1,228 @@
+//! Validation for user-entered settings before they are saved.
+//!
+//! A bad editor command otherwise only shows up later, when "Open in Editor"
+//! quietly fails to launch anything.
+
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+use crate::pty;
+
+/// Placeholders an editor command or line format may use
+const EDITOR_PLACEHOLDERS: &[&str] = &["path", "line"];
+
+#[derive(Error, Debug)]
+pub enum SettingsError {
+    #[error("Editor command is empty")]
+    EmptyEditorCommand,
+    #[error("Editor executable not found: {0}")]
+    EditorNotFound(String),
+    #[error("Invalid editor command template: {0}")]
+    InvalidEditorTemplate(String),
+}
+
+impl SettingsError {
+    /// Stable machine-readable identifier for the frontend to localize
+    pub fn code(&self) -> &'static str {
+        match self {
+            SettingsError::EmptyEditorCommand => "settings.empty_editor_command",
+            SettingsError::EditorNotFound(_) => "settings.editor_not_found",
+            SettingsError::InvalidEditorTemplate(_) => "settings.invalid_editor_template",
+        }
+    }
+}
+
+/// Check that an editor command can be launched: its executable must be an
+/// absolute path to an executable file or resolve on the user's PATH, and its
+/// arguments may only use the `{{ path }}` and `{{ line }}` placeholders.
+/// The executable may be quoted, as the command is run through the shell.
+pub fn validate_editor_command(cmd: &str) -> Result<(), SettingsError> {
+    validate_editor_command_in(cmd, &pty::get_cached_user_path())
+}
+
+/// `validate_editor_command` against an explicit PATH value.
+fn validate_editor_command_in(cmd: &str, path_var: &str) -> Result<(), SettingsError> {
+    let program = program_of(cmd).ok_or(SettingsError::EmptyEditorCommand)?;
+
+    validate_template(cmd)?;
+
+    if resolve_executable(&program, path_var).is_none() {
+        return Err(SettingsError::EditorNotFound(program));
+    }
+    Ok(())
+}
+
+/// The program a command runs: its first shell word with quotes removed (and,
+/// outside Windows, backslash escapes), as the shell that launches it sees it
+pub(crate) fn program_of(cmd: &str) -> Option<String> {
+    let mut program = String::new();
+    let mut quote = None;
+    let mut chars = cmd.trim_start().chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (None, c) if c.is_whitespace() => break,
+            (None, '\'' | '"') => quote = Some(c),
+            (Some(q), c) if c == q => quote = None,
+            (None | Some('"'), '\\') if cfg!(unix) => program.extend(chars.next()),
+            (_, c) => program.push(c),
+        }
+    }
+    (!program.is_empty()).then_some(program)
+}
+
+/// Parse `template` and reject placeholders the editor launcher won't fill in.
+fn validate_template(template: &str) -> Result<(), SettingsError> {
+    let env = minijinja::Environment::new();
+    let parsed = env
+        .template_from_str(template)
+        .map_err(|e| SettingsError::InvalidEditorTemplate(e.to_string()))?;
+
+    let mut unknown: Vec<String> = parsed
+        .undeclared_variables(false)
+        .into_iter()
+        .filter(|name| !EDITOR_PLACEHOLDERS.contains(&name.as_str()))
+        .collect();
+    if unknown.is_empty() {
+        return Ok(());
+    }
+    unknown.sort();
+    Err(SettingsError::InvalidEditorTemplate(format!(
+        "unknown placeholder {}",
+        unknown.join(", ")
+    )))
+}
+
+/// Resolve `program` to an executable file, searching `path_var` for bare names.
+pub(crate) fn resolve_executable(program: &str, path_var: &str) -> Option<PathBuf> {
+    let program = Path::new(program);
+    let names = executable_names(program, pathext().as_deref());
+    if program.components().count() > 1 {
+        return names.into_iter().find(|name| is_executable(name));
+    }
+    std::env::split_paths(path_var)
+        .flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+        .find(|candidate| is_executable(candidate))
+}
+
+/// Extensions Windows tries for a program named without one, None elsewhere
+fn pathext() -> Option<String> {
+    cfg!(windows).then(|| std::env::var("PATHEXT").unwrap_or_else(|_| ".COM;.EXE;.BAT;.CMD".into()))
+}
+
+/// File names `program` may have on disk: itself, then with each `pathext`
+/// extension if it has none
+fn executable_names(program: &Path, pathext: Option<&str>) -> Vec<PathBuf> {
+    let mut names = vec![program.to_path_buf()];
+    if let Some(pathext) = pathext.filter(|_| program.extension().is_none()) {
+        names.extend(pathext.split(';').filter(|ext| !ext.is_empty()).map(|ext| {
+            let mut name = program.as_os_str().to_owned();
+            name.push(ext);
+            PathBuf::from(name)
+        }));
+    }
+    names
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A temp dir containing an executable named `myeditor`
+    fn bin_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("shellflow-settings-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let editor = dir.join("myeditor");
+        std::fs::write(&editor, "#!/bin/sh\n").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&editor, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn accepts_command_on_path_or_absolute() {
+        let dir = bin_dir();
+        let path_var = dir.to_str().unwrap();
+
+        validate_editor_command_in("myeditor", path_var).unwrap();
+        validate_editor_command_in("myeditor --wait -g {{ path }}:{{ line }}", path_var).unwrap();
+        let absolute = format!("{} {{{{ path }}}}", dir.join("myeditor").display());
+        validate_editor_command_in(&absolute, "").unwrap();
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn accepts_quoted_executable_with_spaces() {
+        let dir = bin_dir().join("My Apps");
+        std::fs::create_dir_all(&dir).unwrap();
+        let editor = dir.join("my editor");
+        std::fs::rename(dir.parent().unwrap().join("myeditor"), &editor).unwrap();
+
+        let quoted = format!("\"{}\" --wait {{{{ path }}}}", editor.display());
+        validate_editor_command_in(&quoted, "").unwrap();
+        let single = format!("'{}'", editor.display());
+        validate_editor_command_in(&single, "").unwrap();
+
+        assert_eq!(program_of("  'a b' c").as_deref(), Some("a b"));
+        assert_eq!(program_of("\"\"").as_deref(), None);
+
+        let _ = std::fs::remove_dir_all(dir.parent().unwrap());
+    }
+
+    #[test]
+    fn bare_names_try_each_pathext_extension() {
+        let names = executable_names(Path::new("code"), Some(".EXE;.CMD"));
+        assert_eq!(
+            names,
+            vec![PathBuf::from("code"), PathBuf::from("code.EXE"), PathBuf::from("code.CMD")]
+        );
+        assert_eq!(executable_names(Path::new("code.cmd"), Some(".EXE")).len(), 1);
+        assert_eq!(executable_names(Path::new("code"), None).len(), 1);
+    }
+
+    #[test]
+    fn rejects_missing_executable() {
+        let dir = bin_dir();
+        let path_var = dir.to_str().unwrap();
+
+        let err = validate_editor_command_in("no-such-editor {{ path }}", path_var).unwrap_err();
+        assert_eq!(err.code(), "settings.editor_not_found");
+        // Absolute paths aren't looked up on PATH
+        let err = validate_editor_command_in("/nonexistent/myeditor", path_var).unwrap_err();
+        assert_eq!(err.code(), "settings.editor_not_found");
+        let err = validate_editor_command_in("   ", path_var).unwrap_err();
+        assert_eq!(err.code(), "settings.empty_editor_command");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn rejects_malformed_template() {
+        let dir = bin_dir();
+        let path_var = dir.to_str().unwrap();
+
+        let err = validate_editor_command_in("myeditor {{ path", path_var).unwrap_err();
+        assert_eq!(err.code(), "settings.invalid_editor_template");
+        let err = validate_editor_command_in("myeditor {{ file }}", path_var).unwrap_err();
+        assert_eq!(err.code(), "settings.invalid_editor_template");
+        assert!(err.to_string().contains("file"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
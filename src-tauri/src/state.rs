@@ -1,6 +1,7 @@
 use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 fn default_true() -> bool {
@@ -19,6 +20,10 @@ pub struct Project {
     pub is_active: bool,
     #[serde(default, rename = "lastAccessedAt")]
     pub last_accessed_at: Option<String>,
+    /// Path template for this project's worktrees, overriding the configured
+    /// directory. See `worktree::resolve_layout`.
+    #[serde(default, rename = "worktreeLayout", skip_serializing_if = "Option::is_none")]
+    pub worktree_layout: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -26,22 +31,71 @@ pub struct Worktree {
     pub id: String,
     pub name: String,
     pub path: String,
+    /// Branch checked out in the worktree. Empty for detached worktrees.
     pub branch: String,
     #[serde(rename = "createdAt")]
     pub created_at: String,
     #[serde(default)]
     pub order: i32,
+    /// Worktree was created at a detached HEAD rather than on a new branch
+    #[serde(default)]
+    pub detached: bool,
+    /// Which set of files the changed-files panel shows for this worktree
+    #[serde(default, rename = "changedFilesViewMode")]
+    pub changed_files_view_mode: ChangedFilesViewMode,
+    /// List gitignored files in the uncommitted view, e.g. to clean up stray env files
+    #[serde(default, rename = "showIgnoredFiles")]
+    pub show_ignored_files: bool,
+    /// When the worktree was last selected. Missing for worktrees never opened.
+    #[serde(default, rename = "lastAccessedAt")]
+    pub last_accessed_at: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Source for the changed-files panel
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangedFilesViewMode {
+    /// Working tree changes relative to HEAD
+    #[default]
+    Uncommitted,
+    /// Everything the branch changes relative to its base branch
+    Branch,
+}
+
+impl ChangedFilesViewMode {
+    pub fn toggled(self) -> Self {
+        match self {
+            Self::Uncommitted => Self::Branch,
+            Self::Branch => Self::Uncommitted,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct FileChange {
     pub path: String,
     pub status: FileStatus,
     pub insertions: Option<usize>,
     pub deletions: Option<usize>,
+    /// Present when the entry is a submodule rather than a regular file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub submodule: Option<SubmoduleChange>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// What changed inside a submodule
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubmoduleChange {
+    /// The checked-out commit differs from the one recorded in the superproject
+    pub new_commits: bool,
+    /// Tracked files inside the submodule have been modified
+    pub modified_content: bool,
+    /// The submodule contains untracked files
+    pub untracked_content: bool,
+}
+
+/// Variant order is the display order of changed files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum FileStatus {
     Added,
@@ -49,6 +103,65 @@ pub enum FileStatus {
     Deleted,
     Renamed,
     Untracked,
+    /// Matched by gitignore. Only listed when a worktree shows ignored files.
+    Ignored,
+}
+
+impl FileStatus {
+    /// Same spelling as the serialized form
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FileStatus::Added => "added",
+            FileStatus::Modified => "modified",
+            FileStatus::Deleted => "deleted",
+            FileStatus::Renamed => "renamed",
+            FileStatus::Untracked => "untracked",
+            FileStatus::Ignored => "ignored",
+        }
+    }
+}
+
+/// Number of changed files per status
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatusCounts {
+    pub added: usize,
+    pub modified: usize,
+    pub deleted: usize,
+    pub renamed: usize,
+    pub untracked: usize,
+    pub ignored: usize,
+}
+
+impl StatusCounts {
+    pub fn record(&mut self, status: &FileStatus) {
+        match status {
+            FileStatus::Added => self.added += 1,
+            FileStatus::Modified => self.modified += 1,
+            FileStatus::Deleted => self.deleted += 1,
+            FileStatus::Renamed => self.renamed += 1,
+            FileStatus::Untracked => self.untracked += 1,
+            FileStatus::Ignored => self.ignored += 1,
+        }
+    }
+
+    #[cfg(test)]
+    pub fn total(&self) -> usize {
+        self.added + self.modified + self.deleted + self.renamed + self.untracked + self.ignored
+    }
+}
+
+/// A directory in the changed-files tree.
+/// `counts` covers every file below this directory, not just direct children.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeTree {
+    /// Directory name (empty for the root)
+    pub name: String,
+    /// Path relative to the worktree root (empty for the root)
+    pub path: String,
+    pub directories: Vec<ChangeTree>,
+    pub files: Vec<FileChange>,
+    pub counts: StatusCounts,
 }
 
 /// Information about the current branch relative to base branch
@@ -71,17 +184,211 @@ pub struct DiffContent {
     pub language: String,
 }
 
+/// A named snapshot of which projects are open and what was focused
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Layout {
+    pub open_project_ids: Vec<String>,
+    #[serde(default)]
+    pub active_project_id: Option<String>,
+    #[serde(default)]
+    pub active_worktree_id: Option<String>,
+}
+
+/// Outcome of restoring a layout
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoredLayout {
+    /// Projects that were closed and are now open
+    pub opened_project_ids: Vec<String>,
+    /// Projects that were open and are now closed
+    pub closed_project_ids: Vec<String>,
+    /// Projects in the layout that no longer exist
+    pub missing_project_ids: Vec<String>,
+    /// Focus to restore, dropped if the project or worktree is gone
+    pub active_project_id: Option<String>,
+    pub active_worktree_id: Option<String>,
+}
+
+/// A window's position and size in physical pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WindowBounds {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PersistedState {
     pub projects: Vec<Project>,
+    #[serde(default)]
+    pub layouts: BTreeMap<String, Layout>,
+    /// Name of the task last run in each worktree or project, keyed by its id
+    #[serde(default, rename = "lastTasks")]
+    pub last_tasks: HashMap<String, String>,
+    /// Main window geometry last used with each project, keyed by project id
+    #[serde(default, rename = "windowBounds")]
+    pub window_bounds: HashMap<String, WindowBounds>,
 }
 
 impl Default for PersistedState {
     fn default() -> Self {
-        Self { projects: vec![] }
+        Self {
+            projects: vec![],
+            layouts: BTreeMap::new(),
+            last_tasks: HashMap::new(),
+            window_bounds: HashMap::new(),
+        }
+    }
+}
+
+impl PersistedState {
+    /// Save the currently open projects under `name`, replacing any layout with that name.
+    pub fn save_layout(
+        &mut self,
+        name: &str,
+        active_project_id: Option<String>,
+        active_worktree_id: Option<String>,
+    ) -> Layout {
+        let layout = Layout {
+            open_project_ids: self
+                .projects
+                .iter()
+                .filter(|p| p.is_active)
+                .map(|p| p.id.clone())
+                .collect(),
+            active_project_id,
+            active_worktree_id,
+        };
+        self.layouts.insert(name.to_string(), layout.clone());
+        layout
+    }
+
+    /// Open exactly the projects saved in layout `name`, closing the rest.
+    /// Returns `None` if no layout has that name.
+    pub fn restore_layout(&mut self, name: &str) -> Option<RestoredLayout> {
+        let layout = self.layouts.get(name)?.clone();
+        let mut restored = RestoredLayout::default();
+
+        for id in &layout.open_project_ids {
+            if !self.projects.iter().any(|p| &p.id == id) {
+                restored.missing_project_ids.push(id.clone());
+            }
+        }
+
+        for project in &mut self.projects {
+            let open = layout.open_project_ids.contains(&project.id);
+            if open && !project.is_active {
+                restored.opened_project_ids.push(project.id.clone());
+            } else if !open && project.is_active {
+                restored.closed_project_ids.push(project.id.clone());
+            }
+            project.is_active = open;
+        }
+
+        restored.active_project_id = layout
+            .active_project_id
+            .filter(|id| self.projects.iter().any(|p| &p.id == id && p.is_active));
+        restored.active_worktree_id = layout.active_worktree_id.filter(|id| {
+            self.projects
+                .iter()
+                .filter(|p| p.is_active)
+                .any(|p| p.worktrees.iter().any(|w| &w.id == id))
+        });
+
+        Some(restored)
+    }
+
+    /// Open project paths for compact display, keyed by project id. Projects
+    /// sharing a parent directory (e.g. repos of a monorepo) are shown relative
+    /// to their deepest common ancestor. Full paths are kept when there's only
+    /// one project or the only thing in common is the filesystem root.
+    pub fn display_paths(&self) -> Vec<(String, String)> {
+        let open: Vec<&Project> = self.projects.iter().filter(|p| p.is_active).collect();
+        // Every project keeps at least its own directory name
+        let mut parents = open.iter().map(|p| Path::new(&p.path).parent().unwrap_or(Path::new("")));
+        let mut common = parents.next().map(Path::to_path_buf).unwrap_or_default();
+        for parent in parents {
+            while !parent.starts_with(&common) {
+                if !common.pop() {
+                    break;
+                }
+            }
+        }
+
+        let meaningful = open.len() > 1 && common.components().count() > 1;
+        open.iter()
+            .map(|p| {
+                let path = Path::new(&p.path);
+                let shown = match path.strip_prefix(&common) {
+                    Ok(relative) if meaningful => relative.to_string_lossy().into_owned(),
+                    _ => p.path.clone(),
+                };
+                (p.id.clone(), shown)
+            })
+            .collect()
     }
 }
 
+/// Payload of `state-recovery`: the state file couldn't be read, so the app
+/// started empty
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StateRecovery {
+    pub state_path: String,
+    /// Where the unreadable file was moved. None if moving it failed too, in
+    /// which case the next save overwrites it.
+    pub backup_path: Option<String>,
+    pub error: String,
+}
+
+/// `<file>.corrupt`, or the first free `<file>.corrupt.N`, so earlier backups survive
+fn unused_backup_path(state_file: &Path) -> PathBuf {
+    let with_suffix = |suffix: String| {
+        let mut path = state_file.as_os_str().to_owned();
+        path.push(suffix);
+        PathBuf::from(path)
+    };
+    std::iter::once(with_suffix(".corrupt".to_string()))
+        .chain((1..).map(|n| with_suffix(format!(".corrupt.{}", n))))
+        .find(|path| !path.exists())
+        .expect("some backup name is free")
+}
+
+/// Read persisted state from `state_file`; a missing file is a fresh start.
+/// A file that can't be read or parsed is moved aside to `<file>.corrupt`
+/// (or `<file>.corrupt.N` if earlier backups exist) instead of being
+/// overwritten by the next save, and loading starts empty.
+pub fn load_from_disk(state_file: &Path) -> (PersistedState, Option<StateRecovery>) {
+    let error = match std::fs::read_to_string(state_file) {
+        Ok(content) => match serde_json::from_str(&content) {
+            Ok(persisted) => return (persisted, None),
+            Err(e) => e.to_string(),
+        },
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return (PersistedState::default(), None)
+        }
+        Err(e) => e.to_string(),
+    };
+
+    let backup = unused_backup_path(state_file);
+    let backup_path = match std::fs::rename(state_file, &backup) {
+        Ok(()) => Some(backup.to_string_lossy().to_string()),
+        Err(e) => {
+            eprintln!("[State] Failed to move aside {}: {}", state_file.display(), e);
+            None
+        }
+    };
+    eprintln!("[State] Could not load {}, starting empty: {}", state_file.display(), error);
+    let recovery = StateRecovery {
+        state_path: state_file.to_string_lossy().to_string(),
+        backup_path,
+        error,
+    };
+    (PersistedState::default(), Some(recovery))
+}
+
 #[allow(dead_code)]
 pub struct PtySession {
     pub worktree_id: String,
@@ -91,6 +398,8 @@ pub struct PtySession {
 pub struct AppState {
     pub persisted: RwLock<PersistedState>,
     pub pty_sessions: RwLock<HashMap<String, Arc<PtySession>>>,
+    /// Set when the state file was unreadable at startup
+    pub recovery: RwLock<Option<StateRecovery>>,
 }
 
 impl AppState {
@@ -98,6 +407,7 @@ impl AppState {
         Self {
             persisted: RwLock::new(PersistedState::default()),
             pty_sessions: RwLock::new(HashMap::new()),
+            recovery: RwLock::new(None),
         }
     }
 
@@ -106,37 +416,34 @@ impl AppState {
 
         if let Some(config_dir) = dirs::home_dir() {
             let state_file = config_dir.join(".shellflow").join("state.json");
-            if state_file.exists() {
-                if let Ok(content) = std::fs::read_to_string(&state_file) {
-                    if let Ok(mut persisted) = serde_json::from_str::<PersistedState>(&content) {
-                        // Clean up stale worktrees whose directories no longer exist
-                        let mut cleaned = false;
-                        for project in &mut persisted.projects {
-                            let before_count = project.worktrees.len();
-                            project.worktrees.retain(|w| {
-                                let exists = std::path::Path::new(&w.path).exists();
-                                if !exists {
-                                    eprintln!(
-                                        "[State] Removing stale worktree '{}' - path no longer exists: {}",
-                                        w.name, w.path
-                                    );
-                                }
-                                exists
-                            });
-                            if project.worktrees.len() != before_count {
-                                cleaned = true;
-                            }
-                        }
-
-                        *state.persisted.write() = persisted;
-
-                        // Save cleaned state if any worktrees were removed
-                        if cleaned {
-                            if let Err(e) = state.save() {
-                                eprintln!("[State] Failed to save cleaned state: {}", e);
-                            }
-                        }
+            let (mut persisted, recovery) = load_from_disk(&state_file);
+
+            // Clean up stale worktrees whose directories no longer exist
+            let mut cleaned = false;
+            for project in &mut persisted.projects {
+                let before_count = project.worktrees.len();
+                project.worktrees.retain(|w| {
+                    let exists = std::path::Path::new(&w.path).exists();
+                    if !exists {
+                        eprintln!(
+                            "[State] Removing stale worktree '{}' - path no longer exists: {}",
+                            w.name, w.path
+                        );
                     }
+                    exists
+                });
+                if project.worktrees.len() != before_count {
+                    cleaned = true;
+                }
+            }
+
+            *state.persisted.write() = persisted;
+            *state.recovery.write() = recovery;
+
+            // Save cleaned state if any worktrees were removed
+            if cleaned {
+                if let Err(e) = state.save() {
+                    eprintln!("[State] Failed to save cleaned state: {}", e);
                 }
             }
         }
@@ -159,3 +466,192 @@ impl AppState {
 
 unsafe impl Send for AppState {}
 unsafe impl Sync for AppState {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project(id: &str, worktree_ids: &[&str], is_active: bool) -> Project {
+        let worktrees = worktree_ids
+            .iter()
+            .map(|wt| crate::test_utils::worktree(wt, &format!("/tmp/{}/{}", id, wt)))
+            .collect();
+        Project {
+            is_active,
+            ..crate::test_utils::project(id, &format!("/tmp/{}", id), worktrees)
+        }
+    }
+
+    fn open_ids(state: &PersistedState) -> Vec<&str> {
+        state
+            .projects
+            .iter()
+            .filter(|p| p.is_active)
+            .map(|p| p.id.as_str())
+            .collect()
+    }
+
+    fn at(id: &str, path: &str) -> Project {
+        Project {
+            path: path.to_string(),
+            ..project(id, &[], true)
+        }
+    }
+
+    fn pairs(entries: &[(&str, &str)]) -> Vec<(String, String)> {
+        entries.iter().map(|(id, path)| (id.to_string(), path.to_string())).collect()
+    }
+
+    #[test]
+    fn display_paths_strip_a_shared_parent() {
+        let state = PersistedState {
+            projects: vec![
+                at("api", "/home/dev/monorepo/services/api"),
+                at("web", "/home/dev/monorepo/apps/web"),
+                at("docs", "/home/dev/monorepo/docs"),
+                Project {
+                    is_active: false,
+                    ..at("closed", "/elsewhere/closed")
+                },
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            state.display_paths(),
+            pairs(&[("api", "services/api"), ("web", "apps/web"), ("docs", "docs")])
+        );
+    }
+
+    #[test]
+    fn display_paths_keep_full_paths_without_a_meaningful_prefix() {
+        let unrelated = PersistedState {
+            projects: vec![at("a", "/srv/a"), at("b", "/opt/b")],
+            ..Default::default()
+        };
+        assert_eq!(unrelated.display_paths(), pairs(&[("a", "/srv/a"), ("b", "/opt/b")]));
+
+        let single = PersistedState {
+            projects: vec![at("a", "/home/dev/a")],
+            ..Default::default()
+        };
+        assert_eq!(single.display_paths(), pairs(&[("a", "/home/dev/a")]));
+    }
+
+    #[test]
+    fn restore_layout_reopens_saved_projects() {
+        let mut state = PersistedState {
+            projects: vec![
+                project("a", &["a1"], true),
+                project("b", &["b1", "b2"], true),
+                project("c", &[], false),
+            ],
+            ..Default::default()
+        };
+        state.save_layout("work", Some("b".into()), Some("b2".into()));
+
+        // Close everything, then open something else
+        for p in &mut state.projects {
+            p.is_active = false;
+        }
+        state.projects[2].is_active = true;
+
+        let restored = state.restore_layout("work").unwrap();
+
+        assert_eq!(open_ids(&state), vec!["a", "b"]);
+        assert_eq!(restored.opened_project_ids, vec!["a", "b"]);
+        assert_eq!(restored.closed_project_ids, vec!["c"]);
+        assert!(restored.missing_project_ids.is_empty());
+        assert_eq!(restored.active_project_id.as_deref(), Some("b"));
+        assert_eq!(restored.active_worktree_id.as_deref(), Some("b2"));
+    }
+
+    #[test]
+    fn restore_layout_skips_removed_projects_and_worktrees() {
+        let mut state = PersistedState {
+            projects: vec![project("a", &["a1"], true), project("b", &["b1"], true)],
+            ..Default::default()
+        };
+        state.save_layout("work", Some("b".into()), Some("b1".into()));
+
+        state.projects.retain(|p| p.id != "b");
+
+        let restored = state.restore_layout("work").unwrap();
+
+        assert_eq!(open_ids(&state), vec!["a"]);
+        assert_eq!(restored.missing_project_ids, vec!["b"]);
+        assert_eq!(restored.active_project_id, None);
+        assert_eq!(restored.active_worktree_id, None);
+    }
+
+    #[test]
+    fn restore_unknown_layout_is_none() {
+        let mut state = PersistedState::default();
+        assert!(state.restore_layout("nope").is_none());
+    }
+
+    fn temp_state_file() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("shellflow-state-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.join("state.json")
+    }
+
+    #[test]
+    fn corrupt_state_file_is_set_aside_and_loading_starts_empty() {
+        let state_file = temp_state_file();
+        let corrupt = r#"{ "projects": [ { "id": "p", "name": "#;
+        std::fs::write(&state_file, corrupt).unwrap();
+
+        let (persisted, recovery) = load_from_disk(&state_file);
+
+        assert!(persisted.projects.is_empty());
+        let recovery = recovery.expect("a corrupt file is reported");
+        let backup = state_file.with_file_name("state.json.corrupt");
+        assert_eq!(recovery.backup_path, Some(backup.to_string_lossy().to_string()));
+        assert_eq!(std::fs::read_to_string(&backup).unwrap(), corrupt);
+        assert!(!state_file.exists(), "the next save must not overwrite the backup");
+
+        let _ = std::fs::remove_dir_all(state_file.parent().unwrap());
+    }
+
+    #[test]
+    fn repeated_corruption_keeps_every_backup() {
+        let state_file = temp_state_file();
+        std::fs::write(&state_file, "first").unwrap();
+        load_from_disk(&state_file);
+        std::fs::write(&state_file, "second").unwrap();
+
+        let (_, recovery) = load_from_disk(&state_file);
+
+        let first = state_file.with_file_name("state.json.corrupt");
+        let second = state_file.with_file_name("state.json.corrupt.1");
+        assert_eq!(recovery.unwrap().backup_path, Some(second.to_string_lossy().to_string()));
+        assert_eq!(std::fs::read_to_string(&first).unwrap(), "first");
+        assert_eq!(std::fs::read_to_string(&second).unwrap(), "second");
+
+        let _ = std::fs::remove_dir_all(state_file.parent().unwrap());
+    }
+
+    #[test]
+    fn readable_or_missing_state_file_needs_no_recovery() {
+        let state_file = temp_state_file();
+        assert_eq!(load_from_disk(&state_file).1, None);
+
+        let state = PersistedState {
+            projects: vec![project("a", &["a1"], true)],
+            ..Default::default()
+        };
+        std::fs::write(&state_file, serde_json::to_string(&state).unwrap()).unwrap();
+        let (persisted, recovery) = load_from_disk(&state_file);
+        assert_eq!(recovery, None);
+        assert_eq!(open_ids(&persisted), vec!["a"]);
+
+        let _ = std::fs::remove_dir_all(state_file.parent().unwrap());
+    }
+
+    #[test]
+    fn layouts_default_when_missing_from_state_file() {
+        let state: PersistedState = serde_json::from_str(r#"{ "projects": [] }"#).unwrap();
+        assert!(state.layouts.is_empty());
+    }
+}
@@ -0,0 +1,1155 @@
+//! Bookkeeping for tasks spawned from project config, kept separate from the
+//! PTY layer that actually runs them. Task ids are the PTY ids returned by
+//! `spawn_task`.
+
+use crate::config::{Config, TaskConfig, TaskKind};
+use crate::git::{self, GitError};
+use crate::state::{AppState, PersistedState};
+use crate::template::{expand_template, TemplateContext};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum TaskError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Task {0} has no log file")]
+    NoLogFile(String),
+    #[error("Git error: {0}")]
+    Git(#[from] GitError),
+    #[error("Worktree or project not found: {0}")]
+    EntityNotFound(String),
+    #[error("Template error: {0}")]
+    Template(String),
+    #[error("Task not found: {0}")]
+    TaskNotFound(String),
+    #[error("No task has been run in {0} yet")]
+    NoLastTask(String),
+}
+
+/// Where a task runs: a worktree, or a project's main checkout
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaskTarget {
+    pub cwd: String,
+    pub project_path: String,
+    pub branch: String,
+    /// `None` when running in the project's main checkout
+    pub worktree_name: Option<String>,
+}
+
+/// A task with its templates expanded for a specific target
+#[derive(Debug, Clone, PartialEq)]
+pub struct PreparedTask {
+    pub cwd: String,
+    pub command: String,
+    pub env: HashMap<String, String>,
+    pub shell: Option<String>,
+    pub log_file: Option<PathBuf>,
+}
+
+/// Package manager or build tool a project uses, detected from its files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PackageManager {
+    Bun,
+    Pnpm,
+    Yarn,
+    Npm,
+    Cargo,
+    Make,
+}
+
+/// A task process that hasn't exited yet
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunningTask {
+    pub id: String,
+    /// Worktree or project the task runs in
+    pub worktree_id: String,
+    /// Task name from config
+    pub label: String,
+    pub started_at: String,
+    pub pid: u32,
+}
+
+/// Opt-in rule to run a task whenever a worktree's files change
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AutoRunRule {
+    pub task_name: String,
+    /// Minimum time between runs, so a burst of saves doesn't start a storm of runs
+    pub cooldown_ms: u64,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskAutoRun {
+    pub worktree_id: String,
+    pub task_id: String,
+    pub task_name: String,
+}
+
+/// Auto-run state for one worktree
+#[derive(Debug)]
+struct AutoRunner {
+    rule: AutoRunRule,
+    last_run_at: Option<Instant>,
+    current_task_id: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+enum AutoRunDecision {
+    /// Still cooling down from the previous run
+    Skip,
+    /// Start the task, cancelling the previous run first if there was one
+    Run { cancel: Option<String> },
+}
+
+impl AutoRunner {
+    fn new(rule: AutoRunRule) -> Self {
+        Self {
+            rule,
+            last_run_at: None,
+            current_task_id: None,
+        }
+    }
+
+    fn on_change(&mut self, now: Instant) -> AutoRunDecision {
+        let cooldown = Duration::from_millis(self.rule.cooldown_ms);
+        if let Some(last) = self.last_run_at {
+            if now.duration_since(last) < cooldown {
+                return AutoRunDecision::Skip;
+            }
+        }
+        self.last_run_at = Some(now);
+        AutoRunDecision::Run {
+            cancel: self.current_task_id.take(),
+        }
+    }
+
+    fn started(&mut self, task_id: String) {
+        self.current_task_id = Some(task_id);
+    }
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskLogLine {
+    pub task_id: String,
+    pub line: String,
+}
+
+const FOLLOW_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How long a cancelled task gets to exit after SIGTERM before it is killed
+const CANCEL_GRACE_PERIOD: Duration = Duration::from_secs(3);
+
+lazy_static::lazy_static! {
+    // Every task spawned and not yet exited, across all worktrees
+    static ref RUNNING_TASKS: Mutex<HashMap<String, RunningTask>> = Mutex::new(HashMap::new());
+    // Log file of each running task that declares one
+    static ref TASK_LOGS: Mutex<HashMap<String, PathBuf>> = Mutex::new(HashMap::new());
+    // Worktrees that re-run a task when their files change
+    static ref AUTO_RUNNERS: Mutex<HashMap<String, AutoRunner>> = Mutex::new(HashMap::new());
+    // Active `tail_follow` loops, stopped by sending on the channel
+    static ref FOLLOWERS: Mutex<HashMap<String, Sender<()>>> = Mutex::new(HashMap::new());
+    // Worktrees whose watcher is paused while one of their tasks runs
+    static ref PAUSE_WATCHER: Mutex<HashSet<String>> = Mutex::new(HashSet::new());
+    // Running task id -> worktree whose watcher it paused
+    static ref WATCHER_PAUSES: Mutex<HashMap<String, String>> = Mutex::new(HashMap::new());
+}
+
+/// Resolve the worktree or project with id `entity_id` to the directory a task
+/// should run in and the values its templates are rendered with.
+pub fn resolve_target(
+    persisted: &PersistedState,
+    entity_id: &str,
+) -> Result<TaskTarget, TaskError> {
+    for project in &persisted.projects {
+        if let Some(worktree) = project.worktrees.iter().find(|w| w.id == entity_id) {
+            return Ok(TaskTarget {
+                cwd: worktree.path.clone(),
+                project_path: project.path.clone(),
+                branch: worktree.branch.clone(),
+                worktree_name: Some(worktree.name.clone()),
+            });
+        }
+    }
+
+    let project = persisted
+        .projects
+        .iter()
+        .find(|p| p.id == entity_id)
+        .ok_or_else(|| TaskError::EntityNotFound(entity_id.to_string()))?;
+
+    // The main checkout's branch isn't tracked in state, so ask git
+    let repo = git2::Repository::open(&project.path).map_err(GitError::from)?;
+    Ok(TaskTarget {
+        cwd: project.path.clone(),
+        project_path: project.path.clone(),
+        branch: git::get_current_branch(&repo)?,
+        worktree_name: None,
+    })
+}
+
+/// Remember `task_name` as the last task run in the worktree or project `entity_id`.
+pub fn record_run(persisted: &mut PersistedState, entity_id: &str, task_name: &str) {
+    persisted
+        .last_tasks
+        .insert(entity_id.to_string(), task_name.to_string());
+}
+
+/// Name of the last task run in `entity_id`, for re-running it.
+pub fn last_run(persisted: &PersistedState, entity_id: &str) -> Result<String, TaskError> {
+    persisted
+        .last_tasks
+        .get(entity_id)
+        .cloned()
+        .ok_or_else(|| TaskError::NoLastTask(entity_id.to_string()))
+}
+
+/// Expand a task's command, env and log file for `target`. Env values that
+/// fail to render are passed through unchanged.
+pub fn prepare(task: &TaskConfig, target: &TaskTarget) -> Result<PreparedTask, TaskError> {
+    let mut ctx = TemplateContext::new(&target.project_path).with_branch(&target.branch);
+    if let Some(name) = &target.worktree_name {
+        ctx = ctx.with_worktree_name(name);
+    }
+
+    let command = expand_template(&task.command, &ctx).map_err(TaskError::Template)?;
+    let env = task
+        .env
+        .iter()
+        .map(|(key, value)| {
+            let expanded = expand_template(value, &ctx).unwrap_or_else(|_| value.clone());
+            (key.clone(), expanded)
+        })
+        .collect();
+    let log_file = match &task.log_file {
+        Some(log_file) => {
+            let log_file = expand_template(log_file, &ctx).map_err(TaskError::Template)?;
+            Some(Path::new(&target.cwd).join(log_file))
+        }
+        None => None,
+    };
+
+    Ok(PreparedTask {
+        cwd: target.cwd.clone(),
+        command,
+        env,
+        shell: task.shell.clone(),
+        log_file,
+    })
+}
+
+/// Record a newly spawned task, pausing its worktree's watcher if opted in.
+pub fn register_task(task: RunningTask) {
+    if PAUSE_WATCHER.lock().contains(&task.worktree_id) {
+        crate::watcher::pause(&task.worktree_id);
+        WATCHER_PAUSES
+            .lock()
+            .insert(task.id.clone(), task.worktree_id.clone());
+    }
+    RUNNING_TASKS.lock().insert(task.id.clone(), task);
+}
+
+/// Resume the watcher a task paused, if it paused one
+fn release_watcher_pause(task_id: &str) {
+    if let Some(worktree_id) = WATCHER_PAUSES.lock().remove(task_id) {
+        crate::watcher::resume(&worktree_id);
+    }
+}
+
+/// Enable or disable pausing a worktree's watcher while its tasks run, so
+/// build output doesn't flood the changed-files panel. Changes made during
+/// the run are reported as one refresh when the last task exits. Applies to
+/// tasks started after the call.
+pub fn set_pause_watcher(worktree_id: &str, enabled: bool) {
+    let mut worktrees = PAUSE_WATCHER.lock();
+    if enabled {
+        worktrees.insert(worktree_id.to_string());
+    } else {
+        worktrees.remove(worktree_id);
+    }
+}
+
+pub fn pauses_watcher(worktree_id: &str) -> bool {
+    PAUSE_WATCHER.lock().contains(worktree_id)
+}
+
+/// Tasks that are still running, oldest first. Entries whose process has
+/// died without the exit being reported are dropped on the way.
+pub fn running_tasks() -> Vec<RunningTask> {
+    let mut running = RUNNING_TASKS.lock();
+    #[cfg(unix)]
+    running.retain(|id, task| {
+        let alive = crate::pty::is_process_alive(task.pid);
+        if !alive {
+            release_watcher_pause(id);
+        }
+        alive
+    });
+
+    let mut tasks: Vec<RunningTask> = running.values().cloned().collect();
+    tasks.sort_by(|a, b| {
+        a.started_at
+            .cmp(&b.started_at)
+            .then_with(|| a.id.cmp(&b.id))
+    });
+    tasks
+}
+
+/// Remember where a running task writes its log.
+pub fn register_log(task_id: &str, path: PathBuf) {
+    TASK_LOGS.lock().insert(task_id.to_string(), path);
+}
+
+/// Forget a task once its process has exited, stopping any follow of its log
+/// and resuming a watcher it paused.
+pub fn task_exited(task_id: &str) {
+    RUNNING_TASKS.lock().remove(task_id);
+    release_watcher_pause(task_id);
+    TASK_LOGS.lock().remove(task_id);
+    stop_follow(task_id);
+}
+
+fn log_path(task_id: &str) -> Result<PathBuf, TaskError> {
+    TASK_LOGS
+        .lock()
+        .get(task_id)
+        .cloned()
+        .ok_or_else(|| TaskError::NoLogFile(task_id.to_string()))
+}
+
+/// Last `lines` lines of a running task's log.
+pub fn tail_log(task_id: &str, lines: usize) -> Result<Vec<String>, TaskError> {
+    Ok(last_lines(&log_path(task_id)?, lines)?)
+}
+
+/// Last `count` lines of a file, reading backwards from the end so large logs
+/// aren't loaded whole.
+pub fn last_lines(path: &Path, count: usize) -> std::io::Result<Vec<String>> {
+    const CHUNK: u64 = 8 * 1024;
+
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    let mut start = len;
+    let mut buf: Vec<u8> = Vec::new();
+
+    // Read until we have more newlines than lines wanted (the extra one bounds the first line)
+    while start > 0 && buf.iter().filter(|&&b| b == b'\n').count() <= count {
+        let read_from = start.saturating_sub(CHUNK);
+        let mut chunk = vec![0; (start - read_from) as usize];
+        file.seek(SeekFrom::Start(read_from))?;
+        file.read_exact(&mut chunk)?;
+        chunk.extend_from_slice(&buf);
+        buf = chunk;
+        start = read_from;
+    }
+
+    let text = String::from_utf8_lossy(&buf);
+    let lines: Vec<&str> = text.lines().collect();
+    let skip = lines.len().saturating_sub(count);
+    Ok(lines[skip..].iter().map(|l| l.to_string()).collect())
+}
+
+/// Call `on_line` for each complete line appended to `path` until `stop` fires
+/// or its sender is dropped. Starts at the current end of the file, like
+/// `tail -f`. If the file shrinks (truncated or rotated), reading restarts
+/// from the beginning.
+pub fn follow_log(
+    path: &Path,
+    stop: Receiver<()>,
+    poll: Duration,
+    mut on_line: impl FnMut(String),
+) {
+    let mut offset = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    let mut pending: Vec<u8> = Vec::new();
+
+    loop {
+        if let Ok(mut file) = File::open(path) {
+            let len = file.metadata().map(|m| m.len()).unwrap_or(0);
+            if len < offset {
+                offset = 0;
+                pending.clear();
+            }
+            if len > offset && file.seek(SeekFrom::Start(offset)).is_ok() {
+                let mut appended = Vec::new();
+                if let Ok(read) = file.read_to_end(&mut appended) {
+                    offset += read as u64;
+                    pending.extend_from_slice(&appended);
+                    while let Some(newline) = pending.iter().position(|&b| b == b'\n') {
+                        let line: Vec<u8> = pending.drain(..=newline).collect();
+                        let line = String::from_utf8_lossy(&line);
+                        on_line(line.trim_end_matches(['\n', '\r']).to_string());
+                    }
+                }
+            }
+        }
+
+        match stop.recv_timeout(poll) {
+            Err(RecvTimeoutError::Timeout) => continue,
+            _ => return,
+        }
+    }
+}
+
+/// Stream new lines of a task's log as `task-log-line` events until the task
+/// exits or `stop_follow` is called. Replaces any existing follow of the task.
+pub fn tail_follow(app: AppHandle, task_id: &str) -> Result<(), TaskError> {
+    let path = log_path(task_id)?;
+    stop_follow(task_id);
+
+    let (tx, rx) = channel();
+    FOLLOWERS.lock().insert(task_id.to_string(), tx);
+
+    let task_id = task_id.to_string();
+    thread::spawn(move || {
+        follow_log(&path, rx, FOLLOW_POLL_INTERVAL, |line| {
+            let _ = app.emit(
+                "task-log-line",
+                TaskLogLine {
+                    task_id: task_id.clone(),
+                    line,
+                },
+            );
+        });
+    });
+    Ok(())
+}
+
+pub fn stop_follow(task_id: &str) {
+    if let Some(tx) = FOLLOWERS.lock().remove(task_id) {
+        let _ = tx.send(());
+    }
+}
+
+/// Stop a running task and every process it started (e.g. the server behind
+/// `npm run dev`), then emit `task-cancelled`.
+pub fn cancel_task(app: &AppHandle, state: &AppState, task_id: &str) -> Result<(), TaskError> {
+    let pid = state
+        .pty_sessions
+        .read()
+        .get(task_id)
+        .map(|s| s.child_pid)
+        .filter(|&pid| pid > 0)
+        .ok_or_else(|| TaskError::TaskNotFound(task_id.to_string()))?;
+
+    kill_process_tree(pid);
+    let _ = app.emit("task-cancelled", task_id);
+    Ok(())
+}
+
+/// Terminate `pid` and its descendants: SIGTERM first, then SIGKILL for
+/// anything still alive after a grace period.
+#[cfg(unix)]
+pub fn kill_process_tree(pid: u32) {
+    use crate::pty::{get_child_pids, is_process_alive, send_signal};
+
+    // Collect descendants up front; once the parent dies they are reparented
+    // and can no longer be found through it
+    let mut tree = get_child_pids(pid);
+    tree.push(pid);
+
+    let signal_all = move |tree: &[u32], signal: i32| {
+        // PTY children lead their own session, so the group catches
+        // descendants that were started after we listed them
+        unsafe { libc::killpg(pid as i32, signal) };
+        for &member in tree {
+            send_signal(member, signal);
+        }
+    };
+
+    signal_all(&tree, libc::SIGTERM);
+    thread::spawn(move || {
+        thread::sleep(CANCEL_GRACE_PERIOD);
+        let survivors: Vec<u32> = tree.into_iter().filter(|&p| is_process_alive(p)).collect();
+        if !survivors.is_empty() {
+            signal_all(&survivors, libc::SIGKILL);
+        }
+    });
+}
+
+#[cfg(windows)]
+pub fn kill_process_tree(pid: u32) {
+    // /T takes down the whole tree, /F doesn't wait for windows to close
+    let _ = std::process::Command::new("taskkill")
+        .args(["/T", "/F", "/PID", &pid.to_string()])
+        .output();
+}
+
+/// Enable (`Some`) or disable (`None`) auto-running a task on file changes in a worktree.
+pub fn set_auto_run(worktree_id: &str, rule: Option<AutoRunRule>) {
+    let mut runners = AUTO_RUNNERS.lock();
+    match rule {
+        Some(rule) => {
+            runners.insert(worktree_id.to_string(), AutoRunner::new(rule));
+        }
+        None => {
+            runners.remove(worktree_id);
+        }
+    }
+}
+
+pub fn auto_run_rule(worktree_id: &str) -> Option<AutoRunRule> {
+    AUTO_RUNNERS.lock().get(worktree_id).map(|r| r.rule.clone())
+}
+
+/// Whether any of `paths` is worth re-running a task for: inside the
+/// worktree, outside `.git`, and not ignored by git.
+pub fn has_relevant_change(worktree: &Path, paths: &[PathBuf]) -> bool {
+    let Ok(repo) = git2::Repository::open(worktree) else {
+        return false;
+    };
+    // Backends may report canonical paths (e.g. /private/var on macOS)
+    let roots: Vec<PathBuf> = std::iter::once(worktree.to_path_buf())
+        .chain(worktree.canonicalize().ok())
+        .collect();
+
+    paths.iter().any(|path| {
+        let Some(relative) = roots.iter().find_map(|root| path.strip_prefix(root).ok()) else {
+            return false;
+        };
+        match relative.components().next() {
+            None => false,
+            Some(first) if first.as_os_str() == ".git" => false,
+            Some(_) => !repo.is_path_ignored(relative).unwrap_or(false),
+        }
+    })
+}
+
+/// Called by the watcher after a debounced change. Runs the worktree's
+/// auto-run task unless it's cooling down, cancelling the previous run.
+pub fn on_files_changed(
+    app: &AppHandle,
+    worktree_id: &str,
+    worktree_path: &Path,
+    paths: &[PathBuf],
+) {
+    if !AUTO_RUNNERS.lock().contains_key(worktree_id) || !has_relevant_change(worktree_path, paths)
+    {
+        return;
+    }
+
+    let (decision, task_name) = match AUTO_RUNNERS.lock().get_mut(worktree_id) {
+        Some(runner) => (
+            runner.on_change(Instant::now()),
+            runner.rule.task_name.clone(),
+        ),
+        None => return,
+    };
+    let AutoRunDecision::Run { cancel } = decision else {
+        return;
+    };
+    let Some(state) = app.try_state::<Arc<AppState>>() else {
+        return;
+    };
+
+    if let Some(previous) = cancel {
+        // The previous run may have finished on its own already
+        let _ = cancel_task(app, &state, &previous);
+    }
+
+    match crate::spawn_task(app.clone(), state, worktree_id, &task_name, None, None) {
+        Ok(task_id) => {
+            if let Some(runner) = AUTO_RUNNERS.lock().get_mut(worktree_id) {
+                runner.started(task_id.clone());
+            }
+            let _ = app.emit(
+                "task-auto-run",
+                TaskAutoRun {
+                    worktree_id: worktree_id.to_string(),
+                    task_id,
+                    task_name,
+                },
+            );
+        }
+        Err(e) => eprintln!(
+            "[Tasks] Auto-run of {} failed in {}: {}",
+            task_name, worktree_id, e
+        ),
+    }
+}
+
+/// Detect the project's package manager. JavaScript lockfiles are checked
+/// first since a repo with a Makefile or Cargo.toml may still be driven by npm
+/// scripts; a bare `package.json` falls back to npm.
+pub fn detect_package_manager(project_path: &Path) -> Option<PackageManager> {
+    let has = |file: &str| project_path.join(file).is_file();
+
+    if has("bun.lockb") || has("bun.lock") {
+        Some(PackageManager::Bun)
+    } else if has("pnpm-lock.yaml") {
+        Some(PackageManager::Pnpm)
+    } else if has("yarn.lock") {
+        Some(PackageManager::Yarn)
+    } else if has("package-lock.json") || has("package.json") {
+        Some(PackageManager::Npm)
+    } else if has("Cargo.toml") {
+        Some(PackageManager::Cargo)
+    } else if has("Makefile") || has("makefile") || has("GNUmakefile") {
+        Some(PackageManager::Make)
+    } else {
+        None
+    }
+}
+
+fn suggested_task(name: &str, command: &str, kind: TaskKind) -> TaskConfig {
+    TaskConfig {
+        name: name.to_string(),
+        command: command.to_string(),
+        kind,
+        silent: false,
+        shell: None,
+        env: Default::default(),
+        urls: Default::default(),
+        log_file: None,
+    }
+}
+
+/// Tasks named like this run a git alias rather than a configured command
+pub const ALIAS_TASK_PREFIX: &str = "git ";
+
+/// A `git <alias>` task for each git alias
+pub fn alias_tasks(aliases: &[(String, String)]) -> Vec<TaskConfig> {
+    aliases
+        .iter()
+        .map(|(name, _)| {
+            let command = format!("{}{}", ALIAS_TASK_PREFIX, name);
+            suggested_task(&command, &command, TaskKind::Command)
+        })
+        .collect()
+}
+
+/// The task called `name`: a configured task, or a git alias task when
+/// `git.aliasTasks` is enabled. Configured tasks win on a name clash.
+pub fn find_task(cfg: &Config, project_path: &Path, name: &str) -> Option<TaskConfig> {
+    if let Some(task) = cfg.tasks.iter().find(|t| t.name == name) {
+        return Some(task.clone());
+    }
+    let alias = name
+        .strip_prefix(ALIAS_TASK_PREFIX)
+        .filter(|_| cfg.git.alias_tasks)?;
+    let aliases = git::list_aliases(project_path).ok()?;
+    aliases
+        .iter()
+        .any(|(a, _)| a == alias)
+        .then(|| suggested_task(name, name, TaskKind::Command))
+}
+
+impl PackageManager {
+    /// Build, test and dev tasks to pre-populate the task switcher with
+    pub fn default_tasks(self) -> Vec<TaskConfig> {
+        use TaskKind::{Command, Daemon};
+
+        let (runner, test) = match self {
+            PackageManager::Bun => ("bun run", "bun run test"),
+            PackageManager::Pnpm => ("pnpm", "pnpm test"),
+            PackageManager::Yarn => ("yarn", "yarn test"),
+            PackageManager::Npm => ("npm run", "npm test"),
+            PackageManager::Cargo => {
+                return vec![
+                    suggested_task("build", "cargo build", Command),
+                    suggested_task("test", "cargo test", Command),
+                    suggested_task("run", "cargo run", Command),
+                ]
+            }
+            PackageManager::Make => {
+                return vec![
+                    suggested_task("build", "make", Command),
+                    suggested_task("test", "make test", Command),
+                ]
+            }
+        };
+
+        vec![
+            suggested_task("build", &format!("{} build", runner), Command),
+            suggested_task("test", test, Command),
+            suggested_task("dev", &format!("{} dev", runner), Daemon),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::time::Instant;
+
+    fn temp_log() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("shellflow-tasks-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.join("task.log")
+    }
+
+    fn append(path: &Path, text: &str) {
+        let mut file = std::fs::OpenOptions::new().append(true).open(path).unwrap();
+        file.write_all(text.as_bytes()).unwrap();
+    }
+
+    #[test]
+    fn last_lines_returns_tail() {
+        let path = temp_log();
+        let content: String = (1..=5000).map(|i| format!("line {}\n", i)).collect();
+        std::fs::write(&path, content).unwrap();
+
+        assert_eq!(
+            last_lines(&path, 3).unwrap(),
+            vec!["line 4998", "line 4999", "line 5000"]
+        );
+        assert_eq!(last_lines(&path, 10_000).unwrap().len(), 5000);
+
+        std::fs::write(&path, "only\n").unwrap();
+        assert_eq!(last_lines(&path, 3).unwrap(), vec!["only"]);
+
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn tail_log_requires_registered_log() {
+        assert!(matches!(
+            tail_log("unknown-task", 5),
+            Err(TaskError::NoLogFile(_))
+        ));
+
+        let path = temp_log();
+        std::fs::write(&path, "a\nb\n").unwrap();
+        register_log("tail-log-task", path.clone());
+        assert_eq!(tail_log("tail-log-task", 1).unwrap(), vec!["b"]);
+
+        task_exited("tail-log-task");
+        assert!(tail_log("tail-log-task", 1).is_err());
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn follow_log_emits_appended_lines_and_survives_truncation() {
+        let path = temp_log();
+        std::fs::write(&path, "before follow\n").unwrap();
+
+        let (stop_tx, stop_rx) = channel();
+        let (line_tx, line_rx) = channel();
+        let follow_path = path.clone();
+        let handle = thread::spawn(move || {
+            follow_log(&follow_path, stop_rx, Duration::from_millis(10), |line| {
+                let _ = line_tx.send(line);
+            });
+        });
+
+        let recv = |expected: &str| {
+            let deadline = Instant::now() + Duration::from_secs(5);
+            let line = line_rx
+                .recv_timeout(deadline - Instant::now())
+                .expect("expected a log line");
+            assert_eq!(line, expected);
+        };
+
+        thread::sleep(Duration::from_millis(50));
+        append(&path, "first\nsec");
+        recv("first");
+        append(&path, "ond\n");
+        recv("second");
+
+        // Rotation: the file is replaced by a shorter one
+        std::fs::write(&path, "new\n").unwrap();
+        recv("new");
+
+        stop_tx.send(()).unwrap();
+        handle.join().unwrap();
+        assert!(line_rx.try_recv().is_err());
+
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    fn fixture(files: &[&str]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("shellflow-pm-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for file in files {
+            std::fs::write(dir.join(file), "").unwrap();
+        }
+        dir
+    }
+
+    fn detect(files: &[&str]) -> Option<PackageManager> {
+        let dir = fixture(files);
+        let detected = detect_package_manager(&dir);
+        let _ = std::fs::remove_dir_all(&dir);
+        detected
+    }
+
+    #[test]
+    fn detects_package_manager_from_lockfiles() {
+        assert_eq!(
+            detect(&["package.json", "bun.lockb"]),
+            Some(PackageManager::Bun)
+        );
+        assert_eq!(
+            detect(&["package.json", "bun.lock"]),
+            Some(PackageManager::Bun)
+        );
+        assert_eq!(
+            detect(&["package.json", "pnpm-lock.yaml"]),
+            Some(PackageManager::Pnpm)
+        );
+        assert_eq!(
+            detect(&["package.json", "yarn.lock"]),
+            Some(PackageManager::Yarn)
+        );
+        assert_eq!(
+            detect(&["package.json", "package-lock.json"]),
+            Some(PackageManager::Npm)
+        );
+        assert_eq!(detect(&["package.json"]), Some(PackageManager::Npm));
+        assert_eq!(
+            detect(&["Cargo.toml", "Cargo.lock"]),
+            Some(PackageManager::Cargo)
+        );
+        assert_eq!(detect(&["Makefile"]), Some(PackageManager::Make));
+        assert_eq!(detect(&["README.md"]), None);
+    }
+
+    #[test]
+    fn javascript_lockfiles_win_over_other_manifests() {
+        assert_eq!(
+            detect(&["Makefile", "Cargo.toml", "yarn.lock"]),
+            Some(PackageManager::Yarn)
+        );
+        assert_eq!(
+            detect(&["Makefile", "Cargo.toml"]),
+            Some(PackageManager::Cargo)
+        );
+    }
+
+    #[test]
+    fn default_tasks_use_the_package_manager() {
+        let commands = |pm: PackageManager| -> Vec<(String, String)> {
+            pm.default_tasks()
+                .into_iter()
+                .map(|t| (t.name, t.command))
+                .collect()
+        };
+        let pairs = |items: &[(&str, &str)]| -> Vec<(String, String)> {
+            items
+                .iter()
+                .map(|(a, b)| (a.to_string(), b.to_string()))
+                .collect()
+        };
+
+        assert_eq!(
+            commands(PackageManager::Pnpm),
+            pairs(&[
+                ("build", "pnpm build"),
+                ("test", "pnpm test"),
+                ("dev", "pnpm dev")
+            ])
+        );
+        assert_eq!(
+            commands(PackageManager::Npm),
+            pairs(&[
+                ("build", "npm run build"),
+                ("test", "npm test"),
+                ("dev", "npm run dev")
+            ])
+        );
+        assert_eq!(
+            commands(PackageManager::Cargo),
+            pairs(&[
+                ("build", "cargo build"),
+                ("test", "cargo test"),
+                ("run", "cargo run")
+            ])
+        );
+        assert_eq!(
+            commands(PackageManager::Make),
+            pairs(&[("build", "make"), ("test", "make test")])
+        );
+
+        let dev = PackageManager::Yarn.default_tasks().pop().unwrap();
+        assert_eq!(dev.command, "yarn dev");
+        assert_eq!(dev.kind, TaskKind::Daemon);
+    }
+
+    fn state_with_worktrees(repo: &crate::test_utils::TestRepo) -> PersistedState {
+        let worktree = |id: &str, branch: &str| crate::state::Worktree {
+            name: branch.to_string(),
+            branch: branch.to_string(),
+            ..crate::test_utils::worktree(id, &repo.root().join(branch).to_string_lossy())
+        };
+        let worktrees = vec![worktree("wt-a", "feature-a"), worktree("wt-b", "feature-b")];
+        PersistedState {
+            projects: vec![crate::state::Project {
+                name: "repo".to_string(),
+                ..crate::test_utils::project("project", &repo.path.to_string_lossy(), worktrees)
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn task_runs_in_the_chosen_worktree() {
+        let repo = crate::test_utils::TestRepo::new();
+        let state = state_with_worktrees(&repo);
+
+        let mut task = suggested_task("dev", "echo {{ branch }}", TaskKind::Command);
+        task.env
+            .insert("BRANCH".into(), "{{ branch | sanitize }}".into());
+        task.log_file = Some("log/{{ branch }}.log".into());
+
+        let target = resolve_target(&state, "wt-b").unwrap();
+        let prepared = prepare(&task, &target).unwrap();
+
+        let expected_cwd = repo.root().join("feature-b");
+        assert_eq!(prepared.cwd, expected_cwd.to_string_lossy());
+        assert_eq!(prepared.command, "echo feature-b");
+        assert_eq!(prepared.env["BRANCH"], "feature-b");
+        assert_eq!(
+            prepared.log_file,
+            Some(expected_cwd.join("log/feature-b.log"))
+        );
+    }
+
+    #[test]
+    fn task_in_project_runs_in_main_checkout() {
+        let repo = crate::test_utils::TestRepo::new();
+        let state = state_with_worktrees(&repo);
+
+        let target = resolve_target(&state, "project").unwrap();
+
+        assert_eq!(target.cwd, repo.path.to_string_lossy());
+        assert_eq!(target.branch, "main");
+        assert_eq!(target.worktree_name, None);
+    }
+
+    #[test]
+    fn task_target_must_exist() {
+        let repo = crate::test_utils::TestRepo::new();
+        let state = state_with_worktrees(&repo);
+
+        assert!(matches!(
+            resolve_target(&state, "missing"),
+            Err(TaskError::EntityNotFound(id)) if id == "missing"
+        ));
+    }
+
+    #[cfg(unix)]
+    fn is_running(pid: u32) -> bool {
+        // Zombies still answer kill(0), so check the process state instead
+        std::process::Command::new("ps")
+            .args(["-o", "stat=", "-p", &pid.to_string()])
+            .output()
+            .map(|o| {
+                let stat = String::from_utf8_lossy(&o.stdout);
+                !stat.trim().is_empty() && !stat.trim_start().starts_with('Z')
+            })
+            .unwrap_or(false)
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn kill_process_tree_terminates_children() {
+        use std::os::unix::process::CommandExt;
+
+        // Like a PTY child, the shell leads its own process group
+        let mut shell = std::process::Command::new("sh")
+            .args(["-c", "sleep 30 & sleep 30; wait"])
+            .process_group(0)
+            .spawn()
+            .unwrap();
+        let shell_pid = shell.id();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut sleepers = Vec::new();
+        while sleepers.len() < 2 && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(20));
+            sleepers = crate::pty::get_child_pids(shell_pid);
+        }
+        assert_eq!(sleepers.len(), 2, "expected two sleeping children");
+
+        kill_process_tree(shell_pid);
+        shell.wait().unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while sleepers.iter().any(|&pid| is_running(pid)) && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(20));
+        }
+        assert!(!is_running(shell_pid));
+        for pid in sleepers {
+            assert!(!is_running(pid), "child {} survived cancel", pid);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn running_tasks_tracks_spawn_and_exit() {
+        let mut first = std::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .unwrap();
+        let mut second = std::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .unwrap();
+        for (id, child, label) in [("running-a", &first, "dev"), ("running-b", &second, "test")] {
+            register_task(RunningTask {
+                id: id.to_string(),
+                worktree_id: "wt".to_string(),
+                label: label.to_string(),
+                started_at: crate::worktree::chrono_lite_now(),
+                pid: child.id(),
+            });
+        }
+
+        let ours = || -> Vec<String> {
+            running_tasks()
+                .into_iter()
+                .map(|t| t.id)
+                .filter(|id| id.starts_with("running-"))
+                .collect()
+        };
+        assert_eq!(ours(), vec!["running-a", "running-b"]);
+
+        // Exit reported through the PTY layer
+        task_exited("running-a");
+        assert_eq!(ours(), vec!["running-b"]);
+
+        // Process gone without an exit report
+        second.kill().unwrap();
+        second.wait().unwrap();
+        assert!(ours().is_empty());
+
+        first.kill().unwrap();
+        first.wait().unwrap();
+    }
+
+    #[test]
+    fn git_aliases_become_tasks_when_enabled() {
+        let repo = crate::test_utils::TestRepo::new();
+        repo.git(&["config", "alias.st", "status --short"]);
+        let aliases = vec![("st".to_string(), "status --short".to_string())];
+
+        let tasks = alias_tasks(&aliases);
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].name, "git st");
+        assert_eq!(tasks[0].command, "git st");
+
+        let mut cfg = Config::default();
+        assert!(find_task(&cfg, &repo.path, "git st").is_none());
+        cfg.git.alias_tasks = true;
+        assert_eq!(
+            find_task(&cfg, &repo.path, "git st").unwrap().command,
+            "git st"
+        );
+        assert!(find_task(&cfg, &repo.path, "git nope").is_none());
+
+        // A configured task with the same name takes precedence
+        cfg.tasks.push(suggested_task(
+            "git st",
+            "echo configured",
+            TaskKind::Command,
+        ));
+        assert_eq!(
+            find_task(&cfg, &repo.path, "git st").unwrap().command,
+            "echo configured"
+        );
+    }
+
+    #[test]
+    fn run_last_respawns_the_recorded_task() {
+        let repo = crate::test_utils::TestRepo::new();
+        let mut state = state_with_worktrees(&repo);
+        let tasks = [
+            suggested_task("build", "make {{ branch }}", TaskKind::Command),
+            suggested_task("test", "make test", TaskKind::Command),
+        ];
+
+        assert!(matches!(
+            last_run(&state, "wt-a"),
+            Err(TaskError::NoLastTask(_))
+        ));
+
+        let target = resolve_target(&state, "wt-a").unwrap();
+        let first = prepare(&tasks[0], &target).unwrap();
+        record_run(&mut state, "wt-a", &tasks[0].name);
+
+        let name = last_run(&state, "wt-a").unwrap();
+        let task = tasks.iter().find(|t| t.name == name).unwrap();
+        let again = prepare(task, &resolve_target(&state, "wt-a").unwrap()).unwrap();
+        assert_eq!(again, first);
+        assert_eq!(again.command, "make feature-a");
+
+        // History is per worktree
+        assert!(last_run(&state, "wt-b").is_err());
+    }
+
+    #[test]
+    fn auto_run_respects_cooldown_and_cancels_previous_run() {
+        let mut runner = AutoRunner::new(AutoRunRule {
+            task_name: "test".into(),
+            cooldown_ms: 1000,
+        });
+        let start = Instant::now();
+
+        assert_eq!(
+            runner.on_change(start),
+            AutoRunDecision::Run { cancel: None }
+        );
+        runner.started("run-1".into());
+
+        // A burst of changes within the cooldown starts nothing
+        for ms in [10, 200, 999] {
+            assert_eq!(
+                runner.on_change(start + Duration::from_millis(ms)),
+                AutoRunDecision::Skip
+            );
+        }
+
+        assert_eq!(
+            runner.on_change(start + Duration::from_millis(1000)),
+            AutoRunDecision::Run {
+                cancel: Some("run-1".into())
+            }
+        );
+    }
+
+    #[test]
+    fn auto_run_rules_are_per_worktree() {
+        let rule = AutoRunRule {
+            task_name: "test".into(),
+            cooldown_ms: 500,
+        };
+        set_auto_run("auto-run-wt", Some(rule.clone()));
+        assert_eq!(auto_run_rule("auto-run-wt"), Some(rule));
+        assert_eq!(auto_run_rule("auto-run-other"), None);
+
+        set_auto_run("auto-run-wt", None);
+        assert_eq!(auto_run_rule("auto-run-wt"), None);
+    }
+
+    #[test]
+    fn ignored_and_git_paths_do_not_trigger_auto_run() {
+        let repo = crate::test_utils::TestRepo::new();
+        repo.commit_file(".gitignore", "target/\n", "Ignore build output");
+        let at = |p: &str| vec![repo.path.join(p)];
+
+        assert!(has_relevant_change(&repo.path, &at("src/lib.rs")));
+        assert!(!has_relevant_change(&repo.path, &at("target/debug/out")));
+        assert!(!has_relevant_change(&repo.path, &at(".git/index")));
+        assert!(!has_relevant_change(
+            &repo.path,
+            &[PathBuf::from("/elsewhere/file.rs")]
+        ));
+
+        let mixed = vec![repo.path.join("target/x"), repo.path.join("README.md")];
+        assert!(has_relevant_change(&repo.path, &mixed));
+    }
+}
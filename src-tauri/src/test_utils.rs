@@ -0,0 +1,140 @@
+//! Helpers shared by tests that need a real git repository on disk.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::config::BaseBranch;
+use crate::state::{Project, Worktree};
+
+/// A throwaway git repository in the system temp directory.
+/// The directory is removed when the value is dropped.
+pub struct TestRepo {
+    pub path: PathBuf,
+}
+
+impl TestRepo {
+    /// Create a repository on `main` with a single initial commit.
+    pub fn new() -> Self {
+        let path = std::env::temp_dir()
+            .join(format!("shellflow-test-{}", uuid::Uuid::new_v4()))
+            .join("repo");
+        std::fs::create_dir_all(&path).expect("failed to create test repo dir");
+
+        let repo = Self { path };
+        repo.git(&["init", "-b", "main"]);
+        repo.git(&["config", "user.name", "Shellflow Test"]);
+        repo.git(&["config", "user.email", "test@shellflow.dev"]);
+        repo.git(&["config", "commit.gpgsign", "false"]);
+        repo.commit_file("README.md", "# test\n", "Initial commit");
+        repo
+    }
+
+    /// Directory that contains the repository, useful for sibling worktrees.
+    pub fn root(&self) -> &Path {
+        self.path.parent().unwrap()
+    }
+
+    /// Run a git command in the repository, panicking on failure.
+    pub fn git(&self, args: &[&str]) -> String {
+        git_in(&self.path, args)
+    }
+
+    /// Write a file relative to the repository root.
+    pub fn write(&self, file: &str, content: &str) {
+        write_in(&self.path, file, content);
+    }
+
+    /// Write, stage and commit a file. Returns the new HEAD sha.
+    pub fn commit_file(&self, file: &str, content: &str, message: &str) -> String {
+        self.write(file, content);
+        self.git(&["add", file]);
+        self.git(&["commit", "-m", message]);
+        self.head()
+    }
+
+    /// The sha HEAD currently points at.
+    pub fn head(&self) -> String {
+        self.rev_parse("HEAD")
+    }
+
+    pub fn rev_parse(&self, rev: &str) -> String {
+        self.git(&["rev-parse", rev]).trim().to_string()
+    }
+
+    /// Sibling directory the test worktrees are created in.
+    pub fn worktrees_dir(&self) -> String {
+        self.root().join("worktrees").to_string_lossy().to_string()
+    }
+
+    /// Create worktree `name` on a new branch of the same name, from the default base.
+    pub fn create_worktree(&self, project: &mut Project, name: &str) -> Worktree {
+        crate::worktree::create_worktree(
+            project,
+            Some(name.into()),
+            Some(&self.worktrees_dir()),
+            &BaseBranch::default(),
+        )
+        .unwrap()
+    }
+}
+
+impl Drop for TestRepo {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(self.root());
+    }
+}
+
+/// Run a git command in `dir`, panicking on failure. Returns stdout.
+pub fn git_in(dir: &Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .expect("failed to run git");
+    assert!(
+        output.status.success(),
+        "git {:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+/// Write a file relative to `dir`, creating parent directories as needed.
+pub fn write_in(dir: &Path, file: &str, content: &str) {
+    let path = dir.join(file);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).unwrap();
+    }
+    std::fs::write(path, content).unwrap();
+}
+
+/// A worktree record on a branch named after `id`. Nothing is created on disk.
+pub fn worktree(id: &str, path: &str) -> Worktree {
+    Worktree {
+        id: id.to_string(),
+        name: id.to_string(),
+        path: path.to_string(),
+        branch: id.to_string(),
+        created_at: String::new(),
+        order: 0,
+        detached: false,
+        changed_files_view_mode: Default::default(),
+        show_ignored_files: false,
+        last_accessed_at: None,
+    }
+}
+
+/// An open project record named after `id`. Nothing is created on disk.
+pub fn project(id: &str, path: &str, worktrees: Vec<Worktree>) -> Project {
+    Project {
+        id: id.to_string(),
+        name: id.to_string(),
+        path: path.to_string(),
+        worktrees,
+        order: 0,
+        is_active: true,
+        last_accessed_at: None,
+        worktree_layout: None,
+    }
+}
@@ -0,0 +1,260 @@
+//! UTC timestamps in the `YYYY-MM-DDTHH:MM:SSZ` form stored in state,
+//! without an external date dependency.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SECS_PER_DAY: i64 = 86400;
+
+/// Seconds since the Unix epoch
+pub fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+fn is_leap_year(year: i64) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        _ => 0,
+    }
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    // Count years from March so the leap day falls at the end of the year
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month_from_march = (i64::from(month) + 9) % 12;
+    let day_of_year = (153 * month_from_march + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// Proleptic Gregorian (year, month, day) for days since 1970-01-01
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_from_march + 2) / 5 + 1) as u32;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Format seconds since the epoch as `YYYY-MM-DDTHH:MM:SSZ`
+pub fn format_iso(epoch: i64) -> String {
+    let (year, month, day) = civil_from_days(epoch.div_euclid(SECS_PER_DAY));
+    let secs_of_day = epoch.rem_euclid(SECS_PER_DAY);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        secs_of_day / 3600,
+        (secs_of_day % 3600) / 60,
+        secs_of_day % 60
+    )
+}
+
+/// Format seconds since the epoch in local time for display, e.g.
+/// `2025-06-01T14:30:00+02:00`. Stored values stay in UTC.
+pub fn format_local(epoch: u64) -> String {
+    let epoch = i64::try_from(epoch).unwrap_or(i64::MAX);
+    format_with_offset(epoch, local_offset_secs(epoch))
+}
+
+/// Format `epoch` shifted by a UTC offset in seconds, with the offset as suffix
+fn format_with_offset(epoch: i64, offset_secs: i64) -> String {
+    let utc = format_iso(epoch + offset_secs);
+    let sign = if offset_secs < 0 { '-' } else { '+' };
+    let offset_mins = offset_secs.abs() / 60;
+    format!(
+        "{}{}{:02}:{:02}",
+        utc.trim_end_matches('Z'),
+        sign,
+        offset_mins / 60,
+        offset_mins % 60
+    )
+}
+
+/// The system's UTC offset in effect at `epoch`, so daylight saving is applied per date
+#[cfg(unix)]
+// Field widths differ between platforms
+#[allow(clippy::unnecessary_cast)]
+fn local_offset_secs(epoch: i64) -> i64 {
+    let time = epoch as libc::time_t;
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    if unsafe { libc::localtime_r(&time, &mut tm) }.is_null() {
+        return 0;
+    }
+    tm.tm_gmtoff as i64
+}
+
+/// The system's current UTC offset. Windows only reports the rules for now,
+/// so past and future dates use today's daylight saving state.
+#[cfg(windows)]
+fn local_offset_secs(_epoch: i64) -> i64 {
+    #[repr(C)]
+    struct SystemTime {
+        fields: [u16; 8],
+    }
+
+    #[repr(C)]
+    struct TimeZoneInformation {
+        bias: i32,
+        standard_name: [u16; 32],
+        standard_date: SystemTime,
+        standard_bias: i32,
+        daylight_name: [u16; 32],
+        daylight_date: SystemTime,
+        daylight_bias: i32,
+    }
+
+    extern "system" {
+        fn GetTimeZoneInformation(info: *mut TimeZoneInformation) -> u32;
+    }
+
+    const TIME_ZONE_ID_INVALID: u32 = 0xFFFF_FFFF;
+    const TIME_ZONE_ID_DAYLIGHT: u32 = 2;
+
+    let mut info: TimeZoneInformation = unsafe { std::mem::zeroed() };
+    let bias = match unsafe { GetTimeZoneInformation(&mut info) } {
+        TIME_ZONE_ID_INVALID => return 0,
+        TIME_ZONE_ID_DAYLIGHT => info.bias + info.daylight_bias,
+        _ => info.bias + info.standard_bias,
+    };
+    // Bias is minutes to add to local time to get UTC
+    -i64::from(bias) * 60
+}
+
+/// Parse a `YYYY-MM-DDTHH:MM:SSZ` timestamp into seconds since the epoch.
+/// Returns None unless every field is in range for a real calendar date.
+pub fn parse_iso(s: &str) -> Option<i64> {
+    let (date, time) = s.strip_suffix('Z')?.split_once('T')?;
+
+    let mut date_parts = date.splitn(3, '-');
+    let year: i64 = date_parts.next()?.parse().ok()?;
+    let month: u32 = date_parts.next()?.parse().ok()?;
+    let day: u32 = date_parts.next()?.parse().ok()?;
+
+    let mut time_parts = time.splitn(3, ':');
+    let hour: i64 = time_parts.next()?.parse().ok()?;
+    let minute: i64 = time_parts.next()?.parse().ok()?;
+    let second: i64 = time_parts.next()?.parse().ok()?;
+
+    if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+        return None;
+    }
+    if !(0..24).contains(&hour) || !(0..60).contains(&minute) || !(0..60).contains(&second) {
+        return None;
+    }
+
+    Some(days_from_civil(year, month, day) * SECS_PER_DAY + hour * 3600 + minute * 60 + second)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_known_dates() {
+        assert_eq!(format_iso(0), "1970-01-01T00:00:00Z");
+        assert_eq!(format_iso(951_782_400), "2000-02-29T00:00:00Z");
+        assert_eq!(format_iso(1_700_000_000), "2023-11-14T22:13:20Z");
+        assert_eq!(format_iso(1_735_689_599), "2024-12-31T23:59:59Z");
+    }
+
+    #[test]
+    fn formats_across_leap_day() {
+        assert_eq!(format_iso(1_709_164_799), "2024-02-28T23:59:59Z");
+        assert_eq!(format_iso(1_709_164_800), "2024-02-29T00:00:00Z");
+        assert_eq!(format_iso(1_709_251_199), "2024-02-29T23:59:59Z");
+        assert_eq!(format_iso(1_709_251_200), "2024-03-01T00:00:00Z");
+        // 1900 and 2100 aren't leap years
+        assert_eq!(format_iso(-2_203_891_200), "1900-03-01T00:00:00Z");
+        assert_eq!(format_iso(4_107_542_400), "2100-03-01T00:00:00Z");
+    }
+
+    #[test]
+    fn parses_known_dates() {
+        assert_eq!(parse_iso("1970-01-01T00:00:00Z"), Some(0));
+        assert_eq!(parse_iso("2000-02-29T00:00:00Z"), Some(951_782_400));
+        assert_eq!(parse_iso("2023-11-14T22:13:20Z"), Some(1_700_000_000));
+    }
+
+    #[test]
+    fn rejects_out_of_range_fields() {
+        for invalid in [
+            "2025-13-01T00:00:00Z",
+            "2025-00-10T00:00:00Z",
+            "2025-02-29T00:00:00Z",
+            "2025-04-31T00:00:00Z",
+            "2025-01-00T00:00:00Z",
+            "2025-01-01T24:00:00Z",
+            "2025-01-01T00:60:00Z",
+            "2025-01-01T00:00:00",
+            "not a timestamp",
+        ] {
+            assert_eq!(parse_iso(invalid), None, "{}", invalid);
+        }
+        assert!(parse_iso("2024-02-29T12:00:00Z").is_some());
+    }
+
+    #[test]
+    fn formats_with_fixed_offset() {
+        // 2023-11-14T22:13:20Z
+        let epoch = 1_700_000_000;
+        assert_eq!(format_with_offset(epoch, 0), "2023-11-14T22:13:20+00:00");
+        assert_eq!(
+            format_with_offset(epoch, -5 * 3600),
+            "2023-11-14T17:13:20-05:00"
+        );
+        // Crosses into the next day, and the next year
+        assert_eq!(
+            format_with_offset(epoch, 5 * 3600 + 1800),
+            "2023-11-15T03:43:20+05:30"
+        );
+        assert_eq!(
+            format_with_offset(1_704_067_199, 3600),
+            "2024-01-01T00:59:59+01:00"
+        );
+        // Crosses back into the previous day
+        assert_eq!(
+            format_with_offset(1_700_006_400, -8 * 3600),
+            "2023-11-14T16:00:00-08:00"
+        );
+        assert_eq!(
+            format_with_offset(1_699_920_000, -3600),
+            "2023-11-13T23:00:00-01:00"
+        );
+    }
+
+    #[test]
+    fn format_then_parse_round_trips() {
+        // Every ~1.3 days from 1970 to past 2100, covering every month and leap day
+        for epoch in (0..4_200_000_000i64).step_by(113_017) {
+            let formatted = format_iso(epoch);
+            assert_eq!(parse_iso(&formatted), Some(epoch), "{}", formatted);
+        }
+        let now = now_secs() as i64;
+        assert_eq!(parse_iso(&format_iso(now)), Some(now));
+    }
+}
@@ -0,0 +1,255 @@
+//! App version reporting and update checks.
+//!
+//! Checking only reads the configured endpoint's manifest (`{"version": ...}`,
+//! optionally with `changelogUrl`); nothing is downloaded or installed.
+
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::process::Command;
+
+/// Where release notes live when the manifest doesn't say
+const RELEASES_URL: &str = "https://github.com/shkm/shellflow/releases";
+
+/// Give up on the update endpoint after this long, so offline use isn't slowed down
+const FETCH_TIMEOUT_SECS: &str = "5";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppInfo {
+    pub version: String,
+    /// Latest version offered by the update endpoint, if one is configured and reachable
+    pub latest_version: Option<String>,
+    pub update_available: bool,
+    pub changelog_url: Option<String>,
+    /// Why the update check failed (e.g. offline). The version is still reported.
+    pub update_error: Option<String>,
+}
+
+impl AppInfo {
+    /// Just the running version, with no update information
+    fn for_version(version: &str) -> Self {
+        Self {
+            version: version.to_string(),
+            latest_version: None,
+            update_available: false,
+            changelog_url: None,
+            update_error: None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UpdateManifest {
+    version: String,
+    changelog_url: Option<String>,
+}
+
+/// A `major.minor.patch[-pre]` version. A leading `v` is accepted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Version {
+    numbers: [u64; 3],
+    pre: Option<String>,
+}
+
+fn parse_version(version: &str) -> Option<Version> {
+    let version = version.trim();
+    let version = version.strip_prefix('v').unwrap_or(version);
+    // Build metadata never affects precedence
+    let version = version.split('+').next()?;
+    let (core, pre) = match version.split_once('-') {
+        Some((core, pre)) => (core, Some(pre.to_string())),
+        None => (version, None),
+    };
+
+    let mut numbers = [0u64; 3];
+    let mut parts = core.split('.');
+    for number in numbers.iter_mut() {
+        *number = parts.next()?.parse().ok()?;
+    }
+    if parts.next().is_some() {
+        return None;
+    }
+    Some(Version { numbers, pre })
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.numbers
+            .cmp(&other.numbers)
+            .then_with(|| match (&self.pre, &other.pre) {
+                (None, None) => Ordering::Equal,
+                // A pre-release sorts before its release
+                (None, Some(_)) => Ordering::Greater,
+                (Some(_), None) => Ordering::Less,
+                (Some(a), Some(b)) => compare_pre_release(a, b),
+            })
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Compare dot-separated pre-release identifiers: numeric ones numerically,
+/// and numeric before alphanumeric, as semver specifies.
+fn compare_pre_release(a: &str, b: &str) -> Ordering {
+    let mut a_ids = a.split('.');
+    let mut b_ids = b.split('.');
+    loop {
+        match (a_ids.next(), b_ids.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) => {
+                let order = match (x.parse::<u64>(), y.parse::<u64>()) {
+                    (Ok(x), Ok(y)) => x.cmp(&y),
+                    (Ok(_), Err(_)) => Ordering::Less,
+                    (Err(_), Ok(_)) => Ordering::Greater,
+                    (Err(_), Err(_)) => x.cmp(y),
+                };
+                if order != Ordering::Equal {
+                    return order;
+                }
+            }
+        }
+    }
+}
+
+/// Order two version strings, or `None` if either isn't a valid version.
+pub fn compare_versions(a: &str, b: &str) -> Option<Ordering> {
+    Some(parse_version(a)?.cmp(&parse_version(b)?))
+}
+
+fn fetch_manifest(endpoint: &str) -> Result<UpdateManifest, String> {
+    let output = Command::new("curl")
+        .args(["-fsSL", "--max-time", FETCH_TIMEOUT_SECS, endpoint])
+        .output()
+        .map_err(|e| format!("Failed to run curl: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "Could not reach {}: {}",
+            endpoint,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    serde_json::from_slice(&output.stdout).map_err(|e| format!("Invalid update manifest: {}", e))
+}
+
+/// Combine the running version with a fetched manifest (or the reason fetching failed).
+fn info_from_manifest(current: &str, manifest: Result<UpdateManifest, String>) -> AppInfo {
+    let mut info = AppInfo::for_version(current);
+
+    match manifest {
+        Ok(manifest) => {
+            info.update_available =
+                compare_versions(&manifest.version, current) == Some(Ordering::Greater);
+            info.changelog_url = Some(manifest.changelog_url.unwrap_or_else(|| {
+                format!(
+                    "{}/tag/v{}",
+                    RELEASES_URL,
+                    manifest.version.trim_start_matches('v')
+                )
+            }));
+            info.latest_version = Some(manifest.version);
+        }
+        Err(e) => info.update_error = Some(e),
+    }
+    info
+}
+
+/// Report the running version and, when `endpoint` is set, the latest release.
+/// A failed check (offline, bad manifest) is reported in `update_error`.
+pub fn app_info(current: &str, endpoint: Option<&str>) -> AppInfo {
+    match endpoint {
+        Some(endpoint) => info_from_manifest(current, fetch_manifest(endpoint)),
+        None => AppInfo::for_version(current),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compares_release_versions() {
+        assert_eq!(
+            compare_versions("0.12.0", "0.11.0"),
+            Some(Ordering::Greater)
+        );
+        assert_eq!(compare_versions("0.11.0", "0.11.1"), Some(Ordering::Less));
+        assert_eq!(compare_versions("v1.2.3", "1.2.3"), Some(Ordering::Equal));
+        assert_eq!(compare_versions("1.10.0", "1.9.9"), Some(Ordering::Greater));
+        assert_eq!(
+            compare_versions("1.2.3+build.5", "1.2.3"),
+            Some(Ordering::Equal)
+        );
+    }
+
+    #[test]
+    fn pre_releases_sort_before_their_release() {
+        assert_eq!(
+            compare_versions("1.0.0-beta", "1.0.0"),
+            Some(Ordering::Less)
+        );
+        assert_eq!(
+            compare_versions("1.0.0-beta.2", "1.0.0-beta.11"),
+            Some(Ordering::Less)
+        );
+        assert_eq!(
+            compare_versions("1.0.0-alpha", "1.0.0-alpha.1"),
+            Some(Ordering::Less)
+        );
+        assert_eq!(
+            compare_versions("1.0.0-rc.1", "1.0.0-beta"),
+            Some(Ordering::Greater)
+        );
+    }
+
+    #[test]
+    fn invalid_versions_are_not_comparable() {
+        assert_eq!(compare_versions("1.2", "1.2.0"), None);
+        assert_eq!(compare_versions("1.2.3.4", "1.2.3"), None);
+        assert_eq!(compare_versions("latest", "1.0.0"), None);
+    }
+
+    #[test]
+    fn manifest_with_newer_version_offers_update() {
+        let manifest = UpdateManifest {
+            version: "0.12.0".into(),
+            changelog_url: None,
+        };
+        let info = info_from_manifest("0.11.0", Ok(manifest));
+
+        assert!(info.update_available);
+        assert_eq!(info.latest_version.as_deref(), Some("0.12.0"));
+        assert_eq!(
+            info.changelog_url.as_deref(),
+            Some("https://github.com/shkm/shellflow/releases/tag/v0.12.0")
+        );
+
+        let same = UpdateManifest {
+            version: "0.11.0".into(),
+            changelog_url: Some("https://example.com/notes".into()),
+        };
+        let info = info_from_manifest("0.11.0", Ok(same));
+        assert!(!info.update_available);
+        assert_eq!(
+            info.changelog_url.as_deref(),
+            Some("https://example.com/notes")
+        );
+    }
+
+    #[test]
+    fn failed_check_still_reports_version() {
+        let info = info_from_manifest("0.11.0", Err("offline".into()));
+
+        assert_eq!(info.version, "0.11.0");
+        assert!(!info.update_available);
+        assert_eq!(info.latest_version, None);
+        assert_eq!(info.update_error.as_deref(), Some("offline"));
+    }
+}
@@ -1,14 +1,17 @@
 use crate::config;
 use crate::git;
-use crate::state::FileChange;
-use notify::{Config, Event, RecommendedWatcher, RecursiveMode, Watcher};
+use crate::state::{AppState, ChangedFilesViewMode, FileChange, Project};
+use notify::event::{AccessKind, AccessMode, ModifyKind};
+use notify::{Config, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher, WatcherKind};
 use parking_lot::Mutex;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::cell::Cell;
 use std::path::{Path, PathBuf};
-use std::sync::mpsc::{channel, Sender};
-use std::thread;
-use std::time::Duration;
-use tauri::{AppHandle, Emitter};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
 
 /// Resolve the actual git directory for a given repo/worktree path.
 /// For regular repos, this is <path>/.git
@@ -40,145 +43,1300 @@ pub struct FilesChanged {
     pub files: Vec<FileChange>,
 }
 
+/// Payload of `git-busy` and `git-idle`
+#[derive(Clone, serde::Serialize)]
+pub struct GitLock {
+    pub worktree_path: String,
+}
+
+/// Payload of `watcher-error`: the worktree's changes may no longer be reported
+#[derive(Clone, serde::Serialize)]
+pub struct WatcherError {
+    pub worktree_id: String,
+    pub message: String,
+}
+
+/// Payload of `branch-changed`: a different branch was checked out in the
+/// worktree, e.g. from an external terminal
+#[derive(Clone, serde::Serialize)]
+pub struct BranchChanged {
+    pub worktree_id: String,
+    /// Empty when HEAD is detached
+    pub branch: String,
+    pub detached: bool,
+}
+
+/// Payload of `worktree-status`: commits the worktree's branch is ahead of
+/// and behind its base branch
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct WorktreeStatus {
+    pub worktree_id: String,
+    pub base: String,
+    pub ahead: usize,
+    pub behind: usize,
+}
+
 #[derive(Clone, serde::Serialize)]
 pub struct WorktreeRemoved {
     pub worktree_path: String,
 }
 
-// Track active watchers so we can stop them
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectPathMissing {
+    pub project_id: String,
+    pub project_path: String,
+    /// The project directory itself is gone, not just some of its worktrees
+    pub project_missing: bool,
+    pub missing_worktree_paths: Vec<String>,
+}
+
+/// Diagnostic counters for a worktree's file watcher
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatcherStats {
+    /// Raw filesystem events received from notify
+    pub events_received: u64,
+    /// `files-changed` events emitted after debouncing
+    pub emissions_sent: u64,
+    pub last_emission_at: Option<String>,
+    /// The platform has no native backend and notify is polling instead
+    pub poll_fallback: bool,
+    /// Changes are detected by comparing file contents rather than metadata
+    pub compare_contents: bool,
+}
+
+thread_local! {
+    // Set on the dispatch thread, which must not wait on itself: a signal
+    // handler that stops watching a worktree would block the loop it runs on
+    static IN_WATCH_THREAD: Cell<bool> = const { Cell::new(false) };
+}
+
 lazy_static::lazy_static! {
-    static ref WATCHERS: Mutex<HashMap<String, Sender<()>>> = Mutex::new(HashMap::new());
+    // Shared by every worktree watch, created by the first `watch_worktree`
+    static ref MANAGER: Mutex<Option<Arc<WatcherManager>>> = Mutex::new(None);
+    static ref WATCHER_STATS: Mutex<HashMap<String, WatcherStats>> = Mutex::new(HashMap::new());
+    // Last error per worktree watcher, kept after a failed watcher is torn down
+    static ref WATCH_FAILURES: Mutex<HashMap<String, String>> = Mutex::new(HashMap::new());
+    // Worktrees temporarily elevated to content comparison (cleared when watching stops)
+    static ref COMPARE_CONTENTS: Mutex<HashSet<String>> = Mutex::new(HashSet::new());
+    // Worktree id -> number of outstanding pauses; changes are held while non-zero
+    static ref PAUSES: Mutex<HashMap<String, usize>> = Mutex::new(HashMap::new());
+    // Last uncommitted changes emitted per worktree, updated incrementally from watch events
+    static ref KNOWN_FILES: Mutex<HashMap<String, KnownFiles>> = Mutex::new(HashMap::new());
+    // Worktree id -> (branch, detached) seen by the watcher, not yet persisted
+    static ref PENDING_BRANCHES: Mutex<HashMap<String, (String, bool)>> =
+        Mutex::new(HashMap::new());
+    // Last `worktree-status` emitted per worktree, so unchanged counts aren't re-sent
+    static ref KNOWN_STATUS: Mutex<HashMap<String, WorktreeStatus>> = Mutex::new(HashMap::new());
+}
+
+/// A worktree's last emitted changed files and the settings they were listed with
+struct KnownFiles {
+    /// Include submodules, include ignored files
+    settings: (bool, bool),
+    files: Vec<FileChange>,
+}
+
+/// notify config for the shared watcher. Metadata-only detection is the
+/// default; content comparison is opt-in per worktree via `set_compare_contents`,
+/// and while any worktree has it on the shared watcher compares for all of them.
+fn watcher_config(poll_interval: Duration) -> Config {
+    Config::default()
+        .with_poll_interval(poll_interval)
+        .with_compare_contents(!COMPARE_CONTENTS.lock().is_empty())
+}
+
+/// Stats for a worktree's watcher, or `None` if it isn't being watched
+pub fn watcher_stats(worktree_id: &str) -> Option<WatcherStats> {
+    WATCHER_STATS.lock().get(worktree_id).cloned()
+}
+
+/// The last error a worktree's watcher hit, if any. Cleared when watching restarts.
+pub fn watch_failure(worktree_id: &str) -> Option<String> {
+    WATCH_FAILURES.lock().get(worktree_id).cloned()
 }
 
+fn record_failure(worktree_id: &str, error: String) {
+    eprintln!("[Watcher] {}", error);
+    WATCH_FAILURES.lock().insert(worktree_id.to_string(), error);
+}
+
+fn record_event(worktree_id: &str) {
+    if let Some(stats) = WATCHER_STATS.lock().get_mut(worktree_id) {
+        stats.events_received += 1;
+    }
+}
+
+fn record_emission(worktree_id: &str) {
+    if let Some(stats) = WATCHER_STATS.lock().get_mut(worktree_id) {
+        stats.emissions_sent += 1;
+        stats.last_emission_at = Some(crate::worktree::chrono_lite_now());
+    }
+}
+
+/// Which events a worktree watcher drops before they reach the debounce
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WatchOptions {
+    /// Drop changes inside the git directory except to the index and HEAD
+    pub ignore_git: bool,
+    /// Drop changes to paths the repository's gitignore rules match
+    pub respect_gitignore: bool,
+    /// Attempts to re-establish a watch lost mid-run before giving up
+    pub max_restarts: u32,
+    /// Wait before the first restart attempt, doubled for each next one
+    pub restart_delay: Duration,
+    /// Rescan interval of the polling backend, used where there are no native
+    /// events. The shared watcher polls at the shortest interval any worktree asks for.
+    pub poll_interval: Duration,
+    /// Quiet period after the last change in a worktree before it is reported
+    pub debounce: Duration,
+}
+
+impl Default for WatchOptions {
+    fn default() -> Self {
+        WatchOptions::from(&config::WatcherConfig::default())
+    }
+}
+
+impl From<&config::WatcherConfig> for WatchOptions {
+    fn from(cfg: &config::WatcherConfig) -> Self {
+        WatchOptions {
+            ignore_git: cfg.ignore_git,
+            respect_gitignore: cfg.respect_gitignore,
+            max_restarts: cfg.max_restarts,
+            restart_delay: Duration::from_millis(cfg.restart_delay_ms),
+            poll_interval: Duration::from_millis(cfg.poll_interval_ms),
+            debounce: Duration::from_millis(cfg.debounce_ms),
+        }
+    }
+}
+
+/// Git files whose changes alter what the changed files list shows
+const GIT_FILES_KEPT: [&str; 2] = ["index", "HEAD"];
+
+/// `WatchOptions` resolved against a worktree's root and git directory
+struct IgnoreRules {
+    options: WatchOptions,
+    /// The root as given and canonicalized, since backends may report either
+    roots: Vec<PathBuf>,
+    git_dirs: Vec<PathBuf>,
+    repo: Option<git2::Repository>,
+}
+
+impl IgnoreRules {
+    fn new(root: &Path, git_dir: Option<&Path>, options: WatchOptions) -> Self {
+        let with_canonical = |path: &Path| -> Vec<PathBuf> {
+            std::iter::once(path.to_path_buf()).chain(path.canonicalize().ok()).collect()
+        };
+        IgnoreRules {
+            options,
+            roots: with_canonical(root),
+            git_dirs: git_dir.map(with_canonical).unwrap_or_default(),
+            repo: options
+                .respect_gitignore
+                .then(|| git2::Repository::open(root).ok())
+                .flatten(),
+        }
+    }
+
+    fn ignores(&self, path: &Path) -> bool {
+        let in_git_dir = self.git_dirs.iter().any(|dir| path.starts_with(dir));
+        let relative = self.roots.iter().find_map(|root| path.strip_prefix(root).ok());
+        let in_dot_git = relative
+            .and_then(|r| r.components().next())
+            .is_some_and(|first| first.as_os_str() == ".git");
+
+        if in_git_dir || in_dot_git {
+            let kept = path
+                .file_name()
+                .is_some_and(|name| GIT_FILES_KEPT.iter().any(|kept| name == *kept));
+            return self.options.ignore_git && !kept;
+        }
+        match (&self.repo, relative) {
+            (Some(repo), Some(relative)) if !relative.as_os_str().is_empty() => {
+                repo.is_path_ignored(relative).unwrap_or(false)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// What the watch loop reports to its owner. Keeping the loop free of Tauri
+/// types lets tests drive it with a plain channel.
+#[derive(Debug, Clone, PartialEq)]
+enum WatchSignal {
+    /// Files changed and the debounce period has passed. Carries the paths
+    /// touched during the debounce window, and whether something happened
+    /// that those paths can't describe (a rename, a removal, a lost watch),
+    /// so the changed files have to be listed from scratch.
+    Changed { paths: Vec<PathBuf>, rescan: bool },
+    /// The watched root directory was removed and didn't come back within the
+    /// restart attempts; the worktree is no longer watched after reporting it
+    RootRemoved,
+    /// Another process took (`true`) or released (`false`) the git index lock
+    IndexLock(bool),
+    /// HEAD now points at this branch, or is detached when it's empty
+    BranchChanged(String),
+    /// Watching couldn't start or the backend reported an error
+    Failed(String),
+}
+
+/// Short name of the branch checked out at `root`, empty when HEAD is
+/// detached (as detached worktrees are stored), None when it can't be read
+fn current_branch(root: &Path) -> Option<String> {
+    let repo = git2::Repository::open(root).ok()?;
+    let head = repo.head().ok()?;
+    if !head.is_branch() {
+        return Some(String::new());
+    }
+    git::get_current_branch(&repo).ok()
+}
+
+/// Directories under `root` whose events are dropped. This is the configured
+/// worktree base, so a project watcher doesn't react to its own worktrees.
+fn excluded_dirs(root: &Path) -> Vec<PathBuf> {
+    let cfg = config::load_config_for_project(root.to_str());
+    crate::worktree::resolve_worktree_directory(cfg.worktree.directory.as_deref(), root, None, None)
+        .ok()
+        .filter(|base| base.starts_with(root) && base != root)
+        .into_iter()
+        .collect()
+}
+
+/// Whether `path` is in an excluded directory or inside one of the
+/// repository's own worktrees nested below `root`
+fn is_excluded(
+    path: &Path,
+    root: &Path,
+    excluded: &[PathBuf],
+    nested: &mut NestedWorktrees,
+) -> bool {
+    excluded.iter().any(|dir| path.starts_with(dir)) || nested.contains(root, path)
+}
+
+/// Directories below a watched root that hold a linked worktree of the same
+/// repository, which is watched separately. Submodules and unrelated nested
+/// repositories aren't worktrees, so changes inside them still count. Each
+/// directory is checked once and remembered, as this runs for every event.
+struct NestedWorktrees {
+    /// `<common git dir>/worktrees`, where linked worktrees' `.git` files point
+    worktrees_dir: Option<PathBuf>,
+    known: HashMap<PathBuf, bool>,
+}
+
+impl NestedWorktrees {
+    fn new(root: &Path) -> Self {
+        NestedWorktrees {
+            worktrees_dir: git::common_dir(root).ok().map(|dir| dir.join("worktrees")),
+            known: HashMap::new(),
+        }
+    }
+
+    /// Whether `path` is inside a nested worktree
+    fn contains(&mut self, root: &Path, path: &Path) -> bool {
+        let Some(worktrees_dir) = &self.worktrees_dir else {
+            return false;
+        };
+        let Some(parent) = path.strip_prefix(root).ok().and_then(Path::parent) else {
+            return false;
+        };
+        // A `.git` appearing may turn its directory into a worktree
+        if path.file_name().is_some_and(|name| name == ".git") {
+            self.known.remove(path.parent().unwrap_or(root));
+        }
+        let mut dir = root.to_path_buf();
+        parent.components().any(|component| {
+            dir.push(component);
+            *self
+                .known
+                .entry(dir.clone())
+                .or_insert_with(|| is_linked_worktree(&dir, worktrees_dir))
+        })
+    }
+}
+
+/// Whether `dir`'s `.git` is a `gitdir:` file pointing into `worktrees_dir`
+fn is_linked_worktree(dir: &Path, worktrees_dir: &Path) -> bool {
+    let Ok(content) = std::fs::read_to_string(dir.join(".git")) else {
+        return false;
+    };
+    let Some(gitdir) = content.strip_prefix("gitdir:") else {
+        return false;
+    };
+    let gitdir = dir.join(gitdir.trim());
+    gitdir.canonicalize().unwrap_or(gitdir).starts_with(worktrees_dir)
+}
+
+/// Held by git while it writes the index; other git commands fail until it's gone
+const INDEX_LOCK: &str = "index.lock";
+
+/// Whether another process currently holds the worktree's git index lock
+pub fn is_index_locked(worktree_path: &Path) -> bool {
+    resolve_git_dir(worktree_path).is_some_and(|dir| dir.join(INDEX_LOCK).exists())
+}
+
+/// Whether an event only reports a file being opened or read. Closing a
+/// file after writing it is a change.
+fn is_read_only(event: &Event) -> bool {
+    matches!(event.kind, EventKind::Access(kind) if kind != AccessKind::Close(AccessMode::Write))
+}
+
+/// Whether an event means the watched root itself is gone (not just something inside it)
+fn is_root_removal(event: &Event, root: &Path) -> bool {
+    matches!(event.kind, EventKind::Remove(_))
+        && (event.paths.iter().any(|p| p == root) || !root.exists())
+}
+
+/// Watch a worktree with the watcher options configured for its project
 pub fn watch_worktree(app: AppHandle, worktree_id: String, worktree_path: String) {
+    let cfg = config::load_config_for_project(Some(&worktree_path));
+    let options = WatchOptions::from(&cfg.watcher);
+    watch_worktree_with_options(app, worktree_id, worktree_path, options);
+}
+
+pub fn watch_worktree_with_options(
+    app: AppHandle,
+    worktree_id: String,
+    worktree_path: String,
+    options: WatchOptions,
+) {
+    let manager = manager(&app);
     // Check if already watching this worktree
-    if WATCHERS.lock().contains_key(&worktree_id) {
+    if manager.is_watching(&worktree_id) {
         return;
     }
 
-    // Create stop channel
-    let (stop_tx, stop_rx) = channel::<()>();
-    WATCHERS.lock().insert(worktree_id.clone(), stop_tx);
+    WATCH_FAILURES.lock().remove(&worktree_id);
+    WATCHER_STATS.lock().insert(
+        worktree_id.clone(),
+        WatcherStats {
+            poll_fallback: RecommendedWatcher::kind() == WatcherKind::PollWatcher,
+            compare_contents: COMPARE_CONTENTS.lock().contains(&worktree_id),
+            ..Default::default()
+        },
+    );
+    manager.add_with_options(&worktree_id, Path::new(&worktree_path), options);
+}
 
-    let worktree_id_clone = worktree_id.clone();
+/// The shared manager, created on first use with signals reported through `app`
+fn manager(app: &AppHandle) -> Arc<WatcherManager> {
+    let app = app.clone();
+    MANAGER
+        .lock()
+        .get_or_insert_with(|| {
+            Arc::new(WatcherManager::new(move |id, root, signal| {
+                on_worktree_signal(&app, id, root, signal)
+            }))
+        })
+        .clone()
+}
 
-    thread::spawn(move || {
-        let (tx, rx) = channel::<notify::Result<Event>>();
+/// The shared manager, if anything was ever watched
+fn running_manager() -> Option<Arc<WatcherManager>> {
+    MANAGER.lock().clone()
+}
 
-        let config = Config::default()
-            .with_poll_interval(Duration::from_secs(2))
-            .with_compare_contents(false);
+/// Turn a worktree's watch signal into frontend events
+fn on_worktree_signal(app: &AppHandle, id: &str, root: &Path, signal: WatchSignal) {
+    let worktree_path = root.to_string_lossy().to_string();
+    match signal {
+        WatchSignal::Changed { paths, rescan } => {
+            crate::worktree::invalidate_project_stats_for_worktree(&worktree_path);
+            crate::tasks::on_files_changed(app, id, root, &paths);
 
-        let mut watcher: RecommendedWatcher = match Watcher::new(tx, config) {
-            Ok(w) => w,
-            Err(e) => {
-                eprintln!("Failed to create watcher: {}", e);
-                WATCHERS.lock().remove(&worktree_id_clone);
-                return;
+            let scope = if rescan { None } else { Some(paths.as_slice()) };
+            emit_changed_files_in(app, id, &worktree_path, scope);
+        }
+        WatchSignal::BranchChanged(branch) => {
+            let detached = branch.is_empty();
+            // This runs on the dispatch thread, which commands holding the
+            // state lock may be waiting on, so the update is queued and
+            // persisted from another thread once the lock is free
+            if let Some(state) = app.try_state::<Arc<AppState>>() {
+                let state = Arc::clone(&state);
+                PENDING_BRANCHES.lock().insert(id.to_string(), (branch.clone(), detached));
+                thread::spawn(move || apply_pending_branches(&state));
+            }
+            let worktree_id = id.to_string();
+            let _ = app.emit("branch-changed", BranchChanged { worktree_id, branch, detached });
+        }
+        WatchSignal::IndexLock(locked) => {
+            let event = if locked { "git-busy" } else { "git-idle" };
+            let _ = app.emit(event, GitLock { worktree_path });
+        }
+        WatchSignal::Failed(message) => {
+            let _ = app.emit(
+                "watcher-error",
+                WatcherError {
+                    worktree_id: id.to_string(),
+                    message,
+                },
+            );
+        }
+        WatchSignal::RootRemoved => {
+            eprintln!("[Watcher] Worktree folder deleted externally: {}", worktree_path);
+            crate::worktree::invalidate_project_stats_for_worktree(&worktree_path);
+            let _ = app.emit("worktree-removed", WorktreeRemoved { worktree_path });
+            // The whole project may have gone with it
+            if let Some(project) = owning_project(app, id) {
+                check_project_paths(app, &project);
             }
+        }
+    }
+}
+
+/// Record queued branch changes in the persisted worktrees and save them.
+/// The queue is drained under the write lock, so a later change for the same
+/// worktree can't be overwritten by an earlier one.
+fn apply_pending_branches(state: &AppState) {
+    {
+        let mut persisted = state.persisted.write();
+        let pending: Vec<_> = PENDING_BRANCHES.lock().drain().collect();
+        if pending.is_empty() {
+            return;
+        }
+        for (id, (branch, detached)) in pending {
+            let worktree = persisted
+                .projects
+                .iter_mut()
+                .flat_map(|p| p.worktrees.iter_mut())
+                .find(|w| w.id == id);
+            if let Some(worktree) = worktree {
+                worktree.branch = branch;
+                worktree.detached = detached;
+            }
+        }
+    }
+    if let Err(e) = state.save() {
+        eprintln!("[Watcher] Failed to save branch change: {}", e);
+    }
+}
+
+/// Emit `files-changed` with the worktree's current changed files
+fn emit_changed_files(app: &AppHandle, worktree_id: &str, worktree_path: &str) {
+    emit_changed_files_in(app, worktree_id, worktree_path, None);
+}
+
+/// Emit `files-changed` after the files at `scope` changed. Only those paths
+/// are re-examined when the last emitted list can be updated in place;
+/// otherwise, or without a scope, everything is listed again.
+fn emit_changed_files_in(
+    app: &AppHandle,
+    worktree_id: &str,
+    worktree_path: &str,
+    scope: Option<&[PathBuf]>,
+) {
+    // Read per emission so config edits apply without restarting the watcher
+    let cfg = crate::config::load_config_for_project(Some(worktree_path));
+    let (mode, show_ignored) = view_settings(app, worktree_id);
+    let include_submodules = cfg.changed_files.include_submodules;
+    let settings = (include_submodules, show_ignored);
+    let uncommitted = mode == ChangedFilesViewMode::Uncommitted;
+
+    let known = scope.filter(|_| uncommitted).and_then(|paths| {
+        let known = KNOWN_FILES.lock();
+        let known = known.get(worktree_id).filter(|k| k.settings == settings)?;
+        Some((paths, known.files.clone()))
+    });
+    let path = Path::new(worktree_path);
+    let files = match known {
+        Some((paths, previous)) => {
+            git::get_changed_files_scoped(path, paths, &previous, include_submodules, show_ignored)
+        }
+        None => git::get_changed_files_for_mode(
+            path,
+            mode,
+            &cfg.worktree.base_branch,
+            include_submodules,
+            show_ignored,
+        ),
+    };
+
+    if let Ok(files) = files {
+        if uncommitted {
+            let known = KnownFiles { settings, files: files.clone() };
+            KNOWN_FILES.lock().insert(worktree_id.to_string(), known);
+        } else {
+            KNOWN_FILES.lock().remove(worktree_id);
+        }
+        let _ = app.emit(
+            "files-changed",
+            FilesChanged {
+                worktree_path: worktree_path.to_string(),
+                files,
+            },
+        );
+        record_emission(worktree_id);
+    }
+    // Only git's own files (HEAD, refs, the index) can move the branch, and a
+    // commit always touches the index
+    if scope.is_some_and(|paths| !touches_git_dir(paths, path)) {
+        return;
+    }
+    if let Ok(status) = worktree_status(worktree_id, path, &cfg.worktree.base_branch) {
+        let mut known = KNOWN_STATUS.lock();
+        if known.get(worktree_id) != Some(&status) {
+            known.insert(worktree_id.to_string(), status.clone());
+            let _ = app.emit("worktree-status", status);
+        }
+    }
+}
+
+/// Whether any of `paths` is inside the worktree's git directory, either its
+/// `.git` or the directory a linked worktree's `.git` file points at
+fn touches_git_dir(paths: &[PathBuf], worktree_path: &Path) -> bool {
+    let git_dirs: Vec<PathBuf> = resolve_git_dir(worktree_path)
+        .into_iter()
+        .flat_map(|dir| std::iter::once(dir.clone()).chain(dir.canonicalize().ok()))
+        .collect();
+    paths.iter().any(|path| {
+        git_dirs.iter().any(|dir| path.starts_with(dir))
+            || path.components().any(|c| c.as_os_str() == ".git")
+    })
+}
+
+/// How far a worktree's branch has moved from its base branch
+pub fn worktree_status(
+    worktree_id: &str,
+    worktree_path: &Path,
+    base_branch: &config::BaseBranch,
+) -> Result<WorktreeStatus, git::GitError> {
+    let base = git::resolve_worktree_base(worktree_path, base_branch)?;
+    let (ahead, behind) = git::ahead_behind(worktree_path, &base)?;
+    Ok(WorktreeStatus {
+        worktree_id: worktree_id.to_string(),
+        base,
+        ahead,
+        behind,
+    })
+}
+
+/// Watch `root` recursively, and its git directory for index changes
+fn watch_paths(
+    watcher: &mut RecommendedWatcher,
+    root: &Path,
+    git_dir: Option<&Path>,
+) -> Result<(), String> {
+    watcher
+        .watch(root, RecursiveMode::Recursive)
+        .map_err(|e| format!("Failed to watch {}: {}", root.display(), e))?;
+
+    // Also watch the git index file to detect staging/unstaging changes.
+    // For regular repos, .git is a directory; for worktrees, .git is a file
+    // pointing to the actual git directory (e.g., .git/worktrees/<name>).
+    // The index file is in the git directory.
+    if let Some(git_dir) = git_dir {
+        // Watch the git directory (non-recursive) to catch index changes
+        if let Err(e) = watcher.watch(git_dir, RecursiveMode::NonRecursive) {
+            // Non-fatal: we can still watch file changes even if we can't watch the index
+            eprintln!("[Watcher] Failed to watch git dir {:?}: {}", git_dir, e);
+        }
+    }
+    Ok(())
+}
+
+/// Whether a backend error means the watch on `root` itself is lost, rather
+/// than one path inside it failing
+fn is_fatal(error: &notify::Error, root: &Path) -> bool {
+    !root.exists()
+        || matches!(error.kind, notify::ErrorKind::WatchNotFound)
+        || error.paths.iter().any(|p| p == root)
+}
+
+/// How often roots are checked for removal. Removal events usually catch it
+/// sooner; the check covers backends that miss it.
+const EXISTENCE_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Receives every worktree's signals, with the worktree's id and root
+type SignalHandler = Box<dyn FnMut(&str, &Path, WatchSignal) + Send>;
+
+/// What the dispatch thread is asked to do
+enum Message {
+    Event(notify::Result<Event>),
+    Add {
+        worktree_id: String,
+        root: PathBuf,
+        options: WatchOptions,
+    },
+    /// Acknowledged once no more signals will be sent for the worktree
+    Remove(String, Sender<()>),
+    /// Recreate the watcher so a changed `watcher_config` takes effect
+    Reconfigure,
+    Shutdown,
+}
+
+/// One notify watcher and one dispatch thread shared by every watched
+/// worktree, rather than a thread and a set of OS watches each. Events are
+/// routed to the worktrees whose root or git directory they fall under.
+pub struct WatcherManager {
+    tx: Sender<Message>,
+    /// Roots of the registered worktrees by id, shared with the dispatch thread
+    watched: Arc<Mutex<HashMap<String, PathBuf>>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl WatcherManager {
+    fn new<F>(on_signal: F) -> Self
+    where
+        F: FnMut(&str, &Path, WatchSignal) + Send + 'static,
+    {
+        let (tx, rx) = channel();
+        let watched = Arc::new(Mutex::new(HashMap::new()));
+        let mut dispatch = Dispatch {
+            watcher: None,
+            tx: tx.clone(),
+            worktrees: HashMap::new(),
+            watched: watched.clone(),
+            on_signal: Box::new(on_signal),
+            last_existence_check: Instant::now(),
+            poll_interval: WatchOptions::default().poll_interval,
         };
+        let thread = thread::spawn(move || {
+            IN_WATCH_THREAD.with(|flag| flag.set(true));
+            dispatch.run(rx);
+        });
+        WatcherManager {
+            tx,
+            watched,
+            thread: Some(thread),
+        }
+    }
+
+    /// Watch `path` as `worktree_id`. Returns false if the worktree is already
+    /// watched.
+    pub fn add_with_options(&self, worktree_id: &str, path: &Path, options: WatchOptions) -> bool {
+        // Held until the request is queued, so a watch that fails right away
+        // can't be unregistered before it was registered
+        let mut watched = self.watched.lock();
+        if watched.contains_key(worktree_id) {
+            return false;
+        }
+        watched.insert(worktree_id.to_string(), path.to_path_buf());
+        let _ = self.tx.send(Message::Add {
+            worktree_id: worktree_id.to_string(),
+            root: path.to_path_buf(),
+            options,
+        });
+        true
+    }
 
-        let path = Path::new(&worktree_path);
-        if let Err(e) = watcher.watch(path, RecursiveMode::Recursive) {
-            eprintln!("Failed to watch path: {}", e);
-            WATCHERS.lock().remove(&worktree_id_clone);
+    /// Stop watching a worktree. Waits until the dispatch thread has dropped
+    /// it, so no signal for it follows, unless called from a signal handler.
+    pub fn remove(&self, worktree_id: &str) {
+        if self.watched.lock().remove(worktree_id).is_none() {
             return;
         }
+        let (ack_tx, ack_rx) = channel();
+        let _ = self.tx.send(Message::Remove(worktree_id.to_string(), ack_tx));
+        if !IN_WATCH_THREAD.with(Cell::get) {
+            let _ = ack_rx.recv();
+        }
+    }
+
+    pub fn is_watching(&self, worktree_id: &str) -> bool {
+        self.watched.lock().contains_key(worktree_id)
+    }
+
+    pub fn watched_ids(&self) -> HashSet<String> {
+        self.watched.lock().keys().cloned().collect()
+    }
+
+    /// Recreate the shared watcher, e.g. after content comparison was toggled
+    fn reconfigure(&self) {
+        let _ = self.tx.send(Message::Reconfigure);
+    }
+}
+
+impl Drop for WatcherManager {
+    /// Stop the dispatch thread and wait for it, unless dropped on it
+    fn drop(&mut self) {
+        let _ = self.tx.send(Message::Shutdown);
+        if let Some(thread) = self.thread.take().filter(|_| !IN_WATCH_THREAD.with(Cell::get)) {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// A worktree registered with the dispatch thread
+struct WatchedWorktree {
+    root: PathBuf,
+    git_dir: Option<PathBuf>,
+    options: WatchOptions,
+    /// Whether another git process holds the index, reported on each change
+    index_lock: Option<PathBuf>,
+    index_locked: bool,
+    /// The checked out branch, reported when HEAD changes to another one
+    branch: Option<String>,
+    /// Nested worktrees (e.g. `.worktrees/` inside a project) are registered separately
+    excluded: Vec<PathBuf>,
+    nested: NestedWorktrees,
+    ignored: IgnoreRules,
+    // Trailing-edge debounce: reported once nothing changed for `options.debounce`
+    pending_update: bool,
+    pending_paths: HashSet<PathBuf>,
+    pending_rescan: bool,
+    last_event_time: Instant,
+    /// Set while the watch on the root is lost and being re-established
+    restart: Option<Backoff>,
+}
+
+/// Progress re-establishing a lost watch, e.g. to a directory briefly moved
+/// away during a rebase. The first attempt waits `restart_delay` and each
+/// next one twice as long as the last, up to `max_restarts` attempts.
+struct Backoff {
+    attempts: u32,
+    next_attempt_at: Instant,
+    last_error: String,
+}
+
+impl WatchedWorktree {
+    fn new(root: PathBuf, git_dir: Option<PathBuf>, options: WatchOptions) -> Self {
+        let index_lock = git_dir.as_ref().map(|dir| dir.join(INDEX_LOCK));
+        WatchedWorktree {
+            index_locked: index_lock.as_ref().is_some_and(|lock| lock.exists()),
+            index_lock,
+            branch: current_branch(&root),
+            excluded: excluded_dirs(&root),
+            nested: NestedWorktrees::new(&root),
+            ignored: IgnoreRules::new(&root, git_dir.as_deref(), options),
+            root,
+            git_dir,
+            options,
+            pending_update: false,
+            pending_paths: HashSet::new(),
+            pending_rescan: false,
+            last_event_time: Instant::now(),
+            restart: None,
+        }
+    }
+
+    /// Whether events for `path` concern this worktree
+    fn owns(&self, path: &Path) -> bool {
+        let mut dirs = self.ignored.roots.iter().chain(&self.ignored.git_dirs);
+        dirs.any(|dir| path.starts_with(dir))
+    }
+
+    /// Whether unwatching `path` would cut off this worktree's own watches
+    fn covers(&self, path: &Path) -> bool {
+        path.starts_with(&self.root) || self.git_dir.as_deref() == Some(path)
+    }
+
+    /// Whether any of this worktree's watches are at or below `path`
+    fn watches_inside(&self, path: &Path) -> bool {
+        self.root.starts_with(path)
+            || self.git_dir.as_ref().is_some_and(|dir| dir.starts_with(path))
+    }
 
-        // Also watch the git index file to detect staging/unstaging changes.
-        // For regular repos, .git is a directory; for worktrees, .git is a file
-        // pointing to the actual git directory (e.g., .git/worktrees/<name>).
-        // The index file is in the git directory.
-        if let Some(git_dir) = resolve_git_dir(path) {
-            // Watch the git directory (non-recursive) to catch index changes
-            if let Err(e) = watcher.watch(&git_dir, RecursiveMode::NonRecursive) {
-                // Non-fatal: we can still watch file changes even if we can't watch the index
-                eprintln!("[Watcher] Failed to watch git dir {:?}: {}", git_dir, e);
+    /// Whether `path` is the worktree's HEAD, in its git directory
+    fn is_head(&self, path: &Path) -> bool {
+        path.file_name().is_some_and(|name| name == "HEAD")
+            && path.parent().is_some_and(|dir| self.ignored.git_dirs.iter().any(|d| d == dir))
+    }
+
+    /// Apply an event to this worktree. Returns why the watch on the root was
+    /// lost, if the event means it was.
+    fn on_event(
+        &mut self,
+        id: &str,
+        event: &Event,
+        on_signal: &mut SignalHandler,
+    ) -> Option<String> {
+        record_event(id);
+        if is_root_removal(event, &self.root) {
+            return Some("the directory was removed".to_string());
+        }
+        let owned: Vec<&PathBuf> = event.paths.iter().filter(|p| self.owns(p)).collect();
+        let reported_paths = !owned.is_empty();
+        let touches_lock = owned.iter().any(|p| p.ends_with(INDEX_LOCK));
+        if let Some(lock) = self.index_lock.as_ref().filter(|_| touches_lock) {
+            if lock.exists() != self.index_locked {
+                self.index_locked = !self.index_locked;
+                on_signal(id, &self.root, WatchSignal::IndexLock(self.index_locked));
+            }
+        }
+        if owned.iter().any(|p| self.is_head(p)) {
+            let branch = current_branch(&self.root);
+            if let Some(branch) = branch.filter(|b| self.branch.as_ref() != Some(b)) {
+                self.branch = Some(branch.clone());
+                on_signal(id, &self.root, WatchSignal::BranchChanged(branch));
             }
         }
+        // The lock comes and goes around every index write; the
+        // index change itself is what refreshes the file list
+        let paths: Vec<PathBuf> = owned
+            .into_iter()
+            .filter(|p| !p.ends_with(INDEX_LOCK))
+            .filter(|p| !is_excluded(p, &self.root, &self.excluded, &mut self.nested))
+            .filter(|p| !self.ignored.ignores(p))
+            .cloned()
+            .collect();
+        // New event: mark pending and reset timer, unless it was
+        // entirely inside excluded directories
+        if !paths.is_empty() || !(reported_paths || touches_lock) {
+            self.pending_update = true;
+            // Without paths, or once paths moved or vanished, a status
+            // limited to the reported paths could miss or keep stale entries
+            let moved = matches!(event.kind, EventKind::Modify(ModifyKind::Name(_)));
+            self.pending_rescan |=
+                paths.is_empty() || moved || matches!(event.kind, EventKind::Remove(_));
+            self.pending_paths.extend(paths);
+            self.last_event_time = Instant::now();
+        }
+        None
+    }
 
-        // Trailing-edge debounce: wait until no events for this duration
-        let debounce_duration = Duration::from_millis(500);
-        let mut pending_update = false;
-        let mut last_event_time = std::time::Instant::now();
+    /// Start re-establishing the watch on the root
+    fn lose(&mut self, id: &str, reason: String) {
+        eprintln!("[Watcher] Lost watch on {} for {}: {}", self.root.display(), id, reason);
+        self.restart = Some(Backoff {
+            attempts: 0,
+            next_attempt_at: Instant::now() + self.options.restart_delay,
+            last_error: format!("{} does not exist", self.root.display()),
+        });
+    }
+}
 
-        // Check for external folder deletion every 2 seconds (not every loop iteration)
-        let existence_check_interval = Duration::from_secs(2);
-        let mut last_existence_check = std::time::Instant::now();
+/// The shared watcher, created on first use
+fn ensure_watcher<'a>(
+    watcher: &'a mut Option<RecommendedWatcher>,
+    tx: &Sender<Message>,
+    poll_interval: Duration,
+) -> Result<&'a mut RecommendedWatcher, String> {
+    if watcher.is_none() {
+        let tx = tx.clone();
+        let handler = move |event| {
+            let _ = tx.send(Message::Event(event));
+        };
+        let created = RecommendedWatcher::new(handler, watcher_config(poll_interval))
+            .map_err(|e| format!("Failed to create watcher: {}", e))?;
+        *watcher = Some(created);
+    }
+    Ok(watcher.as_mut().expect("watcher was just created"))
+}
 
+/// State owned by the dispatch thread
+struct Dispatch {
+    /// `None` until the first worktree is added, or after creating it failed
+    watcher: Option<RecommendedWatcher>,
+    /// Cloned into every watcher created, so its events reach the loop
+    tx: Sender<Message>,
+    worktrees: HashMap<String, WatchedWorktree>,
+    watched: Arc<Mutex<HashMap<String, PathBuf>>>,
+    on_signal: SignalHandler,
+    last_existence_check: Instant,
+    /// What the current watcher polls at, when polling
+    poll_interval: Duration,
+}
+
+impl Dispatch {
+    fn run(&mut self, rx: Receiver<Message>) {
         loop {
-            // Check for stop signal
-            if stop_rx.try_recv().is_ok() {
-                eprintln!("[Watcher] Stopping watcher for {}", worktree_id_clone);
-                break;
+            // Use short timeout to check for debounce expiry and restarts
+            match rx.recv_timeout(Duration::from_millis(100)) {
+                Ok(Message::Event(Ok(event))) => self.route(event),
+                Ok(Message::Event(Err(error))) => self.route_error(error),
+                Ok(Message::Add {
+                    worktree_id,
+                    root,
+                    options,
+                }) => self.add(worktree_id, root, options),
+                Ok(Message::Remove(worktree_id, ack)) => {
+                    eprintln!("[Watcher] Stopping watcher for {}", worktree_id);
+                    self.remove(&worktree_id);
+                    let _ = ack.send(());
+                    self.retune(None);
+                }
+                Ok(Message::Reconfigure) => self.reconfigure(),
+                Ok(Message::Shutdown) | Err(RecvTimeoutError::Disconnected) => return,
+                Err(RecvTimeoutError::Timeout) => {}
             }
+            self.tick();
+        }
+    }
 
-            // Use short timeout to check for debounce expiry
-            match rx.recv_timeout(Duration::from_millis(100)) {
-                Ok(Ok(_event)) => {
-                    // New event: mark pending and reset timer
-                    pending_update = true;
-                    last_event_time = std::time::Instant::now();
+    fn add(&mut self, worktree_id: String, root: PathBuf, options: WatchOptions) {
+        self.retune(Some(options.poll_interval));
+        let git_dir = resolve_git_dir(&root);
+        let watched = ensure_watcher(&mut self.watcher, &self.tx, self.poll_interval)
+            .and_then(|watcher| watch_paths(watcher, &root, git_dir.as_deref()));
+        if let Err(error) = watched {
+            self.watched.lock().remove(&worktree_id);
+            WATCHER_STATS.lock().remove(&worktree_id);
+            record_failure(&worktree_id, error.clone());
+            (self.on_signal)(&worktree_id, &root, WatchSignal::Failed(error));
+            return;
+        }
+        self.worktrees.insert(worktree_id, WatchedWorktree::new(root, git_dir, options));
+    }
+
+    /// Drop a worktree, and the watches no other worktree relies on
+    fn remove(&mut self, worktree_id: &str) -> Option<WatchedWorktree> {
+        let removed = self.worktrees.remove(worktree_id)?;
+        let Some(watcher) = self.watcher.as_mut() else {
+            return Some(removed);
+        };
+        for path in std::iter::once(&removed.root).chain(&removed.git_dir) {
+            if self.worktrees.values().any(|wt| wt.covers(path)) {
+                continue;
+            }
+            let _ = watcher.unwatch(path);
+            // Unwatching a directory drops every watch below it, including
+            // those of worktrees registered inside it
+            for wt in self.worktrees.values().filter(|wt| wt.watches_inside(path)) {
+                let _ = watch_paths(watcher, &wt.root, wt.git_dir.as_deref());
+            }
+        }
+        Some(removed)
+    }
+
+    /// Hand an event to every worktree it concerns. Events without paths
+    /// (e.g. a rescan) could concern any of them.
+    fn route(&mut self, event: Event) {
+        // Reads (including our own git status) change nothing
+        if is_read_only(&event) {
+            return;
+        }
+        for (id, wt) in self.worktrees.iter_mut() {
+            if wt.restart.is_some()
+                || !(event.paths.is_empty() || event.paths.iter().any(|p| wt.owns(p)))
+            {
+                continue;
+            }
+            if let Some(reason) = wt.on_event(id, &event, &mut self.on_signal) {
+                wt.lose(id, reason);
+            }
+        }
+    }
+
+    fn route_error(&mut self, error: notify::Error) {
+        for (id, wt) in self.worktrees.iter_mut() {
+            if wt.restart.is_some()
+                || !(error.paths.is_empty() || error.paths.iter().any(|p| wt.owns(p)))
+            {
+                continue;
+            }
+            if is_fatal(&error, &wt.root) {
+                wt.lose(id, error.to_string());
+            } else {
+                let message = format!("Watch error: {}", error);
+                record_failure(id, message.clone());
+                (self.on_signal)(id, &wt.root, WatchSignal::Failed(message));
+            }
+        }
+    }
+
+    /// Retry lost watches, notice removed roots and report settled changes
+    fn tick(&mut self) {
+        let now = Instant::now();
+        let check_existence =
+            now.duration_since(self.last_existence_check) >= EXISTENCE_CHECK_INTERVAL;
+        if check_existence {
+            self.last_existence_check = now;
+        }
+
+        let mut gave_up = Vec::new();
+        for (id, wt) in self.worktrees.iter_mut() {
+            if let Some(backoff) = wt.restart.as_mut() {
+                if backoff.attempts >= wt.options.max_restarts {
+                    gave_up.push(id.clone());
+                    continue;
                 }
-                Ok(Err(e)) => {
-                    eprintln!("Watch error: {}", e);
+                if now < backoff.next_attempt_at {
+                    continue;
                 }
-                Err(_) => {
-                    // Timeout - check if we should process pending update
+                if wt.root.exists() {
+                    let restarted = ensure_watcher(&mut self.watcher, &self.tx, self.poll_interval)
+                        .and_then(|watcher| watch_paths(watcher, &wt.root, wt.git_dir.as_deref()));
+                    match restarted {
+                        Ok(()) => {
+                            eprintln!("[Watcher] Restarted watcher for {}", id);
+                            wt.restart = None;
+                            // Whatever changed while nothing was watching
+                            wt.pending_update = true;
+                            wt.pending_rescan = true;
+                            wt.last_event_time = now;
+                            continue;
+                        }
+                        Err(error) => backoff.last_error = error,
+                    }
                 }
+                backoff.attempts += 1;
+                let delay = wt.options.restart_delay * 2u32.saturating_pow(backoff.attempts);
+                backoff.next_attempt_at = now + delay;
+                continue;
             }
 
-            // Periodically check if worktree folder was deleted externally
-            if last_existence_check.elapsed() >= existence_check_interval {
-                last_existence_check = std::time::Instant::now();
-                if !path.exists() {
-                    eprintln!(
-                        "[Watcher] Worktree folder deleted externally: {}",
-                        worktree_path
-                    );
-                    let _ = app.emit(
-                        "worktree-removed",
-                        WorktreeRemoved {
-                            worktree_path: worktree_path.clone(),
-                        },
-                    );
-                    break;
-                }
+            if check_existence && !wt.root.exists() {
+                wt.lose(id, "the directory is gone".to_string());
+                continue;
             }
 
-            // Process pending update after debounce period of quiet
-            if pending_update && last_event_time.elapsed() >= debounce_duration {
-                pending_update = false;
+            // Process pending update after debounce period of quiet. While
+            // paused, changes keep piling up and go out together on resume.
+            if wt.pending_update
+                && now.duration_since(wt.last_event_time) >= wt.options.debounce
+                && !is_paused(id)
+            {
+                wt.pending_update = false;
+                let paths = wt.pending_paths.drain().collect();
+                let rescan = std::mem::take(&mut wt.pending_rescan);
+                (self.on_signal)(id, &wt.root, WatchSignal::Changed { paths, rescan });
+            }
+        }
 
-                // Get changed files and emit
-                if let Ok(files) = git::get_changed_files(path) {
-                    let _ = app.emit(
-                        "files-changed",
-                        FilesChanged {
-                            worktree_path: worktree_path.clone(),
-                            files,
-                        },
-                    );
-                }
+        for id in gave_up {
+            self.give_up(&id);
+        }
+    }
+
+    /// Stop retrying a lost watch: report the worktree removed if its root is
+    /// gone, otherwise failed
+    fn give_up(&mut self, worktree_id: &str) {
+        let Some(wt) = self.remove(worktree_id) else {
+            return;
+        };
+        self.watched.lock().remove(worktree_id);
+        WATCHER_STATS.lock().remove(worktree_id);
+        self.retune(None);
+        if !wt.root.exists() {
+            (self.on_signal)(worktree_id, &wt.root, WatchSignal::RootRemoved);
+            return;
+        }
+        let error = wt.restart.map(|backoff| backoff.last_error).unwrap_or_default();
+        record_failure(worktree_id, error.clone());
+        (self.on_signal)(worktree_id, &wt.root, WatchSignal::Failed(error));
+    }
+
+    /// Poll at the shortest interval the worktrees (and one about to be added)
+    /// ask for, recreating a polling watcher whose interval no longer fits
+    fn retune(&mut self, adding: Option<Duration>) {
+        let wanted = self
+            .worktrees
+            .values()
+            .map(|wt| wt.options.poll_interval)
+            .chain(adding)
+            .min()
+            .unwrap_or(self.poll_interval);
+        if wanted == self.poll_interval {
+            return;
+        }
+        self.poll_interval = wanted;
+        if RecommendedWatcher::kind() == WatcherKind::PollWatcher && self.watcher.is_some() {
+            self.reconfigure();
+        }
+    }
+
+    /// Recreate the watcher so a changed `watcher_config` applies
+    fn reconfigure(&mut self) {
+        self.watcher = None;
+        for (id, wt) in self.worktrees.iter_mut() {
+            if wt.restart.is_some() {
+                continue;
+            }
+            let watched = ensure_watcher(&mut self.watcher, &self.tx, self.poll_interval)
+                .and_then(|watcher| watch_paths(watcher, &wt.root, wt.git_dir.as_deref()));
+            if let Err(error) = watched {
+                wt.lose(id, error);
             }
         }
+    }
+}
 
-        WATCHERS.lock().remove(&worktree_id_clone);
-    });
+/// The project a worktree belongs to
+fn owning_project(app: &AppHandle, worktree_id: &str) -> Option<Project> {
+    let state = app.try_state::<Arc<AppState>>()?;
+    let persisted = state.persisted.read();
+    persisted
+        .projects
+        .iter()
+        .find(|p| p.worktrees.iter().any(|w| w.id == worktree_id))
+        .cloned()
+}
+
+/// Which of a project's directories are gone, or `None` if all exist
+pub fn missing_project_paths(project: &Project) -> Option<ProjectPathMissing> {
+    let project_missing = !Path::new(&project.path).is_dir();
+    let missing_worktree_paths: Vec<String> = project
+        .worktrees
+        .iter()
+        .filter(|wt| !Path::new(&wt.path).is_dir())
+        .map(|wt| wt.path.clone())
+        .collect();
+
+    if !project_missing && missing_worktree_paths.is_empty() {
+        return None;
+    }
+    Some(ProjectPathMissing {
+        project_id: project.id.clone(),
+        project_path: project.path.clone(),
+        project_missing,
+        missing_worktree_paths,
+    })
+}
+
+/// Stop the watchers that can only fail now that paths are gone: all of the
+/// project's if its directory is missing, otherwise those of missing worktrees.
+/// Reports through `emit` and returns true when anything is missing.
+fn handle_missing_paths<F: FnOnce(ProjectPathMissing)>(project: &Project, emit: F) -> bool {
+    let Some(missing) = missing_project_paths(project) else {
+        crate::menu::set_project_path_missing(&project.id, false);
+        return false;
+    };
+
+    for wt in &project.worktrees {
+        if missing.project_missing || missing.missing_worktree_paths.contains(&wt.path) {
+            stop_watching(&wt.id);
+            stop_merge_watcher(&wt.id);
+            stop_rebase_watcher(&wt.id);
+        }
+    }
+    if missing.project_missing {
+        stop_main_head_watcher(&project.id);
+    }
+    crate::menu::set_project_path_missing(&project.id, missing.project_missing);
+    emit(missing);
+    true
+}
+
+/// Check a project's directories still exist. If not, stop its watchers,
+/// disable menu items that need them, and emit `project-path-missing`.
+pub fn check_project_paths(app: &AppHandle, project: &Project) -> bool {
+    handle_missing_paths(project, |missing| {
+        let _ = app.emit("project-path-missing", missing);
+    })
+}
+
+/// The persisted changed-files view mode for a worktree, and whether it shows ignored files
+fn view_settings(app: &AppHandle, worktree_id: &str) -> (ChangedFilesViewMode, bool) {
+    app.try_state::<Arc<AppState>>()
+        .and_then(|state| {
+            state
+                .persisted
+                .read()
+                .projects
+                .iter()
+                .flat_map(|p| &p.worktrees)
+                .find(|w| w.id == worktree_id)
+                .map(|w| (w.changed_files_view_mode, w.show_ignored_files))
+        })
+        .unwrap_or_default()
 }
 
 pub fn stop_watching(worktree_id: &str) {
-    if let Some(tx) = WATCHERS.lock().remove(worktree_id) {
-        let _ = tx.send(());
+    stop_watcher_thread(worktree_id);
+    COMPARE_CONTENTS.lock().remove(worktree_id);
+    WATCH_FAILURES.lock().remove(worktree_id);
+    KNOWN_FILES.lock().remove(worktree_id);
+    KNOWN_STATUS.lock().remove(worktree_id);
+}
+
+fn stop_watcher_thread(worktree_id: &str) {
+    WATCHER_STATS.lock().remove(worktree_id);
+    if let Some(manager) = running_manager() {
+        manager.remove(worktree_id);
     }
 }
 
-pub fn stop_all_watchers() {
-    // Stop file watchers
-    let watchers = std::mem::take(&mut *WATCHERS.lock());
-    for (_, tx) in watchers {
-        let _ = tx.send(());
+/// Toggle content comparison for a worktree's watcher, recreating the shared
+/// watcher so the new config takes effect, and watching the worktree if it
+/// wasn't. Useful when tools write files without touching mtime.
+pub fn set_compare_contents(app: AppHandle, worktree_id: String, worktree_path: String, enabled: bool) {
+    if enabled {
+        COMPARE_CONTENTS.lock().insert(worktree_id.clone());
+    } else {
+        COMPARE_CONTENTS.lock().remove(&worktree_id);
     }
+    if let Some(stats) = WATCHER_STATS.lock().get_mut(&worktree_id) {
+        stats.compare_contents = enabled;
+    }
+
+    if let Some(manager) = running_manager() {
+        manager.reconfigure();
+    }
+    watch_worktree(app, worktree_id, worktree_path);
+}
+
+/// Hold back change notifications for a worktree. Changes keep accumulating
+/// and are reported as a single refresh once every pause has been released.
+/// Pauses nest, so overlapping tasks each take and release their own.
+pub fn pause(worktree_id: &str) {
+    *PAUSES.lock().entry(worktree_id.to_string()).or_insert(0) += 1;
+}
+
+/// Release one `pause` of a worktree's notifications
+pub fn resume(worktree_id: &str) {
+    let mut pauses = PAUSES.lock();
+    if let Some(count) = pauses.get_mut(worktree_id) {
+        *count -= 1;
+        if *count == 0 {
+            pauses.remove(worktree_id);
+        }
+    }
+}
+
+pub fn is_paused(worktree_id: &str) -> bool {
+    PAUSES.lock().contains_key(worktree_id)
+}
+
+/// Focus can flap quickly (e.g. alt-tabbing past the app), so refresh at most this often
+const FOCUS_REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+
+lazy_static::lazy_static! {
+    static ref LAST_FOCUS_REFRESH: Mutex<Option<Instant>> = Mutex::new(None);
+}
+
+/// Refresh every worktree being watched when the app regains focus, emitting
+/// `files-changed` right away instead of waiting for the debounced watcher.
+/// Changes made while unfocused (e.g. in another terminal) may not have
+/// produced events we saw. Returns how many worktrees were refreshed, which
+/// is 0 when throttled.
+pub fn on_focus_refresh(app: &AppHandle) -> usize {
+    let worktrees = match app.try_state::<Arc<AppState>>() {
+        Some(state) => {
+            let watched = running_manager().map(|m| m.watched_ids()).unwrap_or_default();
+            watched_worktrees(&state.persisted.read().projects, &watched)
+        }
+        None => return 0,
+    };
+    let mut last = LAST_FOCUS_REFRESH.lock();
+    refresh_if_due(&mut last, Instant::now(), &worktrees, |id, path| {
+        emit_changed_files(app, id, path)
+    })
+}
+
+/// (id, path) of the worktrees in `watched`
+fn watched_worktrees(projects: &[Project], watched: &HashSet<String>) -> Vec<(String, String)> {
+    projects
+        .iter()
+        .flat_map(|p| &p.worktrees)
+        .filter(|w| watched.contains(&w.id))
+        .map(|w| (w.id.clone(), w.path.clone()))
+        .collect()
+}
+
+/// Run `refresh` for each worktree unless the last refresh was too recent
+fn refresh_if_due<F: FnMut(&str, &str)>(
+    last: &mut Option<Instant>,
+    now: Instant,
+    worktrees: &[(String, String)],
+    mut refresh: F,
+) -> usize {
+    if last.is_some_and(|at| now.duration_since(at) < FOCUS_REFRESH_INTERVAL) {
+        return 0;
+    }
+    *last = Some(now);
+    for (id, path) in worktrees {
+        refresh(id, path);
+    }
+    worktrees.len()
+}
+
+pub fn stop_all_watchers() {
+    // Stop file watchers, waiting for the dispatch thread unless something
+    // else still holds the manager
+    let manager = MANAGER.lock().take();
+    drop(manager);
 
     // Stop config watcher
     stop_config_watcher();
@@ -344,6 +1502,95 @@ pub fn stop_rebase_watcher(worktree_id: &str) {
     }
 }
 
+// Track active main checkout HEAD watchers, keyed by project id
+lazy_static::lazy_static! {
+    static ref HEAD_WATCHERS: Mutex<HashMap<String, Sender<()>>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MainHeadChanged {
+    pub project_id: String,
+    pub project_path: String,
+    pub head: git::HeadInfo,
+}
+
+/// Watch the main checkout's git directory and emit `main-head-changed`
+/// whenever its branch or commit changes (checkout, commit, reset).
+pub fn watch_main_head(app: AppHandle, project_id: String, project_path: String) {
+    if HEAD_WATCHERS.lock().contains_key(&project_id) {
+        return;
+    }
+
+    let Some(git_dir) = resolve_git_dir(Path::new(&project_path)) else {
+        eprintln!("[HeadWatcher] Could not resolve git dir for {:?}", project_path);
+        return;
+    };
+
+    let (stop_tx, stop_rx) = channel::<()>();
+    HEAD_WATCHERS.lock().insert(project_id.clone(), stop_tx);
+
+    thread::spawn(move || {
+        let (tx, rx) = channel::<notify::Result<Event>>();
+        let config = Config::default().with_poll_interval(Duration::from_secs(2));
+        let watcher = RecommendedWatcher::new(tx, config).and_then(|mut watcher| {
+            // HEAD lives directly in the git dir; refs change alongside the index and logs
+            watcher.watch(&git_dir, RecursiveMode::NonRecursive)?;
+            Ok(watcher)
+        });
+        let _watcher = match watcher {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                eprintln!("[HeadWatcher] Failed to watch {:?}: {}", git_dir, e);
+                HEAD_WATCHERS.lock().remove(&project_id);
+                return;
+            }
+        };
+
+        let mut last = git::main_head(Path::new(&project_path)).ok();
+        loop {
+            if stop_rx.try_recv().is_ok() {
+                eprintln!("[HeadWatcher] Stopping HEAD watcher for {}", project_id);
+                return;
+            }
+
+            match rx.recv_timeout(Duration::from_millis(200)) {
+                Ok(Ok(_)) => {
+                    let Ok(head) = git::main_head(Path::new(&project_path)) else {
+                        continue;
+                    };
+                    if last.as_ref() != Some(&head) {
+                        let _ = app.emit(
+                            "main-head-changed",
+                            MainHeadChanged {
+                                project_id: project_id.clone(),
+                                project_path: project_path.clone(),
+                                head: head.clone(),
+                            },
+                        );
+                        last = Some(head);
+                    }
+                }
+                Ok(Err(e)) => eprintln!("[HeadWatcher] Watch error: {}", e),
+                Err(_) => {
+                    // Timeout: stop once the repository is gone
+                    if !git_dir.exists() {
+                        break;
+                    }
+                }
+            }
+        }
+
+        HEAD_WATCHERS.lock().remove(&project_id);
+    });
+}
+
+pub fn stop_main_head_watcher(project_id: &str) {
+    if let Some(tx) = HEAD_WATCHERS.lock().remove(project_id) {
+        let _ = tx.send(());
+    }
+}
+
 // Track active config watcher
 lazy_static::lazy_static! {
     static ref CONFIG_WATCHER: Mutex<Option<Sender<()>>> = Mutex::new(None);
@@ -455,6 +1702,8 @@ pub fn watch_config(app: AppHandle, project_path: Option<String>) {
                         project_path: project_path_clone.clone(),
                     },
                 );
+                // The worktree directory setting may now nest projects' worktrees
+                crate::warn_if_base_dir_nested(&app);
             }
         }
 
@@ -573,3 +1822,690 @@ pub fn stop_mappings_watcher() {
         let _ = tx.send(());
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn debounce() -> Duration {
+        WatchOptions::default().debounce
+    }
+
+    /// A manager watching only `root`, whose signals arrive on the returned
+    /// channel. Dropping the manager stops it.
+    fn watch_one(
+        id: &str,
+        root: &Path,
+        options: WatchOptions,
+    ) -> (WatcherManager, Receiver<WatchSignal>) {
+        let (signal_tx, signal_rx) = channel();
+        let manager = WatcherManager::new(move |_, _, signal| {
+            let _ = signal_tx.send(signal);
+        });
+        manager.add_with_options(id, root, options);
+        (manager, signal_rx)
+    }
+
+    #[test]
+    fn watcher_stats_count_events_and_emissions() {
+        let id = "stats-test-worktree";
+        WATCHER_STATS.lock().insert(id.to_string(), WatcherStats::default());
+
+        for _ in 0..3 {
+            record_event(id);
+        }
+        record_emission(id);
+
+        let stats = watcher_stats(id).unwrap();
+        assert_eq!(stats.events_received, 3);
+        assert_eq!(stats.emissions_sent, 1);
+        assert!(stats.last_emission_at.is_some());
+
+        WATCHER_STATS.lock().remove(id);
+        assert!(watcher_stats(id).is_none());
+    }
+
+    #[test]
+    fn focus_refreshes_each_watched_worktree_once_per_interval() {
+        let project = |id: &str, worktree_ids: &[&str]| {
+            let worktrees = worktree_ids
+                .iter()
+                .map(|wt| crate::test_utils::worktree(wt, &format!("/tmp/{}/{}", id, wt)))
+                .collect();
+            crate::test_utils::project(id, &format!("/tmp/{}", id), worktrees)
+        };
+        let projects = vec![project("a", &["a1", "a2"]), project("b", &["b1", "b2"])];
+        let watched: HashSet<String> = ["a1", "a2", "b2"].iter().map(|s| s.to_string()).collect();
+        let worktrees = watched_worktrees(&projects, &watched);
+
+        let mut refreshed: Vec<String> = Vec::new();
+        let mut last = None;
+        let start = Instant::now();
+        // Focus toggles rapidly: only the first one refreshes
+        for offset in [0, 100, 1500] {
+            let now = start + Duration::from_millis(offset);
+            refresh_if_due(&mut last, now, &worktrees, |id, _| refreshed.push(id.to_string()));
+        }
+        assert_eq!(refreshed, vec!["a1", "a2", "b2"]);
+
+        let later = start + FOCUS_REFRESH_INTERVAL + Duration::from_millis(1);
+        let count = refresh_if_due(&mut last, later, &worktrees, |id, path| {
+            assert!(path.ends_with(id));
+            refreshed.push(id.to_string());
+        });
+        assert_eq!(count, 3);
+        assert_eq!(refreshed.len(), 6);
+    }
+
+    #[test]
+    fn compare_contents_applies_to_recreated_watcher_config() {
+        let id = "compare-contents-test-worktree";
+        let poll_interval = Duration::from_secs(2);
+        assert!(!watcher_config(poll_interval).compare_contents());
+
+        COMPARE_CONTENTS.lock().insert(id.to_string());
+        let config = watcher_config(poll_interval);
+        assert!(config.compare_contents());
+        assert_eq!(config.poll_interval(), Some(poll_interval));
+        let (tx, _rx) = channel::<notify::Result<Event>>();
+        assert!(RecommendedWatcher::new(tx, config).is_ok());
+
+        stop_watching(id);
+        assert!(!watcher_config(poll_interval).compare_contents());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn changes_during_a_task_are_held_until_it_exits() {
+        let root = std::env::temp_dir()
+            .join(format!("shellflow-watch-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&root).unwrap();
+
+        let id = "task-pause-test".to_string();
+        crate::tasks::set_pause_watcher(&id, true);
+        let (manager, signal_rx) = watch_one(&id, &root, WatchOptions::default());
+        thread::sleep(Duration::from_millis(200));
+
+        // A build that keeps writing output for longer than the debounce period
+        let mut build = std::process::Command::new("sh")
+            .args(["-c", "for i in 1 2 3 4 5 6; do echo $i >> out.txt; sleep 0.2; done"])
+            .current_dir(&root)
+            .spawn()
+            .unwrap();
+        crate::tasks::register_task(crate::tasks::RunningTask {
+            id: "task-pause-build".to_string(),
+            worktree_id: id.clone(),
+            label: "build".to_string(),
+            started_at: crate::worktree::chrono_lite_now(),
+            pid: build.id(),
+        });
+        assert!(is_paused(&id));
+
+        build.wait().unwrap();
+        // Past the debounce period after the last write, still nothing
+        assert!(signal_rx.recv_timeout(Duration::from_millis(1000)).is_err());
+
+        crate::tasks::task_exited("task-pause-build");
+        assert!(!is_paused(&id));
+        match signal_rx.recv_timeout(Duration::from_secs(5)) {
+            Ok(WatchSignal::Changed { paths, .. }) => {
+                assert!(paths.iter().any(|p| p.ends_with("out.txt")))
+            }
+            other => panic!("expected one change after the task exited, got {:?}", other),
+        }
+        assert!(signal_rx.recv_timeout(Duration::from_millis(800)).is_err());
+
+        crate::tasks::set_pause_watcher(&id, false);
+        drop(manager);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn burst_of_changes_is_emitted_once_after_it_settles() {
+        let root = std::env::temp_dir()
+            .join(format!("shellflow-watch-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&root).unwrap();
+
+        let (manager, signal_rx) = watch_one("debounce-test", &root, WatchOptions::default());
+        thread::sleep(Duration::from_millis(200));
+
+        for name in ["a.txt", "b.txt", "c.txt"] {
+            std::fs::write(root.join(name), name).unwrap();
+            thread::sleep(Duration::from_millis(100));
+        }
+
+        match signal_rx.recv_timeout(Duration::from_secs(5)) {
+            Ok(WatchSignal::Changed { paths, .. }) => {
+                for name in ["a.txt", "b.txt", "c.txt"] {
+                    assert!(paths.iter().any(|p| p.ends_with(name)), "{} missing", name);
+                }
+            }
+            other => panic!("expected one change for the burst, got {:?}", other),
+        }
+        assert!(signal_rx.recv_timeout(debounce() * 2).is_err());
+
+        drop(manager);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn removals_ask_for_a_full_rescan() {
+        let root = std::env::temp_dir()
+            .join(format!("shellflow-watch-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&root).unwrap();
+
+        let (manager, signal_rx) = watch_one("rescan-test", &root, WatchOptions::default());
+        thread::sleep(Duration::from_millis(200));
+
+        std::fs::write(root.join("a.txt"), "a").unwrap();
+        match signal_rx.recv_timeout(Duration::from_secs(5)) {
+            Ok(WatchSignal::Changed { rescan, .. }) => assert!(!rescan),
+            other => panic!("expected a change for the write, got {:?}", other),
+        }
+
+        std::fs::remove_file(root.join("a.txt")).unwrap();
+        match signal_rx.recv_timeout(Duration::from_secs(5)) {
+            Ok(WatchSignal::Changed { rescan, .. }) => assert!(rescan),
+            other => panic!("expected a change for the removal, got {:?}", other),
+        }
+
+        drop(manager);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn short_debounce_reports_sooner_than_the_default() {
+        let root = std::env::temp_dir()
+            .join(format!("shellflow-watch-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&root).unwrap();
+
+        let options = WatchOptions {
+            debounce: Duration::from_millis(50),
+            ..WatchOptions::default()
+        };
+        let (manager, signal_rx) = watch_one("short-debounce-test", &root, options);
+        thread::sleep(Duration::from_millis(200));
+
+        let written = Instant::now();
+        std::fs::write(root.join("quick.txt"), "quick").unwrap();
+        match signal_rx.recv_timeout(Duration::from_secs(5)) {
+            Ok(WatchSignal::Changed { paths, .. }) => {
+                assert!(paths.iter().any(|p| p.ends_with("quick.txt")))
+            }
+            other => panic!("expected a change, got {:?}", other),
+        }
+        let elapsed = written.elapsed();
+        assert!(elapsed < debounce(), "reported after {:?}", elapsed);
+
+        drop(manager);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn git_churn_and_ignored_paths_are_not_emitted() {
+        let repo = crate::test_utils::TestRepo::new();
+        repo.commit_file(".gitignore", "target/\n", "ignore build output");
+
+        let (manager, signal_rx) = watch_one("ignore-test", &repo.path, WatchOptions::default());
+        thread::sleep(Duration::from_millis(200));
+        let next_change = |wait: Duration| loop {
+            match signal_rx.recv_timeout(wait) {
+                Ok(WatchSignal::Changed { paths, .. }) => return Some(paths),
+                Ok(_) => {}
+                Err(_) => return None,
+            }
+        };
+
+        std::fs::write(repo.path.join(".git/ORIG_HEAD"), "churn").unwrap();
+        std::fs::create_dir_all(repo.path.join("target/debug")).unwrap();
+        std::fs::write(repo.path.join("target/debug/app"), "binary").unwrap();
+        assert_eq!(next_change(debounce() * 3), None);
+
+        std::fs::write(repo.path.join("README.md"), "edited\n").unwrap();
+        let paths = next_change(Duration::from_secs(5)).expect("tracked change is emitted");
+        assert!(paths.iter().any(|p| p.ends_with("README.md")));
+
+        drop(manager);
+    }
+
+    #[test]
+    fn detached_head_reads_as_an_empty_branch() {
+        let repo = crate::test_utils::TestRepo::new();
+        assert_eq!(current_branch(&repo.path).as_deref(), Some("main"));
+
+        let head = repo.head();
+        repo.git(&["checkout", "--detach", &head]);
+        assert_eq!(current_branch(&repo.path).as_deref(), Some(""));
+    }
+
+    #[test]
+    fn only_git_dir_changes_refresh_the_status() {
+        let repo = crate::test_utils::TestRepo::new();
+        let mut project = crate::worktree::create_project(&repo.path).unwrap();
+        let wt = repo.create_worktree(&mut project, "feature");
+        let wt_path = Path::new(&wt.path);
+        let git_dir = resolve_git_dir(wt_path).unwrap();
+
+        assert!(!touches_git_dir(&[wt_path.join("src/main.rs")], wt_path));
+        assert!(touches_git_dir(&[wt_path.join("src/main.rs"), git_dir.join("index")], wt_path));
+        assert!(touches_git_dir(&[repo.path.join(".git/refs/heads/main")], &repo.path));
+    }
+
+    #[test]
+    fn rewriting_head_reports_the_new_branch() {
+        let repo = crate::test_utils::TestRepo::new();
+        repo.git(&["branch", "other"]);
+        let worktree = std::env::temp_dir()
+            .join(format!("shellflow-watch-wt-{}", uuid::Uuid::new_v4()));
+        repo.git(&["worktree", "add", "-b", "feature", &worktree.to_string_lossy()]);
+        let git_dir = resolve_git_dir(&worktree).unwrap();
+        assert!(!git_dir.starts_with(&worktree), "linked worktrees keep HEAD elsewhere");
+
+        let (manager, signal_rx) = watch_one("branch-test", &worktree, WatchOptions::default());
+        thread::sleep(Duration::from_millis(200));
+        std::fs::write(git_dir.join("HEAD"), "ref: refs/heads/other\n").unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut branch = None;
+        while let Some(left) = deadline.checked_duration_since(Instant::now()) {
+            if let Ok(WatchSignal::BranchChanged(name)) = signal_rx.recv_timeout(left) {
+                branch = Some(name);
+                break;
+            }
+        }
+        assert_eq!(branch.as_deref(), Some("other"));
+
+        drop(manager);
+        let _ = std::fs::remove_dir_all(&worktree);
+    }
+
+    #[test]
+    fn watch_options_control_what_is_ignored() {
+        let repo = crate::test_utils::TestRepo::new();
+        repo.write(".gitignore", "target/\n");
+        let git_dir = repo.path.join(".git");
+        let rules = |ignore_git, respect_gitignore| {
+            let options = WatchOptions {
+                ignore_git,
+                respect_gitignore,
+                ..WatchOptions::default()
+            };
+            IgnoreRules::new(&repo.path, Some(&git_dir), options)
+        };
+
+        let all = rules(true, true);
+        assert!(all.ignores(&git_dir.join("ORIG_HEAD")));
+        assert!(!all.ignores(&git_dir.join("index")));
+        assert!(!all.ignores(&git_dir.join("HEAD")));
+        assert!(all.ignores(&repo.path.join("target/debug/app")));
+        assert!(!all.ignores(&repo.path.join("src/main.rs")));
+
+        let none = rules(false, false);
+        assert!(!none.ignores(&git_dir.join("ORIG_HEAD")));
+        assert!(!none.ignores(&repo.path.join("target/debug/app")));
+    }
+
+    #[test]
+    fn failing_to_watch_reports_the_error() {
+        let root = std::env::temp_dir()
+            .join(format!("shellflow-watch-missing-{}", uuid::Uuid::new_v4()));
+        let id = "watch-error-test".to_string();
+
+        let (manager, signal_rx) = watch_one(&id, &root, WatchOptions::default());
+
+        match signal_rx.recv_timeout(Duration::from_secs(5)) {
+            Ok(WatchSignal::Failed(message)) => {
+                assert!(message.contains(&root.display().to_string()), "{}", message)
+            }
+            other => panic!("expected a failure signal, got {:?}", other),
+        }
+        assert!(!manager.is_watching(&id));
+        assert!(watch_failure(&id).is_some());
+    }
+
+    #[test]
+    fn pauses_nest_and_are_per_worktree() {
+        pause("nested-pause-a");
+        pause("nested-pause-a");
+        assert!(is_paused("nested-pause-a"));
+        assert!(!is_paused("nested-pause-b"));
+
+        resume("nested-pause-a");
+        assert!(is_paused("nested-pause-a"));
+        resume("nested-pause-a");
+        assert!(!is_paused("nested-pause-a"));
+        // Releasing more than was taken is harmless
+        resume("nested-pause-a");
+        assert!(!is_paused("nested-pause-a"));
+    }
+
+    #[test]
+    fn index_lock_reports_busy_then_idle() {
+        let repo = crate::test_utils::TestRepo::new();
+        let lock = repo.path.join(".git").join(INDEX_LOCK);
+
+        let options = WatchOptions::default();
+        let (manager, signal_rx) = watch_one("index-lock-test", &repo.path, options);
+        thread::sleep(Duration::from_millis(200));
+
+        let next_lock_signal = || {
+            let deadline = Instant::now() + Duration::from_secs(5);
+            while Instant::now() < deadline {
+                if let Ok(WatchSignal::IndexLock(locked)) =
+                    signal_rx.recv_timeout(Duration::from_millis(100))
+                {
+                    return Some(locked);
+                }
+            }
+            None
+        };
+
+        std::fs::write(&lock, "").unwrap();
+        assert_eq!(next_lock_signal(), Some(true));
+        assert!(is_index_locked(&repo.path));
+
+        std::fs::remove_file(&lock).unwrap();
+        assert_eq!(next_lock_signal(), Some(false));
+        assert!(!is_index_locked(&repo.path));
+
+        drop(manager);
+    }
+
+    #[test]
+    fn removing_watched_root_signals_and_exits() {
+        let root = std::env::temp_dir()
+            .join(format!("shellflow-watch-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("file.txt"), "content").unwrap();
+
+        let id = "root-removal-test".to_string();
+        let (manager, signal_rx) = watch_one(&id, &root, WatchOptions::default());
+
+        // Give the backend a moment to register the watch before removing
+        thread::sleep(Duration::from_millis(200));
+        std::fs::remove_dir_all(&root).unwrap();
+
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        let mut removed = false;
+        while std::time::Instant::now() < deadline {
+            match signal_rx.recv_timeout(Duration::from_millis(100)) {
+                Ok(WatchSignal::RootRemoved) => {
+                    removed = true;
+                    break;
+                }
+                Ok(
+                    WatchSignal::Changed { .. }
+                    | WatchSignal::IndexLock(_)
+                    | WatchSignal::BranchChanged(_)
+                    | WatchSignal::Failed(_),
+                )
+                | Err(_) => {}
+            }
+        }
+        assert!(removed, "expected a root removal signal");
+        assert!(!manager.is_watching(&id), "worktree should be dropped after root removal");
+    }
+
+    #[test]
+    fn recreated_root_is_watched_again() {
+        let root = std::env::temp_dir()
+            .join(format!("shellflow-watch-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&root).unwrap();
+
+        let options = WatchOptions {
+            restart_delay: Duration::from_millis(100),
+            ..WatchOptions::default()
+        };
+        let (manager, signal_rx) = watch_one("root-recreated-test", &root, options);
+
+        thread::sleep(Duration::from_millis(200));
+        std::fs::remove_dir_all(&root).unwrap();
+        std::fs::create_dir_all(&root).unwrap();
+        // Past the first restart attempt
+        thread::sleep(Duration::from_millis(500));
+        let file = root.join("after.txt");
+        std::fs::write(&file, "content").unwrap();
+
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        let mut resumed = false;
+        while !resumed && std::time::Instant::now() < deadline {
+            match signal_rx.recv_timeout(Duration::from_millis(100)) {
+                Ok(WatchSignal::Changed { paths, .. }) => resumed = paths.contains(&file),
+                Ok(WatchSignal::RootRemoved) => panic!("root was recreated"),
+                Ok(WatchSignal::Failed(error)) => panic!("watcher failed: {}", error),
+                Ok(WatchSignal::IndexLock(_) | WatchSignal::BranchChanged(_)) | Err(_) => {}
+            }
+        }
+        assert!(resumed, "expected events from the recreated root");
+
+        drop(manager);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn removed_project_directory_reports_and_stops_watchers() {
+        let repo = crate::test_utils::TestRepo::new();
+        let mut project = crate::worktree::create_project(&repo.path).unwrap();
+        // Keep the worktree outside the project so only the project directory disappears
+        let wt = repo.create_worktree(&mut project, "feature");
+
+        // Register the worktree with the shared manager, as watch_worktree does
+        let manager = MANAGER
+            .lock()
+            .get_or_insert_with(|| Arc::new(WatcherManager::new(|_, _, _| {})))
+            .clone();
+        manager.add_with_options(&wt.id, Path::new(&wt.path), WatchOptions::default());
+
+        assert!(!handle_missing_paths(&project, |_| panic!("nothing is missing")));
+        assert!(manager.is_watching(&wt.id));
+
+        std::fs::remove_dir_all(&repo.path).unwrap();
+        let mut reported = None;
+        assert!(handle_missing_paths(&project, |missing| reported = Some(missing)));
+
+        assert_eq!(
+            reported,
+            Some(ProjectPathMissing {
+                project_id: project.id.clone(),
+                project_path: project.path.clone(),
+                project_missing: true,
+                missing_worktree_paths: vec![],
+            })
+        );
+        assert!(!manager.is_watching(&wt.id));
+    }
+
+    #[test]
+    fn removed_worktree_gets_no_more_signals() {
+        let root = std::env::temp_dir()
+            .join(format!("shellflow-watch-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&root).unwrap();
+
+        let id = "stop-handle-test";
+        let (manager, signal_rx) = watch_one(id, &root, WatchOptions::default());
+        thread::sleep(Duration::from_millis(200));
+
+        manager.remove(id);
+        assert!(!manager.is_watching(id));
+        std::fs::write(root.join("after.txt"), "content").unwrap();
+        assert!(signal_rx.recv_timeout(debounce() * 3).is_err());
+
+        drop(manager);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    /// Ids of the worktrees reported changed within `wait`, with the paths
+    fn changed_ids(
+        signal_rx: &Receiver<(String, WatchSignal)>,
+        wait: Duration,
+    ) -> Vec<(String, Vec<PathBuf>)> {
+        let deadline = Instant::now() + wait;
+        let mut changed = Vec::new();
+        while let Some(left) = deadline.checked_duration_since(Instant::now()) {
+            if let Ok((id, WatchSignal::Changed { paths, .. })) = signal_rx.recv_timeout(left) {
+                changed.push((id, paths));
+            }
+        }
+        changed
+    }
+
+    #[test]
+    fn events_are_routed_to_the_worktree_they_happen_in() {
+        let base = std::env::temp_dir()
+            .join(format!("shellflow-watch-{}", uuid::Uuid::new_v4()));
+        let first = base.join("first");
+        let second = base.join("second");
+        std::fs::create_dir_all(&first).unwrap();
+        std::fs::create_dir_all(&second).unwrap();
+
+        let (signal_tx, signal_rx) = channel();
+        let manager = WatcherManager::new(move |id, _, signal| {
+            let _ = signal_tx.send((id.to_string(), signal));
+        });
+        let options = WatchOptions::default();
+        manager.add_with_options("route-first", &first, options);
+        manager.add_with_options("route-second", &second, options);
+        thread::sleep(Duration::from_millis(200));
+
+        std::fs::write(first.join("a.txt"), "a").unwrap();
+        let changed = changed_ids(&signal_rx, debounce() * 3);
+        assert_eq!(changed, vec![("route-first".to_string(), vec![first.join("a.txt")])]);
+
+        std::fs::write(second.join("b.txt"), "b").unwrap();
+        let changed = changed_ids(&signal_rx, debounce() * 3);
+        assert_eq!(changed, vec![("route-second".to_string(), vec![second.join("b.txt")])]);
+
+        // The other worktree keeps its watch when one is removed
+        manager.remove("route-first");
+        std::fs::write(first.join("c.txt"), "c").unwrap();
+        std::fs::write(second.join("d.txt"), "d").unwrap();
+        let changed = changed_ids(&signal_rx, debounce() * 3);
+        assert_eq!(changed, vec![("route-second".to_string(), vec![second.join("d.txt")])]);
+
+        drop(manager);
+        let _ = std::fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn removing_an_outer_worktree_keeps_nested_ones_watched() {
+        let outer = std::env::temp_dir()
+            .join(format!("shellflow-watch-{}", uuid::Uuid::new_v4()));
+        let nested = outer.join(".worktrees/nested");
+        std::fs::create_dir_all(&nested).unwrap();
+
+        let (signal_tx, signal_rx) = channel();
+        let manager = WatcherManager::new(move |id, _, signal| {
+            let _ = signal_tx.send((id.to_string(), signal));
+        });
+        let options = WatchOptions::default();
+        manager.add_with_options("nested-inner", &nested, options);
+        manager.add_with_options("nested-outer", &outer, options);
+        thread::sleep(Duration::from_millis(200));
+
+        manager.remove("nested-outer");
+        std::fs::write(nested.join("inner.txt"), "inner").unwrap();
+        let changed = changed_ids(&signal_rx, debounce() * 3);
+        assert_eq!(changed, vec![("nested-inner".to_string(), vec![nested.join("inner.txt")])]);
+
+        drop(manager);
+        let _ = std::fs::remove_dir_all(&outer);
+    }
+
+    #[test]
+    fn removed_worktree_directory_is_reported_without_project() {
+        let repo = crate::test_utils::TestRepo::new();
+        let mut project = crate::worktree::create_project(&repo.path).unwrap();
+        let wt = repo.create_worktree(&mut project, "feature");
+        std::fs::remove_dir_all(&wt.path).unwrap();
+
+        let missing = missing_project_paths(&project).unwrap();
+        assert!(!missing.project_missing);
+        assert_eq!(missing.missing_worktree_paths, vec![wt.path.clone()]);
+    }
+
+    #[test]
+    fn recording_for_unwatched_worktree_is_ignored() {
+        record_event("not-watched");
+        record_emission("not-watched");
+        assert!(watcher_stats("not-watched").is_none());
+    }
+
+    #[test]
+    fn nested_worktree_paths_are_excluded() {
+        let repo = crate::test_utils::TestRepo::new();
+        let nested = repo.path.join("elsewhere/wt");
+        repo.git(&["worktree", "add", "-b", "nested", nested.to_str().unwrap()]);
+        let excluded = vec![repo.path.join(".worktrees")];
+        let mut worktrees = NestedWorktrees::new(&repo.path);
+        let mut is_excluded =
+            |path: PathBuf| is_excluded(&path, &repo.path, &excluded, &mut worktrees);
+
+        assert!(is_excluded(nested.join("src/main.rs")));
+        assert!(is_excluded(repo.path.join(".worktrees/x/file")));
+        assert!(!is_excluded(repo.path.join("src/main.rs")));
+        assert!(!is_excluded(repo.path.join(".git/index")));
+        assert!(!is_excluded(repo.path.join("elsewhere/file")));
+    }
+
+    #[test]
+    fn submodules_and_nested_repositories_are_not_excluded() {
+        let repo = crate::test_utils::TestRepo::new();
+        let inner = repo.path.join("vendor/inner");
+        std::fs::create_dir_all(&inner).unwrap();
+        crate::test_utils::git_in(&inner, &["init"]);
+        // How a submodule's checkout points at its repository
+        let module = repo.path.join("vendor/module");
+        std::fs::create_dir_all(repo.path.join(".git/modules/module")).unwrap();
+        crate::test_utils::write_in(&module, ".git", "gitdir: ../../.git/modules/module\n");
+        let mut worktrees = NestedWorktrees::new(&repo.path);
+
+        assert!(!worktrees.contains(&repo.path, &inner.join("lib.rs")));
+        assert!(!worktrees.contains(&repo.path, &module.join("lib.rs")));
+
+        // Becoming a worktree later is noticed when its `.git` changes
+        let wt = repo.path.join("vendor/wt");
+        std::fs::create_dir_all(&wt).unwrap();
+        assert!(!worktrees.contains(&repo.path, &wt.join("file")));
+        std::fs::remove_dir(&wt).unwrap();
+        repo.git(&["worktree", "add", "-b", "wt", wt.to_str().unwrap()]);
+        assert!(worktrees.contains(&repo.path, &wt.join(".git")));
+        assert!(worktrees.contains(&repo.path, &wt.join("file")));
+    }
+
+    #[test]
+    fn changes_in_nested_worktree_are_not_emitted() {
+        let repo = crate::test_utils::TestRepo::new();
+        let nested = repo.path.join(".worktrees/wt");
+        repo.git(&["worktree", "add", "-b", "wt", nested.to_str().unwrap()]);
+
+        let options = WatchOptions::default();
+        let (manager, signal_rx) = watch_one("nested-worktree-test", &repo.path, options);
+        thread::sleep(Duration::from_millis(200));
+
+        let touched = |signal_rx: &Receiver<WatchSignal>, wait: Duration| -> Vec<PathBuf> {
+            let deadline = std::time::Instant::now() + wait;
+            let mut paths = Vec::new();
+            while let Some(left) = deadline.checked_duration_since(std::time::Instant::now()) {
+                let signal = signal_rx.recv_timeout(left);
+                if let Ok(WatchSignal::Changed { paths: changed, .. }) = signal {
+                    paths.extend(changed);
+                }
+            }
+            paths
+        };
+
+        std::fs::write(nested.join("inner.txt"), "nested change").unwrap();
+        let paths = touched(&signal_rx, Duration::from_millis(1500));
+        assert!(
+            paths.iter().all(|p| !p.starts_with(&nested)),
+            "nested worktree paths were emitted: {:?}",
+            paths
+        );
+
+        std::fs::write(repo.path.join("outer.txt"), "root change").unwrap();
+        let paths = touched(&signal_rx, Duration::from_millis(1500));
+        assert!(paths.iter().any(|p| p.ends_with("outer.txt")));
+
+        drop(manager);
+    }
+}
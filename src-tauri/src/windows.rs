@@ -0,0 +1,380 @@
+//! Secondary app windows (settings, mappings) opened natively from the menu,
+//! and the main window's per-project geometry.
+//!
+//! Each window has a fixed label, so there is at most one of each: opening
+//! one that already exists brings it to the front instead of creating another.
+
+use parking_lot::Mutex;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, PhysicalPosition, PhysicalSize, WebviewUrl, WebviewWindowBuilder};
+
+use crate::state::{AppState, WindowBounds};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuxWindow {
+    Settings,
+    Mappings,
+}
+
+impl AuxWindow {
+    /// The window a menu action opens, if it is handled natively.
+    pub fn from_action(action: &str) -> Option<Self> {
+        match action {
+            "app::openSettings" => Some(AuxWindow::Settings),
+            "app::openMappings" => Some(AuxWindow::Mappings),
+            _ => None,
+        }
+    }
+
+    /// Window label, unique per window kind.
+    pub fn label(self) -> &'static str {
+        match self {
+            AuxWindow::Settings => "settings",
+            AuxWindow::Mappings => "mappings",
+        }
+    }
+
+    fn title(self) -> &'static str {
+        match self {
+            AuxWindow::Settings => "Settings",
+            AuxWindow::Mappings => "Key Mappings",
+        }
+    }
+
+    /// Frontend entry point; the `window` query selects the view to render.
+    fn url(self) -> WebviewUrl {
+        WebviewUrl::App(format!("index.html?window={}", self.label()).into())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowOutcome {
+    Created,
+    Focused,
+}
+
+/// The operations `open_or_focus` needs, so the single-instance logic can be
+/// tested without a running app.
+pub trait WindowHost {
+    /// Show and focus the window with `label`. False if there is none.
+    fn focus_existing(&self, label: &str) -> bool;
+    fn create(&self, window: AuxWindow) -> tauri::Result<()>;
+}
+
+impl WindowHost for AppHandle {
+    fn focus_existing(&self, label: &str) -> bool {
+        let Some(window) = self.get_webview_window(label) else {
+            return false;
+        };
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+        true
+    }
+
+    fn create(&self, window: AuxWindow) -> tauri::Result<()> {
+        WebviewWindowBuilder::new(self, window.label(), window.url())
+            .title(window.title())
+            .inner_size(800.0, 600.0)
+            .min_inner_size(500.0, 400.0)
+            .build()?;
+        Ok(())
+    }
+}
+
+/// Focus `window` if it is already open, otherwise create it.
+pub fn open_or_focus<H: WindowHost>(host: &H, window: AuxWindow) -> tauri::Result<WindowOutcome> {
+    if host.focus_existing(window.label()) {
+        return Ok(WindowOutcome::Focused);
+    }
+    host.create(window)?;
+    Ok(WindowOutcome::Created)
+}
+
+/// Moves and resizes arrive in bursts while dragging; save once they settle
+const BOUNDS_SAVE_DELAY: Duration = Duration::from_millis(500);
+
+lazy_static::lazy_static! {
+    /// Project whose geometry the main window currently shows
+    static ref WINDOW_PROJECT: Mutex<Option<String>> = Mutex::new(None);
+    /// When the main window last moved or resized, while a save is pending
+    static ref LAST_BOUNDS_CHANGE: Mutex<Option<Instant>> = Mutex::new(None);
+}
+
+/// The visible area `bounds` should be shown in: the one containing its
+/// center, else the one it overlaps most, else the first. None if there are
+/// no areas at all.
+fn area_for(bounds: WindowBounds, areas: &[WindowBounds]) -> Option<WindowBounds> {
+    let center_x = i64::from(bounds.x) + i64::from(bounds.width) / 2;
+    let center_y = i64::from(bounds.y) + i64::from(bounds.height) / 2;
+    let contains_center = |a: &&WindowBounds| {
+        (i64::from(a.x)..i64::from(a.x) + i64::from(a.width)).contains(&center_x)
+            && (i64::from(a.y)..i64::from(a.y) + i64::from(a.height)).contains(&center_y)
+    };
+    let overlap = |a: &WindowBounds| {
+        let span = |start: i32, len: u32, other_start: i32, other_len: u32| {
+            let end = (i64::from(start) + i64::from(len))
+                .min(i64::from(other_start) + i64::from(other_len));
+            (end - i64::from(start.max(other_start))).max(0)
+        };
+        span(bounds.x, bounds.width, a.x, a.width) * span(bounds.y, bounds.height, a.y, a.height)
+    };
+
+    areas
+        .iter()
+        .find(contains_center)
+        .or_else(|| {
+            areas
+                .iter()
+                .filter(|a| overlap(a) > 0)
+                .max_by_key(|a| overlap(a))
+        })
+        .or(areas.first())
+        .copied()
+}
+
+/// Shrink `bounds` to fit within `area`, then move it fully inside, so a
+/// window saved on a disconnected or larger screen doesn't open off-screen.
+pub fn clamp_to_visible(bounds: WindowBounds, area: WindowBounds) -> WindowBounds {
+    let width = bounds.width.min(area.width);
+    let height = bounds.height.min(area.height);
+    // Both differences fit in i32 since the sizes are at most the area's
+    let max_x = area.x + (area.width - width) as i32;
+    let max_y = area.y + (area.height - height) as i32;
+    WindowBounds {
+        x: bounds.x.clamp(area.x, max_x),
+        y: bounds.y.clamp(area.y, max_y),
+        width,
+        height,
+    }
+}
+
+fn work_areas(app: &AppHandle) -> Vec<WindowBounds> {
+    app.available_monitors()
+        .unwrap_or_default()
+        .iter()
+        .map(|m| {
+            let area = m.work_area();
+            WindowBounds {
+                x: area.position.x,
+                y: area.position.y,
+                width: area.size.width,
+                height: area.size.height,
+            }
+        })
+        .collect()
+}
+
+/// Give the main window the geometry last saved for `project_id`, kept on a
+/// visible screen, and save future moves and resizes under that project.
+/// Returns the bounds applied, or None if the project has none saved yet.
+pub fn restore_project_bounds(app: &AppHandle, project_id: &str) -> Option<WindowBounds> {
+    *WINDOW_PROJECT.lock() = Some(project_id.to_string());
+
+    let saved = app
+        .try_state::<Arc<AppState>>()?
+        .persisted
+        .read()
+        .window_bounds
+        .get(project_id)
+        .copied()?;
+    let window = app.get_webview_window("main")?;
+    let bounds = match area_for(saved, &work_areas(app)) {
+        Some(area) => clamp_to_visible(saved, area),
+        None => saved,
+    };
+
+    let _ = window.set_size(PhysicalSize::new(bounds.width, bounds.height));
+    let _ = window.set_position(PhysicalPosition::new(bounds.x, bounds.y));
+    Some(bounds)
+}
+
+/// Note that the main window moved or resized. The bounds are saved for the
+/// current project once changes stop for `BOUNDS_SAVE_DELAY`.
+pub fn main_window_bounds_changed(app: &AppHandle) {
+    let mut last = LAST_BOUNDS_CHANGE.lock();
+    let pending = last.is_some();
+    *last = Some(Instant::now());
+    if pending {
+        return;
+    }
+
+    let app = app.clone();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(BOUNDS_SAVE_DELAY);
+        let mut last = LAST_BOUNDS_CHANGE.lock();
+        if last.is_some_and(|at| at.elapsed() >= BOUNDS_SAVE_DELAY) {
+            *last = None;
+            drop(last);
+            save_main_window_bounds(&app);
+            return;
+        }
+    });
+}
+
+fn save_main_window_bounds(app: &AppHandle) {
+    let Some(project_id) = WINDOW_PROJECT.lock().clone() else {
+        return;
+    };
+    let (Some(window), Some(state)) = (
+        app.get_webview_window("main"),
+        app.try_state::<Arc<AppState>>(),
+    ) else {
+        return;
+    };
+    // A maximized or minimized window's bounds aren't a geometry to restore
+    if window.is_maximized().unwrap_or(false) || window.is_minimized().unwrap_or(false) {
+        return;
+    }
+    let (Ok(position), Ok(size)) = (window.outer_position(), window.inner_size()) else {
+        return;
+    };
+
+    let bounds = WindowBounds {
+        x: position.x,
+        y: position.y,
+        width: size.width,
+        height: size.height,
+    };
+    let changed = state
+        .persisted
+        .write()
+        .window_bounds
+        .insert(project_id, bounds)
+        != Some(bounds);
+    if changed {
+        if let Err(e) = state.save() {
+            log::warn!("[windows] Failed to save window bounds: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    /// Tracks open window labels and which one was focused last.
+    #[derive(Default)]
+    struct FakeHost {
+        open: RefCell<Vec<&'static str>>,
+        focused: RefCell<Option<String>>,
+    }
+
+    impl WindowHost for FakeHost {
+        fn focus_existing(&self, label: &str) -> bool {
+            if !self.open.borrow().contains(&label) {
+                return false;
+            }
+            *self.focused.borrow_mut() = Some(label.to_string());
+            true
+        }
+
+        fn create(&self, window: AuxWindow) -> tauri::Result<()> {
+            self.open.borrow_mut().push(window.label());
+            *self.focused.borrow_mut() = Some(window.label().to_string());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn second_open_focuses_instead_of_creating() {
+        let host = FakeHost::default();
+
+        assert_eq!(
+            open_or_focus(&host, AuxWindow::Settings).unwrap(),
+            WindowOutcome::Created
+        );
+        assert_eq!(
+            open_or_focus(&host, AuxWindow::Mappings).unwrap(),
+            WindowOutcome::Created
+        );
+        assert_eq!(host.focused.borrow().as_deref(), Some("mappings"));
+
+        assert_eq!(
+            open_or_focus(&host, AuxWindow::Settings).unwrap(),
+            WindowOutcome::Focused
+        );
+        assert_eq!(host.focused.borrow().as_deref(), Some("settings"));
+        assert_eq!(*host.open.borrow(), vec!["settings", "mappings"]);
+    }
+
+    fn bounds(x: i32, y: i32, width: u32, height: u32) -> WindowBounds {
+        WindowBounds {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn off_screen_bounds_are_pulled_into_the_visible_area() {
+        let screen = bounds(0, 25, 1440, 875);
+
+        // Already visible: unchanged
+        assert_eq!(
+            clamp_to_visible(bounds(100, 100, 800, 600), screen),
+            bounds(100, 100, 800, 600)
+        );
+        // Past the right and bottom edges
+        assert_eq!(
+            clamp_to_visible(bounds(2000, 1200, 800, 600), screen),
+            bounds(640, 300, 800, 600)
+        );
+        // Above the menu bar and left of the screen
+        assert_eq!(
+            clamp_to_visible(bounds(-500, -40, 800, 600), screen),
+            bounds(0, 25, 800, 600)
+        );
+        // Saved on a bigger screen: shrunk to fit
+        assert_eq!(
+            clamp_to_visible(bounds(-10, 0, 2560, 1400), screen),
+            bounds(0, 25, 1440, 875)
+        );
+    }
+
+    #[test]
+    fn bounds_use_the_screen_they_were_on_if_still_connected() {
+        let left = bounds(0, 0, 1440, 900);
+        let right = bounds(1440, 0, 1920, 1080);
+
+        assert_eq!(
+            area_for(bounds(2000, 100, 800, 600), &[left, right]),
+            Some(right)
+        );
+        // Straddling both: the one holding the center wins
+        assert_eq!(
+            area_for(bounds(1200, 100, 800, 600), &[left, right]),
+            Some(right)
+        );
+        assert_eq!(
+            area_for(bounds(1000, 100, 800, 600), &[left, right]),
+            Some(left)
+        );
+        // The right screen was disconnected
+        let saved = bounds(2000, 100, 800, 600);
+        let area = area_for(saved, &[left]).unwrap();
+        assert_eq!(clamp_to_visible(saved, area), bounds(640, 100, 800, 600));
+        // Partly visible on a screen but centered off it
+        assert_eq!(
+            area_for(bounds(-700, 100, 800, 600), &[right, left]),
+            Some(left)
+        );
+        assert_eq!(area_for(saved, &[]), None);
+    }
+
+    #[test]
+    fn only_settings_and_mappings_are_native() {
+        assert_eq!(
+            AuxWindow::from_action("app::openSettings"),
+            Some(AuxWindow::Settings)
+        );
+        assert_eq!(
+            AuxWindow::from_action("app::openMappings"),
+            Some(AuxWindow::Mappings)
+        );
+        assert_eq!(AuxWindow::from_action("app::quit"), None);
+    }
+}
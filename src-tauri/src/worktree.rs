@@ -1,8 +1,12 @@
-use crate::config::BaseBranch;
+use crate::config::{BaseBranch, WorktreeConfig};
 use crate::git;
-use crate::state::{Project, Worktree};
+use crate::state::{FileChange, FileStatus, Project, Worktree};
 use crate::template::{expand_template, TemplateContext};
+use crate::time;
 use log::info;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::time::Instant;
 use thiserror::Error;
@@ -18,10 +22,61 @@ pub enum WorktreeError {
     NotARepository,
     #[error("Worktree not found: {0}")]
     WorktreeNotFound(String),
+    #[error("Project not found: {0}")]
+    ProjectNotFound(String),
     #[error("Could not generate unique branch name after {0} attempts")]
     NameGenerationFailed(u32),
     #[error("Template error: {0}")]
     Template(String),
+    #[error("Not a directory: {0}")]
+    NotADirectory(String),
+    #[error("Invalid worktree layout: {0}")]
+    InvalidLayout(String),
+    #[error("Directory is not writable: {0}")]
+    NotWritable(String),
+    #[error("Worktree order doesn't match the project: {0}")]
+    OrderMismatch(String),
+    #[error("Branch is already checked out at {0}")]
+    BranchInUse(String),
+    #[error("Not part of this project's repository: {0}")]
+    ForeignRepository(String),
+    #[error("No uncommitted work to move out of {0}")]
+    NothingToPromote(String),
+    #[error("Work couldn't be moved or put back, it is kept in stash \"{stash_id}\": {reason}")]
+    StashKept { stash_id: String, reason: String },
+    #[error("Worktrees of {project_path} would be created inside project {nested_in}: {base_dir}")]
+    BaseDirInsideProject {
+        project_path: String,
+        base_dir: String,
+        /// Id and path of the open project the worktrees would land in
+        nested_in_id: String,
+        nested_in: String,
+    },
+}
+
+impl WorktreeError {
+    /// Stable machine-readable identifier for the frontend to localize.
+    /// Wrapped git errors keep the underlying git code so the cause isn't lost.
+    pub fn code(&self) -> &'static str {
+        match self {
+            WorktreeError::Git(e) => e.code(),
+            WorktreeError::Io(_) => "worktree.io",
+            WorktreeError::NotARepository => "worktree.not_a_repository",
+            WorktreeError::WorktreeNotFound(_) => "worktree.not_found",
+            WorktreeError::ProjectNotFound(_) => "worktree.project_not_found",
+            WorktreeError::NameGenerationFailed(_) => "worktree.name_generation_failed",
+            WorktreeError::Template(_) => "worktree.template",
+            WorktreeError::NotADirectory(_) => "worktree.not_a_directory",
+            WorktreeError::InvalidLayout(_) => "worktree.invalid_layout",
+            WorktreeError::NotWritable(_) => "worktree.not_writable",
+            WorktreeError::OrderMismatch(_) => "worktree.order_mismatch",
+            WorktreeError::BranchInUse(_) => "worktree.branch_in_use",
+            WorktreeError::ForeignRepository(_) => "worktree.foreign_repository",
+            WorktreeError::NothingToPromote(_) => "worktree.nothing_to_promote",
+            WorktreeError::StashKept { .. } => "worktree.stash_kept",
+            WorktreeError::BaseDirInsideProject { .. } => "worktree.base_dir_inside_project",
+        }
+    }
 }
 
 /// Generate a random worktree name using petname (adjective-animal format)
@@ -95,46 +150,257 @@ pub fn resolve_worktree_directory(
     Ok(path)
 }
 
+/// Canonicalize a path chosen or dropped by the user so the same project
+/// always gets the same path: symlinks and trailing slashes are resolved,
+/// and anything that isn't a directory is rejected.
+pub fn normalize_project_path(input: &Path) -> Result<PathBuf, WorktreeError> {
+    let path = input.canonicalize()?;
+    if !path.is_dir() {
+        return Err(WorktreeError::NotADirectory(input.to_string_lossy().to_string()));
+    }
+    Ok(path)
+}
+
+/// Canonical form for comparing paths. Symlinks are resolved through the
+/// deepest part that exists, so a deleted file still lines up with its
+/// worktree; a path with no existing part is returned as is.
+pub fn canonical(path: &Path) -> PathBuf {
+    if let Ok(resolved) = path.canonicalize() {
+        return resolved;
+    }
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) if !parent.as_os_str().is_empty() => {
+            canonical(parent).join(name)
+        }
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Whether a worktree's recorded path is itself a symlink to its directory
+pub fn is_symlinked(worktree: &Worktree) -> bool {
+    std::fs::symlink_metadata(&worktree.path).is_ok_and(|m| m.file_type().is_symlink())
+}
+
+/// The worktree containing `file`, comparing canonical paths so symlinked
+/// worktrees and files reached through a symlink still match. The innermost
+/// worktree wins when worktrees are nested.
+pub fn find_by_path<'a>(projects: &'a [Project], file: &Path) -> Option<&'a Worktree> {
+    let file = canonical(file);
+    projects
+        .iter()
+        .flat_map(|p| &p.worktrees)
+        .map(|wt| (wt, canonical(Path::new(&wt.path))))
+        .filter(|(_, root)| file.starts_with(root))
+        .max_by_key(|(_, root)| root.components().count())
+        .map(|(wt, _)| wt)
+}
+
+/// File stems of project docs, in the order they are offered
+const DOC_FILES: [&str; 2] = ["readme", "contributing"];
+const DOC_DIRS: [&str; 2] = ["docs", "doc"];
+
+/// Documentation at the root of a project: README and CONTRIBUTING files in
+/// any format (README.md, readme.rst, ...), then a docs directory. Empty if
+/// the project has none or its directory is gone.
+pub fn find_docs(project: &Project) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(&project.path) else {
+        return Vec::new();
+    };
+    let mut docs: Vec<(usize, PathBuf)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().to_lowercase();
+            let rank = if path.is_dir() {
+                DOC_FILES.len() + DOC_DIRS.iter().position(|dir| *dir == name)?
+            } else {
+                let stem = name.split('.').next().unwrap_or_default();
+                DOC_FILES.iter().position(|file| *file == stem)?
+            };
+            Some((rank, path))
+        })
+        .collect();
+    docs.sort();
+    docs.into_iter().map(|(_, path)| path).collect()
+}
+
 pub fn create_project(path: &Path) -> Result<Project, WorktreeError> {
-    if !git::is_git_repo(path) {
+    let path = normalize_project_path(path)?;
+    if !git::is_git_repo(&path) {
         return Err(WorktreeError::NotARepository);
     }
 
     Ok(Project {
         id: Uuid::new_v4().to_string(),
-        name: git::get_repo_name(path),
+        name: git::get_repo_name(&path),
         path: path.to_string_lossy().to_string(),
         worktrees: vec![],
         order: 0,
         is_active: true,
         last_accessed_at: Some(chrono_lite_now()),
+        worktree_layout: None,
+    })
+}
+
+/// Resolve a per-project worktree layout to the full path of worktree `name`.
+///
+/// `{repo}` is the repository directory and `{name}` the worktree name, so
+/// `{repo}-worktrees/{name}` puts worktrees next to the main checkout. A
+/// leading `~/` expands to the home directory. The result must be absolute.
+pub fn resolve_layout(
+    layout: &str,
+    project_path: &Path,
+    name: &str,
+) -> Result<PathBuf, WorktreeError> {
+    if !layout.contains("{name}") {
+        return Err(WorktreeError::InvalidLayout(format!(
+            "{} must contain {{name}}",
+            layout
+        )));
+    }
+
+    let resolved = layout
+        .replace("{repo}", &project_path.to_string_lossy())
+        .replace("{name}", name);
+    let path = match resolved.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir()
+            .ok_or_else(|| WorktreeError::InvalidLayout("No home directory".into()))?
+            .join(rest),
+        None => PathBuf::from(resolved),
+    };
+
+    if !path.is_absolute() {
+        return Err(WorktreeError::InvalidLayout(format!(
+            "{} does not resolve to an absolute path",
+            layout
+        )));
+    }
+    // Collapse `..` so `{repo}/../x` is stored and compared as a clean path
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            std::path::Component::ParentDir => {
+                normalized.pop();
+            }
+            std::path::Component::CurDir => {}
+            other => normalized.push(other),
+        }
+    }
+    Ok(normalized)
+}
+
+/// Check a layout resolves to an absolute location we can create worktrees in.
+/// Writability is probed at the closest directory that already exists.
+pub fn validate_layout(layout: &str, project_path: &Path) -> Result<(), WorktreeError> {
+    let sample = resolve_layout(layout, project_path, "shellflow-layout-check")?;
+    let parent = sample
+        .parent()
+        .ok_or_else(|| WorktreeError::InvalidLayout(format!("{} has no parent", layout)))?;
+
+    check_writable(parent).map_err(|e| match e {
+        WorktreeError::NotWritable(reason) => WorktreeError::InvalidLayout(reason),
+        other => other,
     })
 }
 
+/// Check that no open project creates its worktrees inside another open
+/// project, where that project's watcher would see all of their churn. A
+/// project's own worktree directory is fine: its watcher excludes it.
+/// Reports the first such project and the project it lands in.
+pub fn validate_base_dir(projects: &[Project]) -> Result<(), WorktreeError> {
+    let open: Vec<&Project> = projects.iter().filter(|p| p.is_active).collect();
+    for project in &open {
+        let project_path = Path::new(&project.path);
+        let base_dir = match &project.worktree_layout {
+            Some(layout) => resolve_layout(layout, project_path, "shellflow-layout-check")
+                .ok()
+                .and_then(|path| path.parent().map(Path::to_path_buf)),
+            None => {
+                let cfg = crate::config::load_config_for_project(Some(&project.path));
+                let directory = cfg.worktree.directory.as_deref();
+                resolve_worktree_directory(directory, project_path, None, None).ok()
+            }
+        };
+        let Some(base_dir) = base_dir else {
+            continue;
+        };
+
+        let nested_in = open
+            .iter()
+            .filter(|other| other.id != project.id)
+            .find(|other| base_dir.starts_with(&other.path));
+        if let Some(other) = nested_in {
+            return Err(WorktreeError::BaseDirInsideProject {
+                project_path: project.path.clone(),
+                base_dir: base_dir.to_string_lossy().to_string(),
+                nested_in_id: other.id.clone(),
+                nested_in: other.path.clone(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Check we can create files in `path`, or in the closest directory above it
+/// that exists when `path` hasn't been created yet. Catches read-only or
+/// foreign-owned directories up front instead of failing partway through.
+pub fn check_writable(path: &Path) -> Result<(), WorktreeError> {
+    let existing = path.ancestors().find(|p| p.is_dir()).ok_or_else(|| {
+        WorktreeError::NotWritable(format!("{} has no existing parent", path.display()))
+    })?;
+
+    let probe = existing.join(format!(".shellflow-write-test-{}", Uuid::new_v4()));
+    std::fs::write(&probe, b"").map_err(|e| {
+        WorktreeError::NotWritable(format!("{} is not writable: {}", existing.display(), e))
+    })?;
+    let _ = std::fs::remove_file(&probe);
+    Ok(())
+}
+
+/// What a new branch worktree starts from
+#[derive(Debug, Clone, Copy)]
+pub enum WorktreeStart<'a> {
+    /// The branch picked by the base branch policy
+    Base(&'a BaseBranch),
+    /// An explicit committish (branch, tag, sha...)
+    Commit(&'a str),
+}
+
 pub fn create_worktree(
     project: &mut Project,
     name: Option<String>,
     worktree_directory: Option<&str>,
     base_branch: &BaseBranch,
+) -> Result<Worktree, WorktreeError> {
+    create_worktree_from(
+        project,
+        name,
+        worktree_directory,
+        WorktreeStart::Base(base_branch),
+    )
+}
+
+/// Create a branch worktree starting from `start_from`
+pub fn create_worktree_from(
+    project: &mut Project,
+    name: Option<String>,
+    worktree_directory: Option<&str>,
+    start_from: WorktreeStart,
 ) -> Result<Worktree, WorktreeError> {
     let total_start = Instant::now();
     info!("[worktree::create_worktree] Starting...");
 
-    let project_path = Path::new(&project.path);
-    let worktree_name = match name {
-        Some(n) => n,
-        None => generate_unique_worktree_name(project_path)?,
-    };
+    let (worktree_name, worktree_path) = worktree_target(project, name, worktree_directory)?;
     info!("[worktree::create_worktree] worktree_name: {}", worktree_name);
+    let project_path = Path::new(&project.path);
+    check_branch_available(project_path, &worktree_name)?;
+    let worktree_base = worktree_path.parent().unwrap_or(project_path).to_path_buf();
 
-    // Create worktree directory using template expansion
-    let worktree_base = resolve_worktree_directory(
-        worktree_directory,
-        project_path,
-        Some(&worktree_name), // branch name is the same as worktree name
-        Some(&worktree_name),
-    )?;
-    let worktree_path = worktree_base.join(&worktree_name);
+    if let Some(warning) = nesting_warning(project_path, &worktree_base) {
+        log::warn!("[worktree::create_worktree] {}", warning);
+    }
+
+    check_writable(&worktree_base)?;
 
     let start = Instant::now();
     std::fs::create_dir_all(&worktree_base)?;
@@ -142,7 +408,17 @@ pub fn create_worktree(
 
     // Create git worktree
     let start = Instant::now();
-    git::create_worktree(project_path, &worktree_path, &worktree_name, base_branch)?;
+    match start_from {
+        WorktreeStart::Base(base_branch) => {
+            git::create_worktree(project_path, &worktree_path, &worktree_name, base_branch)?
+        }
+        WorktreeStart::Commit(start_point) => git::create_worktree_with_start(
+            project_path,
+            &worktree_path,
+            &worktree_name,
+            Some(start_point),
+        )?,
+    }
     info!("[worktree::create_worktree] git::create_worktree took {:?}", start.elapsed());
 
     let worktree = Worktree {
@@ -152,6 +428,10 @@ pub fn create_worktree(
         branch: worktree_name,
         created_at: chrono_lite_now(),
         order: project.worktrees.len() as i32,
+        detached: false,
+        changed_files_view_mode: Default::default(),
+        show_ignored_files: false,
+        last_accessed_at: None,
     };
 
     project.worktrees.push(worktree.clone());
@@ -160,6 +440,227 @@ pub fn create_worktree(
     Ok(worktree)
 }
 
+/// With `autoCreateFirstWorktree` on, give a project that has no worktrees
+/// one with a generated name. Skipped (None) when the setting is off, the
+/// project already has worktrees, or the repository has no commits to
+/// branch from.
+pub fn auto_create_first_worktree(
+    project: &mut Project,
+    cfg: &WorktreeConfig,
+) -> Result<Option<Worktree>, WorktreeError> {
+    if !cfg.auto_create_first_worktree || !project.worktrees.is_empty() {
+        return Ok(None);
+    }
+    let project_path = Path::new(&project.path);
+    if !git::is_git_repo(project_path) || git::last_commit(project_path)?.is_none() {
+        info!("[worktree::auto_create_first_worktree] Skipped: {} has no commits", project.path);
+        return Ok(None);
+    }
+
+    create_worktree(project, None, cfg.directory.as_deref(), &cfg.base_branch).map(Some)
+}
+
+/// Fail with `BranchInUse` if `branch` is checked out in any worktree of the
+/// repo, including the main checkout, rather than leaving git to reject it
+pub fn check_branch_available(project_path: &Path, branch: &str) -> Result<(), WorktreeError> {
+    match git::worktree_for_branch(project_path, branch)? {
+        Some(path) => Err(WorktreeError::BranchInUse(path.to_string_lossy().to_string())),
+        None => Ok(()),
+    }
+}
+
+/// Turn the uncommitted work in a scratch terminal's directory into a tracked
+/// worktree on a new branch `name`. The scratch directory must be a checkout
+/// of the project's repository (typically the main checkout); the new
+/// worktree starts at its HEAD and its changes, untracked files included,
+/// move across, leaving the scratch directory clean.
+pub fn promote_scratch_to_worktree(
+    project: &mut Project,
+    scratch_dir: &Path,
+    name: String,
+    worktree_directory: Option<&str>,
+) -> Result<Worktree, WorktreeError> {
+    let scratch = scratch_dir.to_string_lossy().to_string();
+    let project_path = Path::new(&project.path);
+    let scratch_repo = git::common_dir(scratch_dir).map_err(|_| WorktreeError::NotARepository)?;
+    if scratch_repo != git::common_dir(project_path)? {
+        return Err(WorktreeError::ForeignRepository(scratch));
+    }
+    if !git::has_uncommitted_changes_at_path(scratch_dir)? {
+        return Err(WorktreeError::NothingToPromote(scratch));
+    }
+    let start_point = git::last_commit(scratch_dir)?
+        .map(|commit| commit.sha)
+        .ok_or_else(|| WorktreeError::NothingToPromote(scratch.clone()))?;
+    check_branch_available(project_path, &name)?;
+
+    // Stashes are shared by all worktrees of a repository, so the work can
+    // be stashed here and popped in the new worktree
+    let stash_id = git::stash_changes(scratch_dir)?;
+    let created = create_worktree_from(
+        project,
+        Some(name),
+        worktree_directory,
+        WorktreeStart::Commit(&start_point),
+    );
+    let worktree = match created {
+        Ok(worktree) => worktree,
+        Err(e) => {
+            git::stash_pop(scratch_dir, &stash_id)?;
+            return Err(e);
+        }
+    };
+    if let Err(e) = git::stash_pop(Path::new(&worktree.path), &stash_id) {
+        // Undo the promotion: no worktree or branch, the work back where it was
+        let project_path = PathBuf::from(&project.path);
+        let rolled_back = delete_worktree(project, &worktree.id)
+            .and_then(|_| Ok(git::delete_local_branch(&project_path, &worktree.branch)?))
+            .and_then(|_| Ok(git::stash_pop(scratch_dir, &stash_id)?));
+        return Err(match rolled_back {
+            Ok(()) => e.into(),
+            Err(rollback) => WorktreeError::StashKept {
+                stash_id,
+                reason: format!("{}; then {}", e, rollback),
+            },
+        });
+    }
+
+    info!("[worktree::promote_scratch] Moved work from {} to {}", scratch, worktree.path);
+    Ok(worktree)
+}
+
+/// Name and path for a new branch worktree, generating a name if none is
+/// given. The project's layout wins over the configured directory, which
+/// puts the worktree in `{directory}/{name}`.
+fn worktree_target(
+    project: &Project,
+    name: Option<String>,
+    worktree_directory: Option<&str>,
+) -> Result<(String, PathBuf), WorktreeError> {
+    let project_path = Path::new(&project.path);
+    let worktree_name = match name {
+        Some(n) => n,
+        None => generate_unique_worktree_name(project_path)?,
+    };
+
+    // Branch name is the same as worktree name; the directory is a flat version of it
+    let path_for = |dir_name: &str| match &project.worktree_layout {
+        Some(layout) => resolve_layout(layout, project_path, dir_name),
+        None => Ok(resolve_worktree_directory(
+            worktree_directory,
+            project_path,
+            Some(&worktree_name),
+            Some(&worktree_name),
+        )?
+        .join(dir_name)),
+    };
+
+    // Different branches can flatten to the same name (`a/b` and `a-b`), so
+    // number the directory if another branch's worktree already has it
+    let dir_name = branch_to_dir_name(&worktree_name);
+    let mut worktree_path = path_for(&dir_name)?;
+    let mut n = 1;
+    while project
+        .worktrees
+        .iter()
+        .any(|w| w.branch != worktree_name && Path::new(&w.path) == worktree_path)
+    {
+        n += 1;
+        worktree_path = path_for(&format!("{}-{}", dir_name, n))?;
+    }
+    Ok((worktree_name, worktree_path))
+}
+
+/// A flat directory name for a branch: `feature/login` becomes
+/// `feature-login`. Path separators and characters that aren't safe in file
+/// names become `-`, runs of `-` collapse, and leading dots (hidden
+/// directories, `..`) and trailing dots are dropped. Unicode letters and
+/// digits are kept.
+pub fn branch_to_dir_name(branch: &str) -> String {
+    let mut name = String::with_capacity(branch.len());
+    for c in branch.chars() {
+        let safe = c.is_alphanumeric() || matches!(c, '.' | '_' | '-');
+        let c = if safe { c } else { '-' };
+        if !(c == '-' && name.ends_with('-')) {
+            name.push(c);
+        }
+    }
+
+    let name = name.trim_matches(|c| c == '-' || c == '.');
+    if name.is_empty() {
+        "worktree".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+/// Where `create_worktree` would put a worktree, resolved without creating anything
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorktreePathPreview {
+    /// The given name, or the one generated for it
+    pub name: String,
+    pub path: PathBuf,
+    /// Something is already at `path`, so creating there would fail
+    pub exists: bool,
+}
+
+/// Resolve the path `create_worktree` would use for `name`. When no name is
+/// given one is generated; pass it back to `create_worktree` to land on the
+/// previewed path.
+pub fn preview_worktree_path(
+    project: &Project,
+    name: Option<String>,
+    worktree_directory: Option<&str>,
+) -> Result<WorktreePathPreview, WorktreeError> {
+    let (name, path) = worktree_target(project, name, worktree_directory)?;
+    let exists = path.symlink_metadata().is_ok();
+    Ok(WorktreePathPreview { name, path, exists })
+}
+
+/// Create a worktree at the project's HEAD without creating a branch.
+pub fn create_detached_worktree(
+    project: &mut Project,
+    worktree_directory: Option<&str>,
+) -> Result<Worktree, WorktreeError> {
+    let project_path = Path::new(&project.path);
+    let worktree_name = generate_unique_worktree_name(project_path)?;
+
+    let worktree_path = match &project.worktree_layout {
+        Some(layout) => resolve_layout(layout, project_path, &worktree_name)?,
+        None => resolve_worktree_directory(
+            worktree_directory,
+            project_path,
+            None,
+            Some(&worktree_name),
+        )?
+        .join(&worktree_name),
+    };
+
+    if let Some(parent) = worktree_path.parent() {
+        check_writable(parent)?;
+        std::fs::create_dir_all(parent)?;
+    }
+    git::create_detached_worktree(project_path, &worktree_path)?;
+
+    let worktree = Worktree {
+        id: Uuid::new_v4().to_string(),
+        name: worktree_name,
+        path: worktree_path.to_string_lossy().to_string(),
+        branch: String::new(),
+        created_at: chrono_lite_now(),
+        order: project.worktrees.len() as i32,
+        detached: true,
+        changed_files_view_mode: Default::default(),
+        show_ignored_files: false,
+        last_accessed_at: None,
+    };
+
+    project.worktrees.push(worktree.clone());
+
+    Ok(worktree)
+}
+
 /// Copy gitignored files from the project to the worktree, excluding patterns in `except`
 pub fn copy_gitignored_files(
     project_path: &Path,
@@ -259,12 +760,20 @@ pub fn delete_worktree(project: &mut Project, worktree_id: &str) -> Result<(), W
 
     let worktree = &project.worktrees[worktree_idx];
 
+    // Removing the directory needs write access to it and to its parent
+    let worktree_path = Path::new(&worktree.path);
+    if worktree_path.is_dir() {
+        check_writable(worktree_path)?;
+        if let Some(parent) = worktree_path.parent() {
+            check_writable(parent)?;
+        }
+    }
+
     // Delete worktree
     let project_path = Path::new(&project.path);
     git::delete_worktree(project_path, &worktree.name)?;
 
     // Remove worktree directory if it still exists
-    let worktree_path = Path::new(&worktree.path);
     if worktree_path.exists() {
         std::fs::remove_dir_all(worktree_path)?;
     }
@@ -274,92 +783,645 @@ pub fn delete_worktree(project: &mut Project, worktree_id: &str) -> Result<(), W
     Ok(())
 }
 
-// Simple timestamp without external chrono dependency
-pub fn chrono_lite_now() -> String {
-    use std::time::{SystemTime, UNIX_EPOCH};
-    let duration = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default();
-    let secs = duration.as_secs();
-
-    // Convert to ISO-8601-ish format (simplified)
-    let days_since_1970 = secs / 86400;
-    let years = 1970 + days_since_1970 / 365;
-    let remaining_days = days_since_1970 % 365;
-    let month = (remaining_days / 30) + 1;
-    let day = (remaining_days % 30) + 1;
-    let hour = (secs % 86400) / 3600;
-    let min = (secs % 3600) / 60;
-    let sec = secs % 60;
-
-    format!(
-        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
-        years, month, day, hour, min, sec
-    )
+/// Put a project's worktrees in the given order, which must list each of
+/// them exactly once. The `order` fields are rewritten so the order survives
+/// a reload.
+pub fn reorder_worktrees(
+    project: &mut Project,
+    ordered_ids: &[String],
+) -> Result<(), WorktreeError> {
+    let mut seen = HashSet::new();
+    for id in ordered_ids {
+        if !seen.insert(id.as_str()) {
+            return Err(WorktreeError::OrderMismatch(format!("{} is listed twice", id)));
+        }
+        if !project.worktrees.iter().any(|w| &w.id == id) {
+            return Err(WorktreeError::WorktreeNotFound(id.clone()));
+        }
+    }
+    if let Some(missing) = project.worktrees.iter().find(|w| !seen.contains(w.id.as_str())) {
+        return Err(WorktreeError::OrderMismatch(format!("{} is missing", missing.id)));
+    }
+
+    for worktree in &mut project.worktrees {
+        worktree.order = ordered_ids.iter().position(|id| id == &worktree.id).unwrap_or(0) as i32;
+    }
+    project.worktrees.sort_by_key(|w| w.order);
+    Ok(())
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::path::PathBuf;
+/// The changed file in a worktree that was modified most recently on disk,
+/// or None if nothing has changed. Deleted files and submodules are skipped.
+pub fn last_changed_file(worktree_path: &Path) -> Result<Option<PathBuf>, WorktreeError> {
+    let changes = git::get_changed_files(worktree_path, false, false)?;
 
-    #[test]
-    fn test_generate_worktree_name_format() {
-        let name = generate_worktree_name();
-        // Should be in adjective-animal format with hyphen separator
-        assert!(name.contains('-'), "Name should contain hyphen: {}", name);
-        let parts: Vec<&str> = name.split('-').collect();
-        assert_eq!(parts.len(), 2, "Name should have exactly 2 parts: {}", name);
-        assert!(!parts[0].is_empty(), "First part should not be empty");
-        assert!(!parts[1].is_empty(), "Second part should not be empty");
+    let latest = changes
+        .iter()
+        .filter(|c| c.status != FileStatus::Deleted && c.submodule.is_none())
+        .filter_map(|c| {
+            let path = worktree_path.join(&c.path);
+            let metadata = std::fs::metadata(&path).ok().filter(|m| m.is_file())?;
+            Some((metadata.modified().ok()?, path))
+        })
+        // Ties go to the first path alphabetically so the answer is stable
+        .max_by(|(a_time, a_path), (b_time, b_path)| {
+            a_time.cmp(b_time).then_with(|| b_path.cmp(a_path))
+        });
+    Ok(latest.map(|(_, path)| path))
+}
+
+/// Output format of an exported change list
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Json,
+    Csv,
+}
+
+/// The worktree's changed files, as shown in its current view mode, formatted
+/// for pasting into a report.
+pub fn export_change_list(
+    projects: &[Project],
+    worktree_id: &str,
+    base_branch: &BaseBranch,
+    include_submodules: bool,
+    format: ExportFormat,
+) -> Result<String, WorktreeError> {
+    let worktree = projects
+        .iter()
+        .flat_map(|p| p.worktrees.iter())
+        .find(|w| w.id == worktree_id)
+        .ok_or_else(|| WorktreeError::WorktreeNotFound(worktree_id.to_string()))?;
+
+    let changes = git::get_changed_files_for_mode(
+        Path::new(&worktree.path),
+        worktree.changed_files_view_mode,
+        base_branch,
+        include_submodules,
+        worktree.show_ignored_files,
+    )?;
+    format_change_list(&changes, format)
+}
+
+/// A worktree's state at a glance, for the sidebar
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusSummary {
+    /// Files with uncommitted changes, untracked and conflicted ones included
+    pub changed_files: usize,
+    /// Commits ahead of the upstream; 0 without one
+    pub ahead: usize,
+    /// Commits behind the upstream; 0 without one
+    pub behind: usize,
+    pub operation: Option<git::Operation>,
+}
+
+pub fn status_summary(worktree_path: &Path) -> Result<StatusSummary, WorktreeError> {
+    let (ahead, behind) = git::ahead_behind_upstream(worktree_path)?.unwrap_or((0, 0));
+    Ok(StatusSummary {
+        changed_files: git::uncommitted_file_count(worktree_path)?,
+        ahead,
+        behind,
+        operation: git::operation_in_progress(worktree_path)?,
+    })
+}
+
+/// Compact badge for a worktree, e.g. `●3↑2↓1`: `●` changed files, `↑`
+/// commits ahead, `↓` commits behind, each left out when 0, followed by any
+/// operation in progress (`●1 rebase`). `plain` swaps the glyphs for ASCII
+/// (`*3 +2 -1`). Empty for a clean worktree in sync with its upstream.
+pub fn format_badge(summary: &StatusSummary, plain: bool) -> String {
+    let (dirty, ahead, behind) = if plain { ("*", "+", "-") } else { ("●", "↑", "↓") };
+    // Glyphs are distinct enough to run together; ASCII signs need spacing
+    let separator = if plain { " " } else { "" };
+    let counts = [
+        (dirty, summary.changed_files),
+        (ahead, summary.ahead),
+        (behind, summary.behind),
+    ]
+    .into_iter()
+    .filter(|(_, count)| *count > 0)
+    .map(|(glyph, count)| format!("{}{}", glyph, count))
+    .collect::<Vec<_>>()
+    .join(separator);
+
+    let operation = summary.operation.map(|op| op.as_str().to_string());
+    Some(counts)
+        .filter(|counts| !counts.is_empty())
+        .into_iter()
+        .chain(operation)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The badge for a worktree looked up by id (see `format_badge`)
+pub fn status_badge(
+    projects: &[Project],
+    worktree_id: &str,
+    plain: bool,
+) -> Result<String, WorktreeError> {
+    let worktree = projects
+        .iter()
+        .flat_map(|p| p.worktrees.iter())
+        .find(|w| w.id == worktree_id)
+        .ok_or_else(|| WorktreeError::WorktreeNotFound(worktree_id.to_string()))?;
+    let summary = status_summary(Path::new(&worktree.path))?;
+    Ok(format_badge(&summary, plain))
+}
+
+/// Render changes as a JSON array or as CSV with a header row.
+/// Line counts are empty (CSV) or null (JSON) when git didn't report them.
+pub fn format_change_list(
+    changes: &[FileChange],
+    format: ExportFormat,
+) -> Result<String, WorktreeError> {
+    match format {
+        ExportFormat::Json => {
+            let rows: Vec<_> = changes
+                .iter()
+                .map(|c| {
+                    serde_json::json!({
+                        "path": c.path,
+                        "status": c.status,
+                        "insertions": c.insertions,
+                        "deletions": c.deletions,
+                    })
+                })
+                .collect();
+            serde_json::to_string_pretty(&rows).map_err(|e| WorktreeError::Io(e.into()))
+        }
+        ExportFormat::Csv => {
+            let count = |n: Option<usize>| n.map(|n| n.to_string()).unwrap_or_default();
+            let mut csv = String::from("path,status,insertions,deletions\n");
+            for c in changes {
+                csv.push_str(&format!(
+                    "{},{},{},{}\n",
+                    csv_field(&c.path),
+                    c.status.as_str(),
+                    count(c.insertions),
+                    count(c.deletions)
+                ));
+            }
+            Ok(csv)
+        }
     }
+}
 
-    #[test]
-    fn test_generate_worktree_name_uniqueness() {
-        // Generate several names and ensure they're not all the same
-        let names: Vec<String> = (0..10).map(|_| generate_worktree_name()).collect();
-        let unique_count = names.iter().collect::<std::collections::HashSet<_>>().len();
-        // With random generation, we should get mostly unique names
-        assert!(unique_count > 1, "Names should have some variety");
+/// Quote a CSV field when it contains a separator, quote or line break (RFC 4180)
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
     }
+}
 
-    #[test]
-    fn test_resolve_worktree_directory_default() {
-        let project_path = PathBuf::from("/home/user/myproject");
-        let result = resolve_worktree_directory(None, &project_path, None, None).unwrap();
-        assert_eq!(result, PathBuf::from("/home/user/myproject/.worktrees"));
+/// Summary of a project's worktrees for the project dashboard
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectStats {
+    pub worktree_count: usize,
+    pub total_disk_bytes: u64,
+    /// Worktrees with uncommitted changes (including untracked files)
+    pub dirty_count: usize,
+}
+
+// Stats are expensive to compute (full directory walks), so keep the last
+// result per project until something relevant changes.
+lazy_static::lazy_static! {
+    static ref PROJECT_STATS: Mutex<HashMap<String, (Vec<String>, ProjectStats)>> =
+        Mutex::new(HashMap::new());
+    // Worktree id -> (path, bytes), invalidated alongside the project stats
+    static ref WORKTREE_DISK_USAGE: Mutex<HashMap<String, (String, u64)>> =
+        Mutex::new(HashMap::new());
+}
+
+/// Per-worktree work (directory walks, git queries) runs on at most this many
+/// threads at once, so a large project doesn't saturate the disk
+const WORKTREE_WORKERS: usize = 4;
+
+/// Apply `f` to every item on up to `WORKTREE_WORKERS` threads. Results come
+/// back in no particular order.
+fn map_bounded<T, R, F>(items: &[T], f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    let next = std::sync::atomic::AtomicUsize::new(0);
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..WORKTREE_WORKERS.min(items.len()))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut results = Vec::new();
+                    loop {
+                        let i = next.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                        let Some(item) = items.get(i) else {
+                            return results;
+                        };
+                        results.push(f(item));
+                    }
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|w| w.join().unwrap_or_default())
+            .collect()
+    })
+}
+
+/// Disk usage of one worktree, for the cleanup view
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorktreeDiskUsage {
+    pub worktree_id: String,
+    pub bytes: u64,
+}
+
+/// Total size in bytes of the files under `path`. Symlinks are not followed.
+pub fn disk_usage(path: &Path) -> Result<u64, std::io::Error> {
+    let metadata = std::fs::symlink_metadata(path)?;
+    if !metadata.is_dir() {
+        return Ok(metadata.len());
     }
 
-    #[test]
-    fn test_resolve_worktree_directory_with_placeholder() {
-        let project_path = PathBuf::from("/home/user/myproject");
-        let result = resolve_worktree_directory(
-            Some("{{ repo_directory }}/.worktrees"),
-            &project_path,
-            None,
-            None,
-        )
-        .unwrap();
-        assert_eq!(result, PathBuf::from("/home/user/myproject/.worktrees"));
+    let mut total = 0;
+    for entry in std::fs::read_dir(path)? {
+        total += disk_usage(&entry?.path())?;
     }
+    Ok(total)
+}
 
-    #[test]
-    fn test_resolve_worktree_directory_no_spaces_placeholder() {
-        let project_path = PathBuf::from("/home/user/myproject");
-        let result = resolve_worktree_directory(
-            Some("{{repo_directory}}/trees"),
-            &project_path,
-            None,
-            None,
-        )
-        .unwrap();
-        assert_eq!(result, PathBuf::from("/home/user/myproject/trees"));
+/// Describe a problematic nesting of the project and its worktree directory:
+/// the project living inside the worktree directory, or the worktree directory
+/// sitting inside the repository without being gitignored (so every worktree
+/// shows up as an untracked change).
+pub fn nesting_warning(project_path: &Path, worktree_base: &Path) -> Option<String> {
+    if project_path.starts_with(worktree_base) {
+        return Some(format!(
+            "Project {} is inside its worktree directory {}",
+            project_path.display(),
+            worktree_base.display()
+        ));
     }
 
-    #[test]
-    fn test_resolve_worktree_directory_absolute_path() {
-        let project_path = PathBuf::from("/home/user/myproject");
-        let result =
+    let relative = worktree_base.strip_prefix(project_path).ok()?;
+    let repo = git2::Repository::open(project_path).ok()?;
+    // Directory ignore rules only match with a trailing slash
+    let ignored = repo.is_path_ignored(relative.join("")).unwrap_or(false);
+    (!ignored).then(|| {
+        format!(
+            "Worktree directory {} is inside the repository but not gitignored",
+            worktree_base.display()
+        )
+    })
+}
+
+/// Where a worktree lives relative to its project, for display: `.` for the
+/// project itself, `../fuzzy-fox` for a sibling, `worktrees/fuzzy-fox` when
+/// nested. Paths that only share the filesystem root (or are on another
+/// volume, where no relative path exists) are "external".
+pub fn relative_to_project(project: &Project, worktree: &Worktree) -> String {
+    relative_path(Path::new(&project.path), Path::new(&worktree.path))
+        .map(|p| p.to_string_lossy().replace('\\', "/"))
+        .unwrap_or_else(|| "external".to_string())
+}
+
+fn relative_path(from: &Path, to: &Path) -> Option<PathBuf> {
+    use std::path::Component;
+
+    let from: Vec<Component> = from.components().collect();
+    let to: Vec<Component> = to.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    let shares_directory = from[..common]
+        .iter()
+        .any(|c| matches!(c, Component::Normal(_)));
+    if !shares_directory {
+        return None;
+    }
+
+    let mut relative: PathBuf = from[common..].iter().map(|_| Component::ParentDir).collect();
+    relative.extend(&to[common..]);
+    if relative.as_os_str().is_empty() {
+        relative.push(".");
+    }
+    Some(relative)
+}
+
+/// Free bytes available to the current user on the filesystem holding `base`.
+/// `base` doesn't have to exist yet; its nearest existing ancestor is queried.
+pub fn free_space_for(base: &Path) -> Result<u64, WorktreeError> {
+    let existing = base
+        .ancestors()
+        .find(|p| p.exists())
+        .ok_or_else(|| WorktreeError::NotADirectory(base.to_string_lossy().to_string()))?;
+    Ok(sys_free_space(existing)?)
+}
+
+#[cfg(unix)]
+fn sys_free_space(path: &Path) -> Result<u64, std::io::Error> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes()).map_err(std::io::Error::other)?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    // Field widths differ between platforms
+    #[allow(clippy::unnecessary_cast)]
+    Ok(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+#[cfg(windows)]
+fn sys_free_space(path: &Path) -> Result<u64, std::io::Error> {
+    use std::os::windows::ffi::OsStrExt;
+
+    extern "system" {
+        fn GetDiskFreeSpaceExW(
+            directory: *const u16,
+            free_to_caller: *mut u64,
+            total: *mut u64,
+            total_free: *mut u64,
+        ) -> i32;
+    }
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(std::iter::once(0)).collect();
+    let mut free_to_caller = 0u64;
+    let ok = unsafe {
+        GetDiskFreeSpaceExW(
+            wide.as_ptr(),
+            &mut free_to_caller,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+        )
+    };
+    if ok == 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(free_to_caller)
+}
+
+/// Aggregate worktree count, disk usage and dirty state across a project's worktrees.
+/// Worktrees whose directory no longer exists count towards the total only.
+pub fn project_stats(project: &Project) -> Result<ProjectStats, WorktreeError> {
+    let mut stats = ProjectStats {
+        worktree_count: project.worktrees.len(),
+        total_disk_bytes: 0,
+        dirty_count: 0,
+    };
+
+    for worktree in &project.worktrees {
+        let path = Path::new(&worktree.path);
+        if !path.exists() {
+            continue;
+        }
+        stats.total_disk_bytes += disk_usage(path)?;
+        if git::has_uncommitted_changes_at_path(path)? {
+            stats.dirty_count += 1;
+        }
+    }
+
+    Ok(stats)
+}
+
+/// Disk usage of each of a project's worktrees, largest first. Sizes are
+/// cached per worktree until its watcher reports a change; uncached ones are
+/// walked in parallel on a bounded number of threads. Missing directories count as 0.
+pub fn disk_usage_breakdown(project: &Project) -> Result<Vec<WorktreeDiskUsage>, WorktreeError> {
+    let mut breakdown = Vec::new();
+    let mut pending: Vec<&Worktree> = Vec::new();
+    {
+        let cache = WORKTREE_DISK_USAGE.lock();
+        for worktree in &project.worktrees {
+            match cache.get(&worktree.id) {
+                Some((_, bytes)) => breakdown.push(WorktreeDiskUsage {
+                    worktree_id: worktree.id.clone(),
+                    bytes: *bytes,
+                }),
+                None => pending.push(worktree),
+            }
+        }
+    }
+
+    let measured = map_bounded(&pending, |worktree| {
+        let path = Path::new(&worktree.path);
+        let bytes = if path.exists() { disk_usage(path) } else { Ok(0) };
+        bytes.map(|bytes| (*worktree, bytes))
+    });
+
+    let mut cache = WORKTREE_DISK_USAGE.lock();
+    for result in measured {
+        let (worktree, bytes) = result?;
+        cache.insert(worktree.id.clone(), (worktree.path.clone(), bytes));
+        breakdown.push(WorktreeDiskUsage {
+            worktree_id: worktree.id.clone(),
+            bytes,
+        });
+    }
+
+    breakdown.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.worktree_id.cmp(&b.worktree_id)));
+    Ok(breakdown)
+}
+
+/// A worktree whose branch differs from its upstream
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DivergedWorktree {
+    pub worktree_id: String,
+    /// Commits on the branch that aren't on the upstream
+    pub ahead: usize,
+    /// Commits on the upstream that aren't on the branch
+    pub behind: usize,
+}
+
+/// A project's worktrees that are ahead of or behind their upstream, for a
+/// "branches needing attention" view. Worktrees without an upstream, detached
+/// ones and missing directories are skipped, as are those in sync.
+pub fn diverged_worktrees(project: &Project) -> Result<Vec<DivergedWorktree>, WorktreeError> {
+    let candidates: Vec<&Worktree> = project
+        .worktrees
+        .iter()
+        .filter(|w| !w.detached && Path::new(&w.path).exists())
+        .collect();
+
+    let counts = map_bounded(&candidates, |worktree| {
+        git::ahead_behind_upstream(Path::new(&worktree.path)).map(|c| (*worktree, c))
+    });
+
+    let mut diverged = Vec::new();
+    for result in counts {
+        if let (worktree, Some((ahead, behind))) = result? {
+            if ahead > 0 || behind > 0 {
+                diverged.push(DivergedWorktree {
+                    worktree_id: worktree.id.clone(),
+                    ahead,
+                    behind,
+                });
+            }
+        }
+    }
+    diverged.sort_by_key(|d| {
+        project.worktrees.iter().find(|w| w.id == d.worktree_id).map(|w| w.order)
+    });
+    Ok(diverged)
+}
+
+/// Groups of a project's worktree ids whose paths resolve to the same
+/// directory, e.g. after a bad import. Each group is in list order, so its
+/// first id is the entry `drop_duplicate_paths` keeps.
+pub fn find_duplicate_paths(project: &Project) -> Vec<Vec<String>> {
+    let mut groups: Vec<(PathBuf, Vec<String>)> = Vec::new();
+    for worktree in &project.worktrees {
+        let path = canonical(Path::new(&worktree.path));
+        match groups.iter_mut().find(|(p, _)| *p == path) {
+            Some((_, ids)) => ids.push(worktree.id.clone()),
+            None => groups.push((path, vec![worktree.id.clone()])),
+        }
+    }
+    groups
+        .into_iter()
+        .map(|(_, ids)| ids)
+        .filter(|ids| ids.len() > 1)
+        .collect()
+}
+
+/// Remove every worktree entry that duplicates an earlier entry's path,
+/// returning the removed ids. Only the entries are dropped; the directory
+/// stays, since the kept entry still points at it.
+pub fn drop_duplicate_paths(project: &mut Project) -> Vec<String> {
+    let dropped: Vec<String> = find_duplicate_paths(project)
+        .into_iter()
+        .flat_map(|ids| ids.into_iter().skip(1))
+        .collect();
+    project.worktrees.retain(|w| !dropped.contains(&w.id));
+    dropped
+}
+
+/// Cached stats for a project, if still valid
+pub fn cached_project_stats(project_path: &str) -> Option<ProjectStats> {
+    PROJECT_STATS
+        .lock()
+        .get(project_path)
+        .map(|(_, stats)| stats.clone())
+}
+
+pub fn cache_project_stats(project: &Project, stats: ProjectStats) {
+    let worktree_paths = project.worktrees.iter().map(|w| w.path.clone()).collect();
+    PROJECT_STATS
+        .lock()
+        .insert(project.path.clone(), (worktree_paths, stats));
+}
+
+pub fn invalidate_project_stats(project_path: &str) {
+    PROJECT_STATS.lock().remove(project_path);
+}
+
+/// Drop cached stats for whichever project contains `worktree_path`
+pub fn invalidate_project_stats_for_worktree(worktree_path: &str) {
+    PROJECT_STATS
+        .lock()
+        .retain(|_, (paths, _)| !paths.iter().any(|p| p == worktree_path));
+    WORKTREE_DISK_USAGE
+        .lock()
+        .retain(|_, (path, _)| path != worktree_path);
+}
+
+/// Worktrees with no activity for more than `days` days, oldest first
+pub fn stale_worktrees(project: &Project, days: u64) -> Vec<Worktree> {
+    stale_worktrees_at(project, days, time::now_secs() as i64)
+}
+
+fn stale_worktrees_at(project: &Project, days: u64, now: i64) -> Vec<Worktree> {
+    let threshold = i64::try_from(days.saturating_mul(86400)).unwrap_or(i64::MAX);
+    let mut stale: Vec<(i64, &Worktree)> = project
+        .worktrees
+        .iter()
+        .filter_map(|wt| last_activity(wt).map(|at| (at, wt)))
+        .filter(|(at, _)| now.saturating_sub(*at) > threshold)
+        .collect();
+    stale.sort_by_key(|(at, _)| *at);
+    stale.into_iter().map(|(_, wt)| wt.clone()).collect()
+}
+
+/// When the worktree was last used: when it was last opened, or failing that
+/// the later of its last commit and its creation. None if none of these is known.
+fn last_activity(wt: &Worktree) -> Option<i64> {
+    if let Some(accessed) = wt.last_accessed_at.as_deref().and_then(time::parse_iso) {
+        return Some(accessed);
+    }
+    let committed = git::last_commit_time(Path::new(&wt.path)).ok().flatten();
+    committed.max(time::parse_iso(&wt.created_at))
+}
+
+/// Current UTC time as an RFC 3339 timestamp (see `time::format_iso`)
+pub fn chrono_lite_now() -> String {
+    time::format_iso(time::now_secs() as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_generate_worktree_name_format() {
+        let name = generate_worktree_name();
+        // Should be in adjective-animal format with hyphen separator
+        assert!(name.contains('-'), "Name should contain hyphen: {}", name);
+        let parts: Vec<&str> = name.split('-').collect();
+        assert_eq!(parts.len(), 2, "Name should have exactly 2 parts: {}", name);
+        assert!(!parts[0].is_empty(), "First part should not be empty");
+        assert!(!parts[1].is_empty(), "Second part should not be empty");
+    }
+
+    #[test]
+    fn test_generate_worktree_name_uniqueness() {
+        // Generate several names and ensure they're not all the same
+        let names: Vec<String> = (0..10).map(|_| generate_worktree_name()).collect();
+        let unique_count = names.iter().collect::<std::collections::HashSet<_>>().len();
+        // With random generation, we should get mostly unique names
+        assert!(unique_count > 1, "Names should have some variety");
+    }
+
+    #[test]
+    fn test_resolve_worktree_directory_default() {
+        let project_path = PathBuf::from("/home/user/myproject");
+        let result = resolve_worktree_directory(None, &project_path, None, None).unwrap();
+        assert_eq!(result, PathBuf::from("/home/user/myproject/.worktrees"));
+    }
+
+    #[test]
+    fn test_resolve_worktree_directory_with_placeholder() {
+        let project_path = PathBuf::from("/home/user/myproject");
+        let result = resolve_worktree_directory(
+            Some("{{ repo_directory }}/.worktrees"),
+            &project_path,
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(result, PathBuf::from("/home/user/myproject/.worktrees"));
+    }
+
+    #[test]
+    fn test_resolve_worktree_directory_no_spaces_placeholder() {
+        let project_path = PathBuf::from("/home/user/myproject");
+        let result = resolve_worktree_directory(
+            Some("{{repo_directory}}/trees"),
+            &project_path,
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(result, PathBuf::from("/home/user/myproject/trees"));
+    }
+
+    #[test]
+    fn test_resolve_worktree_directory_absolute_path() {
+        let project_path = PathBuf::from("/home/user/myproject");
+        let result =
             resolve_worktree_directory(Some("/var/worktrees"), &project_path, None, None).unwrap();
         assert_eq!(result, PathBuf::from("/var/worktrees"));
     }
@@ -441,5 +1503,935 @@ mod tests {
         assert!(date_parts[0].parse::<u32>().is_ok(), "Year should be numeric");
         assert!(date_parts[1].parse::<u32>().is_ok(), "Month should be numeric");
         assert!(date_parts[2].parse::<u32>().is_ok(), "Day should be numeric");
+
+        // Month and day must be valid for the calendar
+        assert!(time::parse_iso(&timestamp).is_some(), "Invalid date: {}", timestamp);
+    }
+
+    #[test]
+    fn test_create_worktree_rejects_branch_checked_out_elsewhere() {
+        let repo = crate::test_utils::TestRepo::new();
+        let mut project = create_project(&repo.path).unwrap();
+        let base = BaseBranch::default();
+        let first_dir = repo.root().join("first").to_string_lossy().to_string();
+        let second_dir = repo.root().join("second").to_string_lossy().to_string();
+
+        let wt = create_worktree(&mut project, Some("feature".into()), Some(&first_dir), &base)
+            .unwrap();
+        let result = create_worktree(&mut project, Some("feature".into()), Some(&second_dir), &base);
+
+        let expected = std::fs::canonicalize(&wt.path).unwrap();
+        match result {
+            Err(WorktreeError::BranchInUse(path)) => {
+                assert_eq!(std::fs::canonicalize(path).unwrap(), expected)
+            }
+            other => panic!("expected BranchInUse, got {:?}", other),
+        }
+        assert_eq!(project.worktrees.len(), 1);
+        assert!(!repo.root().join("second").join("feature").exists());
+
+        // The main checkout's branch counts too
+        let main_branch = repo.git(&["branch", "--show-current"]);
+        let result = create_worktree(
+            &mut project,
+            Some(main_branch.trim().to_string()),
+            Some(&second_dir),
+            &base,
+        );
+        assert!(matches!(result, Err(WorktreeError::BranchInUse(_))));
+    }
+
+    #[test]
+    fn test_last_changed_file_picks_most_recent_change() {
+        let repo = crate::test_utils::TestRepo::new();
+        repo.commit_file("old.txt", "one\n", "add old");
+        repo.commit_file("gone.txt", "bye\n", "add gone");
+
+        assert_eq!(last_changed_file(&repo.path).unwrap(), None);
+
+        let set_mtime = |name: &str, secs: u64| {
+            let file = std::fs::File::options().write(true).open(repo.path.join(name)).unwrap();
+            let time = std::time::UNIX_EPOCH + std::time::Duration::from_secs(secs);
+            file.set_modified(time).unwrap();
+        };
+        repo.write("old.txt", "two\n");
+        repo.write("new.txt", "untracked\n");
+        std::fs::remove_file(repo.path.join("gone.txt")).unwrap();
+        set_mtime("old.txt", 1_700_000_000);
+        set_mtime("new.txt", 1_700_000_100);
+
+        let latest = last_changed_file(&repo.path).unwrap().unwrap();
+        assert_eq!(latest, repo.path.join("new.txt"));
+
+        set_mtime("old.txt", 1_700_000_200);
+        let latest = last_changed_file(&repo.path).unwrap().unwrap();
+        assert_eq!(latest, repo.path.join("old.txt"));
+    }
+
+    #[test]
+    fn test_promote_scratch_moves_work_into_tracked_worktree() {
+        let repo = crate::test_utils::TestRepo::new();
+        repo.commit_file("notes.txt", "draft\n", "add notes");
+        let head = repo.head();
+        let mut project = create_project(&repo.path).unwrap();
+        let directory = repo.worktrees_dir();
+
+        // A clean scratch directory has nothing worth keeping
+        let result =
+            promote_scratch_to_worktree(&mut project, &repo.path, "keep".into(), Some(&directory));
+        assert!(matches!(result, Err(WorktreeError::NothingToPromote(_))));
+
+        repo.write("notes.txt", "draft\nmore\n");
+        repo.write("idea.txt", "new\n");
+        let wt =
+            promote_scratch_to_worktree(&mut project, &repo.path, "keep".into(), Some(&directory))
+                .unwrap();
+
+        assert_eq!(wt.branch, "keep");
+        assert!(!wt.detached);
+        assert_eq!(project.worktrees.len(), 1);
+        assert_eq!(project.worktrees[0].id, wt.id);
+        let wt_path = Path::new(&wt.path);
+        assert_eq!(crate::test_utils::git_in(wt_path, &["rev-parse", "HEAD"]).trim(), head);
+        assert_eq!(std::fs::read_to_string(wt_path.join("notes.txt")).unwrap(), "draft\nmore\n");
+        assert_eq!(std::fs::read_to_string(wt_path.join("idea.txt")).unwrap(), "new\n");
+        // The scratch directory is left clean
+        assert!(!git::has_uncommitted_changes_at_path(&repo.path).unwrap());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_promote_scratch_rolls_back_when_the_work_cannot_move() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let repo = crate::test_utils::TestRepo::new();
+        let mut project = create_project(&repo.path).unwrap();
+        // The new worktree gets its own idea.txt, so the stashed one can't be restored there
+        let hook = repo.path.join(".git/hooks/post-checkout");
+        std::fs::write(&hook, "#!/bin/sh\necho hook > idea.txt\n").unwrap();
+        std::fs::set_permissions(&hook, std::fs::Permissions::from_mode(0o755)).unwrap();
+        repo.write("README.md", "edited\n");
+        repo.write("idea.txt", "new\n");
+
+        let directory = repo.worktrees_dir();
+        let result =
+            promote_scratch_to_worktree(&mut project, &repo.path, "keep".into(), Some(&directory));
+
+        assert!(result.is_err());
+        assert!(project.worktrees.is_empty());
+        assert!(!repo.root().join("worktrees/keep").exists());
+        assert!(!git::branch_exists(&repo.path, "keep").unwrap());
+        assert_eq!(std::fs::read_to_string(repo.path.join("README.md")).unwrap(), "edited\n");
+        assert_eq!(std::fs::read_to_string(repo.path.join("idea.txt")).unwrap(), "new\n");
+        assert!(repo.git(&["stash", "list"]).is_empty());
+    }
+
+    #[test]
+    fn test_promote_scratch_rejects_other_repositories() {
+        let repo = crate::test_utils::TestRepo::new();
+        let other = crate::test_utils::TestRepo::new();
+        other.write("stray.txt", "x\n");
+        let mut project = create_project(&repo.path).unwrap();
+
+        let result = promote_scratch_to_worktree(&mut project, &other.path, "keep".into(), None);
+        assert!(matches!(result, Err(WorktreeError::ForeignRepository(_))));
+
+        let plain = repo.root().join("plain");
+        std::fs::create_dir_all(&plain).unwrap();
+        let result = promote_scratch_to_worktree(&mut project, &plain, "keep".into(), None);
+        assert!(matches!(result, Err(WorktreeError::NotARepository)));
+        assert!(project.worktrees.is_empty());
+    }
+
+    #[test]
+    fn test_create_and_delete_detached_worktree() {
+        let repo = crate::test_utils::TestRepo::new();
+        let head = repo.head();
+        let mut project = create_project(&repo.path).unwrap();
+        let directory = repo.worktrees_dir();
+
+        let wt = create_detached_worktree(&mut project, Some(&directory)).unwrap();
+
+        assert!(wt.detached);
+        assert!(wt.branch.is_empty());
+        assert_eq!(project.worktrees.len(), 1);
+        let wt_path = Path::new(&wt.path);
+        assert!(wt_path.exists());
+        let symbolic = crate::test_utils::git_in(wt_path, &["rev-parse", "--abbrev-ref", "HEAD"]);
+        assert_eq!(symbolic.trim(), "HEAD");
+        let wt_head = crate::test_utils::git_in(wt_path, &["rev-parse", "HEAD"]);
+        assert_eq!(wt_head.trim(), head);
+        // No branch named after the worktree should have been created
+        assert!(!git::branch_exists(&repo.path, &wt.name).unwrap());
+
+        delete_worktree(&mut project, &wt.id).unwrap();
+
+        assert!(project.worktrees.is_empty());
+        assert!(!wt_path.exists());
+        let list = repo.git(&["worktree", "list", "--porcelain"]);
+        assert!(!list.contains(&wt.name), "worktree still registered: {}", list);
+    }
+
+    #[test]
+    fn test_project_stats_aggregates_worktrees() {
+        let repo = crate::test_utils::TestRepo::new();
+        let mut project = create_project(&repo.path).unwrap();
+
+        let clean = repo.create_worktree(&mut project, "clean");
+        let dirty = repo.create_worktree(&mut project, "dirty");
+        crate::test_utils::write_in(Path::new(&dirty.path), "scratch.txt", "work in progress\n");
+
+        let stats = project_stats(&project).unwrap();
+
+        let expected_bytes = disk_usage(Path::new(&clean.path)).unwrap()
+            + disk_usage(Path::new(&dirty.path)).unwrap();
+        assert_eq!(stats.worktree_count, 2);
+        assert_eq!(stats.dirty_count, 1);
+        assert!(stats.total_disk_bytes > 0);
+        assert_eq!(stats.total_disk_bytes, expected_bytes);
+    }
+
+    #[test]
+    fn test_preview_worktree_path_matches_create() {
+        let repo = crate::test_utils::TestRepo::new();
+        let mut project = create_project(&repo.path).unwrap();
+        let directory = repo.worktrees_dir();
+
+        let preview = preview_worktree_path(&project, None, Some(&directory)).unwrap();
+        assert!(!preview.exists);
+        assert!(!preview.path.exists(), "preview must not create anything");
+
+        let wt = create_worktree(
+            &mut project,
+            Some(preview.name.clone()),
+            Some(&directory),
+            &BaseBranch::default(),
+        )
+        .unwrap();
+        assert_eq!(Path::new(&wt.path), preview.path);
+
+        let again = preview_worktree_path(&project, Some(preview.name), Some(&directory)).unwrap();
+        assert_eq!(again.path, preview.path);
+        assert!(again.exists);
+    }
+
+    #[test]
+    fn test_resolve_layout() {
+        let project_path = PathBuf::from("/home/user/myproject");
+        assert_eq!(
+            resolve_layout("{repo}-worktrees/{name}", &project_path, "feature").unwrap(),
+            PathBuf::from("/home/user/myproject-worktrees/feature")
+        );
+        assert_eq!(
+            resolve_layout("{repo}/../trees/{name}", &project_path, "feature").unwrap(),
+            PathBuf::from("/home/user/trees/feature")
+        );
+        assert!(matches!(
+            resolve_layout("trees/{name}", &project_path, "feature"),
+            Err(WorktreeError::InvalidLayout(_))
+        ));
+        assert!(matches!(
+            resolve_layout("{repo}-worktrees", &project_path, "feature"),
+            Err(WorktreeError::InvalidLayout(_))
+        ));
+    }
+
+    fn project_with_layout(id: &str, path: &str, layout: &str) -> Project {
+        Project {
+            id: id.into(),
+            path: path.into(),
+            worktree_layout: Some(layout.into()),
+            ..project_with_worktrees(&[])
+        }
+    }
+
+    #[test]
+    fn test_validate_base_dir_rejects_worktrees_inside_another_project() {
+        let projects = vec![
+            project_with_layout("lib", "/code/lib", "{repo}-worktrees/{name}"),
+            project_with_layout("app", "/code/app", "/code/lib/trees/{name}"),
+        ];
+
+        match validate_base_dir(&projects) {
+            Err(WorktreeError::BaseDirInsideProject {
+                project_path,
+                base_dir,
+                nested_in_id,
+                ..
+            }) => {
+                assert_eq!(project_path, "/code/app");
+                assert_eq!(base_dir, "/code/lib/trees");
+                assert_eq!(nested_in_id, "lib");
+            }
+            other => panic!("expected a nested base dir, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_validate_base_dir_accepts_own_and_sibling_directories() {
+        let mut projects = vec![
+            project_with_layout("lib", "/code/lib", "{repo}/.worktrees/{name}"),
+            project_with_layout("app", "/code/app", "{repo}-worktrees/{name}"),
+        ];
+        validate_base_dir(&projects).unwrap();
+
+        // Only open projects count
+        projects.push(project_with_layout("web", "/code/web", "/code/lib/trees/{name}"));
+        projects[0].is_active = false;
+        validate_base_dir(&projects).unwrap();
+    }
+
+    #[test]
+    fn test_sibling_layout_places_worktree_next_to_repo() {
+        let repo = crate::test_utils::TestRepo::new();
+        let mut project = create_project(&repo.path).unwrap();
+        let layout = "{repo}-worktrees/{name}";
+        validate_layout(layout, Path::new(&project.path)).unwrap();
+        project.worktree_layout = Some(layout.to_string());
+
+        // The layout wins over the configured `worktrees_dir`
+        let wt = repo.create_worktree(&mut project, "sibling");
+
+        let expected = PathBuf::from(format!("{}-worktrees/sibling", project.path));
+        assert_eq!(PathBuf::from(&wt.path), expected);
+        assert!(expected.join(".git").exists());
+        let preview = preview_worktree_path(&project, Some("sibling".into()), None).unwrap();
+        assert_eq!(preview.path, expected);
+
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_validate_layout_rejects_read_only_location() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let repo = crate::test_utils::TestRepo::new();
+        let locked = repo.root().join("locked");
+        std::fs::create_dir_all(&locked).unwrap();
+        std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o555)).unwrap();
+        let layout = format!("{}/trees/{{name}}", locked.display());
+
+        // Root ignores permission bits, so there's nothing to reject
+        let writable_anyway = std::fs::write(locked.join("probe"), b"").is_ok();
+        let result = validate_layout(&layout, &repo.path);
+        std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        if !writable_anyway {
+            assert!(matches!(result, Err(WorktreeError::InvalidLayout(_))));
+        }
+        assert!(validate_layout("{repo}-worktrees/{name}", &repo.path).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_check_writable_rejects_read_only_directory() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let repo = crate::test_utils::TestRepo::new();
+        let locked = repo.root().join("locked");
+        std::fs::create_dir_all(&locked).unwrap();
+        std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o555)).unwrap();
+
+        // Root ignores permission bits, so there's nothing to reject
+        let writable_anyway = std::fs::write(locked.join("probe"), b"").is_ok();
+        let _ = std::fs::remove_file(locked.join("probe"));
+        let result = check_writable(&locked);
+        let nested = check_writable(&locked.join("not/created/yet"));
+        std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        if !writable_anyway {
+            assert!(matches!(result, Err(WorktreeError::NotWritable(_))));
+            assert!(matches!(nested, Err(WorktreeError::NotWritable(_))));
+        }
+        assert!(check_writable(repo.root()).is_ok());
+        assert!(check_writable(&repo.root().join("missing/child")).is_ok());
+        // The probe file is cleaned up
+        assert_eq!(std::fs::read_dir(&locked).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn branch_names_become_flat_directory_names() {
+        let cases = [
+            ("feature/login", "feature-login"),
+            ("user\\fix//typo", "user-fix-typo"),
+            ("release/1.2.0", "release-1.2.0"),
+            ("../escape", "escape"),
+            (".hidden.", "hidden"),
+            ("café/naïve", "café-naïve"),
+            ("修正/バグ", "修正-バグ"),
+            ("what? *now*: <ok>", "what-now-ok"),
+            ("/", "worktree"),
+        ];
+        for (branch, expected) in cases {
+            let name = branch_to_dir_name(branch);
+            assert_eq!(name, expected, "for {:?}", branch);
+            assert_eq!(Path::new(&name).components().count(), 1);
+        }
+    }
+
+    #[test]
+    fn branches_with_slashes_get_flat_unique_directories() {
+        let repo = crate::test_utils::TestRepo::new();
+        let mut project = create_project(&repo.path).unwrap();
+
+        let slashed = repo.create_worktree(&mut project, "feature/login");
+        assert_eq!(Path::new(&slashed.path), repo.root().join("worktrees/feature-login"));
+        assert_eq!(slashed.branch, "feature/login");
+
+        let dashed = repo.create_worktree(&mut project, "feature-login");
+        assert_eq!(Path::new(&dashed.path), repo.root().join("worktrees/feature-login-2"));
+    }
+
+    #[test]
+    fn diverged_worktrees_reports_ahead_and_behind_counts() {
+        use crate::test_utils::{git_in, write_in};
+
+        let repo = crate::test_utils::TestRepo::new();
+        let mut project = create_project(&repo.path).unwrap();
+        for name in ["ahead", "diverged", "synced", "untracked"] {
+            repo.create_worktree(&mut project, name);
+        }
+        let path = |name: &str| repo.root().join("worktrees").join(name);
+        let commit = |name: &str, file: &str| {
+            write_in(&path(name), file, "content\n");
+            git_in(&path(name), &["add", file]);
+            git_in(&path(name), &["commit", "-m", file]);
+        };
+        for name in ["ahead", "diverged", "synced"] {
+            git_in(&path(name), &["branch", "--set-upstream-to=main"]);
+        }
+
+        commit("diverged", "d.txt");
+        repo.commit_file("main.txt", "m\n", "Upstream moved on");
+        for name in ["ahead", "synced"] {
+            git_in(&path(name), &["merge", "--ff-only", "main"]);
+        }
+        commit("ahead", "a.txt");
+        commit("ahead", "b.txt");
+
+        let diverged = diverged_worktrees(&project).unwrap();
+
+        let id = |name: &str| project.worktrees.iter().find(|w| w.name == name).unwrap().id.clone();
+        let entry = |name: &str, ahead, behind| DivergedWorktree {
+            worktree_id: id(name),
+            ahead,
+            behind,
+        };
+        assert_eq!(diverged, vec![entry("ahead", 2, 0), entry("diverged", 1, 1)]);
+    }
+
+    #[test]
+    fn first_worktree_is_created_only_when_enabled() {
+        let repo = crate::test_utils::TestRepo::new();
+        let mut project = create_project(&repo.path).unwrap();
+        let mut cfg = WorktreeConfig {
+            directory: Some(repo.worktrees_dir()),
+            ..Default::default()
+        };
+
+        assert!(auto_create_first_worktree(&mut project, &cfg).unwrap().is_none());
+        assert!(project.worktrees.is_empty());
+
+        cfg.auto_create_first_worktree = true;
+        let wt = auto_create_first_worktree(&mut project, &cfg).unwrap().unwrap();
+        let ids: Vec<&str> = project.worktrees.iter().map(|w| w.id.as_str()).collect();
+        assert_eq!(ids, vec![wt.id.as_str()]);
+        assert!(Path::new(&wt.path).join(".git").exists());
+
+        // Only the first one
+        assert!(auto_create_first_worktree(&mut project, &cfg).unwrap().is_none());
+        assert_eq!(project.worktrees.len(), 1);
+    }
+
+    #[test]
+    fn first_worktree_is_skipped_for_unborn_head() {
+        let repo = crate::test_utils::TestRepo::new();
+        repo.git(&["checkout", "-q", "--orphan", "empty"]);
+        repo.git(&["rm", "-rq", "--cached", "."]);
+        let mut project = create_project(&repo.path).unwrap();
+        let cfg = WorktreeConfig {
+            auto_create_first_worktree: true,
+            directory: Some(repo.worktrees_dir()),
+            ..Default::default()
+        };
+
+        assert!(auto_create_first_worktree(&mut project, &cfg).unwrap().is_none());
+        assert!(project.worktrees.is_empty());
+    }
+
+    #[test]
+    fn test_preview_worktree_path_detects_stray_directory() {
+        let repo = crate::test_utils::TestRepo::new();
+        let project = create_project(&repo.path).unwrap();
+        let directory = repo.worktrees_dir();
+        std::fs::create_dir_all(repo.root().join("worktrees/taken")).unwrap();
+
+        let preview =
+            preview_worktree_path(&project, Some("taken".into()), Some(&directory)).unwrap();
+        assert!(preview.exists);
+        let free = preview_worktree_path(&project, Some("free".into()), Some(&directory)).unwrap();
+        assert!(!free.exists);
+    }
+
+    #[test]
+    fn test_disk_usage_breakdown_sorted_and_cached() {
+        let repo = crate::test_utils::TestRepo::new();
+        let mut project = create_project(&repo.path).unwrap();
+        let small = repo.create_worktree(&mut project, "small");
+        let large = repo.create_worktree(&mut project, "large");
+        std::fs::write(Path::new(&small.path).join("data.bin"), vec![0u8; 10_000]).unwrap();
+        std::fs::write(Path::new(&large.path).join("data.bin"), vec![0u8; 50_000]).unwrap();
+
+        let breakdown = disk_usage_breakdown(&project).unwrap();
+
+        let ids: Vec<&str> = breakdown.iter().map(|u| u.worktree_id.as_str()).collect();
+        assert_eq!(ids, vec![large.id.as_str(), small.id.as_str()]);
+        assert_eq!(breakdown[0].bytes - breakdown[1].bytes, 40_000);
+        assert_eq!(breakdown[0].bytes, disk_usage(Path::new(&large.path)).unwrap());
+
+        // Cached until the worktree reports a change
+        std::fs::write(Path::new(&small.path).join("more.bin"), vec![0u8; 100_000]).unwrap();
+        assert_eq!(disk_usage_breakdown(&project).unwrap(), breakdown);
+        invalidate_project_stats_for_worktree(&small.path);
+        let updated = disk_usage_breakdown(&project).unwrap();
+        assert_eq!(updated[0].worktree_id, small.id);
+        assert_eq!(updated[0].bytes, breakdown[1].bytes + 100_000);
+    }
+
+    #[test]
+    fn test_stale_worktrees_uses_last_accessed() {
+        let repo = crate::test_utils::TestRepo::new();
+        let mut project = create_project(&repo.path).unwrap();
+        repo.create_worktree(&mut project, "old");
+        repo.create_worktree(&mut project, "recent");
+
+        let now = time::now_secs() as i64;
+        project.worktrees[0].last_accessed_at = Some(time::format_iso(now - 45 * 86400));
+        project.worktrees[1].last_accessed_at = Some(time::format_iso(now - 2 * 86400));
+
+        let stale = stale_worktrees_at(&project, 30, now);
+
+        let names: Vec<&str> = stale.iter().map(|wt| wt.name.as_str()).collect();
+        assert_eq!(names, vec!["old"]);
+    }
+
+    #[test]
+    fn test_stale_worktrees_falls_back_to_created_at() {
+        let repo = crate::test_utils::TestRepo::new();
+        let mut project = create_project(&repo.path).unwrap();
+        repo.create_worktree(&mut project, "never-opened");
+        repo.create_worktree(&mut project, "opened");
+
+        // Forty days on, only the worktree opened in the meantime is still fresh
+        let now = time::now_secs() as i64;
+        let later = now + 40 * 86400;
+        project.worktrees[1].last_accessed_at = Some(time::format_iso(later - 86400));
+
+        let stale = stale_worktrees_at(&project, 30, later);
+
+        let names: Vec<&str> = stale.iter().map(|wt| wt.name.as_str()).collect();
+        assert_eq!(names, vec!["never-opened"]);
+        assert!(stale_worktrees_at(&project, 30, now).is_empty());
+    }
+
+    #[test]
+    fn test_project_stats_cache_invalidated_by_worktree() {
+        let repo = crate::test_utils::TestRepo::new();
+        let mut project = create_project(&repo.path).unwrap();
+        let wt = repo.create_worktree(&mut project, "feature");
+
+        let stats = project_stats(&project).unwrap();
+        cache_project_stats(&project, stats.clone());
+        assert_eq!(cached_project_stats(&project.path), Some(stats));
+
+        invalidate_project_stats_for_worktree(&wt.path);
+        assert_eq!(cached_project_stats(&project.path), None);
+    }
+
+    #[test]
+    fn test_normalize_project_path_rejects_file() {
+        let repo = crate::test_utils::TestRepo::new();
+        let file = repo.path.join("README.md");
+
+        let result = normalize_project_path(&file);
+
+        assert!(matches!(result, Err(WorktreeError::NotADirectory(_))));
+        assert!(create_project(&file).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinked_worktree_is_flagged_and_found_by_path() {
+        let repo = crate::test_utils::TestRepo::new();
+        let mut project = create_project(&repo.path).unwrap();
+        let mut wt = repo.create_worktree(&mut project, "feature");
+        assert!(!is_symlinked(&wt));
+
+        let link = repo.root().join("feature-link");
+        std::os::unix::fs::symlink(&wt.path, &link).unwrap();
+        wt.path = link.to_string_lossy().to_string();
+        project.worktrees = vec![wt.clone()];
+        assert!(is_symlinked(&wt));
+
+        let projects = vec![project];
+        let real = canonical(&link);
+        let files = [link.join("README.md"), real.join("README.md"), link.join("gone/file.rs")];
+        for file in files {
+            let found = find_by_path(&projects, &file).map(|w| w.id.as_str());
+            assert_eq!(found, Some(wt.id.as_str()), "{} not mapped", file.display());
+        }
+        // The project root isn't inside any worktree
+        assert!(find_by_path(&projects, &repo.path.join("README.md")).is_none());
+    }
+
+    #[test]
+    fn test_find_docs_lists_readme_contributing_then_docs_dir() {
+        let repo = crate::test_utils::TestRepo::new();
+        repo.write("CONTRIBUTING.md", "# Contributing\n");
+        repo.write("docs/guide.md", "# Guide\n");
+        repo.write("readme-notes.txt", "not the readme\n");
+        repo.write("src/README.md", "not at the root\n");
+        let project = create_project(&repo.path).unwrap();
+        let root = Path::new(&project.path);
+
+        assert_eq!(
+            find_docs(&project),
+            vec![root.join("README.md"), root.join("CONTRIBUTING.md"), root.join("docs")]
+        );
+    }
+
+    #[test]
+    fn test_find_docs_without_docs_is_empty() {
+        let repo = crate::test_utils::TestRepo::new();
+        let mut project = create_project(&repo.path).unwrap();
+        std::fs::remove_file(repo.path.join("README.md")).unwrap();
+        assert!(find_docs(&project).is_empty());
+
+        project.path = repo.root().join("missing").to_string_lossy().to_string();
+        assert!(find_docs(&project).is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_normalize_project_path_resolves_symlink() {
+        let repo = crate::test_utils::TestRepo::new();
+        let link = repo.root().join("link");
+        std::os::unix::fs::symlink(&repo.path, &link).unwrap();
+
+        let normalized = normalize_project_path(&link).unwrap();
+
+        assert_eq!(normalized, repo.path.canonicalize().unwrap());
+        let project = create_project(&link).unwrap();
+        assert_eq!(Path::new(&project.path), normalized);
+        assert_eq!(project.name, "repo");
+    }
+
+    #[test]
+    fn test_normalize_project_path_strips_trailing_slash() {
+        let repo = crate::test_utils::TestRepo::new();
+        let with_slash = PathBuf::from(format!("{}/", repo.path.display()));
+
+        let normalized = normalize_project_path(&with_slash).unwrap();
+
+        assert_eq!(normalized, normalize_project_path(&repo.path).unwrap());
+        assert!(!normalized.to_string_lossy().ends_with('/'));
+    }
+
+    fn project_with_worktrees(ids: &[&str]) -> Project {
+        let worktrees = ids
+            .iter()
+            .enumerate()
+            .map(|(i, id)| Worktree {
+                order: i as i32,
+                ..crate::test_utils::worktree(id, &format!("/tmp/p/{}", id))
+            })
+            .collect();
+        crate::test_utils::project("p", "/tmp/p", worktrees)
+    }
+
+    #[test]
+    fn test_find_duplicate_paths_groups_same_directory() {
+        let repo = crate::test_utils::TestRepo::new();
+        let mut project = project_with_worktrees(&["a", "b", "c", "d"]);
+        let dir = repo.path.to_string_lossy().to_string();
+        project.worktrees[0].path = dir.clone();
+        // Same directory spelled differently still counts
+        project.worktrees[2].path = format!("{}/./", dir);
+        project.worktrees[3].path = "/tmp/p/elsewhere".into();
+
+        assert_eq!(find_duplicate_paths(&project), vec![vec!["a".to_string(), "c".to_string()]]);
+        assert!(find_duplicate_paths(&project_with_worktrees(&["x", "y"])).is_empty());
+    }
+
+    #[test]
+    fn test_drop_duplicate_paths_keeps_first_entry() {
+        let mut project = project_with_worktrees(&["a", "b", "c", "d"]);
+        project.worktrees[1].path = project.worktrees[0].path.clone();
+        project.worktrees[3].path = project.worktrees[0].path.clone();
+
+        assert_eq!(drop_duplicate_paths(&mut project), vec!["b".to_string(), "d".to_string()]);
+        let ids: Vec<&str> = project.worktrees.iter().map(|w| w.id.as_str()).collect();
+        assert_eq!(ids, vec!["a", "c"]);
+        assert!(drop_duplicate_paths(&mut project).is_empty());
+    }
+
+    fn file_change(path: &str, status: FileStatus, lines: Option<(usize, usize)>) -> FileChange {
+        FileChange {
+            path: path.to_string(),
+            status,
+            insertions: lines.map(|(i, _)| i),
+            deletions: lines.map(|(_, d)| d),
+            submodule: None,
+        }
+    }
+
+    #[test]
+    fn csv_export_quotes_paths_with_commas_and_quotes() {
+        let changes = vec![
+            file_change("src/plain.rs", FileStatus::Modified, Some((3, 1))),
+            file_change("docs/a, b.md", FileStatus::Added, Some((2, 0))),
+            file_change("say \"hi\".txt", FileStatus::Untracked, None),
+        ];
+
+        let csv = format_change_list(&changes, ExportFormat::Csv).unwrap();
+
+        assert_eq!(
+            csv,
+            "path,status,insertions,deletions\n\
+             src/plain.rs,modified,3,1\n\
+             \"docs/a, b.md\",added,2,0\n\
+             \"say \"\"hi\"\".txt\",untracked,,\n"
+        );
+    }
+
+    #[test]
+    fn json_export_is_an_array_of_changes() {
+        let changes = vec![
+            file_change("a, \"b\".rs", FileStatus::Deleted, Some((0, 4))),
+            file_change("new.txt", FileStatus::Untracked, None),
+        ];
+
+        let json = format_change_list(&changes, ExportFormat::Json).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(
+            parsed,
+            serde_json::json!([
+                {"path": "a, \"b\".rs", "status": "deleted", "insertions": 0, "deletions": 4},
+                {"path": "new.txt", "status": "untracked", "insertions": null, "deletions": null},
+            ])
+        );
+        let empty = format_change_list(&[], ExportFormat::Json).unwrap();
+        let empty: serde_json::Value = serde_json::from_str(&empty).unwrap();
+        assert_eq!(empty, serde_json::json!([]));
+    }
+
+    #[test]
+    fn export_reads_the_worktree_changes_and_rejects_unknown_ids() {
+        let repo = crate::test_utils::TestRepo::new();
+        repo.write("report, final.md", "done\n");
+        let mut project = project_with_worktrees(&["wt"]);
+        project.worktrees[0].path = repo.path.to_string_lossy().to_string();
+        let projects = vec![project];
+        let base = BaseBranch::default();
+
+        let csv = export_change_list(&projects, "wt", &base, false, ExportFormat::Csv).unwrap();
+        assert_eq!(csv, "path,status,insertions,deletions\n\"report, final.md\",untracked,,\n");
+
+        let err = export_change_list(&projects, "nope", &base, false, ExportFormat::Json)
+            .unwrap_err();
+        assert!(matches!(err, WorktreeError::WorktreeNotFound(ref id) if id == "nope"));
+    }
+
+    #[test]
+    fn badge_for_each_state() {
+        let summary = |changed_files, ahead, behind| StatusSummary {
+            changed_files,
+            ahead,
+            behind,
+            operation: None,
+        };
+
+        assert_eq!(format_badge(&summary(0, 0, 0), false), "");
+        assert_eq!(format_badge(&summary(3, 0, 0), false), "●3");
+        assert_eq!(format_badge(&summary(0, 2, 0), false), "↑2");
+        assert_eq!(format_badge(&summary(3, 2, 1), false), "●3↑2↓1");
+        assert_eq!(format_badge(&summary(3, 2, 1), true), "*3 +2 -1");
+        assert_eq!(format_badge(&summary(0, 0, 0), true), "");
+
+        let rebasing = StatusSummary {
+            operation: Some(git::Operation::Rebase),
+            ..summary(1, 0, 4)
+        };
+        assert_eq!(format_badge(&rebasing, false), "●1↓4 rebase");
+        assert_eq!(format_badge(&rebasing, true), "*1 -4 rebase");
+    }
+
+    #[test]
+    fn status_badge_reads_the_worktree() {
+        let repo = crate::test_utils::TestRepo::new();
+        let mut project = project_with_worktrees(&["wt"]);
+        project.worktrees[0].path = repo.path.to_string_lossy().to_string();
+        let projects = vec![project];
+        assert_eq!(status_badge(&projects, "wt", false).unwrap(), "");
+
+        repo.write("README.md", "edited\n");
+        repo.write("new.txt", "new\n");
+        assert_eq!(status_badge(&projects, "wt", false).unwrap(), "●2");
+
+        // A conflicting merge leaves the worktree mid-merge
+        repo.git(&["checkout", "-q", "-b", "other"]);
+        repo.git(&["checkout", "-q", "--", "README.md"]);
+        std::fs::remove_file(repo.path.join("new.txt")).unwrap();
+        repo.commit_file("README.md", "other\n", "Other side");
+        repo.git(&["checkout", "-q", "main"]);
+        repo.commit_file("README.md", "main\n", "Main side");
+        let _ = std::process::Command::new("git")
+            .args(["merge", "other"])
+            .current_dir(&repo.path)
+            .output()
+            .unwrap();
+        assert_eq!(status_badge(&projects, "wt", true).unwrap(), "*1 merge");
+
+        assert!(matches!(
+            status_badge(&projects, "nope", false),
+            Err(WorktreeError::WorktreeNotFound(_))
+        ));
+    }
+
+    fn ids(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_reorder_worktrees_persists_across_reload() {
+        let mut project = project_with_worktrees(&["a", "b", "c"]);
+        reorder_worktrees(&mut project, &ids(&["c", "a", "b"])).unwrap();
+
+        let order: Vec<(&str, i32)> =
+            project.worktrees.iter().map(|w| (w.id.as_str(), w.order)).collect();
+        assert_eq!(order, vec![("c", 0), ("a", 1), ("b", 2)]);
+
+        // The order fields survive a save and reload, whatever order they're read in
+        let mut json = serde_json::to_value(&project).unwrap();
+        json["worktrees"].as_array_mut().unwrap().reverse();
+        let reloaded: Project = serde_json::from_value(json).unwrap();
+
+        let mut reloaded = reloaded.worktrees;
+        reloaded.sort_by_key(|w| w.order);
+        let reloaded: Vec<&str> = reloaded.iter().map(|w| w.id.as_str()).collect();
+        assert_eq!(reloaded, vec!["c", "a", "b"]);
+    }
+
+    #[test]
+    fn test_reorder_worktrees_rejects_mismatched_ids() {
+        let mut project = project_with_worktrees(&["a", "b"]);
+
+        for (ordered, expect_not_found) in [
+            (ids(&["a"]), false),
+            (ids(&["a", "a", "b"]), false),
+            (ids(&["a", "b", "x"]), true),
+        ] {
+            let result = reorder_worktrees(&mut project, &ordered);
+            if expect_not_found {
+                assert!(matches!(result, Err(WorktreeError::WorktreeNotFound(_))));
+            } else {
+                assert!(matches!(result, Err(WorktreeError::OrderMismatch(_))));
+            }
+        }
+        let unchanged: Vec<&str> = project.worktrees.iter().map(|w| w.id.as_str()).collect();
+        assert_eq!(unchanged, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_relative_to_project() {
+        let project = project_with_worktrees(&[]);
+        let at = |path: &str| Worktree {
+            path: path.to_string(),
+            ..project_with_worktrees(&["wt"]).worktrees[0].clone()
+        };
+
+        assert_eq!(relative_to_project(&project, &at("/tmp/fuzzy-fox")), "../fuzzy-fox");
+        assert_eq!(relative_to_project(&project, &at("/tmp/p/trees/fox")), "trees/fox");
+        assert_eq!(relative_to_project(&project, &at("/tmp/p")), ".");
+        assert_eq!(relative_to_project(&project, &at("/tmp/other/deep/fox")), "../other/deep/fox");
+        assert_eq!(relative_to_project(&project, &at("/var/trees/fox")), "external");
+        assert_eq!(relative_to_project(&project, &at("relative/fox")), "external");
+    }
+
+    #[test]
+    fn test_error_codes_are_stable_and_unique() {
+        let errors = [
+            WorktreeError::Io(std::io::Error::other("boom")),
+            WorktreeError::NotARepository,
+            WorktreeError::WorktreeNotFound("id".into()),
+            WorktreeError::ProjectNotFound("/tmp/project".into()),
+            WorktreeError::NameGenerationFailed(100),
+            WorktreeError::Template("bad".into()),
+            WorktreeError::NotADirectory("/tmp/file".into()),
+            WorktreeError::InvalidLayout("bad".into()),
+            WorktreeError::NotWritable("/tmp/locked".into()),
+            WorktreeError::OrderMismatch("a is missing".into()),
+            WorktreeError::BranchInUse("/tmp/wt".into()),
+            WorktreeError::ForeignRepository("/tmp/other".into()),
+            WorktreeError::NothingToPromote("/tmp/scratch".into()),
+            WorktreeError::StashKept { stash_id: "id".into(), reason: "conflict".into() },
+        ];
+        let codes: Vec<&str> = errors.iter().map(|e| e.code()).collect();
+
+        assert_eq!(
+            codes,
+            vec![
+                "worktree.io",
+                "worktree.not_a_repository",
+                "worktree.not_found",
+                "worktree.project_not_found",
+                "worktree.name_generation_failed",
+                "worktree.template",
+                "worktree.not_a_directory",
+                "worktree.invalid_layout",
+                "worktree.not_writable",
+                "worktree.order_mismatch",
+                "worktree.branch_in_use",
+                "worktree.foreign_repository",
+                "worktree.nothing_to_promote",
+                "worktree.stash_kept",
+            ]
+        );
+        let unique: std::collections::HashSet<_> = codes.iter().collect();
+        assert_eq!(unique.len(), codes.len());
+
+        // Wrapped git errors surface the git code
+        let wrapped = WorktreeError::Git(git::GitError::UncommittedChanges);
+        assert_eq!(wrapped.code(), "git.uncommitted_changes");
+    }
+
+    #[test]
+    fn test_free_space_for_temp_dir() {
+        let dir = std::env::temp_dir();
+        let free = free_space_for(&dir).unwrap();
+        assert!(free > 0);
+
+        // A not-yet-created worktree base reports its parent filesystem
+        let missing = dir.join(format!("shellflow-missing-{}", Uuid::new_v4())).join(".worktrees");
+        assert!(free_space_for(&missing).unwrap() > 0);
+    }
+
+    #[test]
+    fn test_nesting_warning() {
+        let repo = crate::test_utils::TestRepo::new();
+
+        // Worktrees beside the repo are fine
+        assert_eq!(nesting_warning(&repo.path, &repo.root().join("worktrees")), None);
+
+        // Inside the repo, only when not ignored
+        let base = repo.path.join(".worktrees");
+        assert!(nesting_warning(&repo.path, &base).unwrap().contains("not gitignored"));
+        repo.write(".gitignore", ".worktrees/\n");
+        assert_eq!(nesting_warning(&repo.path, &base), None);
+
+        // Project inside the worktree directory
+        let warning = nesting_warning(&repo.path, repo.root()).unwrap();
+        assert!(warning.contains("is inside its worktree directory"));
     }
 }
@@ -26,7 +26,7 @@ import { useScratchTerminals } from './hooks/useScratchTerminals';
 import { useIndicators } from './hooks/useIndicators';
 import { useDrawerTabs } from './hooks/useDrawerTabs';
 import { useSessionTabs, SessionTab } from './hooks/useSessionTabs';
-import { selectFolder, shutdown, ptyKill, ptyForceKill, stashChanges, stashPop, reorderProjects, reorderWorktrees, expandActionPrompt, ActionPromptContext, updateActionAvailability, touchProject } from './lib/tauri';
+import { selectFolder, shutdown, ptyKill, ptyForceKill, stashChanges, stashPop, reorderProjects, reorderWorktrees, expandActionPrompt, ActionPromptContext, updateActionAvailability, touchProject, touchWorktree } from './lib/tauri';
 import { openUrl } from '@tauri-apps/plugin-opener';
 import { ActionContext, ActionId, getMenuAvailability } from './lib/actions';
 import { useActions, ActionHandlers } from './hooks/useActions';
@@ -35,10 +35,11 @@ import { useMappings } from './hooks/useMappings';
 import { getActiveContexts, type ContextState } from './lib/contexts';
 import { createActionHandlers, executeAction } from './lib/actionHandlers';
 import { copyFromActiveTerminal, pasteToActiveTerminal } from './lib/terminalRegistry';
-import { Project, Worktree, RunningTask, MergeCompleted, Session, SessionKind, ChangedFilesViewMode } from './types';
+import { Project, Worktree, RunningTask, MergeCompleted, NavEntry, Session, SessionKind, ChangedFilesViewMode } from './types';
 import { ToastContainer } from './components/Toast';
 import { useToast } from './hooks/useToast';
 import { ThemeProvider, ThemeBorderStyle } from './theme';
+import { errorMessage, isCodedError } from './lib/errors';
 
 const EXPANDED_PROJECTS_KEY = 'shellflow:expandedProjects';
 const SELECTED_TASKS_KEY = 'shellflow:selectedTasks';
@@ -1862,8 +1863,13 @@ function App() {
           setExpandedProjects((prev) => new Set([...prev, project.id]));
           // Activate the newly added project immediately
           setOpenProjectIds((prev) => new Set([...prev, project.id]));
-          setActiveWorktreeId(null);
           setActiveProjectId(project.id);
+          // Focus the worktree created with it, if auto_create_first_worktree made one
+          const [firstWorktree] = project.worktrees;
+          if (firstWorktree) {
+            setOpenWorktreeIds((prev) => new Set([...prev, firstWorktree.id]));
+          }
+          setActiveWorktreeId(firstWorktree?.id ?? null);
         } catch (err) {
           console.error('Failed to add project:', err);
         }
@@ -1895,14 +1901,13 @@ function App() {
           setAutoEditWorktreeId(worktree.id);
         }
       } catch (err) {
-        const errorMessage = String(err);
         // Check if this is an uncommitted changes error
-        if (errorMessage.includes('uncommitted changes')) {
+        if (isCodedError(err) && err.code === 'git.uncommitted_changes') {
           setStashError(null); // Clear any previous error
           setPendingStashProject(project);
         } else {
           console.error('Failed to create worktree:', err);
-          showError(`Failed to create worktree: ${errorMessage}`);
+          showError(`Failed to create worktree: ${errorMessage(err)}`);
         }
       }
     },
@@ -1942,7 +1947,7 @@ function App() {
       }
     } catch (err) {
       console.error('[handleStashAndCreate] Failed:', err);
-      setStashError(String(err));
+      setStashError(errorMessage(err));
       // Try to restore the stash if worktree creation failed
       if (stashId) {
         try {
@@ -1962,6 +1967,7 @@ function App() {
     if (project) {
       // Update last accessed timestamp
       touchProject(project.id).catch(() => {});
+      touchWorktree(worktree.id).catch(() => {});
       // Navigation history is handled by the useEffect that tracks view changes
       setActiveProjectId(project.id);
       // Auto-open project terminal so cmd+0 can switch to it
@@ -2620,6 +2626,28 @@ function App() {
     }
   }, [openEntitiesInOrder, projects]);
 
+  // The backend resolves Session N requests against the list we pass it
+  useEffect(() => {
+    const unlistenActivate = listen<NavEntry>('activate-entry', (event) => {
+      const { type, id } = event.payload;
+      if (type === 'scratch') {
+        setActiveWorktreeId(null);
+        setActiveProjectId(null);
+        setActiveScratchId(id);
+      } else if (type === 'project') {
+        const project = projects.find(p => p.id === id);
+        if (project) handleSelectProject(project);
+      } else {
+        const worktree = projects.flatMap(p => p.worktrees).find(w => w.id === id);
+        if (worktree) handleSelectWorktree(worktree);
+      }
+    });
+
+    return () => {
+      unlistenActivate.then((fn) => fn());
+    };
+  }, [projects, handleSelectProject, handleSelectWorktree]);
+
   // Build the handlers for each action (namespaced format)
   const actionHandlers: ActionHandlers = useMemo(() => ({
     'app::quit': () => {
@@ -3118,7 +3146,7 @@ function App() {
   // Sync action availability to menu bar
   useEffect(() => {
     const menuAvailability = getMenuAvailability(actionContext);
-    updateActionAvailability(menuAvailability);
+    updateActionAvailability(menuAvailability, actionContext.activeProjectId);
   }, [actionContext]);
 
   const pendingDeleteInfo = pendingDeleteId
@@ -0,0 +1,14 @@
+import { describe, it, expect } from 'vitest';
+import { configWindowKind } from './ConfigWindow';
+
+describe('configWindowKind', () => {
+  it('reads the window query', () => {
+    expect(configWindowKind('?window=settings')).toBe('settings');
+    expect(configWindowKind('?window=mappings')).toBe('mappings');
+  });
+
+  it('ignores the main window and unknown views', () => {
+    expect(configWindowKind('')).toBeNull();
+    expect(configWindowKind('?window=other')).toBeNull();
+  });
+});
@@ -0,0 +1,110 @@
+import { useCallback, useEffect, useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import { Banner } from './Banner';
+import { getAppCommand, getAppTarget, type Config, type ConfigError } from '../hooks/useConfig';
+
+/** Config files that get their own native window, selected by `?window=` */
+export type ConfigWindowKind = 'settings' | 'mappings';
+
+const TITLES: Record<ConfigWindowKind, string> = {
+  settings: 'Settings',
+  mappings: 'Key Mappings',
+};
+
+/** The config window `search` (e.g. `?window=settings`) asks for, if any */
+export function configWindowKind(search: string): ConfigWindowKind | null {
+  const kind = new URLSearchParams(search).get('window');
+  return kind === 'settings' || kind === 'mappings' ? kind : null;
+}
+
+interface ConfigWindowProps {
+  kind: ConfigWindowKind;
+}
+
+/**
+ * Shows the effective settings or mappings and opens the user's file in the
+ * configured editor. Reloads when the main window's watcher reports a change.
+ */
+export function ConfigWindow({ kind }: ConfigWindowProps) {
+  const [path, setPath] = useState<string | null>(null);
+  const [content, setContent] = useState<unknown>(null);
+  const [errors, setErrors] = useState<ConfigError[]>([]);
+  const [editor, setEditor] = useState<Config['apps']['editor']>(undefined);
+
+  const load = useCallback(async () => {
+    try {
+      setPath(await invoke<string>('get_config_file_path', { fileType: kind }));
+      const settings = await invoke<{ config: Config; errors: ConfigError[] }>('get_config', {
+        projectPath: null,
+      });
+      setEditor(settings.config.apps.editor);
+      if (kind === 'settings') {
+        setContent(settings.config);
+        setErrors(settings.errors);
+      } else {
+        const result = await invoke<{ mappings: unknown; errors: ConfigError[] }>('get_mappings');
+        setContent(result.mappings);
+        setErrors(result.errors);
+      }
+    } catch (err) {
+      console.error(`Failed to load ${kind}:`, err);
+    }
+  }, [kind]);
+
+  useEffect(() => {
+    load();
+    // The main window owns the watchers; just follow their events here
+    const unlisten = listen(kind === 'settings' ? 'config-changed' : 'mappings-changed', () => {
+      load();
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, [kind, load]);
+
+  const openInEditor = () => {
+    if (!path) return;
+    // Drawers and tabs live in the main window; run anything else as configured
+    const target = getAppTarget(editor, 'terminal');
+    invoke('open_in_editor', {
+      path,
+      app: getAppCommand(editor) ?? null,
+      target: target === 'terminal' ? 'terminal' : 'external',
+      terminalApp: null,
+    }).catch((err) => console.error(`Failed to open ${kind}:`, err));
+  };
+
+  return (
+    <div className="flex flex-col h-screen bg-theme-0 text-theme-1">
+      {errors.length > 0 && (
+        <Banner variant="error">
+          {errors.map((error, i) => (
+            <span key={i}>
+              {i > 0 && ' | '}
+              <span className="font-mono text-xs">{error.file.split('/').pop()}</span>
+              {': '}
+              {error.message}
+            </span>
+          ))}
+        </Banner>
+      )}
+      <div className="flex items-center justify-between gap-4 px-4 py-3 border-b border-theme-1">
+        <div className="min-w-0">
+          <h1 className="text-sm font-medium">{TITLES[kind]}</h1>
+          {path && <p className="text-xs text-theme-2 font-mono truncate">{path}</p>}
+        </div>
+        <button
+          onClick={openInEditor}
+          disabled={!path}
+          className="px-3 py-1 text-sm rounded bg-theme-2 hover:bg-theme-3 disabled:opacity-50"
+        >
+          Open in Editor
+        </button>
+      </div>
+      <pre className="flex-1 overflow-auto p-4 text-xs font-mono">
+        {content === null ? 'Loading...' : JSON.stringify(content, null, 2)}
+      </pre>
+    </div>
+  );
+}
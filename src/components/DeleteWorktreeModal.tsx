@@ -5,6 +5,7 @@ import { Worktree, DeleteWorktreeProgress, DeleteWorktreeCompleted, WorktreeDele
 import { executeDeleteWorktreeWorkflow, checkWorktreeDeleteStatus } from '../lib/tauri';
 import { DeleteConfig } from '../hooks/useConfig';
 import { Modal, ModalHeader, ModalBody, ModalActions, ModalButton } from './Modal';
+import { errorMessage } from '../lib/errors';
 
 interface DeleteWorktreeModalProps {
   worktree: Worktree;
@@ -63,7 +64,7 @@ export function DeleteWorktreeModal({
   useEffect(() => {
     checkWorktreeDeleteStatus(worktree.path, projectPath)
       .then(setDeleteStatus)
-      .catch((err) => setError(err.toString()))
+      .catch((err) => setError(errorMessage(err)))
       .finally(() => setLoading(false));
   }, [worktree.path, projectPath]);
 
@@ -126,7 +127,7 @@ export function DeleteWorktreeModal({
     try {
       await executeDeleteWorktreeWorkflow(worktree.id, { deleteBranch });
     } catch (err) {
-      setError(err instanceof Error ? err.message : String(err));
+      setError(errorMessage(err));
       setExecuting(false);
     }
   }, [worktree.id, deleteBranch]);
@@ -5,6 +5,7 @@ import { Worktree, MergeFeasibility, MergeStrategy, MergeProgress, MergeComplete
 import { MergeConfig } from '../hooks/useConfig';
 import { checkMergeFeasibility, executeMergeWorkflow, cleanupWorktree, abortMerge, abortRebase, MergeOptions } from '../lib/tauri';
 import { Modal, ModalHeader, ModalBody, ModalActions, ModalButton } from './Modal';
+import { errorMessage } from '../lib/errors';
 
 // Re-export for consumers
 export type { MergeOptions };
@@ -63,7 +64,7 @@ export function MergeModal({
   useEffect(() => {
     checkMergeFeasibility(worktree.path, projectPath)
       .then(setFeasibility)
-      .catch((err) => setError(err.toString()))
+      .catch((err) => setError(errorMessage(err)))
       .finally(() => setLoading(false));
   }, [worktree.path, projectPath]);
 
@@ -144,7 +145,7 @@ export function MergeModal({
         deleteRemoteBranch,
       });
     } catch (err) {
-      setError(err instanceof Error ? err.message : String(err));
+      setError(errorMessage(err));
       setExecuting(false);
     }
   }, [worktree.id, strategy, deleteWorktree, deleteLocalBranch, deleteRemoteBranch, buildSteps]);
@@ -169,7 +170,7 @@ export function MergeModal({
         deleteRemoteBranch,
       });
     } catch (err) {
-      setError(err instanceof Error ? err.message : String(err));
+      setError(errorMessage(err));
       setExecuting(false);
     }
   }, [worktree.id, strategy, deleteWorktree, deleteLocalBranch, deleteRemoteBranch, buildSteps]);
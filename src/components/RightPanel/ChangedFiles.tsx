@@ -21,6 +21,7 @@ const statusConfig: Record<FileChange['status'], { color: string; label: string
   deleted: { color: 'text-red-400', label: 'D' },
   renamed: { color: 'text-blue-400', label: 'R' },
   untracked: { color: 'text-theme-2', label: '?' },
+  ignored: { color: 'text-theme-3', label: '!' },
 };
 
 export function ChangedFiles({
@@ -1,4 +1,5 @@
 import { useState, useRef, useEffect, useCallback } from 'react';
+import { errorMessage } from '../../lib/errors';
 
 interface EditableWorktreeNameProps {
   name: string;
@@ -138,7 +139,7 @@ export function EditableWorktreeName({
       }
       previousFocusRef.current = null;
     } catch (err) {
-      setError(String(err));
+      setError(errorMessage(err));
     } finally {
       setIsSubmitting(false);
     }
@@ -44,6 +44,8 @@ export interface TaskConfig {
   shell?: string;
   /** Named URL templates. Key is display label, value is URL template (supports minijinja). */
   urls?: Record<string, string>;
+  /** Log file the task writes to, relative to its working directory. */
+  logFile?: string;
 }
 
 
@@ -68,6 +70,8 @@ export type AppConfig = string | {
   command?: string;
   /** Where to open the app. */
   target?: AppTarget;
+  /** Editor arguments for opening at a line, e.g. "-g {{ path }}:{{ line }}". */
+  lineFormat?: string;
 };
 
 export interface AppsConfig {
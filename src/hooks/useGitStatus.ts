@@ -56,6 +56,7 @@ export function useGitStatus(
         // Fetch uncommitted changes (working tree vs HEAD)
         const result = await invoke<FileChange[]>('get_changed_files', {
           worktreePath: worktree.path,
+          projectPath,
         });
         setFiles(result);
       } else {
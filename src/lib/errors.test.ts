@@ -0,0 +1,16 @@
+import { describe, it, expect } from 'vitest';
+import { errorMessage, isCodedError } from './errors';
+
+describe('errorMessage', () => {
+  it('uses the message of coded command errors', () => {
+    const err = { code: 'git.uncommitted_changes', message: 'Repository has uncommitted changes' };
+    expect(isCodedError(err)).toBe(true);
+    expect(errorMessage(err)).toBe('Repository has uncommitted changes');
+  });
+
+  it('handles plain string and Error rejections', () => {
+    expect(isCodedError('Project not found')).toBe(false);
+    expect(errorMessage('Project not found')).toBe('Project not found');
+    expect(errorMessage(new Error('boom'))).toBe('boom');
+  });
+});
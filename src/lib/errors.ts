@@ -0,0 +1,19 @@
+import { CodedError } from '../types';
+
+/** Whether a rejected command returned a `CodedError` rather than a plain string */
+export function isCodedError(err: unknown): err is CodedError {
+  return (
+    typeof err === 'object' &&
+    err !== null &&
+    typeof (err as CodedError).code === 'string' &&
+    typeof (err as CodedError).message === 'string'
+  );
+}
+
+/** Message to show for a rejected command, whatever shape its error has */
+export function errorMessage(err: unknown): string {
+  if (isCodedError(err) || err instanceof Error) {
+    return err.message;
+  }
+  return String(err);
+}
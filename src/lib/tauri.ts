@@ -12,6 +12,7 @@ import {
   BranchInfo,
   DiffContent,
   ChangedFilesViewMode,
+  MenuAvailabilityChanged,
 } from '../types';
 
 // Project commands
@@ -31,6 +32,10 @@ export async function touchProject(projectId: string): Promise<void> {
   return invoke('touch_project', { projectId });
 }
 
+export async function touchWorktree(worktreeId: string): Promise<void> {
+  return invoke('touch_worktree', { worktreeId });
+}
+
 // Worktree commands
 export async function createWorktree(
   projectPath: string,
@@ -274,7 +279,14 @@ export async function shutdown(): Promise<boolean> {
   return invoke<boolean>('shutdown');
 }
 
-// Update menu item enabled states based on action availability
-export async function updateActionAvailability(availability: Record<string, boolean>): Promise<void> {
-  return invoke<void>('update_action_availability', { availability });
+// Update menu item enabled states based on action availability and the selected project
+// Returns the items whose enabled state changed, with their new state
+export async function updateActionAvailability(
+  availability: Record<string, boolean>,
+  activeProjectId: string | null
+): Promise<MenuAvailabilityChanged> {
+  return invoke<MenuAvailabilityChanged>('update_action_availability', {
+    availability,
+    activeProjectId,
+  });
 }
@@ -1,9 +1,12 @@
 import React from "react";
 import ReactDOM from "react-dom/client";
 import App from "./App";
+import { ConfigWindow, configWindowKind } from "./components/ConfigWindow";
 import "./index.css";
 
-const app = <App />;
+// Settings and mappings open in their own native windows via `?window=`
+const windowKind = configWindowKind(window.location.search);
+const app = windowKind ? <ConfigWindow kind={windowKind} /> : <App />;
 
 ReactDOM.createRoot(document.getElementById("root") as HTMLElement).render(
   import.meta.env.DEV ? <React.StrictMode>{app}</React.StrictMode> : app,
@@ -6,6 +6,8 @@ export interface Project {
   order?: number;
   isActive: boolean;
   lastAccessedAt?: string;
+  /** Path template for new worktrees, e.g. `{repo}-worktrees/{name}` */
+  worktreeLayout?: string;
 }
 
 // Scratch terminal - a general-purpose terminal not tied to any project/worktree
@@ -80,13 +82,196 @@ export interface Worktree {
   branch: string;
   createdAt: string;
   order?: number;
+  detached?: boolean;
+  changedFilesViewMode?: ChangedFilesViewMode;
+  showIgnoredFiles?: boolean;
+  lastAccessedAt?: string;
 }
 
 export interface FileChange {
   path: string;
-  status: 'added' | 'modified' | 'deleted' | 'renamed' | 'untracked';
+  status: 'added' | 'modified' | 'deleted' | 'renamed' | 'untracked' | 'ignored';
   insertions?: number;
   deletions?: number;
+  submodule?: SubmoduleChange;
+}
+
+export interface SubmoduleChange {
+  newCommits: boolean;
+  modifiedContent: boolean;
+  untrackedContent: boolean;
+}
+
+export interface StatusCounts {
+  added: number;
+  modified: number;
+  deleted: number;
+  renamed: number;
+  untracked: number;
+  ignored: number;
+}
+
+export interface ChangeTree {
+  name: string;
+  path: string;
+  directories: ChangeTree[];
+  files: FileChange[];
+  counts: StatusCounts;
+}
+
+export interface WorktreeDiskUsage {
+  worktreeId: string;
+  bytes: number;
+}
+
+export interface ProjectStats {
+  worktreeCount: number;
+  totalDiskBytes: number;
+  dirtyCount: number;
+}
+
+/** Payload of the `project-path-missing` event */
+export interface ProjectPathMissing {
+  projectId: string;
+  projectPath: string;
+  projectMissing: boolean;
+  missingWorktreePaths: string[];
+}
+
+/** Payload of the `menu-availability-changed` event: menu item id to its new enabled state */
+export type MenuAvailabilityChanged = Record<string, boolean>;
+
+/** Payload of the `state-recovery` event */
+export interface StateRecovery {
+  statePath: string;
+  backupPath?: string | null;
+  error: string;
+}
+
+export type FindingSeverity = 'info' | 'warning' | 'error';
+
+export type FindingKind =
+  | 'missingWorktreeDirectory'
+  | 'staleWorktreeMetadata'
+  | 'detachedHead'
+  | 'dirty'
+  | 'orphanedDirectory'
+  | 'watcherFailure'
+  | 'duplicateWorktreePath'
+  | 'symlinkedWorktreePath';
+
+/** A problem found by the `run_diagnostics` health scan */
+export interface Finding {
+  severity: FindingSeverity;
+  kind: FindingKind;
+  projectId: string;
+  worktreeId: string | null;
+  path: string;
+  message: string;
+  suggestion: string;
+}
+
+/** Error from commands whose failures can be localized: `code` is stable, `message` is for logs */
+export interface CodedError {
+  code: string;
+  message: string;
+}
+
+/** Payload of the `worktree-base-dir-invalid` event, sent at startup and on config changes */
+export type WorktreeBaseDirInvalid = CodedError;
+
+export interface AppInfo {
+  version: string;
+  latestVersion: string | null;
+  updateAvailable: boolean;
+  changelogUrl: string | null;
+  updateError: string | null;
+}
+
+export interface SkippedMapping {
+  actionId: string;
+  accelerator: string;
+  reason: string;
+}
+
+export interface MappingChangeReport {
+  updated: string[];
+  skipped: SkippedMapping[];
+}
+
+export interface CommitSummary {
+  sha: string;
+  shortSha: string;
+  author: string;
+  /** Seconds since the epoch */
+  timestamp: number;
+  relativeTime: string;
+  subject: string;
+}
+
+export interface HeadInfo {
+  branch: string | null;
+  sha: string | null;
+  detached: boolean;
+}
+
+/** Payload of the `main-head-changed` event */
+export interface MainHeadChanged {
+  projectId: string;
+  projectPath: string;
+  head: HeadInfo;
+}
+
+/** Payload of the `worktree-status` event */
+export interface WorktreeStatus {
+  worktree_id: string;
+  base: string;
+  ahead: number;
+  behind: number;
+}
+
+/** Payload of the `branch-changed` event */
+export interface BranchChanged {
+  worktree_id: string;
+  /** Empty when HEAD is detached */
+  branch: string;
+  detached: boolean;
+}
+
+/** Payload of the `git-progress` event */
+export interface GitProgress {
+  command: string;
+  phase: string;
+  percent: number;
+}
+
+/** A sidebar row, as listed in `openEntitiesInOrder` and resolved from a Session N index */
+export interface NavEntry {
+  type: 'scratch' | 'project' | 'worktree';
+  id: string;
+}
+
+/** Payload of the worktrees-reordered event */
+export interface WorktreesReordered {
+  projectId: string;
+  worktreeIds: string[];
+}
+
+/** A window's position and size in physical pixels */
+export interface WindowBounds {
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+}
+
+/** Format of an exported changed-file list */
+export type ExportFormat = 'json' | 'csv';
+
+export interface WorktreePathPreview {
+  name: string;
+  path: string;
+  exists: boolean;
 }
 
 export type ChangedFilesViewMode = 'uncommitted' | 'branch';
@@ -130,6 +315,13 @@ export interface MergeFeasibility {
   error: string | null;
 }
 
+/** Result of the `merge_worktree` command */
+export type MergeOutcome =
+  | { kind: 'upToDate' }
+  | { kind: 'fastForward' }
+  | { kind: 'merged' }
+  | { kind: 'conflict'; files: string[] };
+
 export interface MergeWorkflowOptions {
   strategy: MergeStrategy;
   deleteWorktree: boolean;